@@ -71,11 +71,13 @@ impl Termination for RdapTestError {
                 // I/O Errors
                 RdapClientError::Client(_) => 42,
                 RdapClientError::IoError(_) => 43,
+                RdapClientError::Transport(_) => 44,
 
                 // RDAP Server Errors
                 RdapClientError::Response(_) => 60,
                 RdapClientError::ParsingError(_) => 62,
                 RdapClientError::Json(_) => 63,
+                RdapClientError::UnexpectedPage => 64,
 
                 // Bootstrap Errors
                 RdapClientError::BootstrapUnavailable => 70,
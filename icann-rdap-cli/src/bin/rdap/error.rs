@@ -33,6 +33,8 @@ pub enum RdapCliError {
     NoRegistrarFound,
     #[error("No registry found")]
     NoRegistryFound,
+    #[error("RDAP server returned an error response")]
+    ErrorResponse,
 }
 
 impl Termination for RdapCliError {
@@ -55,6 +57,7 @@ impl Termination for RdapCliError {
             Self::BootstrapNotFound => 103,
             Self::NoRegistrarFound => 104,
             Self::NoRegistryFound => 105,
+            Self::ErrorResponse => 106,
 
             // User Errors
             Self::UnknownOutputType => 200,
@@ -65,11 +68,13 @@ impl Termination for RdapCliError {
                 // I/O Errors
                 RdapClientError::Client(_) => 42,
                 RdapClientError::IoError(_) => 43,
+                RdapClientError::Transport(_) => 44,
 
                 // RDAP Server Errors
                 RdapClientError::Response(_) => 60,
                 RdapClientError::ParsingError(_) => 62,
                 RdapClientError::Json(_) => 63,
+                RdapClientError::UnexpectedPage => 64,
 
                 // Bootstrap Errors
                 RdapClientError::BootstrapUnavailable => 70,
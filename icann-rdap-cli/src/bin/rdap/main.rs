@@ -6,6 +6,7 @@ use icann_rdap_cli::dirs;
 use icann_rdap_client::http::create_client;
 use icann_rdap_client::http::Client;
 use icann_rdap_client::http::ClientConfig;
+use icann_rdap_client::rdap::FileResponseCache;
 use icann_rdap_common::check::CheckClass;
 use query::InrBackupBootstrap;
 use query::ProcessType;
@@ -13,6 +14,7 @@ use query::ProcessingParams;
 use query::TldLookup;
 use std::io::IsTerminal;
 use std::str::FromStr;
+use std::sync::Arc;
 use tracing::error;
 use tracing::info;
 #[cfg(debug_assertions)]
@@ -224,6 +226,29 @@ struct Cli {
     )]
     max_cache_age: u32,
 
+    /// Fetch all pages.
+    ///
+    /// When given, search results the server has split into pages
+    /// are fetched by following the "next" link of each page.
+    #[arg(long, required = false, env = "RDAP_ALL_PAGES")]
+    all_pages: bool,
+
+    /// Maximum number of referrals.
+    ///
+    /// Sets the maximum number of referrals to follow from one RDAP server
+    /// to another, such as from a domain registry to a registrar or from an
+    /// RIR to an NIR or LIR. A value of 0 does not follow referrals.
+    ///
+    /// This applies to every type of query, so by default IP address and
+    /// autnum queries are also followed from the RIR to the next registry.
+    #[arg(
+        long,
+        required = false,
+        env = "RDAP_MAX_REFERRALS",
+        default_value = "1"
+    )]
+    max_referrals: usize,
+
     /// Allow HTTP connections.
     ///
     /// When given, allows connections to RDAP servers using HTTP.
@@ -357,6 +382,15 @@ enum QtypeArg {
     /// Nameserver IP Address Search
     NsIp,
 
+    /// Domain Reverse Search (e.g. "fn=Bob*&role=registrant")
+    DomainReverse,
+
+    /// Nameserver Reverse Search (e.g. "handle=FOO-1")
+    NsReverse,
+
+    /// Entity Reverse Search (e.g. "email=bob@example.com")
+    EntityReverse,
+
     /// RDAP URL
     Url,
 }
@@ -595,8 +629,8 @@ pub async fn wrapped_main() -> Result<(), RdapCliError> {
         tld_lookup,
         inr_backup_bootstrap,
         error_on_checks: cli.error_on_checks,
-        no_cache: cli.no_cache,
-        max_cache_age: cli.max_cache_age,
+        all_pages: cli.all_pages,
+        max_referrals: cli.max_referrals,
     };
 
     let client_config = ClientConfig::builder()
@@ -609,7 +643,16 @@ pub async fn wrapped_main() -> Result<(), RdapCliError> {
         .def_retry_secs(cli.def_retry_secs)
         .max_retries(cli.max_retries)
         .build();
-    let rdap_client = create_client(&client_config);
+    let rdap_client = create_client(&client_config).map(|client| {
+        if cli.no_cache {
+            client
+        } else {
+            client.with_response_cache(Arc::new(FileResponseCache::new(
+                dirs::rdap_cache_path(),
+                cli.max_cache_age as i64,
+            )))
+        }
+    });
     if let Ok(client) = rdap_client {
         if !use_pager {
             tracing_subscriber::fmt()
@@ -712,6 +755,9 @@ fn query_type_from_cli(cli: &Cli) -> Result<QueryType, RdapCliError> {
         QtypeArg::DomainNsIp => QueryType::domain_ns_ip_search(&query_value)?,
         QtypeArg::NsName => QueryType::NameserverNameSearch(query_value),
         QtypeArg::NsIp => QueryType::ns_ip_search(&query_value)?,
+        QtypeArg::DomainReverse => QueryType::domain_reverse_search(&query_value)?,
+        QtypeArg::NsReverse => QueryType::ns_reverse_search(&query_value)?,
+        QtypeArg::EntityReverse => QueryType::entity_reverse_search(&query_value)?,
         QtypeArg::Url => QueryType::Url(query_value),
     };
     Ok(q)
@@ -1,26 +1,20 @@
 use icann_rdap_client::http::Client;
 use icann_rdap_common::check::traverse_checks;
 use icann_rdap_common::check::CheckClass;
-use icann_rdap_common::check::CheckParams;
-use icann_rdap_common::check::Checks;
-use icann_rdap_common::check::GetChecks;
-use icann_rdap_common::response::get_related_links;
-use tracing::debug;
 use tracing::error;
-use tracing::info;
 
 use icann_rdap_client::{
     gtld::{GtldParams, ToGtldWhois},
     md::{redacted::replace_redacted_items, MdOptions, MdParams, ToMd},
-    rdap::{QueryType, ResponseData},
-    rdap::{RequestData, RequestResponse, RequestResponses, SourceType},
+    rdap::{QueryType, ReferralChain, ResponseData},
+    rdap::{RequestData, RequestResponse, RequestResponses},
 };
 use termimad::{crossterm::style::Color::*, Alignment, MadSkin};
 
 use crate::bootstrap::get_base_url;
 use crate::bootstrap::BootstrapType;
 use crate::error::RdapCliError;
-use crate::request::do_request;
+use crate::request::do_referral_request;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) enum OutputType {
@@ -83,8 +77,8 @@ pub(crate) struct ProcessingParams {
     pub tld_lookup: TldLookup,
     pub inr_backup_bootstrap: InrBackupBootstrap,
     pub error_on_checks: bool,
-    pub no_cache: bool,
-    pub max_cache_age: u32,
+    pub all_pages: bool,
+    pub max_referrals: usize,
 }
 
 pub(crate) async fn do_query<'a, W: std::io::Write>(
@@ -104,7 +98,7 @@ pub(crate) async fn do_query<'a, W: std::io::Write>(
         QueryType::Domain(_) | QueryType::DomainNameSearch(_) => {
             do_domain_query(query_type, processing_params, client, write).await
         }
-        _ => do_basic_query(query_type, processing_params, None, client, write).await,
+        _ => do_basic_query(query_type, processing_params, client, write).await,
     }
 }
 
@@ -114,8 +108,6 @@ async fn do_domain_query<'a, W: std::io::Write>(
     client: &Client,
     write: &mut W,
 ) -> Result<(), RdapCliError> {
-    let mut transactions = RequestResponses::new();
-
     // special processing for TLD Lookups
     let base_url = if let QueryType::Domain(ref domain) = query_type {
         if domain.is_tld() && matches!(processing_params.tld_lookup, TldLookup::Iana) {
@@ -127,86 +119,41 @@ async fn do_domain_query<'a, W: std::io::Write>(
         get_base_url(&processing_params.bootstrap_type, client, query_type).await?
     };
 
-    let response = do_request(&base_url, query_type, processing_params, client).await;
-    let registrar_response;
-    match response {
-        Ok(response) => {
-            let source_host = response.http_data.host.to_owned();
-            let req_data = RequestData {
-                req_number: 1,
-                source_host: &source_host,
-                source_type: SourceType::DomainRegistry,
-            };
-            let replaced_rdap = replace_redacted_items(response.rdap.clone());
-            let replaced_data = ResponseData {
-                rdap: replaced_rdap,
-                // copy other fields from `response`
-                ..response.clone()
-            };
-            if let ProcessType::Registrar = processing_params.process_type {
-                transactions =
-                    do_no_output(processing_params, &req_data, &replaced_data, transactions);
-            } else {
-                transactions = do_output(
-                    processing_params,
-                    &req_data,
-                    &replaced_data,
-                    write,
-                    transactions,
-                )?;
-            }
-            let regr_source_host;
-            let regr_req_data: RequestData;
-            if !matches!(processing_params.process_type, ProcessType::Registry) {
-                if let Some(url) = get_related_links(&response.rdap).first() {
-                    info!("Querying domain name from registrar.");
-                    debug!("Registrar RDAP Url: {url}");
-                    let query_type = QueryType::Url(url.to_string());
-                    let registrar_response_result =
-                        do_request(&base_url, &query_type, processing_params, client).await;
-                    match registrar_response_result {
-                        Ok(response_data) => {
-                            registrar_response = response_data;
-                            regr_source_host = registrar_response.http_data.host.to_owned();
-                            regr_req_data = RequestData {
-                                req_number: 2,
-                                source_host: &regr_source_host,
-                                source_type: SourceType::DomainRegistrar,
-                            };
-                            if let ProcessType::Registry = processing_params.process_type {
-                                transactions = do_no_output(
-                                    processing_params,
-                                    &regr_req_data,
-                                    &registrar_response,
-                                    transactions,
-                                );
-                            } else {
-                                transactions = do_output(
-                                    processing_params,
-                                    &regr_req_data,
-                                    &registrar_response,
-                                    write,
-                                    transactions,
-                                )?;
-                            }
-                        }
-                        Err(error) => return Err(error),
-                    }
-                } else if matches!(processing_params.process_type, ProcessType::Registrar) {
-                    return Err(RdapCliError::NoRegistrarFound);
-                }
+    let max_referrals = if let ProcessType::Registry = processing_params.process_type {
+        0
+    } else {
+        processing_params.max_referrals
+    };
+    let chain = do_referral_request(
+        &base_url,
+        query_type,
+        max_referrals,
+        processing_params,
+        client,
+    )
+    .await;
+    match chain {
+        Ok(chain) => {
+            if matches!(processing_params.process_type, ProcessType::Registrar)
+                && !chain.has_referrals()
+            {
+                return Err(RdapCliError::NoRegistrarFound);
             }
-            do_final_output(processing_params, write, transactions)?;
+            do_chain_output(
+                processing_params,
+                processing_params.process_type,
+                &chain,
+                write,
+            )
         }
         Err(error) => {
             if matches!(processing_params.process_type, ProcessType::Registry) {
-                return Err(RdapCliError::NoRegistryFound);
+                Err(RdapCliError::NoRegistryFound)
             } else {
-                return Err(error);
+                Err(error)
             }
         }
-    };
-    Ok(())
+    }
 }
 
 async fn do_inr_query<'a, W: std::io::Write>(
@@ -215,7 +162,6 @@ async fn do_inr_query<'a, W: std::io::Write>(
     client: &Client,
     write: &mut W,
 ) -> Result<(), RdapCliError> {
-    let mut transactions = RequestResponses::new();
     let mut base_url = get_base_url(&processing_params.bootstrap_type, client, query_type).await;
     if base_url.is_err()
         && matches!(
@@ -225,79 +171,68 @@ async fn do_inr_query<'a, W: std::io::Write>(
     {
         base_url = Ok("https://rdap.arin.net/registry".to_string());
     };
-    let response = do_request(&base_url?, query_type, processing_params, client).await;
-    match response {
-        Ok(response) => {
-            let source_host = response.http_data.host.to_owned();
-            let req_data = RequestData {
-                req_number: 1,
-                source_host: &source_host,
-                source_type: SourceType::RegionalInternetRegistry,
-            };
-            let replaced_rdap = replace_redacted_items(response.rdap.clone());
-            let replaced_data = ResponseData {
-                rdap: replaced_rdap,
-                // copy other fields from `response`
-                ..response.clone()
-            };
-            transactions = do_output(
-                processing_params,
-                &req_data,
-                &replaced_data,
-                write,
-                transactions,
-            )?;
-            do_final_output(processing_params, write, transactions)?;
-        }
-        Err(error) => return Err(error),
-    };
-    Ok(())
+    let chain = do_referral_request(
+        &base_url?,
+        query_type,
+        processing_params.max_referrals,
+        processing_params,
+        client,
+    )
+    .await?;
+    do_chain_output(processing_params, ProcessType::Standard, &chain, write)
 }
 
 async fn do_basic_query<'a, W: std::io::Write>(
     query_type: &QueryType,
     processing_params: &ProcessingParams,
-    req_data: Option<&'a RequestData<'a>>,
     client: &Client,
     write: &mut W,
 ) -> Result<(), RdapCliError> {
-    let mut transactions = RequestResponses::new();
     let base_url = get_base_url(&processing_params.bootstrap_type, client, query_type).await?;
-    let response = do_request(&base_url, query_type, processing_params, client).await;
-    match response {
-        Ok(response) => {
-            let source_host = response.http_data.host.to_owned();
-            let req_data = if let Some(meta) = req_data {
-                RequestData {
-                    req_number: meta.req_number + 1,
-                    source_host: meta.source_host,
-                    source_type: SourceType::UncategorizedRegistry,
-                }
-            } else {
-                RequestData {
-                    req_number: 1,
-                    source_host: &source_host,
-                    source_type: SourceType::UncategorizedRegistry,
-                }
-            };
-            let replaced_rdap = replace_redacted_items(response.rdap.clone());
-            let replaced_data = ResponseData {
-                rdap: replaced_rdap,
-                // copy other fields from `response`
-                ..response.clone()
-            };
-            transactions = do_output(
-                processing_params,
-                &req_data,
-                &replaced_data,
-                write,
-                transactions,
-            )?;
-            do_final_output(processing_params, write, transactions)?;
+    let chain = do_referral_request(
+        &base_url,
+        query_type,
+        processing_params.max_referrals,
+        processing_params,
+        client,
+    )
+    .await?;
+    do_chain_output(processing_params, ProcessType::Standard, &chain, write)
+}
+
+/// Outputs the responses of a referral chain. When processing for a registry,
+/// only the first response is output, and when processing for a registrar, only the last.
+fn do_chain_output<W: std::io::Write>(
+    processing_params: &ProcessingParams,
+    process_type: ProcessType,
+    chain: &ReferralChain,
+    write: &mut W,
+) -> Result<(), RdapCliError> {
+    let req_data = chain.request_data();
+    let responses: Vec<ResponseData> = chain
+        .referrals()
+        .iter()
+        .map(|referral| ResponseData {
+            rdap: replace_redacted_items(referral.response.rdap.clone()),
+            // copy other fields from `response`
+            ..referral.response.clone()
+        })
+        .collect();
+    let last = responses.len() - 1;
+    let mut transactions = RequestResponses::new();
+    for (i, (req_data, response)) in req_data.iter().zip(responses.iter()).enumerate() {
+        let output = match process_type {
+            ProcessType::Standard => true,
+            ProcessType::Registry => i == 0,
+            ProcessType::Registrar => i == last,
+        };
+        if output {
+            transactions = do_output(processing_params, req_data, response, write, transactions)?;
+        } else {
+            transactions = do_no_output(processing_params, req_data, response, transactions);
         }
-        Err(error) => return Err(error),
-    };
-    Ok(())
+    }
+    do_final_output(processing_params, write, transactions)
 }
 
 fn do_output<'a, W: std::io::Write>(
@@ -368,7 +303,7 @@ fn do_output<'a, W: std::io::Write>(
     };
 
     let req_res = RequestResponse {
-        checks: do_output_checks(response),
+        checks: response.get_checks(),
         req_data,
         res_data: response,
     };
@@ -383,7 +318,7 @@ fn do_no_output<'a>(
     mut transactions: RequestResponses<'a>,
 ) -> RequestResponses<'a> {
     let req_res = RequestResponse {
-        checks: do_output_checks(response),
+        checks: response.get_checks(),
         req_data,
         res_data: response,
     };
@@ -391,20 +326,6 @@ fn do_no_output<'a>(
     transactions
 }
 
-fn do_output_checks(response: &ResponseData) -> Checks {
-    let check_params = CheckParams {
-        do_subchecks: true,
-        root: &response.rdap,
-        parent_type: response.rdap.get_type(),
-        allow_unreg_ext: false,
-    };
-    let mut checks = response.rdap.get_checks(check_params);
-    checks
-        .items
-        .append(&mut response.http_data.get_checks(check_params).items);
-    checks
-}
-
 fn do_final_output<W: std::io::Write>(
     processing_params: &ProcessingParams,
     write: &mut W,
@@ -462,6 +383,12 @@ fn do_final_output<W: std::io::Write>(
     if checks_found && processing_params.error_on_checks {
         return Err(RdapCliError::ErrorOnChecks);
     }
+    if transactions
+        .iter()
+        .any(|req_res| !req_res.res_data.http_data.is_success())
+    {
+        return Err(RdapCliError::ErrorResponse);
+    }
 
     Ok(())
 }
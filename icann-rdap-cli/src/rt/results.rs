@@ -204,6 +204,18 @@ pub enum RunFeature {
 }
 
 impl RunOutcome {
+    /// Gets the outcome of a run answered with a non-2xx HTTP status.
+    pub fn from_status(status_code: u16) -> Self {
+        match StatusCode::from_u16(status_code) {
+            Ok(StatusCode::TOO_MANY_REQUESTS) => Self::HttpTooManyRequestsError,
+            Ok(StatusCode::NOT_FOUND) => Self::HttpNotFoundError,
+            Ok(StatusCode::BAD_REQUEST) => Self::HttpBadRequestError,
+            Ok(StatusCode::UNAUTHORIZED) => Self::HttpUnauthorizedError,
+            Ok(StatusCode::FORBIDDEN) => Self::HttpForbiddenError,
+            _ => Self::HttpNon200Error,
+        }
+    }
+
     pub fn to_md(&self, options: &MdOptions) -> String {
         match self {
             Self::Tested => self.to_bold(options),
@@ -256,7 +268,12 @@ impl TestRun {
     ) -> Self {
         if let Ok(response_data) = rdap_response {
             self.end_time = Some(Utc::now());
-            self.outcome = RunOutcome::Tested;
+            // error responses are checked too, but the run is not a successful test
+            self.outcome = if response_data.http_data.is_success() {
+                RunOutcome::Tested
+            } else {
+                RunOutcome::from_status(response_data.http_data.status_code)
+            };
             self.checks = Some(do_checks(&response_data, options));
             self.response_data = Some(response_data);
         } else {
@@ -268,11 +285,15 @@ impl TestRun {
                 | RdapClientError::BootstrapUnavailable
                 | RdapClientError::BootstrapError(_)
                 | RdapClientError::IanaResponse(_) => RunOutcome::InternalError,
-                RdapClientError::Response(_) => RunOutcome::RdapDataError,
+                RdapClientError::Response(_) | RdapClientError::UnexpectedPage => {
+                    RunOutcome::RdapDataError
+                }
                 RdapClientError::Json(_) | RdapClientError::ParsingError(_) => {
                     RunOutcome::JsonError
                 }
-                RdapClientError::IoError(_) => RunOutcome::NetworkError,
+                RdapClientError::IoError(_) | RdapClientError::Transport(_) => {
+                    RunOutcome::NetworkError
+                }
                 RdapClientError::Client(e) => {
                     if e.is_redirect() {
                         RunOutcome::HttpRedirectError
@@ -280,15 +301,8 @@ impl TestRun {
                         RunOutcome::HttpConnectError
                     } else if e.is_timeout() {
                         RunOutcome::HttpTimeoutError
-                    } else if e.is_status() {
-                        match e.status().unwrap() {
-                            StatusCode::TOO_MANY_REQUESTS => RunOutcome::HttpTooManyRequestsError,
-                            StatusCode::NOT_FOUND => RunOutcome::HttpNotFoundError,
-                            StatusCode::BAD_REQUEST => RunOutcome::HttpBadRequestError,
-                            StatusCode::UNAUTHORIZED => RunOutcome::HttpUnauthorizedError,
-                            StatusCode::FORBIDDEN => RunOutcome::HttpForbiddenError,
-                            _ => RunOutcome::HttpNon200Error,
-                        }
+                    } else if let Some(status) = e.status() {
+                        RunOutcome::from_status(status.as_u16())
                     } else {
                         RunOutcome::HttpProtocolError
                     }
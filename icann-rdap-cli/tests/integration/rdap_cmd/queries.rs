@@ -31,6 +31,24 @@ async fn GIVEN_domain_WHEN_query_THEN_success(#[case] db_domain: &str, #[case] q
     assert.success();
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn GIVEN_tls_server_WHEN_query_without_allow_http_THEN_success() {
+    // GIVEN
+    let mut test_jig = TestJig::new_rdap_tls().await;
+    let mut tx = test_jig.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+        .await
+        .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    test_jig.cmd.arg("foo.example");
+
+    // THEN
+    let assert = test_jig.cmd.assert();
+    assert.success();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn GIVEN_tld_WHEN_query_THEN_success() {
     // GIVEN
@@ -212,3 +230,47 @@ async fn GIVEN_domain_WHEN_search_domain_names_THEN_success() {
     let assert = test_jig.cmd.assert();
     assert.success();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn GIVEN_paged_domains_WHEN_search_domain_names_for_all_pages_THEN_all_domains_output() {
+    // GIVEN
+    let mut test_jig = TestJig::new_rdap_with_dn_search_paged(1).await;
+    let mut tx = test_jig.mem.new_tx().await.expect("new transaction");
+    for name in ["foo1.example", "foo2.example", "foo3.example"] {
+        tx.add_domain(&Domain::basic().ldh_name(name).build())
+            .await
+            .expect("add domain in tx");
+    }
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    test_jig
+        .cmd
+        .arg("--all-pages")
+        .arg("-t")
+        .arg("domain-name")
+        .arg("foo*");
+
+    // THEN
+    let assert = test_jig.cmd.assert();
+    let output = assert.success().get_output().stdout.clone();
+    let output = String::from_utf8(output).expect("output is not utf-8");
+    assert!(output.contains("foo1.example"));
+    assert!(output.contains("foo2.example"));
+    assert!(output.contains("foo3.example"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn GIVEN_no_domain_WHEN_query_THEN_error_response_output() {
+    // GIVEN
+    let mut test_jig = TestJig::new_rdap().await;
+
+    // WHEN
+    test_jig.cmd.arg("-O").arg("json").arg("foo.example");
+
+    // THEN
+    let assert = test_jig.cmd.assert();
+    let output = assert.failure().code(106).get_output().stdout.clone();
+    let output = String::from_utf8(output).expect("output is not UTF-8");
+    assert!(output.contains("\"errorCode\":404"));
+}
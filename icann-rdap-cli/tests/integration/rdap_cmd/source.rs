@@ -1,7 +1,10 @@
 #![allow(non_snake_case)]
 
 use icann_rdap_client::rdap::{RequestResponseOwned, SourceType};
-use icann_rdap_common::response::network::Network;
+use icann_rdap_common::{
+    media_types::RDAP_MEDIA_TYPE,
+    response::{network::Network, types::Link},
+};
 use icann_rdap_srv::storage::StoreOps;
 use rstest::rstest;
 
@@ -42,3 +45,93 @@ async fn GIVEN_inr_query_WHEN_query_THEN_source_is_rir(
         .source_type;
     assert!(matches!(source_type, SourceType::RegionalInternetRegistry));
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn GIVEN_inr_referral_WHEN_query_THEN_source_is_rir_then_nir() {
+    // GIVEN
+    let mut test_jig = TestJig::new_rdap().await;
+    let referral = format!("{}/ip/10.0.0.0/26", test_jig.rdap_base);
+    let mut tx = test_jig.mem.new_tx().await.expect("new transaction");
+    tx.add_network(
+        &Network::basic()
+            .cidr("10.0.0.0/24")
+            .handle("RIR-NET")
+            .link(
+                Link::builder()
+                    .value(&referral)
+                    .href(&referral)
+                    .rel("related")
+                    .media_type(RDAP_MEDIA_TYPE)
+                    .build(),
+            )
+            .build()
+            .expect("cidr parsing"),
+    )
+    .await
+    .expect("add network in tx");
+    tx.add_network(
+        &Network::basic()
+            .cidr("10.0.0.0/26")
+            .handle("NIR-NET")
+            .build()
+            .expect("cidr parsing"),
+    )
+    .await
+    .expect("add network in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    test_jig.cmd.arg("10.0.0.0/24");
+
+    // THEN
+    let output = test_jig.cmd.output().expect("executing inr query");
+    let responses: Vec<RequestResponseOwned> =
+        serde_json::from_slice(&output.stdout).expect("parsing stdout");
+    assert_eq!(responses.len(), 2);
+    assert!(matches!(
+        responses[0].req_data.source_type,
+        SourceType::RegionalInternetRegistry
+    ));
+    assert!(matches!(
+        responses[1].req_data.source_type,
+        SourceType::NationalInternetRegistry
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn GIVEN_inr_referral_WHEN_query_with_no_referrals_THEN_only_rir() {
+    // GIVEN
+    let mut test_jig = TestJig::new_rdap().await;
+    let referral = format!("{}/ip/10.0.0.0/26", test_jig.rdap_base);
+    let mut tx = test_jig.mem.new_tx().await.expect("new transaction");
+    tx.add_network(
+        &Network::basic()
+            .cidr("10.0.0.0/24")
+            .link(
+                Link::builder()
+                    .value(&referral)
+                    .href(&referral)
+                    .rel("related")
+                    .media_type(RDAP_MEDIA_TYPE)
+                    .build(),
+            )
+            .build()
+            .expect("cidr parsing"),
+    )
+    .await
+    .expect("add network in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    test_jig
+        .cmd
+        .arg("--max-referrals")
+        .arg("0")
+        .arg("10.0.0.0/24");
+
+    // THEN
+    let output = test_jig.cmd.output().expect("executing inr query");
+    let responses: Vec<RequestResponseOwned> =
+        serde_json::from_slice(&output.stdout).expect("parsing stdout");
+    assert_eq!(responses.len(), 1);
+}
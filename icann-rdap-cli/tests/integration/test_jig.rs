@@ -1,5 +1,6 @@
 use assert_cmd::Command;
 use icann_rdap_srv::config::ListenConfig;
+use icann_rdap_srv::config::TlsConfig;
 use icann_rdap_srv::server::AppState;
 use icann_rdap_srv::server::Listener;
 use icann_rdap_srv::storage::mem::config::MemConfig;
@@ -27,29 +28,72 @@ pub struct TestJig {
 impl TestJig {
     pub async fn new_rdap() -> Self {
         let common_config = CommonConfig::default();
-        Self::new_common_config(common_config, CommandType::Rdap).await
+        Self::new_common_config(common_config, None, CommandType::Rdap).await
     }
 
     pub async fn new_rdap_with_dn_search() -> Self {
-        let common_config = CommonConfig::builder()
-            .domain_search_by_name_enable(true)
+        let common_config = CommonConfig::default();
+        Self::new_common_config(common_config, None, CommandType::Rdap).await
+    }
+
+    pub async fn new_rdap_with_dn_search_paged(search_page_size: usize) -> Self {
+        let common_config = CommonConfig::default();
+        Self::new_common_config(common_config, Some(search_page_size), CommandType::Rdap).await
+    }
+
+    /// Creates a jig with a server using TLS. The command trusts the test CA
+    /// instead of allowing HTTP.
+    pub async fn new_rdap_tls() -> Self {
+        let listen_config = ListenConfig::builder()
+            .ip_addr("127.0.0.1".to_string())
+            .tls(
+                TlsConfig::builder()
+                    .cert_file(test_tls_file("cert.pem"))
+                    .key_file(test_tls_file("key.pem"))
+                    .build(),
+            )
             .build();
-        Self::new_common_config(common_config, CommandType::Rdap).await
+        Self::new_listen_config(
+            CommonConfig::default(),
+            None,
+            listen_config,
+            CommandType::Rdap,
+        )
+        .await
     }
 
     pub async fn new_rdap_test() -> Self {
         let common_config = CommonConfig::default();
-        Self::new_common_config(common_config, CommandType::RdapTest).await
+        Self::new_common_config(common_config, None, CommandType::RdapTest).await
     }
 
-    pub async fn new_common_config(common_config: CommonConfig, cmd_type: CommandType) -> Self {
+    pub async fn new_common_config(
+        common_config: CommonConfig,
+        search_page_size: Option<usize>,
+        cmd_type: CommandType,
+    ) -> Self {
+        Self::new_listen_config(
+            common_config,
+            search_page_size,
+            ListenConfig::default(),
+            cmd_type,
+        )
+        .await
+    }
+
+    async fn new_listen_config(
+        common_config: CommonConfig,
+        search_page_size: Option<usize>,
+        listen_config: ListenConfig,
+        cmd_type: CommandType,
+    ) -> Self {
         let mem = Mem::new(MemConfig::builder().common_config(common_config).build());
         let app_state = AppState {
-            storage: mem.clone(),
-            bootstrap: false,
+            search_page_size,
+            ..AppState::new(mem.clone())
         };
         let _ = tracing_subscriber::fmt().try_init();
-        let listener = Listener::listen(&ListenConfig::default())
+        let listener = Listener::listen(&listen_config)
             .await
             .expect("listening on interface");
         let rdap_base = listener.rdap_base();
@@ -86,9 +130,13 @@ impl TestJig {
                     .env("RDAP_PAGING", "none")
                     .env("RDAP_OUTPUT", "json-extra")
                     .env("RDAP_LOG", "debug")
-                    .env("RDAP_ALLOW_HTTP", "true")
                     .env("XDG_CACHE_HOME", self.test_dir.path("cache"))
                     .env("XDG_CONFIG_HOME", self.test_dir.path("config"));
+                if self.rdap_base.starts_with("https") {
+                    cmd.env("SSL_CERT_FILE", test_tls_file("ca.pem"));
+                } else {
+                    cmd.env("RDAP_ALLOW_HTTP", "true");
+                }
                 cmd
             }
             CommandType::RdapTest => {
@@ -105,3 +153,11 @@ impl TestJig {
         Self { cmd, ..self }
     }
 }
+
+/// Gets the path of a test certificate or key generated for the server tests.
+fn test_tls_file(name: &str) -> String {
+    format!(
+        "{}/../icann-rdap-srv/resources/test-tls/{name}",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
@@ -0,0 +1,74 @@
+//! A transport replaying canned responses.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use async_trait::async_trait;
+
+use super::{
+    recording::Recording,
+    transport::{Transport, TransportError, TransportResponse},
+};
+
+/// A [Transport] that replays canned responses by URL, so that code making RDAP requests
+/// can be tested deterministically without a network.
+///
+/// ```
+/// use icann_rdap_client::http::{MockTransport, TransportResponse};
+///
+/// let url = "https://rdap.example.com/rdap/domain/foo.example";
+/// let transport = MockTransport::builder()
+///     .response(
+///         url,
+///         TransportResponse::builder()
+///             .url(url)
+///             .header("content-type", "application/rdap+json")
+///             .body(r#"{"objectClassName":"domain","ldhName":"foo.example"}"#)
+///             .build(),
+///     )
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    responses: HashMap<String, TransportResponse>,
+}
+
+#[buildstructor::buildstructor]
+impl MockTransport {
+    #[builder]
+    pub fn new(responses: HashMap<String, TransportResponse>) -> Self {
+        Self { responses }
+    }
+
+    /// Creates a mock transport replaying the responses recorded in a directory
+    /// by a [super::RecordingTransport].
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, TransportError> {
+        let mut responses = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let recording: Recording = serde_json::from_str(&fs::read_to_string(path)?)?;
+                responses.insert(recording.url, recording.response);
+            }
+        }
+        Ok(Self { responses })
+    }
+
+    /// Adds a response to replay for a URL.
+    pub fn add_response(&mut self, url: impl Into<String>, response: TransportResponse) {
+        self.responses.insert(url.into(), response);
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for MockTransport {
+    async fn get(&self, url: &str) -> Result<TransportResponse, TransportError> {
+        self.responses
+            .get(url)
+            .cloned()
+            .ok_or_else(|| TransportError::NoResponse(url.to_string()))
+    }
+}
@@ -1,9 +1,20 @@
 //! The HTTP layer of RDAP.
 
+#[doc(inline)]
+pub use mock::*;
+#[cfg(not(target_arch = "wasm32"))]
+#[doc(inline)]
+pub use recording::*;
 #[doc(inline)]
 pub use reqwest::*;
 #[doc(inline)]
+pub use transport::*;
+#[doc(inline)]
 pub use wrapped::*;
 
+pub(crate) mod mock;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod recording;
 pub(crate) mod reqwest;
+pub(crate) mod transport;
 pub(crate) mod wrapped;
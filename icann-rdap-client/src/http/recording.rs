@@ -0,0 +1,111 @@
+//! A transport recording traffic to disk.
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use pct_str::{PctString, URIReserved};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::transport::{Transport, TransportError, TransportResponse};
+
+/// An HTTP exchange written to disk by a [RecordingTransport].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Recording {
+    /// The URL of the request.
+    pub url: String,
+
+    /// The response to the request.
+    pub response: TransportResponse,
+}
+
+/// A [Transport] that passes requests to another transport and writes each exchange
+/// to a file in a directory. The directory can be replayed with
+/// [super::MockTransport::from_dir].
+pub struct RecordingTransport {
+    transport: Box<dyn Transport>,
+    dir: PathBuf,
+}
+
+impl RecordingTransport {
+    pub fn new(transport: impl Transport + 'static, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            transport: Box::new(transport),
+            dir: dir.into(),
+        }
+    }
+
+    /// Gets the directory the exchanges are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn get(&self, url: &str) -> Result<TransportResponse, TransportError> {
+        let response = self.transport.get(url).await?;
+        let recording = Recording {
+            url: url.to_string(),
+            response,
+        };
+        fs::create_dir_all(&self.dir).await?;
+        let file_name = format!("{}.json", PctString::encode(url.chars(), URIReserved));
+        fs::write(
+            self.dir.join(file_name),
+            serde_json::to_string_pretty(&recording)?,
+        )
+        .await?;
+        Ok(recording.response)
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use test_dir::{DirBuilder, TestDir};
+
+    use crate::http::{MockTransport, Transport, TransportError, TransportResponse};
+
+    use super::RecordingTransport;
+
+    const URL: &str = "https://rdap.example.com/rdap/domain/foo.example";
+
+    #[tokio::test]
+    async fn GIVEN_recorded_exchange_WHEN_replay_from_dir_THEN_same_response() {
+        // GIVEN
+        let temp = TestDir::temp();
+        let response = TransportResponse::builder()
+            .url(URL)
+            .header("Content-Type", "application/rdap+json")
+            .body(r#"{"objectClassName":"domain","ldhName":"foo.example"}"#)
+            .build();
+        let recorder = RecordingTransport::new(
+            MockTransport::builder()
+                .response(URL, response.clone())
+                .build(),
+            temp.root(),
+        );
+        recorder.get(URL).await.expect("recording exchange");
+
+        // WHEN
+        let replayer = MockTransport::from_dir(temp.root()).expect("loading recordings");
+        let actual = replayer.get(URL).await.expect("replaying exchange");
+
+        // THEN
+        assert_eq!(actual, response);
+        assert_eq!(actual.header("content-type"), Some("application/rdap+json"));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_mock_without_url_WHEN_get_THEN_no_response() {
+        // GIVEN
+        let mock = MockTransport::default();
+
+        // WHEN
+        let actual = mock.get(URL).await;
+
+        // THEN
+        assert!(matches!(actual, Err(TransportError::NoResponse(url)) if url == URL));
+    }
+}
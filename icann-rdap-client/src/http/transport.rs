@@ -0,0 +1,124 @@
+//! The transport of HTTP requests.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
+
+use super::{ReqwestClient, ReqwestError};
+
+/// An HTTP response as returned by a [Transport].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransportResponse {
+    /// The HTTP status code.
+    pub status_code: u16,
+
+    /// The URL of the response, which differs from the URL of the request if redirects
+    /// were followed.
+    pub url: String,
+
+    /// The values of the headers of the response, keyed by lowercase name. A header
+    /// repeated in the response has each of its values, in the order received.
+    pub headers: HashMap<String, Vec<String>>,
+
+    /// The body of the response.
+    pub body: String,
+}
+
+#[buildstructor::buildstructor]
+impl TransportResponse {
+    #[builder]
+    pub fn new(
+        status_code: Option<u16>,
+        url: String,
+        headers: HashMap<String, String>,
+        body: Option<String>,
+    ) -> Self {
+        Self {
+            status_code: status_code.unwrap_or(200),
+            url,
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| (name.to_ascii_lowercase(), vec![value]))
+                .collect(),
+            body: body.unwrap_or_default(),
+        }
+    }
+
+    /// Gets the first value of a header by its case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name).first().map(String::as_str)
+    }
+
+    /// Gets the values of a header by its case-insensitive name, which are empty if the
+    /// header is not in the response.
+    pub fn header_values(&self, name: &str) -> &[String] {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Errors from a [Transport].
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error(transparent)]
+    Reqwest(#[from] ReqwestError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("No response for {0}")]
+    NoResponse(String),
+    #[error("Transport error: {0}")]
+    Other(String),
+}
+
+/// Sends HTTP GET requests. Implement this to issue RDAP requests with an HTTP stack
+/// other than Reqwest, or without a network at all.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Transport: Send + Sync {
+    /// Issues a GET request for the URL. Responses are returned whatever their status.
+    async fn get(&self, url: &str) -> Result<TransportResponse, TransportError>;
+}
+
+/// The default [Transport], which uses a Reqwest client.
+pub struct ReqwestTransport {
+    client: ReqwestClient,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: ReqwestClient) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for ReqwestTransport {
+    async fn get(&self, url: &str) -> Result<TransportResponse, TransportError> {
+        let response = self.client.get(url).send().await?;
+        debug!("HTTP version: {:?}", response.version());
+        let status_code = response.status().as_u16();
+        let url = response.url().to_string();
+        let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, value) in response.headers() {
+            if let Ok(value) = value.to_str() {
+                headers
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(value.to_string());
+            }
+        }
+        let body = response.text().await?;
+        Ok(TransportResponse {
+            status_code,
+            url,
+            headers,
+            body,
+        })
+    }
+}
@@ -1,22 +1,26 @@
 //! Wrapped Client.
 
+use std::sync::Arc;
+
 use icann_rdap_common::httpdata::HttpData;
 pub use reqwest::header::HeaderValue;
 use reqwest::header::{
-    ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_TYPE, EXPIRES, LOCATION, RETRY_AFTER,
-    STRICT_TRANSPORT_SECURITY,
+    HeaderName, ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, EXPIRES,
+    LOCATION, RETRY_AFTER, STRICT_TRANSPORT_SECURITY,
 };
 pub use reqwest::Client as ReqwestClient;
-pub use reqwest::Error as ReqwestError;
+use reqwest::Url;
 
 use super::create_reqwest_client;
+use super::transport::{ReqwestTransport, Transport, TransportError};
 use super::ReqwestClientConfig;
+use crate::rdap::ResponseCache;
 use crate::RdapClientError;
 
 #[cfg(not(target_arch = "wasm32"))]
 use {
     super::create_reqwest_client_with_addr, chrono::DateTime, chrono::Utc, reqwest::StatusCode,
-    std::net::SocketAddr, tracing::debug, tracing::info,
+    std::net::SocketAddr, tracing::info,
 };
 
 /// Used by the request functions.
@@ -126,22 +130,44 @@ impl ClientConfig {
     }
 }
 
-/// A wrapper around Reqwest client to give additional features when used with the request functions.
+/// A wrapper around an HTTP [Transport] to give additional features when used with the request functions.
+#[derive(Clone)]
 pub struct Client {
-    /// The reqwest client.
-    pub(crate) reqwest_client: ReqwestClient,
+    /// The transport of requests.
+    pub(crate) transport: Arc<dyn Transport>,
 
     /// Request options.
     pub(crate) request_options: RequestOptions,
+
+    /// The cache of RDAP responses, if responses are cached.
+    pub(crate) response_cache: Option<Arc<dyn ResponseCache>>,
 }
 
 impl Client {
     pub fn new(reqwest_client: ReqwestClient, request_options: RequestOptions) -> Self {
+        Self::new_with_transport(ReqwestTransport::new(reqwest_client), request_options)
+    }
+
+    /// Creates a client sending requests with a [Transport] other than Reqwest,
+    /// such as a [super::MockTransport].
+    pub fn new_with_transport(
+        transport: impl Transport + 'static,
+        request_options: RequestOptions,
+    ) -> Self {
         Self {
-            reqwest_client,
+            transport: Arc::new(transport),
             request_options,
+            response_cache: None,
         }
     }
+
+    /// Caches the responses of RDAP requests made with this client, such as with
+    /// [crate::rdap::rdap_bootstrapped_request()], in a [ResponseCache]. The cache may
+    /// be shared with other clients.
+    pub fn with_response_cache(mut self, response_cache: Arc<dyn ResponseCache>) -> Self {
+        self.response_cache = Some(response_cache);
+        self
+    }
 }
 
 /// Creates a wrapped HTTP client. The wrapped
@@ -153,6 +179,15 @@ pub fn create_client(config: &ClientConfig) -> Result<Client, RdapClientError> {
     Ok(Client::new(client, config.request_options))
 }
 
+/// Creates a wrapped HTTP client using a [Transport] other than Reqwest.
+/// The Reqwest settings of the configuration are not used.
+pub fn create_client_with_transport(
+    config: &ClientConfig,
+    transport: impl Transport + 'static,
+) -> Client {
+    Client::new_with_transport(transport, config.request_options)
+}
+
 /// Creates a wrapped HTTP client.
 /// This will direct the underlying client to connect to a specific socket.
 #[cfg(not(target_arch = "wasm32"))]
@@ -173,26 +208,22 @@ pub(crate) struct WrappedResponse {
 pub(crate) async fn wrapped_request(
     url: &str,
     client: &Client,
-) -> Result<WrappedResponse, ReqwestError> {
+) -> Result<WrappedResponse, TransportError> {
     // send request and loop for possible retries
     #[allow(unused_mut)] //because of wasm32 exclusion below
-    let mut response = client.reqwest_client.get(url).send().await?;
+    let mut response = client.transport.get(url).await?;
 
     // this doesn't work on wasm32 because tokio doesn't work on wasm
     #[cfg(not(target_arch = "wasm32"))]
     {
         let mut tries: u16 = 0;
         loop {
-            debug!("HTTP version: {:?}", response.version());
             // loop if HTTP 429
-            if matches!(response.status(), StatusCode::TOO_MANY_REQUESTS) {
-                let retry_after_header = response
-                    .headers()
-                    .get(RETRY_AFTER)
-                    .map(|value| value.to_str().unwrap().to_string());
+            if response.status_code == StatusCode::TOO_MANY_REQUESTS.as_u16() {
+                let retry_after_header = response.header(RETRY_AFTER.as_str());
                 let retry_after = if let Some(rt) = retry_after_header {
                     info!("Server says too many requests and to retry-after '{rt}'.");
-                    rt
+                    rt.to_string()
                 } else {
                     info!("Server says too many requests but does not offer 'retry-after' value.");
                     client.request_options.def_retry_secs.to_string()
@@ -228,7 +259,7 @@ pub(crate) async fn wrapped_request(
                     break;
                 } else {
                     // send the query again
-                    response = client.reqwest_client.get(url).send().await?;
+                    response = client.transport.get(url).await?;
                 }
 
             // else don't repeat the request
@@ -238,60 +269,36 @@ pub(crate) async fn wrapped_request(
         }
     }
 
-    // throw an error if not 200 OK
-    let response = response.error_for_status()?;
-
-    // get the response
-    let content_type = response
-        .headers()
-        .get(CONTENT_TYPE)
-        .map(|value| value.to_str().unwrap().to_string());
-    let expires = response
-        .headers()
-        .get(EXPIRES)
-        .map(|value| value.to_str().unwrap().to_string());
-    let cache_control = response
-        .headers()
-        .get(CACHE_CONTROL)
-        .map(|value| value.to_str().unwrap().to_string());
-    let location = response
-        .headers()
-        .get(LOCATION)
-        .map(|value| value.to_str().unwrap().to_string());
-    let access_control_allow_origin = response
-        .headers()
-        .get(ACCESS_CONTROL_ALLOW_ORIGIN)
-        .map(|value| value.to_str().unwrap().to_string());
-    let strict_transport_security = response
-        .headers()
-        .get(STRICT_TRANSPORT_SECURITY)
-        .map(|value| value.to_str().unwrap().to_string());
-    let retry_after = response
-        .headers()
-        .get(RETRY_AFTER)
-        .map(|value| value.to_str().unwrap().to_string());
-    let content_length = response.content_length();
-    let status_code = response.status().as_u16();
-    let url = response.url().to_owned();
-    let text = response.text().await?;
+    // the response is returned whatever its status, as RDAP error responses have bodies
+    // and repeated headers are combined into one value as in RFC 9110
+    let header = |name: HeaderName| {
+        let values = response.header_values(name.as_str());
+        (!values.is_empty()).then(|| values.join(", "))
+    };
+    let content_length = header(CONTENT_LENGTH).and_then(|length| length.parse().ok());
+    let url = Url::parse(&response.url)
+        .map_err(|e| TransportError::Other(format!("invalid response URL: {e}")))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| TransportError::Other(format!("response URL {url} has no host")))?
+        .to_owned();
 
     let http_data = HttpData::now()
-        .status_code(status_code)
-        .and_location(location)
+        .status_code(response.status_code)
+        .and_location(header(LOCATION))
         .and_content_length(content_length)
-        .and_content_type(content_type)
+        .and_content_type(header(CONTENT_TYPE))
         .scheme(url.scheme())
-        .host(
-            url.host_str()
-                .expect("URL has no host. This shouldn't happen.")
-                .to_owned(),
-        )
-        .and_expires(expires)
-        .and_cache_control(cache_control)
-        .and_access_control_allow_origin(access_control_allow_origin)
-        .and_strict_transport_security(strict_transport_security)
-        .and_retry_after(retry_after)
+        .host(host)
+        .and_expires(header(EXPIRES))
+        .and_cache_control(header(CACHE_CONTROL))
+        .and_access_control_allow_origin(header(ACCESS_CONTROL_ALLOW_ORIGIN))
+        .and_strict_transport_security(header(STRICT_TRANSPORT_SECURITY))
+        .and_retry_after(header(RETRY_AFTER))
         .build();
 
-    Ok(WrappedResponse { http_data, text })
+    Ok(WrappedResponse {
+        http_data,
+        text: response.body,
+    })
 }
@@ -292,46 +292,38 @@ pub async fn qtype_to_bootstrap_url<F>(
 where
     F: FnOnce(&IanaRegistryType),
 {
+    let reg_type =
+        qtype_to_registry_type(query_type).ok_or(RdapClientError::BootstrapUnavailable)?;
+    fetch_bootstrap(&reg_type, client, store, callback).await?;
     match query_type {
         QueryType::IpV4Addr(_) | QueryType::IpV4Cidr(_) => {
-            fetch_bootstrap(
-                &IanaRegistryType::RdapBootstrapIpv4,
-                client,
-                store,
-                callback,
-            )
-            .await?;
             Ok(store.get_ipv4_query_urls(query_type)?.preferred_url()?)
         }
         QueryType::IpV6Addr(_) | QueryType::IpV6Cidr(_) => {
-            fetch_bootstrap(
-                &IanaRegistryType::RdapBootstrapIpv6,
-                client,
-                store,
-                callback,
-            )
-            .await?;
             Ok(store.get_ipv6_query_urls(query_type)?.preferred_url()?)
         }
-        QueryType::AsNumber(_) => {
-            fetch_bootstrap(&IanaRegistryType::RdapBootstrapAsn, client, store, callback).await?;
-            Ok(store.get_autnum_query_urls(query_type)?.preferred_url()?)
-        }
-        QueryType::Domain(_) => {
-            fetch_bootstrap(&IanaRegistryType::RdapBootstrapDns, client, store, callback).await?;
-            Ok(store.get_domain_query_urls(query_type)?.preferred_url()?)
-        }
-        QueryType::Entity(_) => {
-            fetch_bootstrap(&IanaRegistryType::RdapObjectTags, client, store, callback).await?;
-            Ok(store
-                .get_entity_handle_query_urls(query_type)?
-                .preferred_url()?)
+        QueryType::AsNumber(_) => Ok(store.get_autnum_query_urls(query_type)?.preferred_url()?),
+        QueryType::Entity(_) => Ok(store
+            .get_entity_handle_query_urls(query_type)?
+            .preferred_url()?),
+        _ => Ok(store.get_domain_query_urls(query_type)?.preferred_url()?),
+    }
+}
+
+/// Gets the bootstrap registry of a [QueryType], or `None` if the query type cannot
+/// be bootstrapped.
+pub fn qtype_to_registry_type(query_type: &QueryType) -> Option<IanaRegistryType> {
+    match query_type {
+        QueryType::IpV4Addr(_) | QueryType::IpV4Cidr(_) => {
+            Some(IanaRegistryType::RdapBootstrapIpv4)
         }
-        QueryType::Nameserver(_) => {
-            fetch_bootstrap(&IanaRegistryType::RdapBootstrapDns, client, store, callback).await?;
-            Ok(store.get_domain_query_urls(query_type)?.preferred_url()?)
+        QueryType::IpV6Addr(_) | QueryType::IpV6Cidr(_) => {
+            Some(IanaRegistryType::RdapBootstrapIpv6)
         }
-        _ => Err(RdapClientError::BootstrapUnavailable),
+        QueryType::AsNumber(_) => Some(IanaRegistryType::RdapBootstrapAsn),
+        QueryType::Domain(_) | QueryType::Nameserver(_) => Some(IanaRegistryType::RdapBootstrapDns),
+        QueryType::Entity(_) => Some(IanaRegistryType::RdapObjectTags),
+        _ => None,
     }
 }
 
@@ -9,6 +9,7 @@ use thiserror::Error;
 
 use crate::http::wrapped_request;
 use crate::http::Client;
+use crate::http::TransportError;
 
 /// Response from getting an IANA registry.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,6 +26,19 @@ pub enum IanaResponseError {
     Reqwest(#[from] reqwest::Error),
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    #[error("IANA registry request failed with HTTP status {0}")]
+    HttpStatus(u16),
+    #[error(transparent)]
+    Transport(TransportError),
+}
+
+impl From<TransportError> for IanaResponseError {
+    fn from(value: TransportError) -> Self {
+        match value {
+            TransportError::Reqwest(e) => Self::Reqwest(e),
+            e => Self::Transport(e),
+        }
+    }
 }
 
 /// Issues the HTTP request to get an IANA registry.
@@ -37,6 +51,9 @@ pub async fn iana_request(
     let wrapped_response = wrapped_request(url, client).await?;
     let text = wrapped_response.text;
     let http_data = wrapped_response.http_data;
+    if !http_data.is_success() {
+        return Err(IanaResponseError::HttpStatus(http_data.status_code));
+    }
 
     let json: RdapBootstrapRegistry = serde_json::from_str(&text)?;
     Ok(IanaResponse {
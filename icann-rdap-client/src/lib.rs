@@ -10,6 +10,8 @@ use icann_rdap_common::{
 };
 use thiserror::Error;
 
+use crate::http::TransportError;
+
 pub mod gtld;
 pub mod http;
 pub mod iana;
@@ -27,6 +29,8 @@ pub mod prelude {
     #[doc(inline)]
     pub use crate::rdap::rdap_bootstrapped_request;
     #[doc(inline)]
+    pub use crate::rdap::rdap_referral_request;
+    #[doc(inline)]
     pub use crate::rdap::rdap_request;
     #[doc(inline)]
     pub use crate::rdap::rdap_url_request;
@@ -51,12 +55,18 @@ pub enum RdapClientError {
     #[error(transparent)]
     Client(#[from] reqwest::Error),
 
+    #[error(transparent)]
+    Transport(TransportError),
+
     #[error("Error parsing response")]
     ParsingError(Box<ParsingErrorInfo>),
 
     #[error(transparent)]
     Json(#[from] serde_json::Error),
 
+    #[error("Page of search results does not match the previous pages")]
+    UnexpectedPage,
+
     #[error("RwLock Poison Error")]
     Poison,
 
@@ -76,6 +86,16 @@ pub enum RdapClientError {
     DomainNameError(#[from] DomainNameError),
 }
 
+impl From<TransportError> for RdapClientError {
+    fn from(value: TransportError) -> Self {
+        match value {
+            // kept as client errors so that callers may inspect them
+            TransportError::Reqwest(e) => Self::Client(e),
+            e => Self::Transport(e),
+        }
+    }
+}
+
 impl<T> From<PoisonError<T>> for RdapClientError {
     fn from(_err: PoisonError<T>) -> Self {
         Self::Poison
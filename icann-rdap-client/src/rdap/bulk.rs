@@ -0,0 +1,466 @@
+//! Makes many RDAP requests concurrently.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{Stream, StreamExt};
+use reqwest::Url;
+use tokio::{
+    sync::{Mutex as AsyncMutex, Semaphore},
+    time::{sleep_until, Instant},
+};
+use tracing::debug;
+
+use crate::{
+    http::Client,
+    iana::bootstrap::{qtype_to_bootstrap_url, qtype_to_registry_type, BootstrapStore},
+    RdapClientError,
+};
+
+use super::{
+    qtype::QueryType,
+    request::{rdap_url_request, ResponseData},
+};
+
+/// Options for [rdap_bulk_request].
+#[derive(Clone, Copy, Debug)]
+pub struct BulkOptions {
+    pub(crate) max_concurrent: usize,
+    pub(crate) max_queued: usize,
+    pub(crate) max_concurrent_per_host: usize,
+    pub(crate) min_interval_per_host: Option<Duration>,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 64,
+            max_queued: 1024,
+            max_concurrent_per_host: 4,
+            min_interval_per_host: None,
+        }
+    }
+}
+
+#[buildstructor::buildstructor]
+impl BulkOptions {
+    /// Creates the options for a bulk request.
+    ///
+    /// * max_concurrent - the maximum number of requests sent at once. Defaults to 64.
+    /// * max_queued - the maximum number of queries taken from the stream at once, including
+    ///   those waiting for a busy server. Defaults to 1024, and is at least `max_concurrent`.
+    /// * max_concurrent_per_host - the maximum number of requests sent to one RDAP server at
+    ///   once. Defaults to 4.
+    /// * max_requests_per_sec_per_host - the maximum rate of requests sent to one RDAP server.
+    ///   Defaults to no limit.
+    #[builder]
+    pub fn new(
+        max_concurrent: Option<usize>,
+        max_queued: Option<usize>,
+        max_concurrent_per_host: Option<usize>,
+        max_requests_per_sec_per_host: Option<f64>,
+    ) -> Self {
+        let default = Self::default();
+        let max_concurrent = max_concurrent.unwrap_or(default.max_concurrent).max(1);
+        Self {
+            max_concurrent,
+            max_queued: max_queued.unwrap_or(default.max_queued).max(max_concurrent),
+            max_concurrent_per_host: max_concurrent_per_host
+                .unwrap_or(default.max_concurrent_per_host)
+                .max(1),
+            min_interval_per_host: max_requests_per_sec_per_host
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+        }
+    }
+}
+
+/// The outcome of one query of a bulk request.
+#[derive(Debug)]
+pub struct BulkResponse {
+    /// The query.
+    pub query_type: QueryType,
+
+    /// The response to the query, or the error making it.
+    pub result: Result<ResponseData, RdapClientError>,
+}
+
+/// Makes RDAP requests for a stream of queries using bootstrapping.
+///
+/// This function takes the following parameters:
+/// * queries - a [Stream] of the queries.
+/// * client - a reference to a [crate::http::Client].
+/// * store - a reference to a [BootstrapStore].
+/// * options - the [BulkOptions] limiting how many requests are made at once.
+///
+/// Queries are bootstrapped with `store` as with [super::rdap_bootstrapped_request] and
+/// sent concurrently. Requests to any one RDAP server are limited to the concurrency and
+/// rate set in `options`. Each request is retried on an HTTP 429 status as configured
+/// in the [crate::http::ClientConfig] of `client`.
+///
+/// The returned stream yields a [BulkResponse] for every query in the order the
+/// queries complete, which is not necessarily the order they were given. Queries
+/// waiting for a busy server count toward `max_queued` but not `max_concurrent`, so
+/// that they do not hold up the queries for other servers.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use icann_rdap_client::prelude::*;
+/// use icann_rdap_client::rdap::{rdap_bulk_request, BulkOptions};
+/// use std::str::FromStr;
+/// use tokio::main;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), RdapClientError> {
+///
+///     // create the queries
+///     let queries = vec![
+///         QueryType::from_str("192.168.0.1")?,
+///         QueryType::from_str("icann.org")?,
+///     ];
+///
+///     // create a client (from icann-rdap-common)
+///     let config = ClientConfig::default();
+///     let client = create_client(&config)?;
+///     let store = MemoryBootstrapStore::new();
+///
+///     // issue the RDAP queries, no more than 2 per second to each server
+///     let options = BulkOptions::builder()
+///         .max_requests_per_sec_per_host(2.0)
+///         .build();
+///     let mut responses = rdap_bulk_request(
+///         futures::stream::iter(queries),
+///         &client,
+///         &store,
+///         options,
+///     );
+///     while let Some(response) = responses.next().await {
+///         println!("{} -> {}", response.query_type, response.result.is_ok());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub fn rdap_bulk_request<'a, S>(
+    queries: S,
+    client: &'a Client,
+    store: &'a dyn BootstrapStore,
+    options: BulkOptions,
+) -> impl Stream<Item = BulkResponse> + 'a
+where
+    S: Stream<Item = QueryType> + 'a,
+{
+    let limits = Arc::new(HostLimits::new(options));
+    queries
+        .map(move |query_type| {
+            let limits = limits.clone();
+            async move {
+                let result = bulk_query(&query_type, client, store, &limits).await;
+                BulkResponse { query_type, result }
+            }
+        })
+        .buffer_unordered(options.max_queued)
+}
+
+async fn bulk_query(
+    query_type: &QueryType,
+    client: &Client,
+    store: &dyn BootstrapStore,
+    limits: &HostLimits,
+) -> Result<ResponseData, RdapClientError> {
+    let missing = qtype_to_registry_type(query_type)
+        .map(|reg_type| store.has_bootstrap_registry(&reg_type))
+        .transpose()?
+        .is_some_and(|has| !has);
+    let base_url = {
+        // one at a time when the IANA registry is missing from the store, so that it is
+        // fetched once rather than by every query started before it arrives
+        let _bootstrapping = if missing {
+            Some(limits.bootstrapping.lock().await)
+        } else {
+            None
+        };
+        qtype_to_bootstrap_url(client, store, query_type, |reg| {
+            debug!("Fetching IANA registry {}", reg.url())
+        })
+        .await?
+    };
+    let url = query_type.query_url(&base_url)?;
+    let host = Url::parse(&url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    let limit = limits.host_limit(&host);
+    let _permit = limit
+        .permits
+        .acquire()
+        .await
+        .expect("host semaphore is never closed");
+    if let Some(interval) = limits.min_interval {
+        let start = {
+            let mut next_request = limit.next_request.lock().await;
+            let start = (*next_request).max(Instant::now());
+            *next_request = start + interval;
+            start
+        };
+        sleep_until(start).await;
+    }
+    // the global slot is only taken once the server is ready for the request
+    let _slot = limits
+        .slots
+        .acquire()
+        .await
+        .expect("slot semaphore is never closed");
+    debug!("Requesting RDAP URL {url}");
+    rdap_url_request(&url, client).await
+}
+
+/// The limits on the requests to each host and on all requests.
+struct HostLimits {
+    max_concurrent: usize,
+    min_interval: Option<Duration>,
+    slots: Semaphore,
+    bootstrapping: AsyncMutex<()>,
+    hosts: Mutex<HashMap<String, Arc<HostLimit>>>,
+}
+
+struct HostLimit {
+    permits: Semaphore,
+    next_request: AsyncMutex<Instant>,
+}
+
+impl HostLimits {
+    fn new(options: BulkOptions) -> Self {
+        Self {
+            max_concurrent: options.max_concurrent_per_host,
+            min_interval: options.min_interval_per_host,
+            slots: Semaphore::new(options.max_concurrent),
+            bootstrapping: AsyncMutex::new(()),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn host_limit(&self, host: &str) -> Arc<HostLimit> {
+        let mut hosts = self.hosts.lock().expect("host limits lock poisoned");
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| {
+                Arc::new(HostLimit {
+                    permits: Semaphore::new(self.max_concurrent),
+                    next_request: AsyncMutex::new(Instant::now()),
+                })
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use futures::{stream, StreamExt};
+    use icann_rdap_common::{
+        httpdata::HttpData,
+        iana::{IanaRegistry, IanaRegistryType},
+        response::RdapResponse,
+    };
+    use tokio::time::Instant;
+
+    use crate::{
+        http::{create_client_with_transport, ClientConfig, MockTransport, TransportResponse},
+        iana::{BootstrapStore, MemoryBootstrapStore},
+        rdap::QueryType,
+    };
+
+    use super::{rdap_bulk_request, BulkOptions, BulkResponse};
+
+    fn store() -> MemoryBootstrapStore {
+        let store = MemoryBootstrapStore::new();
+        let bootstrap = r#"
+            {
+                "version": "1.0",
+                "publication": "2024-01-07T10:11:12Z",
+                "description": "Some text",
+                "services": [
+                  [["com"], ["https://rdap.example.com/rdap/"]],
+                  [["net"], ["https://rdap.example.net/rdap/"]]
+                ]
+            }
+        "#;
+        let iana =
+            serde_json::from_str::<IanaRegistry>(bootstrap).expect("cannot parse domain bootstrap");
+        store
+            .put_bootstrap_registry(
+                &IanaRegistryType::RdapBootstrapDns,
+                iana,
+                HttpData::example().build(),
+            )
+            .expect("put iana registry");
+        store
+    }
+
+    fn transport(domains: &[(&str, &str)]) -> MockTransport {
+        let mut transport = MockTransport::default();
+        for (base, domain) in domains {
+            let url = format!("{base}domain/{domain}");
+            transport.add_response(
+                &url,
+                TransportResponse::builder()
+                    .url(&url)
+                    .header("content-type", "application/rdap+json")
+                    .body(format!(
+                        r#"{{"objectClassName":"domain","ldhName":"{domain}"}}"#
+                    ))
+                    .build(),
+            );
+        }
+        transport
+    }
+
+    async fn bulk_request(
+        domains: &[&str],
+        transport: MockTransport,
+        options: BulkOptions,
+    ) -> Vec<BulkResponse> {
+        timed_bulk_request(domains, transport, options)
+            .await
+            .into_iter()
+            .map(|(response, _)| response)
+            .collect()
+    }
+
+    /// Makes a bulk request, giving each response with the time it took to arrive.
+    async fn timed_bulk_request(
+        domains: &[&str],
+        transport: MockTransport,
+        options: BulkOptions,
+    ) -> Vec<(BulkResponse, Duration)> {
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+        let store = store();
+        let queries = domains
+            .iter()
+            .map(|domain| QueryType::from_str(domain).expect("query"))
+            .collect::<Vec<_>>();
+        let start = Instant::now();
+        rdap_bulk_request(stream::iter(queries), &client, &store, options)
+            .map(|response| (response, start.elapsed()))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn GIVEN_queries_for_two_servers_WHEN_bulk_request_THEN_all_answered() {
+        // GIVEN
+        let transport = transport(&[
+            ("https://rdap.example.com/rdap/", "foo.com"),
+            ("https://rdap.example.com/rdap/", "bar.com"),
+            ("https://rdap.example.net/rdap/", "foo.net"),
+        ]);
+
+        // WHEN
+        let actual = bulk_request(
+            &["foo.com", "bar.com", "foo.net"],
+            transport,
+            BulkOptions::default(),
+        )
+        .await;
+
+        // THEN
+        assert_eq!(actual.len(), 3);
+        for response in actual {
+            let QueryType::Domain(domain) = &response.query_type else {
+                panic!("not a domain query")
+            };
+            let RdapResponse::Domain(rdap) = response.result.expect("response").rdap else {
+                panic!("not a domain")
+            };
+            assert_eq!(rdap.ldh_name.as_deref(), Some(domain.to_ascii()));
+        }
+    }
+
+    #[tokio::test]
+    async fn GIVEN_query_without_bootstrap_WHEN_bulk_request_THEN_only_that_query_fails() {
+        // GIVEN
+        let transport = transport(&[("https://rdap.example.com/rdap/", "foo.com")]);
+
+        // WHEN
+        let actual = bulk_request(&["foo.com", "foo.org"], transport, BulkOptions::default()).await;
+
+        // THEN
+        assert_eq!(actual.len(), 2);
+        for response in actual {
+            let QueryType::Domain(domain) = &response.query_type else {
+                panic!("not a domain query")
+            };
+            assert_eq!(response.result.is_ok(), domain.to_ascii() == "foo.com");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn GIVEN_rate_limit_WHEN_bulk_request_to_one_server_THEN_requests_spaced() {
+        // GIVEN
+        let transport = transport(&[
+            ("https://rdap.example.com/rdap/", "foo.com"),
+            ("https://rdap.example.com/rdap/", "bar.com"),
+            ("https://rdap.example.com/rdap/", "baz.com"),
+        ]);
+        let options = BulkOptions::builder()
+            .max_requests_per_sec_per_host(20.0)
+            .build();
+
+        // WHEN
+        let actual =
+            timed_bulk_request(&["foo.com", "bar.com", "baz.com"], transport, options).await;
+
+        // THEN
+        assert_eq!(actual.len(), 3);
+        assert!(actual.iter().all(|(response, _)| response.result.is_ok()));
+        // the clock is paused, so the requests are exactly 50ms apart.
+        let elapsed = actual
+            .iter()
+            .map(|(_, elapsed)| elapsed.as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(elapsed, vec![0, 50, 100]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn GIVEN_busy_server_WHEN_bulk_request_THEN_other_server_not_held_up() {
+        // GIVEN
+        let transport = transport(&[
+            ("https://rdap.example.com/rdap/", "foo.com"),
+            ("https://rdap.example.com/rdap/", "bar.com"),
+            ("https://rdap.example.net/rdap/", "foo.net"),
+        ]);
+        let options = BulkOptions::builder()
+            .max_concurrent(1)
+            .max_requests_per_sec_per_host(2.0)
+            .build();
+
+        // WHEN
+        let actual =
+            timed_bulk_request(&["foo.com", "bar.com", "foo.net"], transport, options).await;
+
+        // THEN
+        let elapsed = actual
+            .iter()
+            .map(|(response, elapsed)| {
+                let QueryType::Domain(domain) = &response.query_type else {
+                    panic!("not a domain query")
+                };
+                (domain.to_ascii().to_string(), elapsed.as_millis())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            elapsed,
+            vec![
+                ("foo.com".to_string(), 0),
+                ("foo.net".to_string(), 0),
+                ("bar.com".to_string(), 500),
+            ]
+        );
+    }
+}
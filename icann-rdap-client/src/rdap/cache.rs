@@ -0,0 +1,407 @@
+//! Caches of RDAP responses.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use icann_rdap_common::{httpdata::HttpData, response::GetSelfLink};
+use pct_str::{PctString, URIReserved};
+use reqwest::Url;
+use tracing::debug;
+
+use crate::RdapClientError;
+
+use super::ResponseData;
+
+/// Defines a trait for things that cache RDAP responses by URL.
+///
+/// Implementations only keep responses. Which responses are cached is decided by the
+/// request functions, which cache successful responses the server allows to be cached.
+pub trait ResponseCache: Send + Sync {
+    /// Gets the response cached for a URL.
+    ///
+    /// This method should return `Ok(None)` if there is no response for the URL or if
+    /// the response has expired.
+    fn get_response(&self, url: &str) -> Result<Option<ResponseData>, RdapClientError>;
+
+    /// Puts the response for a URL into the cache.
+    fn put_response(&self, url: &str, response: &ResponseData) -> Result<(), RdapClientError>;
+}
+
+/// Puts a response into a cache under the URL of the request and the self link of the
+/// response, unless it is an error response or the server does not allow it to be cached.
+/// The response is only cached under a self link of the same scheme and host as the URL
+/// of the request, so that a server cannot answer for the objects of another server.
+pub(crate) fn cache_response(
+    cache: &dyn ResponseCache,
+    url: &str,
+    response: &ResponseData,
+) -> Result<(), RdapClientError> {
+    if !response.http_data.is_success() {
+        return Ok(());
+    }
+    if !response.http_data.should_cache() {
+        debug!("Not caching data according to server policy.");
+        debug!("Expires header: {:?}", &response.http_data.expires);
+        debug!(
+            "Cache-control header: {:?}",
+            &response.http_data.cache_control
+        );
+        return Ok(());
+    }
+    debug!("Caching response for {url}");
+    cache.put_response(url, response)?;
+    if let Some(self_link_href) = response
+        .rdap
+        .get_self_link()
+        .and_then(|self_link| self_link.href.as_ref())
+    {
+        if url != self_link_href && same_origin(url, self_link_href) {
+            debug!("Caching object with self link {self_link_href}");
+            cache.put_response(self_link_href, response)?;
+        }
+    }
+    Ok(())
+}
+
+/// Determines if two URLs have the same scheme, host and port.
+fn same_origin(url: &str, other: &str) -> bool {
+    match (Url::parse(url), Url::parse(other)) {
+        (Ok(url), Ok(other)) => url.origin() == other.origin(),
+        _ => false,
+    }
+}
+
+/// This implementation of [ResponseCache] keeps responses in memory. When it holds its
+/// capacity of responses or of bytes, the least recently used responses are evicted to
+/// make room.
+pub struct MemoryResponseCache {
+    capacity: usize,
+    max_bytes: usize,
+    max_age: i64,
+    entries: Mutex<LruEntries>,
+}
+
+/// Responses by URL with their sizes, along with the order in which they were last used.
+#[derive(Default)]
+struct LruEntries {
+    clock: u64,
+    bytes: usize,
+    responses: HashMap<String, (u64, usize, ResponseData)>,
+    uses: BTreeMap<u64, String>,
+}
+
+impl LruEntries {
+    fn remove(&mut self, url: &str) -> Option<(usize, ResponseData)> {
+        let (used, size, response) = self.responses.remove(url)?;
+        self.uses.remove(&used);
+        self.bytes -= size;
+        Some((size, response))
+    }
+
+    fn insert(&mut self, url: &str, size: usize, response: ResponseData) {
+        self.clock += 1;
+        self.uses.insert(self.clock, url.to_string());
+        self.bytes += size;
+        self.responses
+            .insert(url.to_string(), (self.clock, size, response));
+    }
+}
+
+impl MemoryResponseCache {
+    /// Creates a cache holding at most `capacity` responses and `max_bytes` bytes of
+    /// responses, each for at most `max_age` seconds. The size of a response is that of
+    /// its JSON.
+    pub fn new(capacity: usize, max_bytes: usize, max_age: i64) -> Self {
+        Self {
+            capacity,
+            max_bytes,
+            max_age,
+            entries: Mutex::new(LruEntries::default()),
+        }
+    }
+
+    /// Gets the number of responses in the cache, including expired ones.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .map_or(0, |entries| entries.responses.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCache for MemoryResponseCache {
+    fn get_response(&self, url: &str) -> Result<Option<ResponseData>, RdapClientError> {
+        let mut entries = self.entries.lock()?;
+        let Some((size, response)) = entries.remove(url) else {
+            return Ok(None);
+        };
+        if response.http_data.is_expired(self.max_age) {
+            return Ok(None);
+        }
+        entries.insert(url, size, response.clone());
+        Ok(Some(response))
+    }
+
+    fn put_response(&self, url: &str, response: &ResponseData) -> Result<(), RdapClientError> {
+        let size = serde_json::to_vec(response)?.len();
+        if self.capacity == 0 || size > self.max_bytes {
+            return Ok(());
+        }
+        let mut entries = self.entries.lock()?;
+        entries.remove(url);
+        entries.insert(url, size, response.clone());
+        while entries.responses.len() > self.capacity || entries.bytes > self.max_bytes {
+            let Some(lru_url) = entries.uses.values().next().cloned() else {
+                break;
+            };
+            entries.remove(&lru_url);
+        }
+        Ok(())
+    }
+}
+
+/// This implementation of [ResponseCache] keeps each response in a file of a directory.
+/// The file is named after the percent-encoded URL, and holds the [HttpData] of the
+/// response followed by the response.
+pub struct FileResponseCache {
+    dir: PathBuf,
+    max_age: i64,
+}
+
+impl FileResponseCache {
+    /// Creates a cache keeping responses in `dir` for at most `max_age` seconds.
+    pub fn new(dir: impl Into<PathBuf>, max_age: i64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_age,
+        }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let file_name = format!("{}.cache", PctString::encode(url.chars(), URIReserved));
+        self.dir.join(file_name)
+    }
+}
+
+impl ResponseCache for FileResponseCache {
+    fn get_response(&self, url: &str) -> Result<Option<ResponseData>, RdapClientError> {
+        let path = self.path(url);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        let lines = contents.lines().map(str::to_string).collect::<Vec<_>>();
+        let cache_data = HttpData::from_lines(&lines)?;
+        if cache_data.0.is_expired(self.max_age) {
+            return Ok(None);
+        }
+        debug!("Returning response from cache file {}", path.display());
+        Ok(Some(serde_json::from_str(&cache_data.1.join(""))?))
+    }
+
+    fn put_response(&self, url: &str, response: &ResponseData) -> Result<(), RdapClientError> {
+        fs::create_dir_all(&self.dir)?;
+        let data = serde_json::to_string_pretty(response)?;
+        let cache_contents = response.http_data.to_lines(&data)?;
+        fs::write(self.path(url), cache_contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use icann_rdap_common::{
+        httpdata::HttpData,
+        response::{domain::Domain, types::Link, RdapResponse},
+    };
+    use test_dir::{DirBuilder, TestDir};
+
+    use crate::rdap::ResponseData;
+
+    use super::{cache_response, FileResponseCache, MemoryResponseCache, ResponseCache};
+
+    fn response(ldh_name: &str, http_data: HttpData) -> ResponseData {
+        let rdap = RdapResponse::Domain(
+            Domain::basic()
+                .ldh_name(ldh_name)
+                .link(
+                    Link::builder()
+                        .href(format!("https://rdap.example.com/domain/{ldh_name}"))
+                        .value(format!("https://rdap.example.com/domain/{ldh_name}"))
+                        .rel("self")
+                        .build(),
+                )
+                .build(),
+        );
+        ResponseData {
+            rdap_type: rdap.to_string(),
+            rdap,
+            http_data,
+        }
+    }
+
+    #[test]
+    fn GIVEN_full_memory_cache_WHEN_put_response_THEN_least_recently_used_evicted() {
+        // GIVEN
+        let cache = MemoryResponseCache::new(2, 1_000_000, 3600);
+        cache
+            .put_response("a", &response("a.example", HttpData::example().build()))
+            .expect("put a");
+        cache
+            .put_response("b", &response("b.example", HttpData::example().build()))
+            .expect("put b");
+        cache.get_response("a").expect("get a");
+
+        // WHEN
+        cache
+            .put_response("c", &response("c.example", HttpData::example().build()))
+            .expect("put c");
+
+        // THEN
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_response("a").expect("get a").is_some());
+        assert!(cache.get_response("b").expect("get b").is_none());
+        assert!(cache.get_response("c").expect("get c").is_some());
+    }
+
+    #[test]
+    fn GIVEN_memory_cache_of_bytes_WHEN_put_response_THEN_least_recently_used_evicted() {
+        // GIVEN
+        let a = response("a.example", HttpData::example().build());
+        let size = serde_json::to_vec(&a).expect("serializing").len();
+        let cache = MemoryResponseCache::new(10, size * 2, 3600);
+        cache.put_response("a", &a).expect("put a");
+        cache
+            .put_response("b", &response("b.example", HttpData::example().build()))
+            .expect("put b");
+
+        // WHEN
+        cache
+            .put_response("c", &response("c.example", HttpData::example().build()))
+            .expect("put c");
+
+        // THEN
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_response("a").expect("get a").is_none());
+    }
+
+    #[test]
+    fn GIVEN_response_larger_than_memory_cache_WHEN_put_response_THEN_not_cached() {
+        // GIVEN
+        let cache = MemoryResponseCache::new(10, 10, 3600);
+
+        // WHEN
+        cache
+            .put_response("a", &response("a.example", HttpData::example().build()))
+            .expect("put a");
+
+        // THEN
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn GIVEN_expired_response_WHEN_get_response_THEN_none() {
+        // GIVEN
+        let cache = MemoryResponseCache::new(10, 1_000_000, 3600);
+        let mut http_data = HttpData::example().build();
+        http_data.received = Utc::now() - Duration::hours(2);
+        cache
+            .put_response("a", &response("a.example", http_data))
+            .expect("put a");
+
+        // WHEN
+        let actual = cache.get_response("a").expect("get a");
+
+        // THEN
+        assert!(actual.is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn GIVEN_no_store_response_WHEN_cache_response_THEN_not_cached() {
+        // GIVEN
+        let cache = MemoryResponseCache::new(10, 1_000_000, 3600);
+        let http_data = HttpData::example().cache_control("no-store").build();
+
+        // WHEN
+        cache_response(&cache, "a", &response("a.example", http_data)).expect("caching");
+
+        // THEN
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn GIVEN_response_with_self_link_WHEN_cache_response_THEN_cached_by_url_and_self_link() {
+        // GIVEN
+        let cache = MemoryResponseCache::new(10, 1_000_000, 3600);
+        let url = "https://rdap.example.com/domain/A.EXAMPLE";
+
+        // WHEN
+        cache_response(
+            &cache,
+            url,
+            &response("a.example", HttpData::example().build()),
+        )
+        .expect("caching");
+
+        // THEN
+        assert!(cache.get_response(url).expect("get url").is_some());
+        assert!(cache
+            .get_response("https://rdap.example.com/domain/a.example")
+            .expect("get self link")
+            .is_some());
+    }
+
+    #[test]
+    fn GIVEN_file_cache_WHEN_put_and_get_response_THEN_same_response() {
+        // GIVEN
+        let temp = TestDir::temp();
+        let cache = FileResponseCache::new(temp.path("cache"), 3600);
+        let url = "https://rdap.example.com/domain/a.example";
+        let expected = response("a.example", HttpData::example().build());
+
+        // WHEN
+        cache.put_response(url, &expected).expect("put response");
+        let actual = cache.get_response(url).expect("get response");
+
+        // THEN
+        let actual = actual.expect("response not cached");
+        assert_eq!(actual.rdap, expected.rdap);
+        assert_eq!(actual.http_data, expected.http_data);
+        assert!(cache
+            .get_response("https://rdap.example.com/domain/b.example")
+            .expect("get other")
+            .is_none());
+    }
+
+    #[test]
+    fn GIVEN_self_link_to_other_host_WHEN_cache_response_THEN_cached_by_url_only() {
+        // GIVEN
+        let cache = MemoryResponseCache::new(10, 1_000_000, 3600);
+        let url = "https://rdap.example.net/domain/a.example";
+
+        // WHEN
+        cache_response(
+            &cache,
+            url,
+            &response("a.example", HttpData::example().build()),
+        )
+        .expect("caching");
+
+        // THEN
+        assert_eq!(cache.len(), 1);
+        assert!(cache
+            .get_response("https://rdap.example.com/domain/a.example")
+            .expect("get self link")
+            .is_none());
+    }
+}
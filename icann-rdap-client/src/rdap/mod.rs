@@ -1,15 +1,26 @@
 //! Code for managing RDAP queries.
 
+#[cfg(not(target_arch = "wasm32"))]
+#[doc(inline)]
+pub use bulk::*;
+#[doc(inline)]
+pub use cache::*;
 #[doc(inline)]
 pub use qtype::*;
 #[doc(inline)]
+pub use referral::*;
+#[doc(inline)]
 pub use registered_redactions::*;
 #[doc(inline)]
 pub use request::*;
 #[doc(inline)]
 pub use rr::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod bulk;
+pub(crate) mod cache;
 pub(crate) mod qtype;
+pub(crate) mod referral;
 pub(crate) mod registered_redactions;
 pub(crate) mod request;
 pub(crate) mod rr;
@@ -9,7 +9,7 @@ use icann_rdap_common::{check::string::StringCheck, dns_types::DomainName};
 use lazy_static::lazy_static;
 use pct_str::{PctString, URIReserved};
 use regex::Regex;
-use strum_macros::Display;
+use strum_macros::{Display, EnumString};
 
 use crate::RdapClientError;
 
@@ -64,6 +64,15 @@ pub enum QueryType {
     #[strum(serialize = "Nameserver IP Address Search")]
     NameserverIpSearch(IpAddr),
 
+    #[strum(serialize = "Domain Reverse Search")]
+    DomainReverseSearch(Vec<(ReverseSearchProperty, String)>),
+
+    #[strum(serialize = "Nameserver Reverse Search")]
+    NameserverReverseSearch(Vec<(ReverseSearchProperty, String)>),
+
+    #[strum(serialize = "Entity Reverse Search")]
+    EntityReverseSearch(Vec<(ReverseSearchProperty, String)>),
+
     #[strum(serialize = "Server Help Lookup")]
     Help,
 
@@ -120,10 +129,19 @@ impl QueryType {
             Self::DomainNsIpSearch(value) => {
                 search_query(&value.to_string(), "domains?nsIp", base_url)
             }
-            Self::NameserverNameSearch(value) => search_query(value, "nameserver?name=", base_url),
+            Self::NameserverNameSearch(value) => search_query(value, "nameservers?name", base_url),
             Self::NameserverIpSearch(value) => {
                 search_query(&value.to_string(), "nameservers?ip", base_url)
             }
+            Self::DomainReverseSearch(conditions) => {
+                reverse_search_query(conditions, "domains", base_url)
+            }
+            Self::NameserverReverseSearch(conditions) => {
+                reverse_search_query(conditions, "nameservers", base_url)
+            }
+            Self::EntityReverseSearch(conditions) => {
+                reverse_search_query(conditions, "entities", base_url)
+            }
             Self::Help => Ok(format!("{base_url}/help")),
             Self::Url(url) => Ok(url.to_owned()),
         }
@@ -194,6 +212,81 @@ impl QueryType {
         let value = IpAddr::from_str(ip).map_err(|_e| RdapClientError::InvalidQueryValue)?;
         Ok(Self::NameserverIpSearch(value))
     }
+
+    /// Creates a reverse search for domains from conditions such as "fn=Bob*&role=registrant".
+    pub fn domain_reverse_search(conditions: &str) -> Result<QueryType, RdapClientError> {
+        Ok(Self::DomainReverseSearch(parse_reverse_search(conditions)?))
+    }
+
+    /// Creates a reverse search for nameservers from conditions such as "handle=FOO-1".
+    pub fn ns_reverse_search(conditions: &str) -> Result<QueryType, RdapClientError> {
+        Ok(Self::NameserverReverseSearch(parse_reverse_search(
+            conditions,
+        )?))
+    }
+
+    /// Creates a reverse search for entities from conditions such as "email=bob@example.com".
+    pub fn entity_reverse_search(conditions: &str) -> Result<QueryType, RdapClientError> {
+        Ok(Self::EntityReverseSearch(parse_reverse_search(conditions)?))
+    }
+}
+
+/// The properties of a related entity that may be used in reverse searches (RFC 9536).
+#[derive(Display, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReverseSearchProperty {
+    #[strum(serialize = "fn")]
+    FullName,
+
+    #[strum(serialize = "handle")]
+    Handle,
+
+    #[strum(serialize = "email")]
+    Email,
+
+    #[strum(serialize = "role")]
+    Role,
+}
+
+/// Parses reverse search conditions in the form of "property=value" pairs separated by '&'.
+fn parse_reverse_search(
+    conditions: &str,
+) -> Result<Vec<(ReverseSearchProperty, String)>, RdapClientError> {
+    let conditions = conditions
+        .split('&')
+        .map(|condition| {
+            let (property, value) = condition
+                .split_once('=')
+                .ok_or(RdapClientError::InvalidQueryValue)?;
+            let property = ReverseSearchProperty::from_str(property.trim())
+                .map_err(|_e| RdapClientError::InvalidQueryValue)?;
+            Ok((property, value.trim().to_string()))
+        })
+        .collect::<Result<Vec<_>, RdapClientError>>()?;
+    if conditions
+        .iter()
+        .all(|(property, _)| *property == ReverseSearchProperty::Role)
+    {
+        return Err(RdapClientError::InvalidQueryValue);
+    }
+    Ok(conditions)
+}
+
+fn reverse_search_query(
+    conditions: &[(ReverseSearchProperty, String)],
+    path: &str,
+    base_url: &str,
+) -> Result<String, RdapClientError> {
+    let query = conditions
+        .iter()
+        .map(|(property, value)| {
+            format!(
+                "{property}={}",
+                PctString::encode(value.chars(), URIReserved)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&");
+    Ok(format!("{base_url}/{path}/reverse_search/entity?{query}"))
 }
 
 fn search_query(value: &str, path_query: &str, base_url: &str) -> Result<String, RdapClientError> {
@@ -466,4 +559,46 @@ mod tests {
         // THEN
         assert_eq!(q.unwrap().to_string(), expected)
     }
+
+    #[rstest]
+    #[case(
+        "fn=Bob Smurd&role=registrant",
+        "https://example.com/rdap/domains/reverse_search/entity?fn=Bob%20Smurd&role=registrant"
+    )]
+    #[case(
+        "handle=FOO*",
+        "https://example.com/rdap/domains/reverse_search/entity?handle=FOO%2A"
+    )]
+    fn GIVEN_reverse_search_conditions_WHEN_domain_reverse_search_THEN_url_is_correct(
+        #[case] conditions: &str,
+        #[case] expected: &str,
+    ) {
+        // GIVEN
+        let q = QueryType::domain_reverse_search(conditions).expect("invalid conditions");
+
+        // WHEN
+        let actual = q
+            .query_url("https://example.com/rdap/")
+            .expect("invalid url");
+
+        // THEN
+        assert_eq!(actual, expected)
+    }
+
+    #[rstest]
+    #[case("role=registrant")]
+    #[case("foo=bar")]
+    #[case("fn")]
+    #[case("")]
+    fn GIVEN_bad_reverse_search_conditions_WHEN_entity_reverse_search_THEN_error(
+        #[case] conditions: &str,
+    ) {
+        // GIVEN case input
+
+        // WHEN
+        let q = QueryType::entity_reverse_search(conditions);
+
+        // THEN
+        assert!(q.is_err());
+    }
 }
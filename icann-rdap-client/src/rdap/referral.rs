@@ -0,0 +1,455 @@
+//! Follows the referrals of RDAP servers to other RDAP servers.
+
+use std::{collections::HashSet, sync::Arc};
+
+use icann_rdap_common::response::{get_related_links, GetSelfLink};
+use tracing::{debug, warn};
+
+use crate::{http::Client, RdapClientError};
+
+use super::{
+    qtype::QueryType,
+    request::{rdap_url_request, ResponseData},
+    rr::{RequestData, RequestResponse, RequestResponses, SourceType},
+};
+
+/// A response in a chain of referrals.
+#[derive(Clone, Debug)]
+pub struct Referral {
+    /// The URL that was requested.
+    pub url: String,
+
+    /// The type of the server that gave the response.
+    pub source_type: SourceType,
+
+    /// The response of the server.
+    pub response: ResponseData,
+}
+
+/// The responses from following the referrals of RDAP servers, starting
+/// with the response to the original query.
+///
+/// A domain query is referred from the registry to the registrar, and
+/// an IP address or autnum query from an RIR to an NIR or LIR. The last
+/// referral in the chain holds the most specific data.
+#[derive(Clone, Debug)]
+pub struct ReferralChain {
+    // never empty, as a chain is only created with its first response.
+    referrals: Vec<Referral>,
+    referral_error: Option<Arc<RdapClientError>>,
+}
+
+impl ReferralChain {
+    /// Creates a chain from the response to the original query.
+    pub fn new(first: Referral) -> Self {
+        Self {
+            referrals: vec![first],
+            referral_error: None,
+        }
+    }
+
+    /// Gets the responses in the order they were requested.
+    pub fn referrals(&self) -> &[Referral] {
+        &self.referrals
+    }
+
+    /// Gets the first response, which is the response to the original query.
+    pub fn first(&self) -> &Referral {
+        &self.referrals[0]
+    }
+
+    /// Gets the last response, which is the most specific response.
+    pub fn last(&self) -> &Referral {
+        &self.referrals[self.referrals.len() - 1]
+    }
+
+    /// Gets the error of the referral that could not be requested, such as that of an
+    /// unreachable server, after which the referrals were no longer followed.
+    pub fn referral_error(&self) -> Option<&RdapClientError> {
+        self.referral_error.as_deref()
+    }
+
+    /// Returns true if the original query was referred to another server.
+    pub fn has_referrals(&self) -> bool {
+        self.referrals.len() > 1
+    }
+
+    /// Gets the [RequestData] of each referral, numbered in the order they
+    /// were requested.
+    pub fn request_data(&self) -> Vec<RequestData<'_>> {
+        self.referrals
+            .iter()
+            .enumerate()
+            .map(|(i, referral)| RequestData {
+                req_number: i + 1,
+                source_host: &referral.response.http_data.host,
+                source_type: referral.source_type,
+            })
+            .collect()
+    }
+
+    /// Gets the chain as [RequestResponses], using the [RequestData] from [Self::request_data].
+    pub fn request_responses<'a>(
+        &'a self,
+        req_data: &'a [RequestData<'a>],
+    ) -> RequestResponses<'a> {
+        req_data
+            .iter()
+            .zip(self.referrals.iter())
+            .map(|(req_data, referral)| RequestResponse {
+                req_data,
+                res_data: &referral.response,
+                checks: referral.response.get_checks(),
+            })
+            .collect()
+    }
+}
+
+impl SourceType {
+    /// Gets the type of server at a depth of the referral chain of a query,
+    /// where the server answering the original query is at depth 0.
+    pub fn from_referral_depth(query_type: &QueryType, depth: usize) -> Self {
+        match query_type {
+            QueryType::Domain(_) | QueryType::DomainNameSearch(_) => match depth {
+                0 => Self::DomainRegistry,
+                _ => Self::DomainRegistrar,
+            },
+            QueryType::IpV4Addr(_)
+            | QueryType::IpV6Addr(_)
+            | QueryType::IpV4Cidr(_)
+            | QueryType::IpV6Cidr(_)
+            | QueryType::AsNumber(_) => match depth {
+                0 => Self::RegionalInternetRegistry,
+                1 => Self::NationalInternetRegistry,
+                _ => Self::LocalInternetRegistry,
+            },
+            _ => Self::UncategorizedRegistry,
+        }
+    }
+}
+
+/// Makes an RDAP request with a full RDAP URL and follows the referrals
+/// of the responses.
+///
+/// This function takes the following parameters:
+/// * url - a string reference of the URL
+/// * query_type - the query the URL is for, used to determine the [SourceType] of each response.
+/// * max_depth - the maximum number of referrals to follow.
+/// * client - a reference to a [crate::http::Client].
+///
+/// ```no_run
+/// use icann_rdap_client::prelude::*;
+/// use std::str::FromStr;
+/// use tokio::main;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), RdapClientError> {
+///
+///     // create a query
+///     let query = QueryType::from_str("192.168.0.1")?;
+///
+///     // create a client (from icann-rdap-common)
+///     let config = ClientConfig::default();
+///     let client = create_client(&config)?;
+///
+///     // issue the RDAP query and follow up to 3 referrals
+///     let chain =
+///         rdap_referral_request(
+///             &query.query_url("https://rdap-bootstrap.arin.net/bootstrap")?,
+///             &query,
+///             3,
+///             &client,
+///     ).await?;
+///     let most_specific = &chain.last().response;
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn rdap_referral_request(
+    url: &str,
+    query_type: &QueryType,
+    max_depth: usize,
+    client: &Client,
+) -> Result<ReferralChain, RdapClientError> {
+    let response = rdap_url_request(url, client).await?;
+    rdap_follow_referrals(url, response, query_type, max_depth, client).await
+}
+
+/// Follows the referrals of a response to a query.
+///
+/// This is [rdap_referral_request] for callers that have already made
+/// the first request, for instance to fetch all of its pages.
+///
+/// Each referral is the first `related` link with the RDAP media type that
+/// has not already been visited. Following stops when `max_depth` referrals
+/// have been followed, when a response has no unvisited referral, or when a
+/// server responds with an error. If a referral cannot be requested, the chain
+/// built so far is returned with the error in [ReferralChain::referral_error].
+pub async fn rdap_follow_referrals(
+    url: &str,
+    response: ResponseData,
+    query_type: &QueryType,
+    max_depth: usize,
+    client: &Client,
+) -> Result<ReferralChain, RdapClientError> {
+    let mut visited = HashSet::new();
+    visited.insert(url.to_string());
+    let mut chain = ReferralChain::new(Referral {
+        url: url.to_string(),
+        source_type: SourceType::from_referral_depth(query_type, 0),
+        response,
+    });
+    for depth in 1..=max_depth {
+        let response = &chain.last().response;
+        if !response.http_data.is_success() {
+            break;
+        }
+        // a server linking to itself under another URL is not a referral.
+        if let Some(href) = response
+            .rdap
+            .get_self_link()
+            .and_then(|link| link.href.as_ref())
+        {
+            visited.insert(href.to_string());
+        }
+        let Some(url) = get_related_links(&response.rdap)
+            .into_iter()
+            .find(|url| !visited.contains(*url))
+            .map(str::to_string)
+        else {
+            break;
+        };
+        debug!("Following referral to {url}");
+        let response = match rdap_url_request(&url, client).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Cannot follow referral to {url}: {e}");
+                chain.referral_error = Some(Arc::new(e));
+                break;
+            }
+        };
+        visited.insert(url.clone());
+        chain.referrals.push(Referral {
+            url,
+            source_type: SourceType::from_referral_depth(query_type, depth),
+            response,
+        });
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use std::str::FromStr;
+
+    use icann_rdap_common::response::RdapResponse;
+
+    use crate::{
+        http::{
+            create_client_with_transport, ClientConfig, MockTransport, TransportError,
+            TransportResponse,
+        },
+        rdap::{QueryType, SourceType},
+        RdapClientError,
+    };
+
+    use super::rdap_referral_request;
+
+    fn rdap(url: &str, body: &str) -> TransportResponse {
+        TransportResponse::builder()
+            .url(url)
+            .header("content-type", "application/rdap+json")
+            .body(body)
+            .build()
+    }
+
+    fn network(handle: &str, self_url: &str, related_url: Option<&str>) -> String {
+        let mut links = vec![format!(
+            r#"{{"value":"{self_url}","rel":"self","href":"{self_url}","type":"application/rdap+json"}}"#
+        )];
+        if let Some(related_url) = related_url {
+            links.push(format!(
+                r#"{{"value":"{self_url}","rel":"related","href":"{related_url}","type":"application/rdap+json"}}"#
+            ));
+        }
+        format!(
+            r#"{{"objectClassName":"ip network","handle":"{handle}","startAddress":"10.0.0.0","endAddress":"10.0.0.255","ipVersion":"v4","links":[{}]}}"#,
+            links.join(",")
+        )
+    }
+
+    #[tokio::test]
+    async fn GIVEN_rir_nir_lir_WHEN_referral_request_THEN_chain_of_three() {
+        // GIVEN
+        let rir = "https://rir.example/rdap/ip/10.0.0.1";
+        let nir = "https://nir.example/rdap/ip/10.0.0.1";
+        let lir = "https://lir.example/rdap/ip/10.0.0.1";
+        let transport = MockTransport::builder()
+            .response(rir, rdap(rir, &network("RIR", rir, Some(nir))))
+            .response(nir, rdap(nir, &network("NIR", nir, Some(lir))))
+            .response(lir, rdap(lir, &network("LIR", lir, None)))
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+        let query = QueryType::from_str("10.0.0.1").expect("query");
+
+        // WHEN
+        let actual = rdap_referral_request(rir, &query, 5, &client)
+            .await
+            .expect("referral request");
+
+        // THEN
+        assert_eq!(actual.referrals().len(), 3);
+        assert!(matches!(
+            actual.referrals()[0].source_type,
+            SourceType::RegionalInternetRegistry
+        ));
+        assert!(matches!(
+            actual.referrals()[1].source_type,
+            SourceType::NationalInternetRegistry
+        ));
+        assert!(matches!(
+            actual.referrals()[2].source_type,
+            SourceType::LocalInternetRegistry
+        ));
+        let RdapResponse::Network(network) = &actual.last().response.rdap else {
+            panic!("not a network")
+        };
+        assert_eq!(network.object_common.handle.as_deref(), Some("LIR"));
+        assert_eq!(actual.last().response.http_data.host, "lir.example");
+    }
+
+    #[tokio::test]
+    async fn GIVEN_referral_WHEN_max_depth_reached_THEN_chain_stops() {
+        // GIVEN
+        let rir = "https://rir.example/rdap/ip/10.0.0.1";
+        let nir = "https://nir.example/rdap/ip/10.0.0.1";
+        let lir = "https://lir.example/rdap/ip/10.0.0.1";
+        let transport = MockTransport::builder()
+            .response(rir, rdap(rir, &network("RIR", rir, Some(nir))))
+            .response(nir, rdap(nir, &network("NIR", nir, Some(lir))))
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+        let query = QueryType::from_str("10.0.0.1").expect("query");
+
+        // WHEN
+        let actual = rdap_referral_request(rir, &query, 1, &client)
+            .await
+            .expect("referral request");
+
+        // THEN
+        assert_eq!(actual.referrals().len(), 2);
+        assert_eq!(actual.last().url, nir);
+    }
+
+    #[tokio::test]
+    async fn GIVEN_unreachable_referral_WHEN_referral_request_THEN_chain_so_far() {
+        // GIVEN
+        let rir = "https://rir.example/rdap/ip/10.0.0.1";
+        let nir = "https://nir.example/rdap/ip/10.0.0.1";
+        let transport = MockTransport::builder()
+            .response(rir, rdap(rir, &network("RIR", rir, Some(nir))))
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+        let query = QueryType::from_str("10.0.0.1").expect("query");
+
+        // WHEN
+        let actual = rdap_referral_request(rir, &query, 5, &client)
+            .await
+            .expect("referral request");
+
+        // THEN
+        assert_eq!(actual.referrals().len(), 1);
+        assert_eq!(actual.last().url, rir);
+        assert!(matches!(
+            actual.referral_error(),
+            Some(RdapClientError::Transport(TransportError::NoResponse(url))) if url == nir
+        ));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_unreachable_server_WHEN_referral_request_THEN_error() {
+        // GIVEN
+        let rir = "https://rir.example/rdap/ip/10.0.0.1";
+        let client = create_client_with_transport(
+            &ClientConfig::default(),
+            MockTransport::builder().build(),
+        );
+        let query = QueryType::from_str("10.0.0.1").expect("query");
+
+        // WHEN
+        let actual = rdap_referral_request(rir, &query, 5, &client).await;
+
+        // THEN
+        assert!(actual.is_err());
+    }
+
+    #[tokio::test]
+    async fn GIVEN_referral_cycle_WHEN_referral_request_THEN_chain_stops() {
+        // GIVEN
+        let rir = "https://rir.example/rdap/ip/10.0.0.1";
+        let nir = "https://nir.example/rdap/ip/10.0.0.1";
+        let transport = MockTransport::builder()
+            .response(rir, rdap(rir, &network("RIR", rir, Some(nir))))
+            .response(nir, rdap(nir, &network("NIR", nir, Some(rir))))
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+        let query = QueryType::from_str("10.0.0.1").expect("query");
+
+        // WHEN
+        let actual = rdap_referral_request(rir, &query, 10, &client)
+            .await
+            .expect("referral request");
+
+        // THEN
+        assert_eq!(actual.referrals().len(), 2);
+        let req_data = actual.request_data();
+        let request_responses = actual.request_responses(&req_data);
+        assert_eq!(request_responses.len(), 2);
+        assert_eq!(request_responses[1].req_data.req_number, 2);
+        assert_eq!(request_responses[1].req_data.source_host, "nir.example");
+    }
+
+    #[tokio::test]
+    async fn GIVEN_domain_WHEN_referral_request_THEN_registry_and_registrar() {
+        // GIVEN
+        let registry = "https://registry.example/rdap/domain/foo.example";
+        let registrar = "https://registrar.example/rdap/domain/foo.example";
+        let transport = MockTransport::builder()
+            .response(
+                registry,
+                rdap(
+                    registry,
+                    &format!(
+                        r#"{{"objectClassName":"domain","ldhName":"foo.example","links":[{{"value":"{registry}","rel":"related","href":"{registrar}","type":"application/rdap+json"}}]}}"#
+                    ),
+                ),
+            )
+            .response(
+                registrar,
+                rdap(
+                    registrar,
+                    r#"{"objectClassName":"domain","ldhName":"foo.example"}"#,
+                ),
+            )
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+        let query = QueryType::from_str("foo.example").expect("query");
+
+        // WHEN
+        let actual = rdap_referral_request(registry, &query, 1, &client)
+            .await
+            .expect("referral request");
+
+        // THEN
+        assert!(actual.has_referrals());
+        assert!(matches!(
+            actual.first().source_type,
+            SourceType::DomainRegistry
+        ));
+        assert!(matches!(
+            actual.last().source_type,
+            SourceType::DomainRegistrar
+        ));
+    }
+}
@@ -1,8 +1,16 @@
 //! Functions to make RDAP requests.
 
-use icann_rdap_common::{httpdata::HttpData, iana::IanaRegistryType, response::RdapResponse};
+use std::collections::HashSet;
+
+use icann_rdap_common::{
+    check::{CheckParams, Checks, GetChecks},
+    httpdata::HttpData,
+    iana::IanaRegistryType,
+    response::{error::Error, RdapResponse},
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::{debug, warn};
 
 use crate::{
     http::{wrapped_request, Client},
@@ -10,7 +18,7 @@ use crate::{
     RdapClientError,
 };
 
-use super::qtype::QueryType;
+use super::{cache::cache_response, qtype::QueryType};
 
 /// Makes an RDAP request with a full RDAP URL.
 ///
@@ -18,6 +26,14 @@ use super::qtype::QueryType;
 /// * url - a string reference of the URL
 /// * client - a reference to a [reqwest::Client].
 ///
+/// If the client has a [super::ResponseCache], an unexpired response in the cache is
+/// returned in place of making the request, and the response to the request is cached.
+/// Errors of the cache are logged, and the request is made as if nothing was cached.
+///
+/// If the server responds with an HTTP status other than 2xx, the returned data holds an
+/// [RdapResponse::ErrorResponse]. It is the error response of the server when the server
+/// sends one, and otherwise an error response with the HTTP status as its error code.
+///
 /// ```no_run
 /// use icann_rdap_client::prelude::*;
 /// use std::str::FromStr;
@@ -41,11 +57,42 @@ use super::qtype::QueryType;
 /// }
 /// ```
 pub async fn rdap_url_request(url: &str, client: &Client) -> Result<ResponseData, RdapClientError> {
+    let Some(cache) = &client.response_cache else {
+        return rdap_uncached_url_request(url, client).await;
+    };
+    match cache.get_response(url) {
+        Ok(Some(response)) => {
+            debug!("Returning response for {url} from cache");
+            return Ok(response);
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Cannot get response for {url} from cache: {e}"),
+    }
+    let response = rdap_uncached_url_request(url, client).await?;
+    if let Err(e) = cache_response(cache.as_ref(), url, &response) {
+        warn!("Cannot cache response for {url}: {e}");
+    }
+    Ok(response)
+}
+
+async fn rdap_uncached_url_request(
+    url: &str,
+    client: &Client,
+) -> Result<ResponseData, RdapClientError> {
     let wrapped_response = wrapped_request(url, client).await?;
     // for convenience purposes
     let text = wrapped_response.text;
     let http_data = wrapped_response.http_data;
 
+    if !http_data.is_success() {
+        let rdap = error_response(&text, &http_data);
+        return Ok(ResponseData {
+            http_data,
+            rdap_type: rdap.to_string(),
+            rdap,
+        });
+    }
+
     let json: Result<Value, serde_json::Error> = serde_json::from_str(&text);
     if let Ok(rdap_json) = json {
         let rdap = RdapResponse::try_from(rdap_json)?;
@@ -65,6 +112,26 @@ pub async fn rdap_url_request(url: &str, client: &Client) -> Result<ResponseData
     }
 }
 
+/// Gets the error response of a non-2xx HTTP response. Bodies that are not RDAP error
+/// responses, such as the HTML pages of proxies, are replaced by an error response with
+/// the HTTP status as its error code.
+fn error_response(text: &str, http_data: &HttpData) -> RdapResponse {
+    let rdap = serde_json::from_str::<Value>(text)
+        .ok()
+        .and_then(|json| RdapResponse::try_from(json).ok());
+    if let Some(rdap @ RdapResponse::ErrorResponse(_)) = rdap {
+        return rdap;
+    }
+    let mut error = match &http_data.location {
+        Some(location) if (300..400).contains(&http_data.status_code) => {
+            Error::redirect().url(location).build()
+        }
+        _ => Error::basic().error_code(http_data.status_code).build(),
+    };
+    error.error_code = http_data.status_code;
+    RdapResponse::ErrorResponse(error)
+}
+
 /// Makes an RDAP request with a base URL.
 ///
 /// This function takes the following parameters:
@@ -123,6 +190,9 @@ pub async fn rdap_request(
 /// creating a new store for each request will result it fetching the appropriate IANA
 /// registry with each request which is most likely not the desired behavior.
 ///
+/// Likewise, responses are taken from and put into the [super::ResponseCache] of `client`,
+/// if it has one, so that repeated queries are not sent to the RDAP server.
+///
 /// ```no_run
 /// use icann_rdap_client::prelude::*;
 /// use std::str::FromStr;
@@ -166,6 +236,82 @@ where
     rdap_request(&base_url, query_type, client).await
 }
 
+/// Fetches all the pages of paged search results (RFC 8977).
+///
+/// This function follows the "next" links in the paging metadata of `response`,
+/// appending the search results of each page to those of `response`. The paging
+/// metadata of the returned data is that of the last page fetched.
+///
+/// ```no_run
+/// use icann_rdap_client::prelude::*;
+/// use icann_rdap_client::rdap::rdap_all_pages_request;
+/// use std::str::FromStr;
+/// use tokio::main;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), RdapClientError> {
+///
+///     // create a client (from icann-rdap-common)
+///     let config = ClientConfig::default();
+///     let client = create_client(&config)?;
+///
+///     // issue the RDAP query and fetch the remaining pages
+///     let response =
+///         rdap_url_request(
+///             "https://example.com/rdap/domains?name=example*.com",
+///             &client,
+///     ).await?;
+///     let response = rdap_all_pages_request(response, &client).await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn rdap_all_pages_request(
+    mut response: ResponseData,
+    client: &Client,
+) -> Result<ResponseData, RdapClientError> {
+    let mut fetched = HashSet::new();
+    while let Some(href) = response
+        .rdap
+        .get_paging_metadata()
+        .and_then(|paging| paging.next_link())
+        .and_then(|link| link.href.clone())
+    {
+        // guard against servers that link pages in a loop.
+        if !fetched.insert(href.clone()) {
+            break;
+        }
+        let next = rdap_url_request(&href, client).await?;
+        response.rdap = append_page(response.rdap, next.rdap)?;
+    }
+    Ok(response)
+}
+
+/// Appends the search results of a page to the search results of the previous pages.
+fn append_page(rdap: RdapResponse, page: RdapResponse) -> Result<RdapResponse, RdapClientError> {
+    match (rdap, page) {
+        (RdapResponse::DomainSearchResults(mut rdap), RdapResponse::DomainSearchResults(page)) => {
+            rdap.results.extend(page.results);
+            rdap.paging_metadata = page.paging_metadata;
+            Ok(RdapResponse::DomainSearchResults(rdap))
+        }
+        (
+            RdapResponse::NameserverSearchResults(mut rdap),
+            RdapResponse::NameserverSearchResults(page),
+        ) => {
+            rdap.results.extend(page.results);
+            rdap.paging_metadata = page.paging_metadata;
+            Ok(RdapResponse::NameserverSearchResults(rdap))
+        }
+        (RdapResponse::EntitySearchResults(mut rdap), RdapResponse::EntitySearchResults(page)) => {
+            rdap.results.extend(page.results);
+            rdap.paging_metadata = page.paging_metadata;
+            Ok(RdapResponse::EntitySearchResults(rdap))
+        }
+        _ => Err(RdapClientError::UnexpectedPage),
+    }
+}
+
 /// The data returned from an rdap request.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ResponseData {
@@ -173,3 +319,246 @@ pub struct ResponseData {
     pub rdap_type: String,
     pub http_data: HttpData,
 }
+
+impl ResponseData {
+    /// Gets the checks of the RDAP response and of the HTTP data.
+    pub fn get_checks(&self) -> Checks {
+        let check_params = CheckParams {
+            do_subchecks: true,
+            root: &self.rdap,
+            parent_type: self.rdap.get_type(),
+            allow_unreg_ext: false,
+        };
+        let mut checks = self.rdap.get_checks(check_params);
+        checks
+            .items
+            .append(&mut self.http_data.get_checks(check_params).items);
+        checks
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use std::sync::Arc;
+
+    use icann_rdap_common::{httpdata::HttpData, response::RdapResponse};
+
+    use crate::{
+        http::{create_client_with_transport, ClientConfig, MockTransport, TransportResponse},
+        rdap::{MemoryResponseCache, ResponseCache},
+        RdapClientError,
+    };
+
+    use super::{error_response, rdap_url_request, ResponseData};
+
+    /// A cache that cannot be read or written.
+    struct BrokenCache;
+
+    impl ResponseCache for BrokenCache {
+        fn get_response(&self, _url: &str) -> Result<Option<ResponseData>, RdapClientError> {
+            Err(RdapClientError::Poison)
+        }
+
+        fn put_response(
+            &self,
+            _url: &str,
+            _response: &ResponseData,
+        ) -> Result<(), RdapClientError> {
+            Err(RdapClientError::Poison)
+        }
+    }
+
+    fn http_data(status_code: u16, location: Option<&str>) -> HttpData {
+        HttpData::example()
+            .status_code(status_code)
+            .and_location(location.map(str::to_string))
+            .build()
+    }
+
+    #[test]
+    fn GIVEN_rdap_error_body_WHEN_error_response_THEN_body_returned() {
+        // GIVEN
+        let text = r#"{"errorCode":404,"title":"Not Found","description":["no such domain"]}"#;
+
+        // WHEN
+        let actual = error_response(text, &http_data(404, None));
+
+        // THEN
+        let RdapResponse::ErrorResponse(error) = actual else {
+            panic!("not an error response")
+        };
+        assert_eq!(error.error_code, 404);
+        assert_eq!(error.title.as_deref(), Some("Not Found"));
+        assert_eq!(error.description, Some(vec!["no such domain".to_string()]));
+    }
+
+    #[test]
+    fn GIVEN_html_body_WHEN_error_response_THEN_status_is_error_code() {
+        // GIVEN
+        let text = "<html><body>Bad Gateway</body></html>";
+
+        // WHEN
+        let actual = error_response(text, &http_data(502, None));
+
+        // THEN
+        let RdapResponse::ErrorResponse(error) = actual else {
+            panic!("not an error response")
+        };
+        assert_eq!(error.error_code, 502);
+        assert!(error.title.is_none());
+    }
+
+    #[test]
+    fn GIVEN_redirect_without_body_WHEN_error_response_THEN_redirect_with_location() {
+        // GIVEN
+        let location = "https://rdap.example.net/rdap/domain/foo.example";
+
+        // WHEN
+        let actual = error_response("", &http_data(301, Some(location)));
+
+        // THEN
+        let RdapResponse::ErrorResponse(error) = actual else {
+            panic!("not an error response")
+        };
+        assert_eq!(error.error_code, 301);
+        assert!(error.is_redirect());
+        let notices = error.common.notices.expect("no notices");
+        let links = notices[0].0.links.as_ref().expect("no links");
+        assert_eq!(links[0].href.as_deref(), Some(location));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_mock_transport_WHEN_rdap_url_request_THEN_response_replayed() {
+        // GIVEN
+        let url = "https://rdap.example.com/rdap/domain/foo.example";
+        let transport = MockTransport::builder()
+            .response(
+                url,
+                TransportResponse::builder()
+                    .url(url)
+                    .header("content-type", "application/rdap+json")
+                    .header("cache-control", "max-age=60")
+                    .body(r#"{"objectClassName":"domain","ldhName":"foo.example"}"#)
+                    .build(),
+            )
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+
+        // WHEN
+        let actual = rdap_url_request(url, &client).await.expect("request");
+
+        // THEN
+        let RdapResponse::Domain(domain) = actual.rdap else {
+            panic!("not a domain")
+        };
+        assert_eq!(domain.ldh_name.as_deref(), Some("foo.example"));
+        assert_eq!(actual.http_data.host, "rdap.example.com");
+        assert_eq!(actual.http_data.status_code, 200);
+        assert_eq!(
+            actual.http_data.cache_control.as_deref(),
+            Some("max-age=60")
+        );
+    }
+
+    #[tokio::test]
+    async fn GIVEN_cached_response_WHEN_rdap_url_request_THEN_response_from_cache() {
+        // GIVEN
+        let url = "https://rdap.example.com/rdap/domain/foo.example";
+        let transport = MockTransport::builder()
+            .response(
+                url,
+                TransportResponse::builder()
+                    .url(url)
+                    .body(r#"{"objectClassName":"domain","ldhName":"foo.example"}"#)
+                    .build(),
+            )
+            .build();
+        let cache = Arc::new(MemoryResponseCache::new(10, 1_000_000, 3600));
+        let client = create_client_with_transport(&ClientConfig::default(), transport)
+            .with_response_cache(cache.clone());
+        rdap_url_request(url, &client).await.expect("request");
+
+        // WHEN
+        // the transport of this client has no responses, so only the cache can answer
+        let client =
+            create_client_with_transport(&ClientConfig::default(), MockTransport::default())
+                .with_response_cache(cache);
+        let actual = rdap_url_request(url, &client)
+            .await
+            .expect("cached request");
+
+        // THEN
+        assert!(matches!(actual.rdap, RdapResponse::Domain(_)));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_broken_cache_WHEN_rdap_url_request_THEN_response_from_server() {
+        // GIVEN
+        let url = "https://rdap.example.com/rdap/domain/foo.example";
+        let transport = MockTransport::builder()
+            .response(
+                url,
+                TransportResponse::builder()
+                    .url(url)
+                    .body(r#"{"objectClassName":"domain","ldhName":"foo.example"}"#)
+                    .build(),
+            )
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport)
+            .with_response_cache(Arc::new(BrokenCache));
+
+        // WHEN
+        let actual = rdap_url_request(url, &client).await.expect("request");
+
+        // THEN
+        assert!(matches!(actual.rdap, RdapResponse::Domain(_)));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_repeated_header_WHEN_rdap_url_request_THEN_values_combined() {
+        // GIVEN
+        let url = "https://rdap.example.com/rdap/domain/foo.example";
+        let mut response = TransportResponse::builder()
+            .url(url)
+            .body(r#"{"objectClassName":"domain","ldhName":"foo.example"}"#)
+            .build();
+        response.headers.insert(
+            "cache-control".to_string(),
+            vec!["max-age=60".to_string(), "public".to_string()],
+        );
+        let transport = MockTransport::builder().response(url, response).build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+
+        // WHEN
+        let actual = rdap_url_request(url, &client).await.expect("request");
+
+        // THEN
+        assert_eq!(
+            actual.http_data.cache_control.as_deref(),
+            Some("max-age=60, public")
+        );
+    }
+
+    #[tokio::test]
+    async fn GIVEN_response_url_without_host_WHEN_rdap_url_request_THEN_error() {
+        // GIVEN
+        let url = "https://rdap.example.com/rdap/domain/foo.example";
+        let transport = MockTransport::builder()
+            .response(
+                url,
+                TransportResponse::builder()
+                    .url("data:text/plain,foo")
+                    .body(r#"{"objectClassName":"domain","ldhName":"foo.example"}"#)
+                    .build(),
+            )
+            .build();
+        let client = create_client_with_transport(&ClientConfig::default(), transport);
+
+        // WHEN
+        let actual = rdap_url_request(url, &client).await;
+
+        // THEN
+        assert!(actual.is_err());
+    }
+}
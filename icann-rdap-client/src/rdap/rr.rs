@@ -7,7 +7,7 @@ use strum_macros::Display;
 use crate::rdap::request::ResponseData;
 
 /// Types of RDAP servers.
-#[derive(Serialize, Deserialize, Display, Clone, Copy)]
+#[derive(Serialize, Deserialize, Display, Clone, Copy, Debug)]
 pub enum SourceType {
     #[strum(serialize = "Domain Registry")]
     DomainRegistry,
@@ -15,6 +15,8 @@ pub enum SourceType {
     DomainRegistrar,
     #[strum(serialize = "Regional Internet Registry")]
     RegionalInternetRegistry,
+    #[strum(serialize = "National Internet Registry")]
+    NationalInternetRegistry,
     #[strum(serialize = "Local Internet Registry")]
     LocalInternetRegistry,
     #[strum(serialize = "Uncategorized Registry")]
@@ -115,6 +115,11 @@ impl HttpData {
         false
     }
 
+    /// Returns true if the status code is 2xx.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
     pub fn should_cache(&self) -> bool {
         if let Some(cache_control) = &self.cache_control {
             return !cache_control
@@ -18,7 +18,7 @@ use self::{
     help::Help,
     nameserver::Nameserver,
     network::Network,
-    search::{DomainSearchResults, EntitySearchResults, NameserverSearchResults},
+    search::{DomainSearchResults, EntitySearchResults, NameserverSearchResults, PagingMetadata},
     types::{ExtensionId, Link, Links, RdapConformance},
 };
 
@@ -244,6 +244,21 @@ impl RdapResponse {
         }
     }
 
+    pub fn get_paging_metadata(&self) -> Option<&PagingMetadata> {
+        match self {
+            Self::DomainSearchResults(s) => s.paging_metadata.as_ref(),
+            Self::EntitySearchResults(s) => s.paging_metadata.as_ref(),
+            Self::NameserverSearchResults(s) => s.paging_metadata.as_ref(),
+            Self::Entity(_)
+            | Self::Domain(_)
+            | Self::Nameserver(_)
+            | Self::Autnum(_)
+            | Self::Network(_)
+            | Self::ErrorResponse(_)
+            | Self::Help(_) => None,
+        }
+    }
+
     pub fn has_extension_id(&self, extension_id: ExtensionId) -> bool {
         self.get_conformance().map_or(false, |conformance| {
             conformance.contains(&extension_id.to_extension())
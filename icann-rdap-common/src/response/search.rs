@@ -1,7 +1,12 @@
 use buildstructor::Builder;
 use serde::{Deserialize, Serialize};
 
-use super::{domain::Domain, entity::Entity, nameserver::Nameserver, types::Common};
+use super::{
+    domain::Domain,
+    entity::Entity,
+    nameserver::Nameserver,
+    types::{Common, Link, Links},
+};
 
 /// Represents RDAP domain search results.
 #[derive(Serialize, Deserialize, Builder, Clone, PartialEq, Debug, Eq)]
@@ -9,6 +14,15 @@ pub struct DomainSearchResults {
     #[serde(flatten)]
     pub common: Common,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paging_metadata: Option<PagingMetadata>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sorting_metadata: Option<SortingMetadata>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subsetting_metadata: Option<SubsettingMetadata>,
+
     #[serde(rename = "domainSearchResults")]
     pub results: Vec<Domain>,
 }
@@ -19,6 +33,9 @@ impl DomainSearchResults {
     pub fn new_empty() -> Self {
         Self {
             common: Common::builder().build(),
+            paging_metadata: None,
+            sorting_metadata: None,
+            subsetting_metadata: None,
             results: vec![],
         }
     }
@@ -30,6 +47,15 @@ pub struct NameserverSearchResults {
     #[serde(flatten)]
     pub common: Common,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paging_metadata: Option<PagingMetadata>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sorting_metadata: Option<SortingMetadata>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subsetting_metadata: Option<SubsettingMetadata>,
+
     #[serde(rename = "nameserverSearchResults")]
     pub results: Vec<Nameserver>,
 }
@@ -40,6 +66,9 @@ impl NameserverSearchResults {
     pub fn new_empty() -> Self {
         Self {
             common: Common::builder().build(),
+            paging_metadata: None,
+            sorting_metadata: None,
+            subsetting_metadata: None,
             results: vec![],
         }
     }
@@ -51,6 +80,15 @@ pub struct EntitySearchResults {
     #[serde(flatten)]
     pub common: Common,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paging_metadata: Option<PagingMetadata>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sorting_metadata: Option<SortingMetadata>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subsetting_metadata: Option<SubsettingMetadata>,
+
     #[serde(rename = "entitySearchResults")]
     pub results: Vec<Entity>,
 }
@@ -61,7 +99,144 @@ impl EntitySearchResults {
     pub fn new_empty() -> Self {
         Self {
             common: Common::builder().build(),
+            paging_metadata: None,
+            sorting_metadata: None,
+            subsetting_metadata: None,
             results: vec![],
         }
     }
 }
+
+/// Represents the RFC 8977 paging metadata of search results.
+#[derive(Serialize, Deserialize, Builder, Clone, PartialEq, Debug, Eq)]
+pub struct PagingMetadata {
+    #[serde(rename = "totalCount")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<u64>,
+
+    #[serde(rename = "pageSize")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u64>,
+
+    #[serde(rename = "pageNumber")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+}
+
+impl PagingMetadata {
+    /// Gets the link to the next page of results, if any.
+    pub fn next_link(&self) -> Option<&Link> {
+        self.links.as_ref()?.iter().find(|l| l.is_relation("next"))
+    }
+}
+
+/// Represents the RFC 8977 sorting metadata of search results.
+#[derive(Serialize, Deserialize, Builder, Clone, PartialEq, Debug, Eq)]
+pub struct SortingMetadata {
+    #[serde(rename = "currentSort")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_sort: Option<String>,
+
+    #[serde(rename = "availableSorts")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_sorts: Option<Vec<AvailableSort>>,
+}
+
+/// A sort property available to clients, as described in RFC 8977.
+#[derive(Serialize, Deserialize, Builder, Clone, PartialEq, Debug, Eq)]
+pub struct AvailableSort {
+    pub property: String,
+
+    #[serde(rename = "jsonPath")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+
+    pub default: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+}
+
+/// Represents the RFC 8982 subsetting metadata of search results.
+#[derive(Serialize, Deserialize, Builder, Clone, PartialEq, Debug, Eq)]
+pub struct SubsettingMetadata {
+    #[serde(rename = "currentFieldSet")]
+    pub current_field_set: String,
+
+    #[serde(rename = "availableFieldSets")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_field_sets: Option<Vec<FieldSet>>,
+}
+
+/// A field set available to clients, as described in RFC 8982.
+#[derive(Serialize, Deserialize, Builder, Clone, PartialEq, Debug, Eq)]
+pub struct FieldSet {
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    pub default: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use super::DomainSearchResults;
+
+    #[test]
+    fn GIVEN_paged_search_results_WHEN_deserialize_THEN_next_link_found() {
+        // GIVEN
+        let expected = r#"
+          {
+            "rdapConformance": [ "rdap_level_0", "paging", "sorting" ],
+            "paging_metadata": {
+              "totalCount": 73,
+              "pageSize": 50,
+              "pageNumber": 1,
+              "links": [
+                {
+                  "value": "https://example.com/rdap/domains?name=example*.com",
+                  "rel": "next",
+                  "href": "https://example.com/rdap/domains?name=example*.com&cursor=wJlCDLIl6KTWypN7T6vc6nWEmEYe99Hjf1XY1xmqV-M=",
+                  "type": "application/rdap+json"
+                }
+              ]
+            },
+            "sorting_metadata": {
+              "currentSort": "name",
+              "availableSorts": [
+                {
+                  "property": "registrationDate",
+                  "jsonPath": "$.domainSearchResults[*].events[?(@.eventAction==\"registration\")].eventDate",
+                  "default": false
+                }
+              ]
+            },
+            "domainSearchResults": []
+          }
+        "#;
+
+        // WHEN
+        let actual = serde_json::from_str::<DomainSearchResults>(expected);
+
+        // THEN
+        let actual = actual.unwrap();
+        let paging = actual.paging_metadata.as_ref().expect("no paging metadata");
+        assert_eq!(paging.total_count, Some(73));
+        assert!(paging.next_link().is_some());
+        let sorting = actual.sorting_metadata.expect("no sorting metadata");
+        assert_eq!(sorting.current_sort.as_deref(), Some("name"));
+        assert_eq!(
+            sorting.available_sorts.expect("no available sorts").len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Benchmarks of the transactions of the in-memory store loaded with data made by
+//! `rdap-srv-test-data`.
+//!
+//! The store is loaded with 1 million and then 10 million objects, most of them domains,
+//! with every search enabled. Each transaction changes one object of a kind. Other sizes
+//! can be given as a comma separated list in `RDAP_SRV_BENCH_OBJECTS`, such as
+//! `RDAP_SRV_BENCH_OBJECTS=100000 cargo bench`.
+//!
+//! For comparison, the `full_clone` group models the transactions from before the
+//! store kept its objects in persistent maps, when each transaction cloned the maps
+//! of all the objects. It only clones the domain, nameserver, and entity maps and
+//! leaves out the rebuilding of the search indexes on commit, so it understates the
+//! cost of those transactions.
+
+use std::{collections::HashMap, process::Command, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use icann_rdap_common::response::{
+    autnum::Autnum, domain::Domain, entity::Entity, nameserver::Nameserver, network::Network,
+    RdapResponse,
+};
+use icann_rdap_srv::{
+    config::{ServiceConfig, StorageType},
+    storage::{
+        data::load_data,
+        mem::{config::MemConfig, ops::Mem},
+        CommonConfig, StoreOps,
+    },
+};
+use test_dir::{DirBuilder, TestDir};
+use tokio::{runtime::Runtime, sync::RwLock};
+
+const DEFAULT_OBJECTS: &str = "1000000,10000000";
+
+fn object_counts() -> Vec<u32> {
+    std::env::var("RDAP_SRV_BENCH_OBJECTS")
+        .unwrap_or_else(|_| DEFAULT_OBJECTS.to_string())
+        .split(',')
+        .map(|count| {
+            count
+                .trim()
+                .parse()
+                .expect("parsing RDAP_SRV_BENCH_OBJECTS")
+        })
+        .collect()
+}
+
+/// Makes templates of the objects, of which 70% are domains, 10% each are nameservers
+/// and entities, and 5% each are IPv4 networks and autnums.
+fn make_data(objects: u32) -> TestDir {
+    let data_dir = TestDir::temp();
+    let status = Command::new(env!("CARGO_BIN_EXE_rdap-srv-test-data"))
+        .env_clear()
+        .arg("--data-dir")
+        .arg(data_dir.root())
+        .arg("--base-url")
+        .arg("http://localhost:3000/rdap")
+        .arg("--domains")
+        .arg((objects / 10 * 7).to_string())
+        .arg("--nameservers")
+        .arg((objects / 10).to_string())
+        .arg("--entities")
+        .arg((objects / 10).to_string())
+        .arg("--v4s")
+        .arg((objects / 20).to_string())
+        .arg("--autnums")
+        .arg((objects / 20).to_string())
+        .status()
+        .expect("running rdap-srv-test-data");
+    assert!(status.success(), "rdap-srv-test-data failed");
+    data_dir
+}
+
+async fn load_mem(data_dir: &TestDir) -> Mem {
+    let mem_config = MemConfig::builder()
+        .common_config(CommonConfig::default())
+        .build();
+    let mem = Mem::new(mem_config.clone());
+    mem.init().await.expect("initializing memory");
+    let service_config = ServiceConfig::non_server()
+        .data_dir(data_dir.root().to_string_lossy().to_string())
+        .storage_type(StorageType::Memory(mem_config))
+        .build()
+        .expect("building service config");
+    load_data(&service_config, &mem, false)
+        .await
+        .expect("loading data");
+    mem
+}
+
+fn mem_tx(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("creating runtime");
+    let domain = Domain::basic()
+        .ldh_name("test-domain-0.example")
+        .nameservers(vec![Nameserver::basic()
+            .ldh_name("ns.template.example")
+            .build()
+            .expect("building nameserver")])
+        .build();
+    let network = Network::basic()
+        .cidr("1.0.0.0/26")
+        .build()
+        .expect("building network");
+    let autnum = Autnum::basic().autnum_range(0..0).build();
+
+    let mut group = c.benchmark_group("mem_tx");
+    group.sample_size(10);
+    for objects in object_counts() {
+        let data_dir = make_data(objects);
+        let mem = runtime.block_on(load_mem(&data_dir));
+
+        group.bench_with_input(
+            BenchmarkId::new("update_domain", objects),
+            &mem,
+            |b, mem| {
+                b.to_async(&runtime).iter(|| async {
+                    let mut tx = mem.new_tx().await.expect("new transaction");
+                    tx.add_domain(&domain).await.expect("adding domain");
+                    tx.commit().await.expect("committing");
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("update_network", objects),
+            &mem,
+            |b, mem| {
+                b.to_async(&runtime).iter(|| async {
+                    let mut tx = mem.new_tx().await.expect("new transaction");
+                    tx.add_network(&network).await.expect("adding network");
+                    tx.commit().await.expect("committing");
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("update_autnum", objects),
+            &mem,
+            |b, mem| {
+                b.to_async(&runtime).iter(|| async {
+                    let mut tx = mem.new_tx().await.expect("new transaction");
+                    tx.add_autnum(&autnum).await.expect("adding autnum");
+                    tx.commit().await.expect("committing");
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("rollback", objects), &mem, |b, mem| {
+            b.to_async(&runtime).iter(|| async {
+                let tx = mem.new_tx().await.expect("new transaction");
+                tx.rollback().await.expect("rolling back");
+            })
+        });
+    }
+    group.finish();
+}
+
+/// The maps of objects by name, as the store kept them before they were persistent.
+#[derive(Clone, Default)]
+struct FullMaps {
+    domains: HashMap<String, Arc<RdapResponse>>,
+    nameservers: HashMap<String, Arc<RdapResponse>>,
+    entities: HashMap<String, Arc<RdapResponse>>,
+}
+
+/// Makes the maps with as many domains, nameservers, and entities as [make_data].
+fn make_full_maps(objects: u32) -> FullMaps {
+    let domain = Arc::new(RdapResponse::Domain(
+        Domain::basic().ldh_name("template.example").build(),
+    ));
+    let nameserver = Arc::new(RdapResponse::Nameserver(
+        Nameserver::basic()
+            .ldh_name("ns.template.example")
+            .build()
+            .expect("building nameserver"),
+    ));
+    let entity = Arc::new(RdapResponse::Entity(
+        Entity::basic().handle("TEMPLATE").build(),
+    ));
+    let mut maps = FullMaps::default();
+    for i in 0..objects / 10 * 7 {
+        maps.domains
+            .insert(format!("test-domain-{i}.example"), domain.clone());
+    }
+    for i in 0..objects / 10 {
+        maps.nameservers.insert(
+            format!("ns.test-nameserver-{i}.example"),
+            nameserver.clone(),
+        );
+        maps.entities
+            .insert(format!("test-entity-{i}"), entity.clone());
+    }
+    maps
+}
+
+fn full_clone(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("creating runtime");
+    let domain = Arc::new(RdapResponse::Domain(
+        Domain::basic().ldh_name("test-domain-0.example").build(),
+    ));
+
+    let mut group = c.benchmark_group("full_clone");
+    group.sample_size(10);
+    for objects in object_counts() {
+        let maps = Arc::new(RwLock::new(make_full_maps(objects)));
+
+        group.bench_with_input(
+            BenchmarkId::new("update_domain", objects),
+            &maps,
+            |b, maps| {
+                b.to_async(&runtime).iter(|| async {
+                    let mut tx = maps.read().await.clone();
+                    tx.domains
+                        .insert("test-domain-0.example".to_string(), domain.clone());
+                    *maps.write().await = tx;
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("rollback", objects), &maps, |b, maps| {
+            b.to_async(&runtime).iter(|| async {
+                let tx = maps.read().await.clone();
+                drop(tx);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, mem_tx, full_clone);
+criterion_main!(benches);
@@ -0,0 +1,98 @@
+-- RDAP objects are stored as JSON alongside the columns used to look them up.
+-- Errors (such as redirects) are stored in the same tables as the objects they stand in for.
+
+create table domain (
+    ldh_name text primary key,
+    unicode_name text,
+    rdap jsonb not null
+);
+
+create index domain_unicode_name_idx on domain (unicode_name);
+
+-- supports the prefix matching used by domain search by name
+create index domain_ldh_name_pattern_idx on domain (ldh_name text_pattern_ops);
+
+-- the names a domain is found by in searches: the LDH name and the U-label
+-- forms of the LDH and unicode names, kept lowercased for case-insensitive search
+create table domain_name (
+    name text not null,
+    ldh_name text not null,
+    primary key (name, ldh_name)
+);
+
+create index domain_name_name_idx on domain_name (name text_pattern_ops);
+
+create index domain_name_ldh_name_idx on domain_name (ldh_name);
+
+-- the nameservers of a domain, with names kept lowercased for case-insensitive search
+create table domain_nameserver (
+    ns_ldh_name text not null,
+    ldh_name text not null,
+    primary key (ns_ldh_name, ldh_name)
+);
+
+create index domain_nameserver_ns_ldh_name_idx on domain_nameserver (ns_ldh_name text_pattern_ops);
+
+create index domain_nameserver_ldh_name_idx on domain_nameserver (ldh_name);
+
+-- the addresses of the nameservers of a domain
+create table domain_ns_ip (
+    addr inet not null,
+    ldh_name text not null,
+    primary key (addr, ldh_name)
+);
+
+create index domain_ns_ip_ldh_name_idx on domain_ns_ip (ldh_name);
+
+-- full names ("fn") are kept lowercased for case-insensitive search
+create table entity (
+    handle text primary key,
+    full_name text,
+    rdap jsonb not null
+);
+
+create index entity_full_name_idx on entity (full_name text_pattern_ops);
+
+create table nameserver (
+    ldh_name text primary key,
+    rdap jsonb not null
+);
+
+-- the names a nameserver is found by in searches, as for domains
+create table nameserver_name (
+    name text not null,
+    ldh_name text not null,
+    primary key (name, ldh_name)
+);
+
+create index nameserver_name_name_idx on nameserver_name (name text_pattern_ops);
+
+create index nameserver_name_ldh_name_idx on nameserver_name (ldh_name);
+
+create table nameserver_ip (
+    addr inet not null,
+    ldh_name text not null,
+    primary key (addr, ldh_name)
+);
+
+create index nameserver_ip_ldh_name_idx on nameserver_ip (ldh_name);
+
+create table autnum (
+    start_autnum bigint not null,
+    end_autnum bigint not null,
+    rdap jsonb not null,
+    primary key (start_autnum, end_autnum)
+);
+
+-- a network that is not on a CIDR boundary is stored once per covering CIDR
+create table network (
+    cidr cidr primary key,
+    rdap jsonb not null
+);
+
+create index network_cidr_idx on network using gist (cidr inet_ops);
+
+create table srv_help (
+    host text primary key,
+    rdap jsonb not null
+);
@@ -0,0 +1,98 @@
+-- RDAP objects are stored as JSON alongside the columns used to look them up.
+-- Errors (such as redirects) are stored in the same tables as the objects they stand in for.
+
+create table domain (
+    ldh_name text primary key,
+    unicode_name text,
+    rdap text not null
+);
+
+create index domain_unicode_name_idx on domain (unicode_name);
+
+-- the names a domain is found by in searches: the LDH name and the U-label
+-- forms of the LDH and unicode names. names are kept lowercased, as sqlite only
+-- ignores the case of ASCII letters, and use nocase so that like searches can
+-- use the primary key
+create table domain_name (
+    name text not null collate nocase,
+    ldh_name text not null,
+    primary key (name, ldh_name)
+);
+
+create index domain_name_ldh_name_idx on domain_name (ldh_name);
+
+-- the nameservers of a domain
+create table domain_nameserver (
+    ns_ldh_name text not null collate nocase,
+    ldh_name text not null,
+    primary key (ns_ldh_name, ldh_name)
+);
+
+create index domain_nameserver_ldh_name_idx on domain_nameserver (ldh_name);
+
+-- the addresses of the nameservers of a domain, as in the network table
+create table domain_ns_ip (
+    addr blob not null,
+    ldh_name text not null,
+    primary key (addr, ldh_name)
+);
+
+create index domain_ns_ip_ldh_name_idx on domain_ns_ip (ldh_name);
+
+-- full names ("fn") are searched without regard to case
+create table entity (
+    handle text primary key,
+    full_name text collate nocase,
+    rdap text not null
+);
+
+create index entity_full_name_idx on entity (full_name);
+
+create table nameserver (
+    ldh_name text primary key,
+    rdap text not null
+);
+
+-- the names a nameserver is found by in searches, as for domains
+create table nameserver_name (
+    name text not null collate nocase,
+    ldh_name text not null,
+    primary key (name, ldh_name)
+);
+
+create index nameserver_name_ldh_name_idx on nameserver_name (ldh_name);
+
+-- addresses are as in the network table
+create table nameserver_ip (
+    addr blob not null,
+    ldh_name text not null,
+    primary key (addr, ldh_name)
+);
+
+create index nameserver_ip_ldh_name_idx on nameserver_ip (ldh_name);
+
+create table autnum (
+    start_autnum integer not null,
+    end_autnum integer not null,
+    rdap text not null,
+    primary key (start_autnum, end_autnum)
+);
+
+-- a network that is not on a CIDR boundary is stored once per covering CIDR.
+-- addresses are big-endian blobs (4 octets for v4, 16 for v6) so they compare
+-- in address order.
+create table network (
+    ip_version integer not null,
+    start_addr blob not null,
+    end_addr blob not null,
+    prefix_len integer not null,
+    rdap text not null,
+    primary key (ip_version, start_addr, prefix_len)
+);
+
+create index network_range_idx on network (ip_version, start_addr, end_addr);
+
+create table srv_help (
+    host text primary key,
+    rdap text not null
+);
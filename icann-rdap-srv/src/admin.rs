@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::put,
+    Router,
+};
+use http::{header::WWW_AUTHENTICATE, HeaderValue, StatusCode};
+use tracing::{debug, info};
+
+use crate::{
+    auth::{Authenticator, Principal},
+    error::RdapServerError,
+    rdap::response::{ResponseUtil, BAD_REQUEST, FORBIDDEN, UNAUTHORIZED},
+    server::DynServiceState,
+    storage::data::{Document, ObjectId},
+};
+
+/// Who may use the admin API.
+pub(crate) struct AdminAccess {
+    pub(crate) authenticator: Arc<Authenticator>,
+    pub(crate) role: String,
+}
+
+/// The admin API for changing the objects in storage.
+///
+/// `PUT /objects` adds or replaces the objects of a document, and `DELETE /objects`
+/// removes them. A document is either an RDAP object, as in the `.json` files of the
+/// data directory, or a template, as in the `.template` files. All the objects of a
+/// document are changed in one transaction.
+///
+/// Changes are not written to the data directory. Postgres and SQLite storage is then
+/// managed by the admin API alone, while changes to memory storage, which is loaded from
+/// the data directory, last only until the server is restarted.
+pub(crate) fn admin_router() -> Router<DynServiceState> {
+    Router::new().route("/objects", put(put_objects).delete(delete_objects))
+}
+
+/// Middleware rejecting requests from clients without the admin role, which must be
+/// layered inside the authentication middleware.
+pub(crate) async fn authorize(
+    State(access): State<Arc<AdminAccess>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let principal = request
+        .extensions()
+        .get::<Principal>()
+        .cloned()
+        .unwrap_or_default();
+    if principal.name.is_none() {
+        let mut response = UNAUTHORIZED.response();
+        if let Ok(challenge) = HeaderValue::from_str(&access.authenticator.challenge()) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+        }
+        return response;
+    }
+    if !principal.has_any_role(std::slice::from_ref(&access.role)) {
+        return FORBIDDEN.response();
+    }
+    next.run(request).await
+}
+
+/// Parses a document, or gets `None` for a document that cannot be stored.
+fn parse_document(body: &[u8]) -> Option<(Document, Vec<ObjectId>)> {
+    let checked = Document::parse(body).and_then(|document| {
+        let ids = document.object_ids()?;
+        Ok((document, ids))
+    });
+    match checked {
+        Ok((_, ids)) if ids.is_empty() => {
+            debug!("admin document has no objects");
+            None
+        }
+        Ok(checked) => Some(checked),
+        Err(e) => {
+            debug!("bad admin document: {e}");
+            None
+        }
+    }
+}
+
+/// Adds or replaces the objects of a document.
+#[tracing::instrument(level = "debug", skip(body))]
+pub(crate) async fn put_objects(
+    state: State<DynServiceState>,
+    body: Bytes,
+) -> Result<Response, RdapServerError> {
+    let Some((document, ids)) = parse_document(&body) else {
+        return Ok(BAD_REQUEST.response());
+    };
+    let mut tx = state.get_storage().await?.new_tx().await?;
+    document.add(&mut tx).await?;
+    tx.commit().await?;
+    info!("{} objects put by admin API", ids.len());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Removes the objects of a document. Objects not in storage are ignored.
+#[tracing::instrument(level = "debug", skip(body))]
+pub(crate) async fn delete_objects(
+    state: State<DynServiceState>,
+    body: Bytes,
+) -> Result<Response, RdapServerError> {
+    let Some((_, ids)) = parse_document(&body) else {
+        return Ok(BAD_REQUEST.response());
+    };
+    let mut tx = state.get_storage().await?.new_tx().await?;
+    for id in &ids {
+        id.remove(&mut tx).await?;
+    }
+    tx.commit().await?;
+    info!("{} objects deleted by admin API", ids.len());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
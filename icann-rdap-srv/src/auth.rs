@@ -0,0 +1,492 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::{
+    header::{AUTHORIZATION, VARY, WWW_AUTHENTICATE},
+    HeaderValue,
+};
+use jsonwebtoken::{
+    decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, TokenData, Validation,
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::{
+    config::AuthConfig,
+    error::RdapServerError,
+    rdap::response::{ResponseUtil, UNAUTHORIZED},
+};
+
+/// The role given to every authenticated client.
+pub const AUTHENTICATED_ROLE: &str = "authenticated";
+
+/// How long a successful basic authentication is remembered.
+const VERIFIED_TTL: Duration = Duration::from_secs(60);
+
+/// The most basic authentications remembered at once.
+const MAX_VERIFIED: usize = 10_000;
+
+/// The identity of the client of a request.
+///
+/// This is put into the request extensions by the authentication middleware.
+/// Requests without credentials are anonymous, which have no name and no roles.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Principal {
+    pub name: Option<String>,
+    pub roles: HashSet<String>,
+}
+
+impl Principal {
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    pub fn authenticated(name: impl Into<String>, roles: impl IntoIterator<Item = String>) -> Self {
+        let mut roles: HashSet<String> = roles.into_iter().collect();
+        roles.insert(AUTHENTICATED_ROLE.to_string());
+        Self {
+            name: Some(name.into()),
+            roles,
+        }
+    }
+
+    /// Returns true if the principal has any of the roles.
+    pub fn has_any_role(&self, roles: &[String]) -> bool {
+        roles.iter().any(|role| self.roles.contains(role))
+    }
+}
+
+/// A user of HTTP basic authentication.
+#[derive(Debug)]
+struct BasicUser {
+    bcrypt_hash: String,
+    roles: Vec<String>,
+}
+
+/// A key from the JWKS file.
+struct JwtKey {
+    key_id: Option<String>,
+    algorithm: Option<Algorithm>,
+    key: DecodingKey,
+}
+
+/// Validates JWTs against the keys of a JWKS file.
+struct JwtValidator {
+    keys: Vec<JwtKey>,
+    validation: Validation,
+    roles_claim: String,
+}
+
+/// Authenticates clients using static bearer tokens, HTTP basic authentication, and JWTs.
+pub struct Authenticator {
+    tokens: HashMap<String, Vec<String>>,
+    users: HashMap<String, BasicUser>,
+    jwt: Option<JwtValidator>,
+    /// When basic credentials, keyed by their SHA-256 digest, were last verified.
+    verified: Mutex<HashMap<[u8; 32], Instant>>,
+}
+
+impl Authenticator {
+    /// Creates an authenticator by reading the files of the configuration.
+    pub fn new(config: &AuthConfig) -> Result<Self, RdapServerError> {
+        let tokens = match &config.tokens_file {
+            Some(file) => parse_tokens(&std::fs::read_to_string(file)?, file)?,
+            None => HashMap::new(),
+        };
+        let users = match &config.htpasswd_file {
+            Some(file) => parse_htpasswd(&std::fs::read_to_string(file)?, file)?,
+            None => HashMap::new(),
+        };
+        let jwt = match &config.jwks_file {
+            Some(file) => Some(JwtValidator::new(
+                &std::fs::read_to_string(file)?,
+                file,
+                config,
+            )?),
+            None => None,
+        };
+        Ok(Self {
+            tokens,
+            users,
+            jwt,
+            verified: Mutex::default(),
+        })
+    }
+
+    /// Authenticates the value of an `Authorization` header, returning `None` if
+    /// the credentials are not valid.
+    ///
+    /// Checking basic authentication passwords is CPU intensive, so successful checks
+    /// are remembered for a short time.
+    pub fn authenticate(&self, authorization: &str) -> Option<Principal> {
+        let (scheme, credentials) = authorization.trim().split_once(' ')?;
+        let credentials = credentials.trim();
+        if scheme.eq_ignore_ascii_case("bearer") {
+            if let Some(roles) = self.tokens.get(credentials) {
+                return Some(Principal::authenticated("bearer token", roles.clone()));
+            }
+            self.jwt.as_ref()?.authenticate(credentials)
+        } else if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = String::from_utf8(STANDARD.decode(credentials).ok()?).ok()?;
+            let (name, password) = decoded.split_once(':')?;
+            let user = self.users.get(name)?;
+            let digest: [u8; 32] = Sha256::digest(decoded.as_bytes()).into();
+            if !self.is_verified(&digest, Instant::now()) {
+                if !bcrypt::verify(password, &user.bcrypt_hash).ok()? {
+                    return None;
+                }
+                self.set_verified(digest, Instant::now());
+            }
+            Some(Principal::authenticated(name, user.roles.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn is_verified(&self, digest: &[u8; 32], now: Instant) -> bool {
+        let verified = self.verified.lock().expect("verified credentials poisoned");
+        verified
+            .get(digest)
+            .is_some_and(|at| now.saturating_duration_since(*at) < VERIFIED_TTL)
+    }
+
+    fn set_verified(&self, digest: [u8; 32], now: Instant) {
+        let mut verified = self.verified.lock().expect("verified credentials poisoned");
+        if verified.len() >= MAX_VERIFIED {
+            verified.retain(|_, at| now.saturating_duration_since(*at) < VERIFIED_TTL);
+            if verified.len() >= MAX_VERIFIED {
+                verified.clear();
+            }
+        }
+        verified.insert(digest, now);
+    }
+
+    /// Gets the value of the `WWW-Authenticate` header for the configured schemes.
+    pub(crate) fn challenge(&self) -> String {
+        let mut schemes = vec![];
+        if !self.users.is_empty() {
+            schemes.push(r#"Basic realm="rdap""#);
+        }
+        if !self.tokens.is_empty() || self.jwt.is_some() {
+            schemes.push(r#"Bearer realm="rdap""#);
+        }
+        schemes.join(", ")
+    }
+}
+
+impl JwtValidator {
+    fn new(contents: &str, file: &str, config: &AuthConfig) -> Result<Self, RdapServerError> {
+        let jwks = serde_json::from_str::<JwkSet>(contents)
+            .map_err(|e| RdapServerError::Config(format!("JWKS file {file} is invalid: {e}")))?;
+        let keys = jwks
+            .keys
+            .iter()
+            .map(|jwk| {
+                let algorithm = jwk
+                    .common
+                    .key_algorithm
+                    .map(|alg| Algorithm::from_str(&alg.to_string()))
+                    .transpose();
+                Ok(JwtKey {
+                    key_id: jwk.common.key_id.clone(),
+                    algorithm: algorithm?,
+                    key: DecodingKey::from_jwk(jwk)?,
+                })
+            })
+            .collect::<Result<Vec<JwtKey>, jsonwebtoken::errors::Error>>()
+            .map_err(|e| {
+                RdapServerError::Config(format!("JWKS file {file} has an invalid key: {e}"))
+            })?;
+        let mut validation = Validation::default();
+        if let Some(issuer) = &config.jwt_issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &config.jwt_audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        Ok(Self {
+            keys,
+            validation,
+            roles_claim: config.jwt_roles_claim.clone(),
+        })
+    }
+
+    fn authenticate(&self, token: &str) -> Option<Principal> {
+        let header = decode_header(token).ok()?;
+        let key = match &header.kid {
+            Some(kid) => self
+                .keys
+                .iter()
+                .find(|key| key.key_id.as_ref() == Some(kid))?,
+            None if self.keys.len() == 1 => self.keys.first()?,
+            None => return None,
+        };
+        // the algorithm of the token must not be chosen by the token when the key has one
+        if key.algorithm.is_some_and(|alg| alg != header.alg) {
+            return None;
+        }
+        let mut validation = self.validation.clone();
+        validation.algorithms = vec![header.alg];
+        let token: TokenData<Value> = decode(token, &key.key, &validation)
+            .map_err(|e| debug!("invalid JWT: {e}"))
+            .ok()?;
+        let roles = match token.claims.get(&self.roles_claim) {
+            Some(Value::Array(roles)) => roles
+                .iter()
+                .filter_map(|role| role.as_str().map(str::to_string))
+                .collect(),
+            Some(Value::String(roles)) => roles.split_whitespace().map(str::to_string).collect(),
+            _ => vec![],
+        };
+        let name = token
+            .claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .unwrap_or("JWT");
+        Some(Principal::authenticated(name, roles))
+    }
+}
+
+/// Splits a comma separated list of roles.
+fn parse_roles(roles: &str) -> Vec<String> {
+    roles
+        .split(',')
+        .map(str::trim)
+        .filter(|role| !role.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Gets the lines of a file that are not empty or comments.
+fn content_lines(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Parses a file of `token:role1,role2` lines.
+fn parse_tokens(
+    contents: &str,
+    file: &str,
+) -> Result<HashMap<String, Vec<String>>, RdapServerError> {
+    content_lines(contents)
+        .map(|line| {
+            let (token, roles) = line.split_once(':').unwrap_or((line, ""));
+            if token.is_empty() {
+                return Err(RdapServerError::Config(format!(
+                    "token file {file} has an empty token"
+                )));
+            }
+            Ok((token.to_string(), parse_roles(roles)))
+        })
+        .collect()
+}
+
+/// Parses an htpasswd-style file of `user:hash[:role1,role2]` lines. Only bcrypt
+/// hashes, as created by `htpasswd -B`, are supported.
+fn parse_htpasswd(
+    contents: &str,
+    file: &str,
+) -> Result<HashMap<String, BasicUser>, RdapServerError> {
+    content_lines(contents)
+        .map(|line| {
+            let mut fields = line.splitn(3, ':');
+            let name = fields.next().unwrap_or_default();
+            let hash = fields.next().unwrap_or_default();
+            let roles = fields.next().unwrap_or_default();
+            if !hash.starts_with("$2") {
+                return Err(RdapServerError::Config(format!(
+                    "htpasswd file {file} has a password for {name} that is not a bcrypt hash"
+                )));
+            }
+            Ok((
+                name.to_string(),
+                BasicUser {
+                    bcrypt_hash: hash.to_string(),
+                    roles: parse_roles(roles),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Middleware putting the [Principal] of the request into the request extensions,
+/// or rejecting the request with a 401 RDAP error if the credentials are not valid.
+pub(crate) async fn authenticate(
+    State(authenticator): State<Arc<Authenticator>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get(AUTHORIZATION)
+        .map(|value| value.to_str().unwrap_or_default().to_string());
+    let principal = match authorization {
+        None => Some(Principal::anonymous()),
+        Some(authorization) => {
+            let authenticator = authenticator.clone();
+            tokio::task::spawn_blocking(move || authenticator.authenticate(&authorization))
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("authentication task failed: {e}");
+                    None
+                })
+        }
+    };
+    let mut response = match principal {
+        Some(principal) => {
+            debug!("request from {:?}", principal.name);
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        None => {
+            let mut response = UNAUTHORIZED.response();
+            if let Ok(challenge) = HeaderValue::from_str(&authenticator.challenge()) {
+                response.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+            }
+            response
+        }
+    };
+    // responses depend on the credentials, so shared caches must not mix them up
+    response
+        .headers_mut()
+        .append(VARY, HeaderValue::from_static("authorization"));
+    response
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use sha2::{Digest, Sha256};
+
+    use crate::config::AuthConfig;
+
+    use super::{parse_htpasswd, parse_tokens, Authenticator, AUTHENTICATED_ROLE, VERIFIED_TTL};
+
+    fn new_authenticator() -> Authenticator {
+        let hash = bcrypt::hash("secret", 4).expect("hashing password");
+        Authenticator {
+            tokens: parse_tokens("# comment\nabc123:law-enforcement,registrar\n", "tokens")
+                .expect("parsing tokens"),
+            users: parse_htpasswd(&format!("alice:{hash}:law-enforcement\n"), "htpasswd")
+                .expect("parsing htpasswd"),
+            jwt: None,
+            verified: Mutex::default(),
+        }
+    }
+
+    #[test]
+    fn GIVEN_static_token_WHEN_authenticate_THEN_roles_of_token() {
+        // GIVEN
+        let authenticator = new_authenticator();
+
+        // WHEN
+        let actual = authenticator
+            .authenticate("Bearer abc123")
+            .expect("not authenticated");
+
+        // THEN
+        assert!(actual.roles.contains("law-enforcement"));
+        assert!(actual.roles.contains("registrar"));
+        assert!(actual.roles.contains(AUTHENTICATED_ROLE));
+    }
+
+    #[test]
+    fn GIVEN_basic_credentials_WHEN_authenticate_THEN_roles_of_user() {
+        // GIVEN
+        let authenticator = new_authenticator();
+        let credentials = STANDARD.encode("alice:secret");
+
+        // WHEN
+        let actual = authenticator
+            .authenticate(&format!("Basic {credentials}"))
+            .expect("not authenticated");
+
+        // THEN
+        assert_eq!(actual.name.as_deref(), Some("alice"));
+        assert!(actual.roles.contains("law-enforcement"));
+    }
+
+    #[test]
+    fn GIVEN_wrong_credentials_WHEN_authenticate_THEN_none() {
+        // GIVEN
+        let authenticator = new_authenticator();
+        let credentials = STANDARD.encode("alice:wrong");
+
+        // WHEN
+        let bad_password = authenticator.authenticate(&format!("Basic {credentials}"));
+        let bad_token = authenticator.authenticate("Bearer xyz");
+        let bad_scheme = authenticator.authenticate("Digest abc123");
+
+        // THEN
+        assert!(bad_password.is_none());
+        assert!(bad_token.is_none());
+        assert!(bad_scheme.is_none());
+    }
+
+    #[test]
+    fn GIVEN_verified_basic_credentials_WHEN_authenticate_again_THEN_remembered_until_ttl() {
+        // GIVEN
+        let authenticator = new_authenticator();
+        let credentials = STANDARD.encode("alice:secret");
+        authenticator
+            .authenticate(&format!("Basic {credentials}"))
+            .expect("not authenticated");
+        let wrong = STANDARD.encode("alice:wrong");
+        authenticator.authenticate(&format!("Basic {wrong}"));
+
+        // WHEN
+        let digest: [u8; 32] = Sha256::digest("alice:secret").into();
+        let wrong_digest: [u8; 32] = Sha256::digest("alice:wrong").into();
+        let now = Instant::now();
+
+        // THEN
+        assert!(authenticator.is_verified(&digest, now));
+        assert!(!authenticator.is_verified(&digest, now + VERIFIED_TTL + Duration::from_secs(1)));
+        assert!(!authenticator.is_verified(&wrong_digest, now));
+    }
+
+    #[test]
+    fn GIVEN_htpasswd_with_md5_hash_WHEN_parse_THEN_error() {
+        // GIVEN
+        let contents = "bob:$apr1$abc$def\n";
+
+        // WHEN
+        let actual = parse_htpasswd(contents, "htpasswd");
+
+        // THEN
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn GIVEN_no_files_WHEN_new_authenticator_THEN_no_challenge() {
+        // GIVEN
+        let config = AuthConfig::builder().build();
+
+        // WHEN
+        let actual = Authenticator::new(&config).expect("creating authenticator");
+
+        // THEN
+        assert!(actual.challenge().is_empty());
+    }
+}
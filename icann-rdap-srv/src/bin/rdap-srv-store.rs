@@ -5,9 +5,12 @@ use icann_rdap_common::{check::CheckClass, response::RdapResponse, VERSION};
 use icann_rdap_srv::{
     config::{data_dir, debug_config_vars, LOG},
     error::RdapServerError,
-    storage::data::{
-        trigger_reload, trigger_update, AutnumOrError, DomainOrError, EntityOrError,
-        NameserverOrError, NetworkIdType, NetworkOrError, Template,
+    storage::{
+        bulk::{verify_archive, verify_json_lines},
+        data::{
+            trigger_reload, trigger_update, AutnumOrError, DataFileKind, Document, DomainOrError,
+            EntityOrError, NameserverOrError, NetworkIdType, NetworkOrError, Template,
+        },
     },
     util::bin::check::{check_rdap, to_check_classes, CheckArgs},
 };
@@ -98,11 +101,36 @@ async fn do_validate_then_move(
     let mut errors_found = false;
     while let Some(entry) = entries.next_entry().await? {
         let entry = entry.path();
-        let contents = tokio::fs::read_to_string(&entry).await?;
-        if entry.extension().map_or(false, |ext| ext == "template") {
-            errors_found |= verify_rdap_template(&contents, &entry.to_string_lossy(), check_types)?;
-        } else if entry.extension().map_or(false, |ext| ext == "json") {
-            errors_found |= verify_rdap(&contents, &entry.to_string_lossy(), check_types)?;
+        let path_name = entry.to_string_lossy();
+        match DataFileKind::of(&entry) {
+            Some(DataFileKind::Template) => {
+                let contents = tokio::fs::read_to_string(&entry).await?;
+                errors_found |= verify_rdap_template(&contents, &path_name, check_types)?;
+            }
+            Some(DataFileKind::Json) => {
+                let contents = tokio::fs::read_to_string(&entry).await?;
+                errors_found |= verify_rdap(&contents, &path_name, check_types)?;
+            }
+            Some(DataFileKind::Help) => {
+                let contents = tokio::fs::read_to_string(&entry).await?;
+                errors_found |= verify_rdap(&contents, &path_name, check_types)?;
+            }
+            Some(DataFileKind::JsonLines(compression)) => {
+                errors_found |= verify_json_lines(&entry, compression, |location, document| {
+                    verify_document(document, location, check_types)
+                })
+                .await?;
+            }
+            Some(DataFileKind::Archive(compression)) => {
+                errors_found |= verify_archive(&entry, compression, |location, document| {
+                    verify_document(document, location, check_types)
+                })
+                .await?;
+            }
+            None => {
+                error!("Unknown type of data file at {path_name}");
+                errors_found = true;
+            }
         }
     }
     if errors_found {
@@ -156,136 +184,155 @@ fn verify_rdap(
     Ok(errors_found)
 }
 
+/// Verifies a document of a JSON Lines file or archive.
+fn verify_document(document: Document, location: &str, check_types: &[CheckClass]) -> bool {
+    match document {
+        Document::Object(rdap) => check_rdap(rdap, check_types),
+        Document::Template(template) => {
+            verify_template(template, check_types).unwrap_or_else(|e| {
+                error!("{location}: {e}");
+                true
+            })
+        }
+        Document::SrvHelp { help, .. } => check_rdap(RdapResponse::Help(help), check_types),
+    }
+}
+
 /// Verifies the template files.
 fn verify_rdap_template(
     contents: &str,
     path_name: &str,
     check_types: &[CheckClass],
 ) -> Result<bool, RdapServerError> {
-    let mut errors_found = false;
     debug!("processing {path_name} template");
     let json = serde_json::from_str::<Template>(contents);
     if let Ok(value) = json {
-        match value {
-            Template::Domain { domain, ids } => {
-                for id in ids {
-                    debug!("verifying domain from template for {id:?}");
-                    match &domain {
-                        DomainOrError::DomainObject(domain) => {
-                            let mut domain = domain.clone();
-                            domain.ldh_name = Some(id.ldh_name);
-                            if let Some(unicode_name) = id.unicode_name {
-                                domain.unicode_name = Some(unicode_name);
-                            };
-                            errors_found |= check_rdap(RdapResponse::Domain(domain), check_types);
-                        }
-                        DomainOrError::ErrorResponse(error) => {
-                            errors_found |=
-                                check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
-                        }
-                    };
-                }
+        verify_template(value, check_types)
+    } else {
+        error!("Non JSON template file at {}", path_name.to_owned());
+        Ok(true)
+    }
+}
+
+/// Verifies the objects made from a template.
+fn verify_template(value: Template, check_types: &[CheckClass]) -> Result<bool, RdapServerError> {
+    let mut errors_found = false;
+    match value {
+        Template::Domain { domain, ids } => {
+            for id in ids {
+                debug!("verifying domain from template for {id:?}");
+                match &domain {
+                    DomainOrError::DomainObject(domain) => {
+                        let mut domain = domain.clone();
+                        domain.ldh_name = Some(id.ldh_name);
+                        if let Some(unicode_name) = id.unicode_name {
+                            domain.unicode_name = Some(unicode_name);
+                        };
+                        errors_found |= check_rdap(RdapResponse::Domain(domain), check_types);
+                    }
+                    DomainOrError::ErrorResponse(error) => {
+                        errors_found |=
+                            check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
+                    }
+                };
             }
-            Template::Entity { entity, ids } => {
-                for id in ids {
-                    debug!("verifying entity from template for {id:?}");
-                    match &entity {
-                        EntityOrError::EntityObject(entity) => {
-                            let mut entity = entity.clone();
-                            entity.object_common.handle = Some(id.handle);
-                            errors_found |= check_rdap(RdapResponse::Entity(entity), check_types);
-                        }
-                        EntityOrError::ErrorResponse(error) => {
-                            errors_found |=
-                                check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
-                        }
-                    };
-                }
+        }
+        Template::Entity { entity, ids } => {
+            for id in ids {
+                debug!("verifying entity from template for {id:?}");
+                match &entity {
+                    EntityOrError::EntityObject(entity) => {
+                        let mut entity = entity.clone();
+                        entity.object_common.handle = Some(id.handle);
+                        errors_found |= check_rdap(RdapResponse::Entity(entity), check_types);
+                    }
+                    EntityOrError::ErrorResponse(error) => {
+                        errors_found |=
+                            check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
+                    }
+                };
             }
-            Template::Nameserver { nameserver, ids } => {
-                for id in ids {
-                    debug!("verifying dding nameserver from template for {id:?}");
-                    match &nameserver {
-                        NameserverOrError::NameserverObject(nameserver) => {
-                            let mut nameserver = nameserver.clone();
-                            nameserver.ldh_name = Some(id.ldh_name);
-                            if let Some(unicode_name) = id.unicode_name {
-                                nameserver.unicode_name = Some(unicode_name);
-                            };
-                            errors_found |=
-                                check_rdap(RdapResponse::Nameserver(nameserver), check_types);
-                        }
-                        NameserverOrError::ErrorResponse(error) => {
-                            errors_found |=
-                                check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
-                        }
-                    };
-                }
+        }
+        Template::Nameserver { nameserver, ids } => {
+            for id in ids {
+                debug!("verifying dding nameserver from template for {id:?}");
+                match &nameserver {
+                    NameserverOrError::NameserverObject(nameserver) => {
+                        let mut nameserver = nameserver.clone();
+                        nameserver.ldh_name = Some(id.ldh_name);
+                        if let Some(unicode_name) = id.unicode_name {
+                            nameserver.unicode_name = Some(unicode_name);
+                        };
+                        errors_found |=
+                            check_rdap(RdapResponse::Nameserver(nameserver), check_types);
+                    }
+                    NameserverOrError::ErrorResponse(error) => {
+                        errors_found |=
+                            check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
+                    }
+                };
             }
-            Template::Autnum { autnum, ids } => {
-                for id in ids {
-                    debug!("verifying autnum from template for {id:?}");
-                    match &autnum {
-                        AutnumOrError::AutnumObject(autnum) => {
-                            let mut autnum = autnum.clone();
-                            autnum.start_autnum = Some(id.start_autnum);
-                            autnum.end_autnum = Some(id.end_autnum);
-                            errors_found |= check_rdap(RdapResponse::Autnum(autnum), check_types);
-                        }
-                        AutnumOrError::ErrorResponse(error) => {
-                            errors_found |=
-                                check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
-                        }
-                    };
-                }
+        }
+        Template::Autnum { autnum, ids } => {
+            for id in ids {
+                debug!("verifying autnum from template for {id:?}");
+                match &autnum {
+                    AutnumOrError::AutnumObject(autnum) => {
+                        let mut autnum = autnum.clone();
+                        autnum.start_autnum = Some(id.start_autnum);
+                        autnum.end_autnum = Some(id.end_autnum);
+                        errors_found |= check_rdap(RdapResponse::Autnum(autnum), check_types);
+                    }
+                    AutnumOrError::ErrorResponse(error) => {
+                        errors_found |=
+                            check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
+                    }
+                };
             }
-            Template::Network { network, ids } => {
-                for id in ids {
-                    debug!("verifying network from template for {id:?}");
-                    match &network {
-                        NetworkOrError::NetworkObject(network) => {
-                            let mut network = network.clone();
-                            match id.network_id {
-                                NetworkIdType::Cidr(cidr) => match cidr {
-                                    IpNet::V4(v4) => {
-                                        network.start_address = Some(v4.network().to_string());
-                                        network.end_address = Some(v4.broadcast().to_string());
-                                        network.ip_version = Some("v4".to_string());
-                                    }
-                                    IpNet::V6(v6) => {
-                                        network.start_address = Some(v6.network().to_string());
-                                        network.end_address = Some(v6.broadcast().to_string());
-                                        network.ip_version = Some("v6".to_string());
-                                    }
-                                },
-                                NetworkIdType::Range {
-                                    start_address,
-                                    end_address,
-                                } => {
-                                    let addr: IpAddr = start_address.parse()?;
-                                    if addr.is_ipv4() {
-                                        network.ip_version = Some("v4".to_string());
-                                    } else {
-                                        network.ip_version = Some("v6".to_string());
-                                    }
-                                    network.start_address = Some(start_address);
-                                    network.end_address = Some(end_address);
+        }
+        Template::Network { network, ids } => {
+            for id in ids {
+                debug!("verifying network from template for {id:?}");
+                match &network {
+                    NetworkOrError::NetworkObject(network) => {
+                        let mut network = network.clone();
+                        match id.network_id {
+                            NetworkIdType::Cidr(cidr) => match cidr {
+                                IpNet::V4(v4) => {
+                                    network.start_address = Some(v4.network().to_string());
+                                    network.end_address = Some(v4.broadcast().to_string());
+                                    network.ip_version = Some("v4".to_string());
                                 }
+                                IpNet::V6(v6) => {
+                                    network.start_address = Some(v6.network().to_string());
+                                    network.end_address = Some(v6.broadcast().to_string());
+                                    network.ip_version = Some("v6".to_string());
+                                }
+                            },
+                            NetworkIdType::Range {
+                                start_address,
+                                end_address,
+                            } => {
+                                let addr: IpAddr = start_address.parse()?;
+                                if addr.is_ipv4() {
+                                    network.ip_version = Some("v4".to_string());
+                                } else {
+                                    network.ip_version = Some("v6".to_string());
+                                }
+                                network.start_address = Some(start_address);
+                                network.end_address = Some(end_address);
                             }
-                            errors_found |= check_rdap(RdapResponse::Network(network), check_types);
-                        }
-                        NetworkOrError::ErrorResponse(error) => {
-                            errors_found |=
-                                check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
                         }
-                    };
-                }
+                        errors_found |= check_rdap(RdapResponse::Network(network), check_types);
+                    }
+                    NetworkOrError::ErrorResponse(error) => {
+                        errors_found |=
+                            check_rdap(RdapResponse::ErrorResponse(error.clone()), check_types);
+                    }
+                };
             }
-        };
-    } else {
-        error!("Non JSON template file at {}", path_name.to_owned());
-        errors_found = true;
-    }
+        }
+    };
     Ok(errors_found)
 }
 
@@ -1,8 +1,10 @@
 use envmnt::{get_or, get_parse_or, get_u16};
 use icann_rdap_srv::{
     config::{
-        data_dir, debug_config_vars, ListenConfig, ServiceConfig, StorageType, AUTO_RELOAD,
-        BOOTSTRAP, LISTEN_ADDR, LISTEN_PORT, LOG, UPDATE_ON_BOOTSTRAP,
+        data_dir, debug_config_vars, AuthConfig, CacheConfig, LinkConfig, ListenConfig,
+        RateLimitConfig, ServiceConfig, StorageType, TlsConfig, VirtualHostConfig, ADMIN_ROLE,
+        AUTO_RELOAD, BOOTSTRAP, LISTEN_ADDR, LISTEN_PORT, LOG, REDACTION_POLICY_FILE,
+        SEARCH_PAGE_SIZE, UPDATE_ON_BOOTSTRAP,
     },
     error::RdapServerError,
     server::Listener,
@@ -24,14 +26,26 @@ async fn main() -> Result<(), RdapServerError> {
     let listen_addr = get_or(LISTEN_ADDR, "127.0.0.1");
     let listen_port = get_u16(LISTEN_PORT, 3000);
     let storage_type = StorageType::new_from_env()?;
-    let auto_reload: bool = get_parse_or(AUTO_RELOAD, true)?;
+    let admin_role = Some(get_or(ADMIN_ROLE, "")).filter(|role| !role.is_empty());
+    // storage managed by the admin API has no data directory to reload
+    let auto_reload: bool = get_parse_or(AUTO_RELOAD, admin_role.is_none())?;
     let bootstrap: bool = get_parse_or(BOOTSTRAP, false)?;
     let update_on_bootstrap: bool = get_parse_or(UPDATE_ON_BOOTSTRAP, false)?;
+    // a page size of 0 returns all search results in one page
+    let search_page_size: usize = get_parse_or(SEARCH_PAGE_SIZE, 0)?;
+    let rate_limit = RateLimitConfig::new_from_env()?;
+    let auth = AuthConfig::new_from_env()?;
+    let cache = CacheConfig::new_from_env()?;
+    let redaction_policy_file =
+        Some(get_or(REDACTION_POLICY_FILE, "")).filter(|file| !file.is_empty());
+    let virtual_hosts = VirtualHostConfig::new_from_env()?;
+    let links = LinkConfig::new_from_env()?;
 
     let listener = Listener::listen(
         &ListenConfig::builder()
             .ip_addr(listen_addr)
             .port(listen_port)
+            .and_tls(TlsConfig::new_from_env()?)
             .build(),
     )
     .await?;
@@ -43,6 +57,14 @@ async fn main() -> Result<(), RdapServerError> {
                 .auto_reload(auto_reload)
                 .bootstrap(bootstrap)
                 .update_on_bootstrap(update_on_bootstrap)
+                .and_search_page_size(Some(search_page_size).filter(|size| *size > 0))
+                .and_rate_limit(rate_limit)
+                .and_auth(auth)
+                .and_redaction_policy_file(redaction_policy_file)
+                .and_cache(cache)
+                .and_admin_role(admin_role)
+                .virtual_hosts(virtual_hosts)
+                .and_links(links)
                 .build(),
         )
         .await?;
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use icann_rdap_client::{
     http::{create_client, Client, ClientConfig},
@@ -13,11 +13,13 @@ use tokio::{
     io::{AsyncBufReadExt, BufReader},
     time::sleep,
 };
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 use crate::{
     config::ServiceConfig,
     error::RdapServerError,
+    metrics::record_bootstrap_refresh,
+    status::ServerStatus,
     storage::data::{
         trigger_reload, trigger_update, AutnumId, AutnumOrError, DomainId, DomainOrError, EntityId,
         EntityOrError, NetworkId, NetworkIdType, NetworkOrError, Template,
@@ -26,7 +28,10 @@ use crate::{
 
 const IANA_JSON_SUFFIX: &str = ".iana_cache";
 
-pub async fn init_bootstrap(config: &ServiceConfig) -> Result<(), RdapServerError> {
+pub async fn init_bootstrap(
+    config: &ServiceConfig,
+    status: Arc<ServerStatus>,
+) -> Result<(), RdapServerError> {
     if config.bootstrap {
         info!("Initializing IANA Bootstrap.");
         let client_config = ClientConfig::builder()
@@ -35,22 +40,35 @@ pub async fn init_bootstrap(config: &ServiceConfig) -> Result<(), RdapServerErro
         let client = create_client(&client_config)?;
 
         // do one run of the bootstrapping before starting the thread.
-        process_bootstrap(config, &client).await?;
+        process_bootstrap(config, &client, &status).await?;
 
         // spawn bootstrap thread
-        tokio::spawn(loop_bootstrap(config.clone(), client));
+        tokio::spawn(loop_bootstrap(config.clone(), client, status));
     }
     Ok(())
 }
 
-async fn loop_bootstrap(config: ServiceConfig, client: Client) -> Result<(), RdapServerError> {
+async fn loop_bootstrap(config: ServiceConfig, client: Client, status: Arc<ServerStatus>) {
     loop {
         sleep(Duration::from_millis(60000)).await;
-        process_bootstrap(&config, &client).await?;
+        if let Err(e) = process_bootstrap(&config, &client, &status).await {
+            error!("Bootstrap refresh failed: {e}");
+        }
     }
 }
 
-async fn process_bootstrap(config: &ServiceConfig, client: &Client) -> Result<(), RdapServerError> {
+async fn process_bootstrap(
+    config: &ServiceConfig,
+    client: &Client,
+    status: &ServerStatus,
+) -> Result<(), RdapServerError> {
+    let result = refresh_bootstrap(config, client).await;
+    record_bootstrap_refresh(result.is_ok());
+    status.record_bootstrap(&result);
+    result
+}
+
+async fn refresh_bootstrap(config: &ServiceConfig, client: &Client) -> Result<(), RdapServerError> {
     let mut new_data = false;
     if let Some(iana_reg) =
         fetch_iana_registry(IanaRegistryType::RdapBootstrapDns, client, &config.data_dir).await?
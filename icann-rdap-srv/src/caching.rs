@@ -0,0 +1,148 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use headers::{ETag, Expires, HeaderMapExt, IfModifiedSince, IfNoneMatch, LastModified};
+use http::{
+    header::{AUTHORIZATION, CACHE_CONTROL, ETAG, EXPIRES, LAST_MODIFIED, VARY},
+    HeaderValue, StatusCode,
+};
+
+use crate::{config::CacheConfig, metrics::object_class};
+
+/// The headers of a successful response that are repeated in a 304 response.
+const NOT_MODIFIED_HEADERS: [http::HeaderName; 5] =
+    [ETAG, LAST_MODIFIED, CACHE_CONTROL, EXPIRES, VARY];
+
+/// Middleware adding caching headers to successful responses and answering
+/// conditional requests for unchanged responses with a 304.
+pub(crate) async fn caching(
+    State(config): State<Option<Arc<CacheConfig>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let max_age = config.as_ref().and_then(|config| {
+        let path = request.extensions().get::<MatchedPath>()?;
+        config.max_age(&object_class(path.as_str()))
+    });
+    let if_none_match = request.headers().typed_get::<IfNoneMatch>();
+    let if_modified_since = request.headers().typed_get::<IfModifiedSince>();
+    // responses to authenticated requests may differ by client, so shared caches must not keep them
+    let private = request.headers().contains_key(AUTHORIZATION);
+
+    let mut response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    if let Some(max_age) = max_age {
+        let scope = if private { "private" } else { "public" };
+        let cache_control = HeaderValue::from_str(&format!("{scope}, max-age={max_age}"))
+            .expect("cache control is a valid header value");
+        let headers = response.headers_mut();
+        headers.insert(CACHE_CONTROL, cache_control);
+        headers.typed_insert(Expires::from(
+            SystemTime::now() + Duration::from_secs(max_age),
+        ));
+    }
+    if is_not_modified(response.headers(), if_none_match, if_modified_since) {
+        let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+        for name in NOT_MODIFIED_HEADERS {
+            for value in response.headers().get_all(&name) {
+                not_modified.headers_mut().append(&name, value.clone());
+            }
+        }
+        return not_modified;
+    }
+    response
+}
+
+/// Determines if the response has not changed according to the validators of the
+/// request. As in RFC 9110, `If-Modified-Since` is ignored when `If-None-Match` is present.
+fn is_not_modified(
+    headers: &HeaderMap,
+    if_none_match: Option<IfNoneMatch>,
+    if_modified_since: Option<IfModifiedSince>,
+) -> bool {
+    if let Some(if_none_match) = if_none_match {
+        return headers
+            .typed_get::<ETag>()
+            .is_some_and(|entity_tag| !if_none_match.precondition_passes(&entity_tag));
+    }
+    match (if_modified_since, headers.typed_get::<LastModified>()) {
+        (Some(if_modified_since), Some(last_modified)) => {
+            !if_modified_since.is_modified(SystemTime::from(last_modified))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use std::time::{Duration, SystemTime};
+
+    use axum::http::HeaderMap;
+    use headers::{ETag, HeaderMapExt, IfModifiedSince, IfNoneMatch, LastModified};
+
+    use super::is_not_modified;
+
+    fn new_headers(last_modified: SystemTime) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.typed_insert("\"abc\"".parse::<ETag>().expect("parsing etag"));
+        headers.typed_insert(LastModified::from(last_modified));
+        headers
+    }
+
+    #[test]
+    fn GIVEN_matching_if_none_match_WHEN_is_not_modified_THEN_true() {
+        // GIVEN
+        let headers = new_headers(SystemTime::now());
+        let if_none_match = IfNoneMatch::from("\"abc\"".parse::<ETag>().expect("parsing etag"));
+
+        // WHEN
+        let actual = is_not_modified(&headers, Some(if_none_match), None);
+
+        // THEN
+        assert!(actual);
+    }
+
+    #[test]
+    fn GIVEN_different_if_none_match_and_later_if_modified_since_WHEN_is_not_modified_THEN_false() {
+        // GIVEN
+        let last_modified = SystemTime::now() - Duration::from_secs(3600);
+        let headers = new_headers(last_modified);
+        let if_none_match = IfNoneMatch::from("\"xyz\"".parse::<ETag>().expect("parsing etag"));
+        let if_modified_since = IfModifiedSince::from(SystemTime::now());
+
+        // WHEN
+        let actual = is_not_modified(&headers, Some(if_none_match), Some(if_modified_since));
+
+        // THEN
+        assert!(!actual);
+    }
+
+    #[test]
+    fn GIVEN_if_modified_since_WHEN_is_not_modified_THEN_compared_to_last_modified() {
+        // GIVEN
+        let last_modified = SystemTime::now() - Duration::from_secs(3600);
+        let headers = new_headers(last_modified);
+        let before = IfModifiedSince::from(last_modified - Duration::from_secs(60));
+        let after = IfModifiedSince::from(SystemTime::now());
+
+        // WHEN
+        let modified_before = is_not_modified(&headers, None, Some(before));
+        let modified_after = is_not_modified(&headers, None, Some(after));
+
+        // THEN
+        assert!(!modified_before);
+        assert!(modified_after);
+    }
+}
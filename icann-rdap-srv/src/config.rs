@@ -1,3 +1,6 @@
+use std::{collections::HashMap, path::Path};
+
+use axum_client_ip::SecureClientIpSource;
 use buildstructor::Builder;
 use envmnt::{get_or, get_parse_or};
 use strum_macros::Display;
@@ -5,32 +8,98 @@ use tracing::debug;
 
 use crate::{
     error::RdapServerError,
-    storage::{mem::config::MemConfig, pg::config::PgConfig, CommonConfig},
+    storage::{
+        mem::config::MemConfig, pg::config::PgConfig, sqlite::config::SqliteConfig, CommonConfig,
+    },
 };
 
 pub const LOG: &str = "RDAP_SRV_LOG";
 pub const LISTEN_ADDR: &str = "RDAP_SRV_LISTEN_ADDR";
 pub const LISTEN_PORT: &str = "RDAP_SRV_LISTEN_PORT";
+pub const TLS_CERT: &str = "RDAP_SRV_TLS_CERT";
+pub const TLS_KEY: &str = "RDAP_SRV_TLS_KEY";
 pub const STORAGE: &str = "RDAP_SRV_STORAGE";
 pub const DB_URL: &str = "RDAP_SRV_DB_URL";
+pub const SQLITE_FILE: &str = "RDAP_SRV_SQLITE_FILE";
+pub const SNAPSHOT_FILE: &str = "RDAP_SRV_SNAPSHOT_FILE";
 pub const DATA_DIR: &str = "RDAP_SRV_DATA_DIR";
 pub const AUTO_RELOAD: &str = "RDAP_SRV_AUTO_RELOAD";
 pub const BOOTSTRAP: &str = "RDAP_SRV_BOOTSTRAP";
 pub const UPDATE_ON_BOOTSTRAP: &str = "RDAP_SRV_UPDATE_ON_BOOTSTRAP";
 pub const DOMAIN_SEARCH_BY_NAME_ENABLE: &str = "RDAP_SRV_DOMAIN_SEARCH_BY_NAME";
+pub const DOMAIN_SEARCH_BY_NS_LDH_NAME_ENABLE: &str = "RDAP_SRV_DOMAIN_SEARCH_BY_NS_LDH_NAME";
+pub const DOMAIN_SEARCH_BY_NS_IP_ENABLE: &str = "RDAP_SRV_DOMAIN_SEARCH_BY_NS_IP";
+pub const NAMESERVER_SEARCH_BY_NAME_ENABLE: &str = "RDAP_SRV_NAMESERVER_SEARCH_BY_NAME";
+pub const NAMESERVER_SEARCH_BY_IP_ENABLE: &str = "RDAP_SRV_NAMESERVER_SEARCH_BY_IP";
+pub const ENTITY_SEARCH_BY_FN_ENABLE: &str = "RDAP_SRV_ENTITY_SEARCH_BY_FN";
+pub const ENTITY_SEARCH_BY_HANDLE_ENABLE: &str = "RDAP_SRV_ENTITY_SEARCH_BY_HANDLE";
+pub const REVERSE_SEARCH_ENABLE: &str = "RDAP_SRV_REVERSE_SEARCH";
+pub const PARTIAL_LABEL_SEARCH_ENABLE: &str = "RDAP_SRV_PARTIAL_LABEL_SEARCH";
+pub const SEARCH_PAGE_SIZE: &str = "RDAP_SRV_SEARCH_PAGE_SIZE";
+pub const RATE_LIMIT: &str = "RDAP_SRV_RATE_LIMIT";
+pub const RATE_LIMIT_BURST: &str = "RDAP_SRV_RATE_LIMIT_BURST";
+pub const RATE_LIMIT_V4_PREFIX: &str = "RDAP_SRV_RATE_LIMIT_V4_PREFIX";
+pub const RATE_LIMIT_V6_PREFIX: &str = "RDAP_SRV_RATE_LIMIT_V6_PREFIX";
+pub const CLIENT_IP_SOURCE: &str = "RDAP_SRV_CLIENT_IP_SOURCE";
+pub const AUTH_TOKENS_FILE: &str = "RDAP_SRV_AUTH_TOKENS_FILE";
+pub const AUTH_HTPASSWD_FILE: &str = "RDAP_SRV_AUTH_HTPASSWD_FILE";
+pub const AUTH_JWKS_FILE: &str = "RDAP_SRV_AUTH_JWKS_FILE";
+pub const AUTH_JWT_ISSUER: &str = "RDAP_SRV_AUTH_JWT_ISSUER";
+pub const AUTH_JWT_AUDIENCE: &str = "RDAP_SRV_AUTH_JWT_AUDIENCE";
+pub const AUTH_JWT_ROLES_CLAIM: &str = "RDAP_SRV_AUTH_JWT_ROLES_CLAIM";
+pub const REDACTION_POLICY_FILE: &str = "RDAP_SRV_REDACTION_POLICY_FILE";
+pub const CACHE_MAX_AGE: &str = "RDAP_SRV_CACHE_MAX_AGE";
+pub const CACHE_CLASS_MAX_AGES: &str = "RDAP_SRV_CACHE_CLASS_MAX_AGES";
+pub const ADMIN_ROLE: &str = "RDAP_SRV_ADMIN_ROLE";
+pub const VIRTUAL_HOSTS: &str = "RDAP_SRV_VIRTUAL_HOSTS";
+pub const REWRITE_LINKS: &str = "RDAP_SRV_REWRITE_LINKS";
+pub const PUBLIC_BASE_URL: &str = "RDAP_SRV_PUBLIC_BASE_URL";
+pub const LINK_HOSTS: &str = "RDAP_SRV_LINK_HOSTS";
 
 pub fn debug_config_vars() {
     let var_list = [
         LOG,
         LISTEN_ADDR,
         LISTEN_PORT,
+        TLS_CERT,
+        TLS_KEY,
         STORAGE,
         DB_URL,
+        SQLITE_FILE,
+        SNAPSHOT_FILE,
         DATA_DIR,
         AUTO_RELOAD,
         BOOTSTRAP,
         UPDATE_ON_BOOTSTRAP,
         DOMAIN_SEARCH_BY_NAME_ENABLE,
+        DOMAIN_SEARCH_BY_NS_LDH_NAME_ENABLE,
+        DOMAIN_SEARCH_BY_NS_IP_ENABLE,
+        NAMESERVER_SEARCH_BY_NAME_ENABLE,
+        NAMESERVER_SEARCH_BY_IP_ENABLE,
+        ENTITY_SEARCH_BY_FN_ENABLE,
+        ENTITY_SEARCH_BY_HANDLE_ENABLE,
+        REVERSE_SEARCH_ENABLE,
+        PARTIAL_LABEL_SEARCH_ENABLE,
+        SEARCH_PAGE_SIZE,
+        RATE_LIMIT,
+        RATE_LIMIT_BURST,
+        RATE_LIMIT_V4_PREFIX,
+        RATE_LIMIT_V6_PREFIX,
+        CLIENT_IP_SOURCE,
+        AUTH_TOKENS_FILE,
+        AUTH_HTPASSWD_FILE,
+        AUTH_JWKS_FILE,
+        AUTH_JWT_ISSUER,
+        AUTH_JWT_AUDIENCE,
+        AUTH_JWT_ROLES_CLAIM,
+        REDACTION_POLICY_FILE,
+        CACHE_MAX_AGE,
+        CACHE_CLASS_MAX_AGES,
+        ADMIN_ROLE,
+        VIRTUAL_HOSTS,
+        REWRITE_LINKS,
+        PUBLIC_BASE_URL,
+        LINK_HOSTS,
     ];
     envmnt::vars()
         .iter()
@@ -52,6 +121,353 @@ pub struct ListenConfig {
     /// If specified, determines the port number the server will bind to.
     /// If unspecified, the server let's the OS determine the port.
     pub port: Option<u16>,
+
+    /// If specified, the server will serve HTTPS using this configuration.
+    /// If unspecified, the server will serve HTTP.
+    pub tls: Option<TlsConfig>,
+}
+
+/// TLS configuration.
+#[derive(Debug, Builder, Clone)]
+pub struct TlsConfig {
+    /// Path to a PEM file containing the certificate chain, starting with the certificate
+    /// of the server.
+    pub cert_file: String,
+
+    /// Path to a PEM file containing the private key of the certificate.
+    pub key_file: String,
+}
+
+impl TlsConfig {
+    /// Gets the TLS configuration from the environment, or `None` if TLS is not configured.
+    pub fn new_from_env() -> Result<Option<Self>, RdapServerError> {
+        let cert_file = get_or(TLS_CERT, "");
+        let key_file = get_or(TLS_KEY, "");
+        match (cert_file.is_empty(), key_file.is_empty()) {
+            (true, true) => Ok(None),
+            (false, false) => Ok(Some(
+                Self::builder()
+                    .cert_file(cert_file)
+                    .key_file(key_file)
+                    .build(),
+            )),
+            _ => Err(RdapServerError::Config(format!(
+                "both {TLS_CERT} and {TLS_KEY} must be given for TLS"
+            ))),
+        }
+    }
+}
+
+/// Per-client rate limiting configuration.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// The number of requests per second a client may sustain.
+    pub rate: f64,
+
+    /// The number of requests a client may make in a burst.
+    pub burst: u32,
+
+    /// IPv4 clients within a network of this prefix length share a limit.
+    pub v4_prefix_len: u8,
+
+    /// IPv6 clients within a network of this prefix length share a limit.
+    pub v6_prefix_len: u8,
+
+    /// Determines where the IP address of the client is found, such as
+    /// a header set by a reverse proxy.
+    pub ip_source: SecureClientIpSource,
+}
+
+#[buildstructor::buildstructor]
+impl RateLimitConfig {
+    #[builder]
+    pub fn new(
+        rate: f64,
+        burst: u32,
+        v4_prefix_len: Option<u8>,
+        v6_prefix_len: Option<u8>,
+        ip_source: Option<SecureClientIpSource>,
+    ) -> Self {
+        Self {
+            rate,
+            burst,
+            v4_prefix_len: v4_prefix_len.unwrap_or(32),
+            v6_prefix_len: v6_prefix_len.unwrap_or(64),
+            ip_source: ip_source.unwrap_or(SecureClientIpSource::ConnectInfo),
+        }
+    }
+
+    /// Gets the rate limiting configuration from the environment, or `None` if
+    /// rate limiting is not enabled.
+    pub fn new_from_env() -> Result<Option<Self>, RdapServerError> {
+        let rate: f64 = get_parse_or(RATE_LIMIT, 0.0)?;
+        if rate <= 0.0 {
+            return Ok(None);
+        }
+        let burst = get_parse_or(RATE_LIMIT_BURST, rate.ceil() as u32)?;
+        let v4_prefix_len = get_parse_or(RATE_LIMIT_V4_PREFIX, 32)?;
+        let v6_prefix_len = get_parse_or(RATE_LIMIT_V6_PREFIX, 64)?;
+        let ip_source = get_or(CLIENT_IP_SOURCE, "ConnectInfo")
+            .parse::<SecureClientIpSource>()
+            .map_err(|e| RdapServerError::Config(e.to_string()))?;
+        Ok(Some(
+            Self::builder()
+                .rate(rate)
+                .burst(burst.max(1))
+                .v4_prefix_len(v4_prefix_len)
+                .v6_prefix_len(v6_prefix_len)
+                .ip_source(ip_source)
+                .build(),
+        ))
+    }
+}
+
+/// Authentication configuration. Each configured method is tried according
+/// to the scheme of the `Authorization` header, and requests without the header
+/// are anonymous.
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    /// A file of static bearer tokens, one per line as `token:role1,role2`.
+    pub tokens_file: Option<String>,
+
+    /// An htpasswd-style file of bcrypt password hashes for HTTP basic authentication,
+    /// one per line as `user:hash`, optionally followed by `:role1,role2`.
+    pub htpasswd_file: Option<String>,
+
+    /// A JWKS file of the keys used to validate bearer JWTs.
+    pub jwks_file: Option<String>,
+
+    /// If specified, JWTs must have this issuer.
+    pub jwt_issuer: Option<String>,
+
+    /// If specified, JWTs must have this audience.
+    pub jwt_audience: Option<String>,
+
+    /// The JWT claim holding the roles, either an array or a space separated string.
+    pub jwt_roles_claim: String,
+}
+
+#[buildstructor::buildstructor]
+impl AuthConfig {
+    #[builder]
+    pub fn new(
+        tokens_file: Option<String>,
+        htpasswd_file: Option<String>,
+        jwks_file: Option<String>,
+        jwt_issuer: Option<String>,
+        jwt_audience: Option<String>,
+        jwt_roles_claim: Option<String>,
+    ) -> Self {
+        Self {
+            tokens_file,
+            htpasswd_file,
+            jwks_file,
+            jwt_issuer,
+            jwt_audience,
+            jwt_roles_claim: jwt_roles_claim.unwrap_or("roles".to_string()),
+        }
+    }
+
+    /// Gets the authentication configuration from the environment, or `None` if
+    /// no authentication method is configured.
+    pub fn new_from_env() -> Result<Option<Self>, RdapServerError> {
+        let non_empty = |name: &str| Some(get_or(name, "")).filter(|value| !value.is_empty());
+        let config = Self::builder()
+            .and_tokens_file(non_empty(AUTH_TOKENS_FILE))
+            .and_htpasswd_file(non_empty(AUTH_HTPASSWD_FILE))
+            .and_jwks_file(non_empty(AUTH_JWKS_FILE))
+            .and_jwt_issuer(non_empty(AUTH_JWT_ISSUER))
+            .and_jwt_audience(non_empty(AUTH_JWT_AUDIENCE))
+            .and_jwt_roles_claim(non_empty(AUTH_JWT_ROLES_CLAIM))
+            .build();
+        if config.tokens_file.is_none()
+            && config.htpasswd_file.is_none()
+            && config.jwks_file.is_none()
+        {
+            Ok(None)
+        } else {
+            Ok(Some(config))
+        }
+    }
+}
+
+/// HTTP caching configuration, which determines the `cache-control` and `expires`
+/// headers of successful responses.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// The number of seconds a response may be cached when its object class
+    /// has no maximum age of its own.
+    pub max_age: Option<u64>,
+
+    /// The number of seconds responses may be cached by object class, such as
+    /// `domain`, `ip`, `help` or `domains`.
+    pub class_max_ages: HashMap<String, u64>,
+}
+
+#[buildstructor::buildstructor]
+impl CacheConfig {
+    #[builder]
+    pub fn new(max_age: Option<u64>, class_max_ages: HashMap<String, u64>) -> Self {
+        Self {
+            max_age,
+            class_max_ages,
+        }
+    }
+
+    /// Gets the caching configuration from the environment, or `None` if no
+    /// maximum age is configured. Maximum ages by object class are given as
+    /// `domain=3600,ip=600`.
+    pub fn new_from_env() -> Result<Option<Self>, RdapServerError> {
+        let max_age = match get_or(CACHE_MAX_AGE, "").as_str() {
+            "" => None,
+            max_age => Some(max_age.parse::<u64>().map_err(|e| {
+                RdapServerError::Config(format!("{CACHE_MAX_AGE} is invalid: {e}"))
+            })?),
+        };
+        let class_max_ages = get_or(CACHE_CLASS_MAX_AGES, "")
+            .split(',')
+            .map(str::trim)
+            .filter(|class_max_age| !class_max_age.is_empty())
+            .map(|class_max_age| {
+                class_max_age
+                    .split_once('=')
+                    .and_then(|(class, max_age)| {
+                        Some((class.trim().to_string(), max_age.trim().parse().ok()?))
+                    })
+                    .ok_or_else(|| {
+                        RdapServerError::Config(format!(
+                            "{CACHE_CLASS_MAX_AGES} entry '{class_max_age}' is invalid"
+                        ))
+                    })
+            })
+            .collect::<Result<HashMap<String, u64>, RdapServerError>>()?;
+        if max_age.is_none() && class_max_ages.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Self {
+                max_age,
+                class_max_ages,
+            }))
+        }
+    }
+
+    /// Gets the maximum age of responses of an object class.
+    pub fn max_age(&self, class: &str) -> Option<u64> {
+        self.class_max_ages.get(class).copied().or(self.max_age)
+    }
+}
+
+/// Link rewriting configuration. The self, related and up links of objects, and the links
+/// of notices, that point to this server are rewritten at response time, so that the same
+/// data can be served behind different host names, proxies and ports.
+#[derive(Debug, Clone)]
+pub struct LinkConfig {
+    /// The public base URL links are rewritten to, such as `https://rdap.example.com/rdap`.
+    /// If not specified, links are rewritten to the scheme and host of the request.
+    pub base_url: Option<String>,
+
+    /// The host names whose requests have their links rewritten when there is no public
+    /// base URL. As the `Host` header is chosen by the client, the links of requests with
+    /// other hosts are not rewritten.
+    pub hosts: Vec<String>,
+}
+
+#[buildstructor::buildstructor]
+impl LinkConfig {
+    #[builder]
+    pub fn new(base_url: Option<String>, hosts: Vec<String>) -> Self {
+        Self {
+            base_url: base_url.map(|base_url| base_url.trim_end_matches('/').to_string()),
+            hosts: hosts
+                .into_iter()
+                .map(|host| host.to_ascii_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Gets the link rewriting configuration from the environment, or `None` if links
+    /// are not rewritten. Configuring a public base URL or hosts turns rewriting on.
+    pub fn new_from_env() -> Result<Option<Self>, RdapServerError> {
+        let rewrite_links = get_parse_or(REWRITE_LINKS, false)?;
+        let base_url = Some(get_or(PUBLIC_BASE_URL, "")).filter(|base_url| !base_url.is_empty());
+        if let Some(base_url) = &base_url {
+            if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+                return Err(RdapServerError::Config(format!(
+                    "{PUBLIC_BASE_URL} of '{base_url}' is not an HTTP URL"
+                )));
+            }
+        }
+        let hosts = get_or(LINK_HOSTS, "")
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        if base_url.is_none() && hosts.is_empty() {
+            if rewrite_links {
+                return Err(RdapServerError::Config(format!(
+                    "{REWRITE_LINKS} requires {PUBLIC_BASE_URL} or {LINK_HOSTS}"
+                )));
+            }
+            return Ok(None);
+        }
+        Ok(Some(
+            Self::builder().and_base_url(base_url).hosts(hosts).build(),
+        ))
+    }
+}
+
+/// A virtual host, which is served the objects in the subdirectory of the data directory
+/// named after the host, apart from the objects of every other host.
+#[derive(Debug, Clone)]
+pub struct VirtualHostConfig {
+    /// The name of the host, as in the `Host` header of requests.
+    pub host: String,
+
+    /// If specified, the links of the objects served to the host are rewritten to this
+    /// base URL, such as `https://rdap.example.com/rdap`.
+    pub base_url: Option<String>,
+}
+
+#[buildstructor::buildstructor]
+impl VirtualHostConfig {
+    #[builder]
+    pub fn new(host: String, base_url: Option<String>) -> Self {
+        Self {
+            host: host.to_ascii_lowercase(),
+            base_url: base_url.map(|base_url| base_url.trim_end_matches('/').to_string()),
+        }
+    }
+
+    /// Gets the virtual hosts from the environment, given as
+    /// `rdap.example.com=https://rdap.example.com/rdap,rdap.example.net`.
+    pub fn new_from_env() -> Result<Vec<Self>, RdapServerError> {
+        get_or(VIRTUAL_HOSTS, "")
+            .split(',')
+            .map(str::trim)
+            .filter(|virtual_host| !virtual_host.is_empty())
+            .map(|virtual_host| {
+                let (host, base_url) = match virtual_host.split_once('=') {
+                    Some((host, base_url)) => (host.trim(), Some(base_url.trim().to_string())),
+                    None => (virtual_host, None),
+                };
+                if host.is_empty() || host.contains(['/', '\\']) || host.starts_with('.') {
+                    return Err(RdapServerError::Config(format!(
+                        "{VIRTUAL_HOSTS} entry '{virtual_host}' is invalid"
+                    )));
+                }
+                Ok(Self::builder().host(host).and_base_url(base_url).build())
+            })
+            .collect()
+    }
+
+    /// Gets the data directory of the host, which is a subdirectory of the data directory.
+    pub fn data_dir(&self, data_dir: &str) -> String {
+        Path::new(data_dir)
+            .join(&self.host)
+            .to_string_lossy()
+            .to_string()
+    }
 }
 
 /// Determines the storage type.
@@ -63,18 +479,50 @@ pub enum StorageType {
 
     /// Uses a PostgreSQL database.
     Postgres(PgConfig),
+
+    /// Uses an embedded SQLite database file.
+    Sqlite(SqliteConfig),
 }
 
 impl StorageType {
     pub fn new_from_env() -> Result<Self, RdapServerError> {
         let domain_search_by_name = get_parse_or(DOMAIN_SEARCH_BY_NAME_ENABLE, false)?;
+        let domain_search_by_ns_ldh_name =
+            get_parse_or(DOMAIN_SEARCH_BY_NS_LDH_NAME_ENABLE, false)?;
+        let domain_search_by_ns_ip = get_parse_or(DOMAIN_SEARCH_BY_NS_IP_ENABLE, false)?;
+        let nameserver_search_by_name = get_parse_or(NAMESERVER_SEARCH_BY_NAME_ENABLE, false)?;
+        let nameserver_search_by_ip = get_parse_or(NAMESERVER_SEARCH_BY_IP_ENABLE, false)?;
+        let entity_search_by_fn = get_parse_or(ENTITY_SEARCH_BY_FN_ENABLE, false)?;
+        let entity_search_by_handle = get_parse_or(ENTITY_SEARCH_BY_HANDLE_ENABLE, false)?;
+        let reverse_search = get_parse_or(REVERSE_SEARCH_ENABLE, false)?;
+        let partial_label_search = get_parse_or(
+            PARTIAL_LABEL_SEARCH_ENABLE,
+            CommonConfig::default().partial_label_search_enable,
+        )?;
         let common_config = CommonConfig::builder()
             .domain_search_by_name_enable(domain_search_by_name)
+            .domain_search_by_ns_ldh_name_enable(domain_search_by_ns_ldh_name)
+            .domain_search_by_ns_ip_enable(domain_search_by_ns_ip)
+            .nameserver_search_by_name_enable(nameserver_search_by_name)
+            .nameserver_search_by_ip_enable(nameserver_search_by_ip)
+            .entity_search_by_fn_enable(entity_search_by_fn)
+            .entity_search_by_handle_enable(entity_search_by_handle)
+            .reverse_search_enable(reverse_search)
+            .partial_label_search_enable(partial_label_search)
             .build();
         let storage = get_or(STORAGE, "memory");
+        if reverse_search && storage != "memory" {
+            return Err(RdapServerError::Config(format!(
+                "reverse search is only supported by memory storage, not '{storage}'"
+            )));
+        }
         if storage == "memory" {
+            let snapshot_file = Some(get_or(SNAPSHOT_FILE, "")).filter(|file| !file.is_empty());
             Ok(Self::Memory(
-                MemConfig::builder().common_config(common_config).build(),
+                MemConfig::builder()
+                    .common_config(common_config)
+                    .and_snapshot_file(snapshot_file)
+                    .build(),
             ))
         } else if storage == "postgres" {
             let db_url = get_or(DB_URL, "postgresql://127.0.0.1/rdap");
@@ -84,6 +532,14 @@ impl StorageType {
                     .common_config(common_config)
                     .build(),
             ))
+        } else if storage == "sqlite" {
+            let db_file = get_or(SQLITE_FILE, "/tmp/rdap-srv/rdap.sqlite");
+            Ok(Self::Sqlite(
+                SqliteConfig::builder()
+                    .db_file(db_file)
+                    .common_config(common_config)
+                    .build(),
+            ))
         } else {
             Err(RdapServerError::Config(format!(
                 "storage type of '{storage}' is invalid"
@@ -93,17 +549,74 @@ impl StorageType {
 }
 
 /// RDAP service configuration.
-#[derive(Debug, Builder, Clone)]
+#[derive(Debug, Clone)]
 pub struct ServiceConfig {
     pub storage_type: StorageType,
     pub data_dir: String,
     pub auto_reload: bool,
     pub bootstrap: bool,
     pub update_on_bootstrap: bool,
+    /// If specified, search results are returned in pages of this many results.
+    /// Otherwise all results are returned in one page.
+    pub search_page_size: Option<usize>,
+    pub rate_limit: Option<RateLimitConfig>,
+    pub auth: Option<AuthConfig>,
+    pub redaction_policy_file: Option<String>,
+    pub cache: Option<CacheConfig>,
+    /// If specified, the `/admin` API is served to authenticated clients with this role.
+    ///
+    /// Auto reload must then be off, so that admin changes are not undone by the data
+    /// files. Postgres and SQLite storage is managed by the admin API alone and the data
+    /// directory is not loaded into it. Memory storage is loaded from the data directory,
+    /// and admin changes to it last only until the server is restarted.
+    pub admin_role: Option<String>,
+    /// Hosts served their own objects. Requests for other hosts are served the objects
+    /// in the data directory itself.
+    pub virtual_hosts: Vec<VirtualHostConfig>,
+    /// If specified, links to this server are rewritten at response time.
+    pub links: Option<LinkConfig>,
+    /// The virtual host whose objects are served, which is only set in the configuration
+    /// of the service of each virtual host.
+    pub host: Option<String>,
 }
 
 #[buildstructor::buildstructor]
 impl ServiceConfig {
+    #[builder]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        storage_type: StorageType,
+        data_dir: String,
+        auto_reload: bool,
+        bootstrap: bool,
+        update_on_bootstrap: bool,
+        search_page_size: Option<usize>,
+        rate_limit: Option<RateLimitConfig>,
+        auth: Option<AuthConfig>,
+        redaction_policy_file: Option<String>,
+        cache: Option<CacheConfig>,
+        admin_role: Option<String>,
+        virtual_hosts: Vec<VirtualHostConfig>,
+        links: Option<LinkConfig>,
+    ) -> Self {
+        Self {
+            storage_type,
+            data_dir,
+            auto_reload,
+            bootstrap,
+            update_on_bootstrap,
+            search_page_size,
+            rate_limit,
+            auth,
+            redaction_policy_file,
+            cache,
+            admin_role,
+            virtual_hosts,
+            links,
+            host: None,
+        }
+    }
+
     #[builder(entry = "non_server")]
     pub fn new_non_server(
         data_dir: String,
@@ -120,6 +633,15 @@ impl ServiceConfig {
             auto_reload: false,
             bootstrap: false,
             update_on_bootstrap: false,
+            search_page_size: None,
+            rate_limit: None,
+            auth: None,
+            redaction_policy_file: None,
+            cache: None,
+            admin_role: None,
+            virtual_hosts: vec![],
+            links: None,
+            host: None,
         })
     }
 }
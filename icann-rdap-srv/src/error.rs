@@ -26,6 +26,8 @@ pub enum RdapServerError {
     Config(String),
     #[error(transparent)]
     SqlDb(#[from] sqlx::Error),
+    #[error(transparent)]
+    SqlMigrate(#[from] sqlx::migrate::MigrateError),
     #[error("index data for {0} is missing or empty")]
     EmptyIndexData(String),
     #[error("file at {0} is not JSON")]
@@ -58,6 +60,22 @@ pub enum RdapServerError {
     Bootstrap(String),
     #[error(transparent)]
     RdapClientError(#[from] RdapClientError),
+    #[error(transparent)]
+    Tls(#[from] tokio_rustls::rustls::Error),
+    #[error(transparent)]
+    Watch(#[from] notify::Error),
+    #[error("RDAP response is not a domain, entity, nameserver, autnum, or network")]
+    NonObjectDocument,
+    #[error("unable to redact {0}")]
+    Redaction(String),
+    #[error("{1} documents in {0} cannot be loaded")]
+    BadDocuments(String, usize),
+    #[error("snapshot at {0} is not usable: {1}")]
+    Snapshot(String, String),
+    #[error(transparent)]
+    SnapshotEncode(#[from] rmp_serde::encode::Error),
+    #[error(transparent)]
+    SnapshotDecode(#[from] rmp_serde::decode::Error),
 }
 
 impl IntoResponse for RdapServerError {
@@ -1,7 +1,16 @@
+pub mod admin;
+pub mod auth;
 pub mod bootstrap;
+pub mod caching;
 pub mod config;
 pub mod error;
+pub mod links;
+pub mod metrics;
+pub mod rate_limit;
 pub mod rdap;
+pub mod redact;
 pub mod server;
+pub mod status;
 pub mod storage;
+pub mod tls;
 pub mod util;
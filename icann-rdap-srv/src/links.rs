@@ -0,0 +1,435 @@
+use std::{collections::HashSet, str::FromStr, sync::Arc};
+
+use axum::{
+    extract::{Host, Request, State},
+    http::uri::{Authority, Scheme, Uri},
+    middleware::Next,
+    response::Response,
+};
+use http::{header::VARY, Extensions, HeaderMap, HeaderValue};
+use serde_json::Value;
+
+use crate::{config::LinkConfig, rdap::response::map_rdap_json, redact::SEARCH_RESULTS};
+
+/// The first segments of the paths of RDAP queries, which follow the base URL in links.
+const QUERY_SEGMENTS: [&str; 9] = [
+    "domain",
+    "nameserver",
+    "entity",
+    "autnum",
+    "ip",
+    "domains",
+    "nameservers",
+    "entities",
+    "help",
+];
+
+/// The relations of the links of objects that are rewritten, if they point to this server.
+const REWRITTEN_RELS: [&str; 4] = ["self", "related", "up", "next"];
+
+/// The header of the scheme of the request to a proxy in front of the server.
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// The base URL that links are rewritten to, which is put in the extensions of the request
+/// so that the links made for the response, such as those to other pages of search
+/// results, are made with it.
+#[derive(Clone, Debug)]
+pub(crate) struct LinkBase(pub(crate) String);
+
+/// Middleware rewriting the links of successful RDAP responses to the configured base URL,
+/// or to the scheme and host of the request if it is one of the configured hosts, so that
+/// objects are linked to the host they are served by.
+pub(crate) async fn rewrite_links(
+    State(links): State<Arc<LinkConfig>>,
+    host: Option<Host>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let base_url = links
+        .base_url
+        .clone()
+        .or_else(|| request_base_url(&links, host?, &request));
+    if let Some(base_url) = &base_url {
+        request.extensions_mut().insert(LinkBase(base_url.clone()));
+    }
+    let mut response = next.run(request).await;
+    if links.base_url.is_none() {
+        // the links depend on the headers the host and scheme are taken from
+        response.headers_mut().append(
+            VARY,
+            HeaderValue::from_static("host, x-forwarded-host, x-forwarded-proto"),
+        );
+    }
+    let Some(base_url) = base_url else {
+        return response;
+    };
+    map_rdap_json(response, "rewrite links of", |mut value| {
+        rebase_links(&mut value, &base_url, &links.hosts);
+        Ok(value)
+    })
+    .await
+}
+
+/// Gets the base URL of the scheme and host of a request, or `None` if the host is not one
+/// of the configured hosts.
+fn request_base_url(links: &LinkConfig, Host(host): Host, request: &Request) -> Option<String> {
+    let authority = Authority::from_str(&host).ok()?;
+    if !links
+        .hosts
+        .iter()
+        .any(|link_host| authority.host().eq_ignore_ascii_case(link_host))
+    {
+        return None;
+    }
+    let scheme = request_scheme(request.headers(), request.extensions());
+    Some(format!("{scheme}://{authority}/rdap"))
+}
+
+/// Gets the scheme of a request, which is that of the request to the proxy in front of the
+/// server if there is one, or else that of the request to the server.
+pub(crate) fn request_scheme(headers: &HeaderMap, extensions: &Extensions) -> String {
+    let forwarded = headers
+        .get(X_FORWARDED_PROTO)
+        .and_then(|proto| proto.to_str().ok())
+        .and_then(|proto| proto.split(',').next())
+        .map(|proto| proto.trim().to_ascii_lowercase())
+        .filter(|proto| proto == "http" || proto == "https");
+    // the TLS server marks requests with the scheme they arrived on
+    forwarded.unwrap_or_else(|| {
+        extensions
+            .get::<Scheme>()
+            .map_or("http", |scheme| scheme.as_str())
+            .to_string()
+    })
+}
+
+/// Rewrites the links of an RDAP response, including those of the objects and notices
+/// in it, to a base URL. A link is rewritten only if it has the base of a self link of the
+/// top-level objects, the base URL itself, or a base with one of the given hosts, so that
+/// links to other servers, such as those of embedded registrar objects, are kept. If the
+/// top-level objects have no self links, as in help responses, the links of notices are
+/// rewritten whatever their base.
+pub(crate) fn rebase_links(value: &mut Value, base_url: &str, hosts: &[String]) {
+    let mut bases = HashSet::new();
+    top_level_self_link_bases(value, &mut bases);
+    let any_notice_link = bases.is_empty();
+    bases.insert(base_url.to_string());
+    let bases = LinkBases {
+        bases,
+        hosts,
+        any_notice_link,
+    };
+    rebase_members(value, base_url, &bases, false);
+}
+
+/// The bases of the links that are rewritten.
+struct LinkBases<'a> {
+    bases: HashSet<String>,
+    hosts: &'a [String],
+    any_notice_link: bool,
+}
+
+impl LinkBases<'_> {
+    fn contains(&self, base: &str) -> bool {
+        self.bases.contains(base)
+            || base.parse::<Uri>().ok().is_some_and(|uri| {
+                uri.host().is_some_and(|host| {
+                    self.hosts
+                        .iter()
+                        .any(|link_host| host.eq_ignore_ascii_case(link_host))
+                })
+            })
+    }
+}
+
+/// Collects the bases of the self links of the top-level objects of an RDAP response,
+/// which are the response itself or the objects of search results.
+fn top_level_self_link_bases(value: &Value, bases: &mut HashSet<String>) {
+    let search_results = SEARCH_RESULTS
+        .iter()
+        .find_map(|name| value.get(name))
+        .and_then(Value::as_array);
+    let objects = match search_results {
+        Some(results) => results.iter().collect::<Vec<_>>(),
+        None => vec![value],
+    };
+    for object in objects {
+        if let Some(Value::Array(links)) = object.get("links") {
+            links
+                .iter()
+                .filter(|link| rel(link) == Some("self"))
+                .flat_map(|link| ["href", "value"].map(|member| link.get(member)))
+                .filter_map(|url| split_base(url?.as_str()?))
+                .for_each(|(base, _)| {
+                    bases.insert(base.to_string());
+                });
+        }
+    }
+}
+
+/// Rewrites the links of a JSON value, which is a notice if `notice` is true.
+fn rebase_members(value: &mut Value, base_url: &str, bases: &LinkBases, notice: bool) {
+    match value {
+        Value::Object(members) => {
+            if let Some(Value::Array(links)) = members.get_mut("links") {
+                for link in links.iter_mut() {
+                    if notice && bases.any_notice_link {
+                        rebase_link(link, base_url, None);
+                    } else if notice || rel(link).is_some_and(|rel| REWRITTEN_RELS.contains(&rel)) {
+                        rebase_link(link, base_url, Some(bases));
+                    }
+                }
+            }
+            for (name, value) in members.iter_mut() {
+                rebase_members(value, base_url, bases, name == "notices");
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| rebase_members(value, base_url, bases, notice)),
+        _ => {}
+    }
+}
+
+fn rel(link: &Value) -> Option<&str> {
+    link.get("rel").and_then(Value::as_str)
+}
+
+/// Rewrites the `href` and `value` of a link to a base URL, if the link has one of the
+/// given bases or no bases are given.
+fn rebase_link(link: &mut Value, base_url: &str, bases: Option<&LinkBases>) {
+    for member in ["href", "value"] {
+        let rebased = link
+            .get(member)
+            .and_then(Value::as_str)
+            .and_then(split_base)
+            .filter(|(base, _)| bases.is_none_or(|bases| bases.contains(base)))
+            .map(|(_, query)| format!("{base_url}{query}"));
+        if let Some(rebased) = rebased {
+            link[member] = Value::String(rebased);
+        }
+    }
+}
+
+/// Splits an RDAP URL into its base and the path of the query.
+/// Gets `None` if the URL is not of an RDAP query.
+fn split_base(url: &str) -> Option<(&str, &str)> {
+    // the path begins after the authority, so that host names are not taken for queries
+    let authority = url.find("://")? + 3;
+    let path = authority + url[authority..].find('/')?;
+    let query = QUERY_SEGMENTS
+        .iter()
+        .filter_map(|segment| {
+            url[path..]
+                .match_indices(&format!("/{segment}"))
+                .map(|(i, _)| path + i)
+                .find(|i| {
+                    matches!(
+                        url[i + segment.len() + 1..].chars().next(),
+                        None | Some('/') | Some('?')
+                    )
+                })
+        })
+        .min()?;
+    Some(url.split_at(query))
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::{rebase_links, split_base};
+
+    #[rstest]
+    #[case(
+        "https://old.example/rdap/domain/foo.example",
+        Some(("https://old.example/rdap", "/domain/foo.example"))
+    )]
+    #[case(
+        "http://localhost:3000/rdap/domains?name=foo*",
+        Some(("http://localhost:3000/rdap", "/domains?name=foo*"))
+    )]
+    #[case(
+        "https://old.example/ip/10.0.0.0/8",
+        Some(("https://old.example", "/ip/10.0.0.0/8"))
+    )]
+    #[case("https://domain/rdap/help", Some(("https://domain/rdap", "/help")))]
+    #[case("https://old.example/rdap/domainx/foo", None)]
+    #[case("https://old.example/terms-of-service", None)]
+    #[case("not a url", None)]
+    fn GIVEN_url_WHEN_split_base_THEN_base_and_query_returned(
+        #[case] url: &str,
+        #[case] expected: Option<(&str, &str)>,
+    ) {
+        // GIVEN in case
+
+        // WHEN
+        let actual = split_base(url);
+
+        // THEN
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn GIVEN_nested_links_WHEN_rebase_links_THEN_links_to_server_rewritten() {
+        // GIVEN
+        let mut value = json!({
+            "objectClassName": "domain",
+            "notices": [{
+                "title": "Terms",
+                "links": [
+                    {"rel": "alternate", "href": "https://old.example/rdap/help"},
+                    {"rel": "terms-of-service", "href": "https://old.example/terms-of-service"}
+                ]
+            }],
+            "links": [
+                {"rel": "self", "href": "https://old.example/rdap/domain/foo.example",
+                 "value": "https://old.example/rdap/domain/foo.example"},
+                {"rel": "related", "href": "https://registrar.example/rdap/domain/foo.example"},
+                {"rel": "up", "href": "https://old.example/rdap/domain/example"},
+                {"rel": "alternate", "href": "https://old.example/rdap/domain/foo.example"}
+            ],
+            "entities": [{
+                "objectClassName": "entity",
+                "links": [{"rel": "self", "href": "https://old.example/rdap/entity/bob"}]
+            }]
+        });
+
+        // WHEN
+        rebase_links(&mut value, "https://new.example/rdap", &[]);
+
+        // THEN
+        assert_eq!(
+            value["links"][0]["href"],
+            "https://new.example/rdap/domain/foo.example"
+        );
+        assert_eq!(
+            value["links"][0]["value"],
+            "https://new.example/rdap/domain/foo.example"
+        );
+        assert_eq!(
+            value["links"][1]["href"],
+            "https://registrar.example/rdap/domain/foo.example"
+        );
+        assert_eq!(
+            value["links"][2]["href"],
+            "https://new.example/rdap/domain/example"
+        );
+        assert_eq!(
+            value["links"][3]["href"],
+            "https://old.example/rdap/domain/foo.example"
+        );
+        assert_eq!(
+            value["entities"][0]["links"][0]["href"],
+            "https://new.example/rdap/entity/bob"
+        );
+        assert_eq!(
+            value["notices"][0]["links"][0]["href"],
+            "https://new.example/rdap/help"
+        );
+        assert_eq!(
+            value["notices"][0]["links"][1]["href"],
+            "https://old.example/terms-of-service"
+        );
+    }
+
+    #[test]
+    fn GIVEN_foreign_nested_self_link_WHEN_rebase_links_THEN_foreign_links_kept() {
+        // GIVEN
+        let mut value = json!({
+            "objectClassName": "domain",
+            "links": [
+                {"rel": "self", "href": "https://old.example/rdap/domain/foo.example"},
+                {"rel": "related", "href": "https://rdap.registrar.example/rdap/domain/foo.example"}
+            ],
+            "entities": [{
+                "objectClassName": "entity",
+                "links": [{"rel": "self", "href": "https://rdap.registrar.example/rdap/entity/X"}]
+            }]
+        });
+
+        // WHEN
+        rebase_links(&mut value, "https://new.example/rdap", &[]);
+
+        // THEN
+        assert_eq!(
+            value["links"][0]["href"],
+            "https://new.example/rdap/domain/foo.example"
+        );
+        assert_eq!(
+            value["links"][1]["href"],
+            "https://rdap.registrar.example/rdap/domain/foo.example"
+        );
+        assert_eq!(
+            value["entities"][0]["links"][0]["href"],
+            "https://rdap.registrar.example/rdap/entity/X"
+        );
+    }
+
+    #[test]
+    fn GIVEN_no_top_level_self_link_WHEN_rebase_links_THEN_related_link_kept() {
+        // GIVEN
+        let mut value = json!({
+            "objectClassName": "domain",
+            "links": [
+                {"rel": "related", "href": "https://rdap.registrar.example/rdap/domain/foo.example"}
+            ]
+        });
+
+        // WHEN
+        rebase_links(&mut value, "https://new.example/rdap", &[]);
+
+        // THEN
+        assert_eq!(
+            value["links"][0]["href"],
+            "https://rdap.registrar.example/rdap/domain/foo.example"
+        );
+    }
+
+    #[test]
+    fn GIVEN_link_to_configured_host_WHEN_rebase_links_THEN_link_rewritten() {
+        // GIVEN
+        let mut value = json!({
+            "objectClassName": "domain",
+            "links": [
+                {"rel": "up", "href": "http://rdap.example.com:3000/rdap/domain/example"}
+            ]
+        });
+
+        // WHEN
+        rebase_links(
+            &mut value,
+            "https://new.example/rdap",
+            &["rdap.example.com".to_string()],
+        );
+
+        // THEN
+        assert_eq!(
+            value["links"][0]["href"],
+            "https://new.example/rdap/domain/example"
+        );
+    }
+
+    #[test]
+    fn GIVEN_no_self_links_WHEN_rebase_links_THEN_notice_links_rewritten() {
+        // GIVEN
+        let mut value = json!({
+            "notices": [{
+                "title": "Help",
+                "links": [{"rel": "alternate", "href": "http://localhost:3000/rdap/help"}]
+            }]
+        });
+
+        // WHEN
+        rebase_links(&mut value, "https://new.example/rdap", &[]);
+
+        // THEN
+        assert_eq!(
+            value["notices"][0]["links"][0]["href"],
+            "https://new.example/rdap/help"
+        );
+    }
+}
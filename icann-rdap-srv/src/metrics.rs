@@ -0,0 +1,190 @@
+use std::{collections::HashMap, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{header::CONTENT_TYPE, StatusCode};
+use lazy_static::lazy_static;
+use prometheus::{
+    core::Collector, register_histogram_vec_with_registry, register_int_counter_vec_with_registry,
+    register_int_gauge_vec_with_registry, Encoder, HistogramVec, IntCounterVec, IntGaugeVec,
+    Registry, TextEncoder,
+};
+use tracing::warn;
+
+/// The object class used for requests that do not match a route.
+const UNMATCHED: &str = "unmatched";
+
+lazy_static! {
+    static ref REGISTRY: Registry = Registry::new();
+    static ref REQUESTS: IntCounterVec = register_int_counter_vec_with_registry!(
+        "rdap_srv_requests_total",
+        "Number of requests by object class and HTTP status code.",
+        &["class", "status"],
+        REGISTRY
+    )
+    .expect("registering requests metric");
+    static ref REQUEST_DURATION: HistogramVec = register_histogram_vec_with_registry!(
+        "rdap_srv_request_duration_seconds",
+        "Latency of requests by object class.",
+        &["class"],
+        REGISTRY
+    )
+    .expect("registering request duration metric");
+    static ref OBJECTS_LOADED: IntGaugeVec = register_int_gauge_vec_with_registry!(
+        "rdap_srv_objects_loaded",
+        "Number of objects loaded by host and object class in the last load of the data directory. \
+         The host is empty for the objects of the data directory itself.",
+        &["host", "class"],
+        REGISTRY
+    )
+    .expect("registering objects loaded metric");
+    static ref BOOTSTRAP_REFRESHES: IntCounterVec = register_int_counter_vec_with_registry!(
+        "rdap_srv_bootstrap_refreshes_total",
+        "Number of IANA bootstrap refreshes by result.",
+        &["result"],
+        REGISTRY
+    )
+    .expect("registering bootstrap refreshes metric");
+}
+
+/// Middleware recording the count and latency of requests, including those rejected
+/// before reaching a handler and those matching no route.
+pub(crate) async fn track_metrics(request: Request, next: Next) -> Response {
+    let class = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED.to_string(), |path| object_class(path.as_str()));
+    let start = Instant::now();
+    let response = next.run(request).await;
+    REQUEST_DURATION
+        .with_label_values(&[&class])
+        .observe(start.elapsed().as_secs_f64());
+    REQUESTS
+        .with_label_values(&[&class, response.status().as_str()])
+        .inc();
+    response
+}
+
+/// Gets the object class of a route from its path by removing the path
+/// parameters and the `/rdap` prefix (e.g. `/rdap/domains/reverse_search/:related`
+/// is `domains/reverse_search`).
+pub(crate) fn object_class(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| {
+            !segment.is_empty()
+                && *segment != "rdap"
+                && !segment.starts_with(':')
+                && !segment.starts_with('*')
+        })
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+/// Records the number of objects, by object class, loaded from the data directory of a
+/// virtual host, or of the server itself if there is no host. The counts of the classes
+/// no longer loaded are removed, leaving those of the other hosts.
+pub(crate) fn set_objects_loaded(host: Option<&str>, counts: &HashMap<&'static str, usize>) {
+    let host = host.unwrap_or_default();
+    let stale = OBJECTS_LOADED
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .filter_map(|metric| {
+            let label = |name: &str| {
+                metric
+                    .get_label()
+                    .iter()
+                    .find(|label| label.get_name() == name)
+                    .map(|label| label.get_value().to_string())
+            };
+            (label("host")? == host)
+                .then(|| label("class"))
+                .flatten()
+                .filter(|class| !counts.contains_key(class.as_str()))
+        })
+        .collect::<Vec<String>>();
+    for class in stale {
+        let _ = OBJECTS_LOADED.remove_label_values(&[host, &class]);
+    }
+    for (class, count) in counts {
+        OBJECTS_LOADED
+            .with_label_values(&[host, class])
+            .set(*count as i64);
+    }
+}
+
+/// Records the result of a bootstrap refresh.
+pub(crate) fn record_bootstrap_refresh(success: bool) {
+    let result = if success { "success" } else { "failure" };
+    BOOTSTRAP_REFRESHES.with_label_values(&[result]).inc();
+}
+
+/// Serves the metrics in the Prometheus text format.
+pub(crate) async fn metrics() -> Response {
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    if let Err(e) = encoder.encode(&REGISTRY.gather(), &mut buffer) {
+        warn!("unable to encode metrics: {e}");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    ([(CONTENT_TYPE, encoder.format_type().to_string())], buffer).into_response()
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use super::{object_class, set_objects_loaded, OBJECTS_LOADED};
+
+    #[rstest]
+    #[case("/rdap/domain/:domain", "domain")]
+    #[case("/rdap/ip/*netid", "ip")]
+    #[case("/rdap/domains", "domains")]
+    #[case("/rdap/entities/reverse_search/:related", "entities/reverse_search")]
+    #[case("/rdap/help", "help")]
+    fn GIVEN_route_path_WHEN_object_class_THEN_parameters_removed(
+        #[case] path: &str,
+        #[case] expected: &str,
+    ) {
+        // GIVEN in parameters
+
+        // WHEN
+        let actual = object_class(path);
+
+        // THEN
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn GIVEN_objects_loaded_for_hosts_WHEN_set_objects_loaded_for_one_host_THEN_only_its_counts_replaced(
+    ) {
+        // GIVEN
+        let counts = HashMap::from([("domain", 2), ("entity", 3)]);
+        set_objects_loaded(Some("a.example"), &counts);
+        set_objects_loaded(Some("b.example"), &counts);
+
+        // WHEN
+        set_objects_loaded(Some("a.example"), &HashMap::from([("domain", 1)]));
+
+        // THEN
+        let get = |host, class| {
+            OBJECTS_LOADED
+                .get_metric_with_label_values(&[host, class])
+                .expect("getting gauge")
+                .get()
+        };
+        assert_eq!(get("a.example", "domain"), 1);
+        assert_eq!(get("b.example", "domain"), 2);
+        assert_eq!(get("b.example", "entity"), 3);
+        assert!(OBJECTS_LOADED
+            .remove_label_values(&["a.example", "entity"])
+            .is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use axum_client_ip::SecureClientIp;
+use http::{header::RETRY_AFTER, HeaderValue};
+use ipnet::IpNet;
+use tracing::debug;
+
+use crate::{
+    config::RateLimitConfig,
+    rdap::response::{ResponseUtil, TOO_MANY_REQUESTS},
+};
+
+/// The most buckets kept. When there are this many, the least recently used bucket is removed
+/// to make room for a new one.
+const MAX_BUCKETS: usize = 100_000;
+
+/// A token bucket. Each request takes a token, and tokens are added back at the configured rate.
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, config: &RateLimitConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * config.rate).min(config.burst as f64);
+        self.updated = now;
+    }
+}
+
+/// Token buckets by CIDR, along with the order in which they were last used.
+#[derive(Default)]
+struct Buckets {
+    clock: u64,
+    buckets: HashMap<IpNet, (u64, TokenBucket)>,
+    uses: BTreeMap<u64, IpNet>,
+}
+
+impl Buckets {
+    fn remove(&mut self, key: &IpNet) -> Option<TokenBucket> {
+        let (used, bucket) = self.buckets.remove(key)?;
+        self.uses.remove(&used);
+        Some(bucket)
+    }
+
+    fn insert(&mut self, key: IpNet, bucket: TokenBucket) {
+        self.clock += 1;
+        self.uses.insert(self.clock, key);
+        self.buckets.insert(key, (self.clock, bucket));
+    }
+
+    /// Removes the least recently used bucket if `remove` is true of it.
+    fn remove_lru_if(&mut self, remove: impl Fn(&TokenBucket) -> bool) -> bool {
+        let Some((_, key)) = self.uses.first_key_value() else {
+            return false;
+        };
+        let key = *key;
+        if self
+            .buckets
+            .get(&key)
+            .is_some_and(|(_, bucket)| remove(bucket))
+        {
+            self.remove(&key);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Limits the rate of requests from clients, where clients with addresses in the
+/// same CIDR bucket share a limit.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Takes a token from the bucket of the client. If there are no tokens, the number of seconds
+    /// until there will be one is returned as the error.
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Result<(), u64> {
+        let key = self.bucket_key(ip);
+        let mut buckets = self.buckets.lock().expect("rate limit buckets poisoned");
+
+        // buckets unused for long enough to have refilled are the same as new ones, and
+        // being least recently used they are removed from the front.
+        let refill_time = Duration::try_from_secs_f64(self.config.burst as f64 / self.config.rate)
+            .unwrap_or(Duration::MAX);
+        let refilled =
+            |bucket: &TokenBucket| now.saturating_duration_since(bucket.updated) >= refill_time;
+        while buckets.remove_lru_if(refilled) {}
+        let mut bucket = buckets.remove(&key).unwrap_or(TokenBucket {
+            tokens: self.config.burst as f64,
+            updated: now,
+        });
+        while buckets.buckets.len() >= MAX_BUCKETS && buckets.remove_lru_if(|_| true) {}
+
+        bucket.refill(&self.config, now);
+        let result = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let retry_after = ((1.0 - bucket.tokens) / self.config.rate).ceil() as u64;
+            Err(retry_after.max(1))
+        };
+        buckets.insert(key, bucket);
+        result
+    }
+
+    fn bucket_key(&self, ip: IpAddr) -> IpNet {
+        let ip = ip.to_canonical();
+        let prefix_len = match ip {
+            IpAddr::V4(_) => self.config.v4_prefix_len,
+            IpAddr::V6(_) => self.config.v6_prefix_len,
+        };
+        IpNet::new(ip, prefix_len)
+            .unwrap_or_else(|_| IpNet::from(ip))
+            .trunc()
+    }
+}
+
+/// Middleware rejecting requests from clients over their rate limit with a 429 RDAP error.
+pub(crate) async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    SecureClientIp(ip): SecureClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limiter.check(ip, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            debug!("rate limiting {ip} for {retry_after} seconds");
+            let mut response = TOO_MANY_REQUESTS.response();
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use std::{
+        net::IpAddr,
+        time::{Duration, Instant},
+    };
+
+    use crate::config::RateLimitConfig;
+
+    use super::{RateLimiter, MAX_BUCKETS};
+
+    fn new_limiter() -> RateLimiter {
+        RateLimiter::new(
+            RateLimitConfig::builder()
+                .rate(1.0)
+                .burst(2)
+                .v4_prefix_len(24)
+                .build(),
+        )
+    }
+
+    #[test]
+    fn GIVEN_limiter_WHEN_burst_exceeded_THEN_retry_after_returned() {
+        // GIVEN
+        let limiter = new_limiter();
+        let ip = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+
+        // WHEN
+        let actual = [
+            limiter.check(ip, now),
+            limiter.check(ip, now),
+            limiter.check(ip, now),
+        ];
+
+        // THEN
+        assert_eq!(actual, [Ok(()), Ok(()), Err(1)]);
+    }
+
+    #[test]
+    fn GIVEN_limiter_with_empty_bucket_WHEN_time_passes_THEN_request_allowed() {
+        // GIVEN
+        let limiter = new_limiter();
+        let ip = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+        limiter.check(ip, now).expect("first request");
+        limiter.check(ip, now).expect("second request");
+
+        // WHEN
+        let actual = limiter.check(ip, now + Duration::from_secs(1));
+
+        // THEN
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn GIVEN_limiter_WHEN_clients_in_same_cidr_THEN_limit_shared() {
+        // GIVEN
+        let limiter = new_limiter();
+        let now = Instant::now();
+        limiter
+            .check(IpAddr::from([192, 0, 2, 1]), now)
+            .expect("first request");
+        limiter
+            .check(IpAddr::from([192, 0, 2, 2]), now)
+            .expect("second request");
+
+        // WHEN
+        let same_cidr = limiter.check(IpAddr::from([192, 0, 2, 3]), now);
+        let other_cidr = limiter.check(IpAddr::from([198, 51, 100, 1]), now);
+
+        // THEN
+        assert!(same_cidr.is_err());
+        assert!(other_cidr.is_ok());
+    }
+
+    #[test]
+    fn GIVEN_refilled_bucket_WHEN_other_client_checked_THEN_refilled_bucket_removed() {
+        // GIVEN
+        let limiter = new_limiter();
+        let now = Instant::now();
+        limiter
+            .check(IpAddr::from([192, 0, 2, 1]), now)
+            .expect("first client");
+
+        // WHEN
+        limiter
+            .check(
+                IpAddr::from([198, 51, 100, 1]),
+                now + Duration::from_secs(2),
+            )
+            .expect("second client");
+
+        // THEN
+        let buckets = limiter.buckets.lock().expect("buckets lock");
+        assert_eq!(buckets.buckets.len(), 1);
+        assert_eq!(buckets.uses.len(), 1);
+    }
+
+    #[test]
+    fn GIVEN_max_buckets_WHEN_new_client_checked_THEN_least_recently_used_removed() {
+        // GIVEN
+        let limiter = new_limiter();
+        let now = Instant::now();
+        for i in 0..MAX_BUCKETS as u32 {
+            limiter
+                .check(IpAddr::from((i << 8).to_be_bytes()), now)
+                .expect("client");
+        }
+        let first = IpAddr::from([0, 0, 0, 0]);
+        limiter.check(first, now).expect("first client again");
+
+        // WHEN
+        let actual = limiter.check(IpAddr::from([255, 255, 255, 1]), now);
+
+        // THEN
+        assert!(actual.is_ok());
+        let buckets = limiter.buckets.lock().expect("buckets lock");
+        assert_eq!(buckets.buckets.len(), MAX_BUCKETS);
+        assert!(buckets
+            .buckets
+            .contains_key(&"0.0.0.0/24".parse().expect("cidr")));
+        assert!(!buckets
+            .buckets
+            .contains_key(&"0.0.1.0/24".parse().expect("cidr")));
+    }
+}
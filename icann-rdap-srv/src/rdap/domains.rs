@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use axum::{
     extract::{Query, State},
     response::Response,
@@ -7,29 +9,50 @@ use serde::Deserialize;
 
 use crate::{error::RdapServerError, rdap::response::ResponseUtil, server::DynServiceState};
 
-use super::response::NOT_IMPLEMENTED;
+use super::{
+    response::{BAD_REQUEST, NOT_IMPLEMENTED},
+    search_controls::SearchControls,
+};
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct DomainsParams {
     name: Option<String>,
 
     #[serde(rename = "nsLdhName")]
-    _ns_ldh_name: Option<String>,
+    ns_ldh_name: Option<String>,
 
     #[serde(rename = "nsIp")]
-    _ns_ip: Option<String>,
+    ns_ip: Option<String>,
 }
 
 #[axum_macros::debug_handler]
 #[tracing::instrument(level = "debug")]
 pub(crate) async fn domains(
     Query(params): Query<DomainsParams>,
+    controls: SearchControls,
     state: State<DynServiceState>,
 ) -> Result<Response, RdapServerError> {
     Ok(if let Some(name) = params.name {
         let storage = state.get_storage().await?;
         let results = storage.search_domains_by_name(&name).await?;
-        results.response()
+        controls
+            .apply(results, state.get_search_page_size())
+            .response()
+    } else if let Some(ns_ldh_name) = params.ns_ldh_name {
+        let storage = state.get_storage().await?;
+        let results = storage.search_domains_by_ns_ldh_name(&ns_ldh_name).await?;
+        controls
+            .apply(results, state.get_search_page_size())
+            .response()
+    } else if let Some(ns_ip) = params.ns_ip {
+        if ns_ip.parse::<IpAddr>().is_err() {
+            return Ok(BAD_REQUEST.response());
+        }
+        let storage = state.get_storage().await?;
+        let results = storage.search_domains_by_ns_ip(&ns_ip).await?;
+        controls
+            .apply(results, state.get_search_page_size())
+            .response()
     } else {
         NOT_IMPLEMENTED.response()
     })
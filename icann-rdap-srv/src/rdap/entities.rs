@@ -0,0 +1,42 @@
+use axum::{
+    extract::{Query, State},
+    response::Response,
+};
+
+use serde::Deserialize;
+
+use crate::{error::RdapServerError, rdap::response::ResponseUtil, server::DynServiceState};
+
+use super::{response::NOT_IMPLEMENTED, search_controls::SearchControls};
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EntitiesParams {
+    #[serde(rename = "fn")]
+    full_name: Option<String>,
+
+    handle: Option<String>,
+}
+
+#[axum_macros::debug_handler]
+#[tracing::instrument(level = "debug")]
+pub(crate) async fn entities(
+    Query(params): Query<EntitiesParams>,
+    controls: SearchControls,
+    state: State<DynServiceState>,
+) -> Result<Response, RdapServerError> {
+    Ok(if let Some(full_name) = params.full_name {
+        let storage = state.get_storage().await?;
+        let results = storage.search_entities_by_fn(&full_name).await?;
+        controls
+            .apply(results, state.get_search_page_size())
+            .response()
+    } else if let Some(handle) = params.handle {
+        let storage = state.get_storage().await?;
+        let results = storage.search_entities_by_handle(&handle).await?;
+        controls
+            .apply(results, state.get_search_page_size())
+            .response()
+    } else {
+        NOT_IMPLEMENTED.response()
+    })
+}
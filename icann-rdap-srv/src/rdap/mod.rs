@@ -3,11 +3,15 @@ use icann_rdap_common::response::{error::Error, RdapResponse};
 pub mod autnum;
 pub mod domain;
 pub mod domains;
+pub mod entities;
 pub mod entity;
 pub mod ip;
 pub mod nameserver;
+pub mod nameservers;
 pub mod response;
+pub mod reverse;
 pub mod router;
+pub mod search_controls;
 pub mod srvhelp;
 
 trait ToBootStrap {
@@ -0,0 +1,49 @@
+use std::net::IpAddr;
+
+use axum::{
+    extract::{Query, State},
+    response::Response,
+};
+
+use serde::Deserialize;
+
+use crate::{error::RdapServerError, rdap::response::ResponseUtil, server::DynServiceState};
+
+use super::{
+    response::{BAD_REQUEST, NOT_IMPLEMENTED},
+    search_controls::SearchControls,
+};
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct NameserversParams {
+    name: Option<String>,
+
+    ip: Option<String>,
+}
+
+#[axum_macros::debug_handler]
+#[tracing::instrument(level = "debug")]
+pub(crate) async fn nameservers(
+    Query(params): Query<NameserversParams>,
+    controls: SearchControls,
+    state: State<DynServiceState>,
+) -> Result<Response, RdapServerError> {
+    Ok(if let Some(name) = params.name {
+        let storage = state.get_storage().await?;
+        let results = storage.search_nameservers_by_name(&name).await?;
+        controls
+            .apply(results, state.get_search_page_size())
+            .response()
+    } else if let Some(ip) = params.ip {
+        if ip.parse::<IpAddr>().is_err() {
+            return Ok(BAD_REQUEST.response());
+        }
+        let storage = state.get_storage().await?;
+        let results = storage.search_nameservers_by_ip(&ip).await?;
+        controls
+            .apply(results, state.get_search_page_size())
+            .response()
+    } else {
+        NOT_IMPLEMENTED.response()
+    })
+}
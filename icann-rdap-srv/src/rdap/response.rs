@@ -1,15 +1,27 @@
+use std::time::SystemTime;
+
 use axum::{
+    body::{to_bytes, Body, HttpBody},
     response::{IntoResponse, Response},
     Json,
 };
-use http::StatusCode;
+use chrono::DateTime;
+use headers::{ETag, HeaderMapExt, LastModified};
+use http::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    StatusCode,
+};
 use icann_rdap_common::{
     media_types::RDAP_MEDIA_TYPE,
     response::{error::Error, types::Common, RdapResponse},
 };
 use lazy_static::lazy_static;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use tracing::warn;
 
+use crate::error::RdapServerError;
+
 lazy_static! {
     pub static ref NOT_FOUND: RdapResponse = RdapResponse::ErrorResponse(
         Error::builder()
@@ -23,6 +35,24 @@ lazy_static! {
             .common(Common::builder().build())
             .build()
     );
+    pub static ref TOO_MANY_REQUESTS: RdapResponse = RdapResponse::ErrorResponse(
+        Error::builder()
+            .error_code(429)
+            .common(Common::builder().build())
+            .build()
+    );
+    pub static ref UNAUTHORIZED: RdapResponse = RdapResponse::ErrorResponse(
+        Error::builder()
+            .error_code(401)
+            .common(Common::builder().build())
+            .build()
+    );
+    pub static ref FORBIDDEN: RdapResponse = RdapResponse::ErrorResponse(
+        Error::builder()
+            .error_code(403)
+            .common(Common::builder().build())
+            .build()
+    );
     pub static ref BAD_REQUEST: RdapResponse = RdapResponse::ErrorResponse(
         Error::builder()
             .error_code(400)
@@ -31,11 +61,23 @@ lazy_static! {
     );
 }
 
+/// Creates a 400 error response describing what was wrong with the request.
+pub(crate) fn bad_request(description: impl Into<String>) -> RdapResponse {
+    RdapResponse::ErrorResponse(
+        Error::builder()
+            .error_code(400)
+            .common(Common::builder().build())
+            .description(vec![description.into()])
+            .build(),
+    )
+}
+
 pub(crate) const RDAP_HEADERS: [(&str, &str); 1] = [("content-type", RDAP_MEDIA_TYPE)];
 
 pub(crate) trait ResponseUtil {
     fn status_code(&self) -> StatusCode;
     fn first_notice_link_href(&self) -> Option<&str>;
+    fn last_changed(&self) -> Option<SystemTime>;
     fn response(&self) -> Response;
 }
 
@@ -61,9 +103,46 @@ impl ResponseUtil for RdapResponse {
         }
     }
 
+    /// Gets the date of the `last changed` event of an object class.
+    fn last_changed(&self) -> Option<SystemTime> {
+        let events = match self {
+            RdapResponse::Entity(e) => e.object_common.events.as_ref(),
+            RdapResponse::Domain(d) => d.object_common.events.as_ref(),
+            RdapResponse::Nameserver(n) => n.object_common.events.as_ref(),
+            RdapResponse::Autnum(a) => a.object_common.events.as_ref(),
+            RdapResponse::Network(n) => n.object_common.events.as_ref(),
+            _ => None,
+        }?;
+        let event_date = events
+            .iter()
+            .find(|event| event.event_action.as_deref() == Some("last changed"))?
+            .event_date
+            .as_ref()?;
+        DateTime::parse_from_rfc3339(event_date)
+            .ok()
+            .map(SystemTime::from)
+    }
+
     fn response(&self) -> Response {
         let status_code = self.status_code();
         match status_code {
+            StatusCode::OK => match serde_json::to_vec(self) {
+                Ok(body) => {
+                    let entity_tag = entity_tag(&body);
+                    let mut response = (status_code, RDAP_HEADERS, body).into_response();
+                    response.headers_mut().typed_insert(entity_tag);
+                    if let Some(last_changed) = self.last_changed() {
+                        response
+                            .headers_mut()
+                            .typed_insert(LastModified::from(last_changed));
+                    }
+                    response
+                }
+                Err(e) => {
+                    warn!("unable to serialize response: {e}");
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            },
             StatusCode::MULTIPLE_CHOICES
             | StatusCode::FOUND
             | StatusCode::SEE_OTHER
@@ -85,20 +164,154 @@ impl ResponseUtil for RdapResponse {
     }
 }
 
+/// Computes a strong entity tag from the body of a response.
+pub(crate) fn entity_tag(body: &[u8]) -> ETag {
+    format!("\"{:x}\"", Sha256::digest(body))
+        .parse()
+        .expect("hex digest is a valid entity tag")
+}
+
+/// The largest body of an RDAP response whose JSON is changed by [map_rdap_json].
+pub(crate) const MAX_MAPPED_JSON_BYTES: usize = 4 * 1024 * 1024;
+
+/// Returns true if the response is a successful RDAP response.
+fn is_rdap_json(response: &Response) -> bool {
+    response.status() == StatusCode::OK
+        && response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with(RDAP_MEDIA_TYPE))
+}
+
+/// Returns true if the response is a successful RDAP response whose body is known to be
+/// larger than [MAX_MAPPED_JSON_BYTES], from its Content-Length or the size of its body.
+pub(crate) fn is_oversized_rdap_json(response: &Response) -> bool {
+    let size = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|content_length| content_length.to_str().ok())
+        .and_then(|content_length| content_length.parse::<u64>().ok())
+        .unwrap_or_else(|| response.body().size_hint().lower());
+    is_rdap_json(response) && size > MAX_MAPPED_JSON_BYTES as u64
+}
+
+/// Changes the JSON of a successful RDAP response, such as in middleware redacting it.
+/// Other responses are returned as they are, as are responses known to be larger than
+/// [MAX_MAPPED_JSON_BYTES]. If the JSON cannot be changed, the response is replaced by
+/// an error so that the unchanged JSON is never sent. This includes a body of unknown
+/// size that turns out to be too large, as it has been consumed by then.
+pub(crate) async fn map_rdap_json(
+    response: Response,
+    what: &str,
+    f: impl FnOnce(Value) -> Result<Value, RdapServerError>,
+) -> Response {
+    if !is_rdap_json(&response) {
+        return response;
+    }
+    if is_oversized_rdap_json(&response) {
+        warn!("not able to {what} response larger than {MAX_MAPPED_JSON_BYTES} bytes");
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let value = match to_bytes(body, MAX_MAPPED_JSON_BYTES)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).map_err(|e| e.to_string()))
+    {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("unable to {what} response: {e}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let value = match f(value) {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("unable to {what} response: {e}");
+            return e.into_response();
+        }
+    };
+    match serde_json::to_vec(&value) {
+        Ok(bytes) => {
+            parts.headers.remove(CONTENT_LENGTH);
+            parts.headers.typed_insert(entity_tag(&bytes));
+            Response::from_parts(parts, Body::from(bytes))
+        }
+        Err(e) => {
+            warn!("unable to serialize {what} response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(non_snake_case)]
 mod tests {
 
-    use axum::response::IntoResponse;
-    use http::StatusCode;
+    use axum::{
+        body::{to_bytes, Body},
+        response::IntoResponse,
+    };
+    use http::{
+        header::{CONTENT_TYPE, ETAG, LAST_MODIFIED},
+        StatusCode,
+    };
     use icann_rdap_common::response::{
         domain::Domain,
         error::Error,
-        types::{Link, Notice, NoticeOrRemark},
+        types::{Event, Link, Notice, NoticeOrRemark},
         RdapResponse,
     };
+    use serde_json::{json, Value};
 
-    use crate::rdap::response::{ResponseUtil, NOT_FOUND, NOT_IMPLEMENTED};
+    use crate::rdap::response::{
+        map_rdap_json, ResponseUtil, MAX_MAPPED_JSON_BYTES, NOT_FOUND, NOT_IMPLEMENTED,
+    };
+
+    fn rdap_json_response(body: String) -> axum::response::Response {
+        axum::response::Response::builder()
+            .header(CONTENT_TYPE, "application/rdap+json")
+            .body(Body::from(body))
+            .expect("building response")
+    }
+
+    #[tokio::test]
+    async fn GIVEN_rdap_json_WHEN_map_rdap_json_THEN_json_changed() {
+        // GIVEN
+        let response = rdap_json_response(r#"{"objectClassName":"domain"}"#.to_string());
+
+        // WHEN
+        let actual = map_rdap_json(response, "change", |mut value| {
+            value["ldhName"] = json!("foo.example");
+            Ok(value)
+        })
+        .await;
+
+        // THEN
+        let body = to_bytes(actual.into_body(), usize::MAX)
+            .await
+            .expect("reading body");
+        let value: Value = serde_json::from_slice(&body).expect("parsing body");
+        assert_eq!(value["ldhName"], "foo.example");
+    }
+
+    #[tokio::test]
+    async fn GIVEN_oversized_rdap_json_WHEN_map_rdap_json_THEN_response_unchanged() {
+        // GIVEN
+        let body = format!(r#"{{"remarks":"{}"}}"#, "x".repeat(MAX_MAPPED_JSON_BYTES));
+        let response = rdap_json_response(body.clone());
+
+        // WHEN
+        let actual = map_rdap_json(response, "change", |_| panic!("response mapped")).await;
+
+        // THEN
+        assert_eq!(actual.status(), StatusCode::OK);
+        let actual = to_bytes(actual.into_body(), usize::MAX)
+            .await
+            .expect("reading body");
+        assert_eq!(actual, body.as_bytes());
+    }
 
     #[test]
     fn GIVEN_non_error_WHEN_exec_response_THEN_status_code_is_200() {
@@ -112,6 +325,40 @@ mod tests {
         assert_eq!(actual.into_response().status(), StatusCode::OK);
     }
 
+    #[test]
+    fn GIVEN_domain_with_last_changed_WHEN_exec_response_THEN_etag_and_last_modified() {
+        // GIVEN
+        let domain = RdapResponse::Domain(
+            Domain::basic()
+                .ldh_name("foo.example")
+                .event(
+                    Event::builder()
+                        .event_action("last changed")
+                        .event_date("2024-01-01T00:00:00Z")
+                        .build(),
+                )
+                .build(),
+        );
+
+        // WHEN
+        let actual = domain.response();
+
+        // THEN
+        let etag = actual.headers().get(ETAG).expect("no etag");
+        assert!(etag
+            .to_str()
+            .expect("etag is not a string")
+            .starts_with('"'));
+        assert_eq!(
+            actual
+                .headers()
+                .get(LAST_MODIFIED)
+                .expect("no last-modified"),
+            "Mon, 01 Jan 2024 00:00:00 GMT"
+        );
+        assert_eq!(domain.response().headers().get(ETAG), Some(etag));
+    }
+
     #[test]
     fn GIVEN_not_found_WHEN_exec_response_THEN_status_code_is_501() {
         // GIVEN
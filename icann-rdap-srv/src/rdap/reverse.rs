@@ -0,0 +1,111 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Response,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::RdapServerError, rdap::response::ResponseUtil, server::DynServiceState,
+    storage::ReverseSearchConditions,
+};
+
+use super::{
+    response::{BAD_REQUEST, NOT_IMPLEMENTED},
+    search_controls::SearchControls,
+};
+
+/// The only related resource type defined by RFC 9536.
+const RELATED_ENTITY: &str = "entity";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReverseSearchParams {
+    #[serde(rename = "fn")]
+    full_name: Option<String>,
+
+    handle: Option<String>,
+
+    email: Option<String>,
+
+    role: Option<String>,
+}
+
+impl ReverseSearchParams {
+    /// Gets the search conditions, or `None` if the search is not valid.
+    fn conditions(self) -> Option<ReverseSearchConditions> {
+        if self.full_name.is_none() && self.handle.is_none() && self.email.is_none() {
+            return None;
+        }
+        Some(
+            ReverseSearchConditions::builder()
+                .and_full_name(self.full_name)
+                .and_handle(self.handle)
+                .and_email(self.email)
+                .and_role(self.role)
+                .build(),
+        )
+    }
+}
+
+#[axum_macros::debug_handler]
+#[tracing::instrument(level = "debug")]
+pub(crate) async fn domains_reverse_search(
+    Path(related): Path<String>,
+    Query(params): Query<ReverseSearchParams>,
+    controls: SearchControls,
+    state: State<DynServiceState>,
+) -> Result<Response, RdapServerError> {
+    if related != RELATED_ENTITY {
+        return Ok(NOT_IMPLEMENTED.response());
+    }
+    let Some(conditions) = params.conditions() else {
+        return Ok(BAD_REQUEST.response());
+    };
+    let storage = state.get_storage().await?;
+    let results = storage.reverse_search_domains(&conditions).await?;
+    Ok(controls
+        .apply(results, state.get_search_page_size())
+        .response())
+}
+
+#[axum_macros::debug_handler]
+#[tracing::instrument(level = "debug")]
+pub(crate) async fn nameservers_reverse_search(
+    Path(related): Path<String>,
+    Query(params): Query<ReverseSearchParams>,
+    controls: SearchControls,
+    state: State<DynServiceState>,
+) -> Result<Response, RdapServerError> {
+    if related != RELATED_ENTITY {
+        return Ok(NOT_IMPLEMENTED.response());
+    }
+    let Some(conditions) = params.conditions() else {
+        return Ok(BAD_REQUEST.response());
+    };
+    let storage = state.get_storage().await?;
+    let results = storage.reverse_search_nameservers(&conditions).await?;
+    Ok(controls
+        .apply(results, state.get_search_page_size())
+        .response())
+}
+
+#[axum_macros::debug_handler]
+#[tracing::instrument(level = "debug")]
+pub(crate) async fn entities_reverse_search(
+    Path(related): Path<String>,
+    Query(params): Query<ReverseSearchParams>,
+    controls: SearchControls,
+    state: State<DynServiceState>,
+) -> Result<Response, RdapServerError> {
+    if related != RELATED_ENTITY {
+        return Ok(NOT_IMPLEMENTED.response());
+    }
+    let Some(conditions) = params.conditions() else {
+        return Ok(BAD_REQUEST.response());
+    };
+    let storage = state.get_storage().await?;
+    let results = storage.reverse_search_entities(&conditions).await?;
+    Ok(controls
+        .apply(results, state.get_search_page_size())
+        .response())
+}
@@ -1,13 +1,15 @@
-use axum::{response::IntoResponse, routing::get, Router};
+use axum::{routing::get, Router};
 
 use super::{
     autnum::autnum_by_num,
     domain::domain_by_name,
     domains::domains,
+    entities::entities,
     entity::entity_by_handle,
     ip::network_by_netid,
     nameserver::nameserver_by_name,
-    response::{ResponseUtil, NOT_IMPLEMENTED},
+    nameservers::nameservers,
+    reverse::{domains_reverse_search, entities_reverse_search, nameservers_reverse_search},
     srvhelp::srvhelp,
 };
 
@@ -19,11 +21,19 @@ pub(crate) fn rdap_router() -> Router<crate::server::DynServiceState> {
         .route("/nameserver/:name", get(nameserver_by_name))
         .route("/entity/:handle", get(entity_by_handle))
         .route("/domains", get(domains))
-        .route("/nameservers", get(not_implemented))
-        .route("/entities", get(not_implemented))
+        .route("/nameservers", get(nameservers))
+        .route("/entities", get(entities))
+        .route(
+            "/domains/reverse_search/:related",
+            get(domains_reverse_search),
+        )
+        .route(
+            "/nameservers/reverse_search/:related",
+            get(nameservers_reverse_search),
+        )
+        .route(
+            "/entities/reverse_search/:related",
+            get(entities_reverse_search),
+        )
         .route("/help", get(srvhelp))
 }
-
-async fn not_implemented() -> impl IntoResponse {
-    NOT_IMPLEMENTED.response()
-}
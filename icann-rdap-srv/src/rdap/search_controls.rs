@@ -0,0 +1,697 @@
+use std::cmp::Ordering;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Host, OriginalUri, Query},
+    http::request::Parts,
+    response::Response,
+};
+use icann_rdap_common::{
+    media_types::RDAP_MEDIA_TYPE,
+    response::{
+        domain::Domain,
+        entity::Entity,
+        nameserver::Nameserver,
+        search::{AvailableSort, FieldSet, PagingMetadata, SortingMetadata, SubsettingMetadata},
+        types::{Common, ExtensionId, Link, ObjectCommon},
+        RdapResponse,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    error::RdapServerError,
+    links::{request_scheme, LinkBase},
+    rdap::response::{bad_request, ResponseUtil},
+};
+
+const SORT_PARAM: &str = "sort";
+const CURSOR_PARAM: &str = "cursor";
+const FIELD_SET_PARAM: &str = "fieldSet";
+
+const ID_FIELD_SET: &str = "id";
+const BRIEF_FIELD_SET: &str = "brief";
+const FULL_FIELD_SET: &str = "full";
+
+/// Members of an object, in addition to those of the "id" field set, in the "brief" field set.
+const BRIEF_FIELDS: [&str; 3] = ["handle", "status", "links"];
+
+/// The query parameters of RFC 8977 (paging and sorting) and RFC 8982 (subsetting).
+#[derive(Debug, Default, Deserialize)]
+struct SearchControlParams {
+    count: Option<bool>,
+
+    sort: Option<String>,
+
+    cursor: Option<String>,
+
+    #[serde(rename = "fieldSet")]
+    field_set: Option<String>,
+}
+
+/// The paging, sorting, and subsetting controls of a search request.
+#[derive(Debug)]
+pub(crate) struct SearchControls {
+    params: SearchControlParams,
+
+    /// The URL of the request, from which links to other pages, sorts, and field sets are made.
+    url: Url,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for SearchControls {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<SearchControlParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| bad_request(e.body_text()).response())?;
+        let Host(host) = Host::from_request_parts(parts, state)
+            .await
+            .map_err(|e| bad_request(e.body_text()).response())?;
+        let OriginalUri(uri) = OriginalUri::from_request_parts(parts, state)
+            .await
+            .map_err(|e| match e {})?;
+        // links are made with the base URL that the links of the response are rewritten to
+        let url = match (parts.extensions.get::<LinkBase>(), uri.path_and_query()) {
+            (Some(LinkBase(base_url)), Some(path)) if path.as_str().starts_with("/rdap/") => {
+                format!("{base_url}{}", &path.as_str()["/rdap".len()..])
+            }
+            _ => {
+                let scheme = request_scheme(&parts.headers, &parts.extensions);
+                format!("{scheme}://{host}{uri}")
+            }
+        };
+        let url = Url::parse(&url)
+            .map_err(|e| bad_request(format!("request URL '{url}' is invalid: {e}")).response())?;
+        Ok(Self { params, url })
+    }
+}
+
+impl SearchControls {
+    /// Sorts, pages, and subsets search results. Responses that are not search results,
+    /// such as errors, are returned as is. If the controls are not valid for the type
+    /// of search results, a 400 error describing the invalid control is returned. Without
+    /// a page size, all results are returned in one page.
+    pub(crate) fn apply(&self, response: RdapResponse, page_size: Option<usize>) -> RdapResponse {
+        let applied = match response {
+            RdapResponse::DomainSearchResults(mut search) => self
+                .page_search(&mut search.common, &mut search.results, page_size)
+                .map(|metadata| {
+                    (
+                        search.paging_metadata,
+                        search.sorting_metadata,
+                        search.subsetting_metadata,
+                    ) = metadata;
+                    RdapResponse::DomainSearchResults(search)
+                }),
+            RdapResponse::NameserverSearchResults(mut search) => self
+                .page_search(&mut search.common, &mut search.results, page_size)
+                .map(|metadata| {
+                    (
+                        search.paging_metadata,
+                        search.sorting_metadata,
+                        search.subsetting_metadata,
+                    ) = metadata;
+                    RdapResponse::NameserverSearchResults(search)
+                }),
+            RdapResponse::EntitySearchResults(mut search) => self
+                .page_search(&mut search.common, &mut search.results, page_size)
+                .map(|metadata| {
+                    (
+                        search.paging_metadata,
+                        search.sorting_metadata,
+                        search.subsetting_metadata,
+                    ) = metadata;
+                    RdapResponse::EntitySearchResults(search)
+                }),
+            _ => return response,
+        };
+        applied.unwrap_or_else(|e| match e {
+            RdapServerError::InvalidArg(description) => bad_request(description),
+            e => bad_request(e.to_string()),
+        })
+    }
+
+    /// Replaces the results of a search with a page of them, returning the metadata of the page.
+    fn page_search<T: SearchResult>(
+        &self,
+        common: &mut Common,
+        results: &mut Vec<T>,
+        page_size: Option<usize>,
+    ) -> Result<PageMetadata, RdapServerError> {
+        let page = self.page(std::mem::take(results), page_size)?;
+        add_extensions(common);
+        *results = page.results;
+        Ok((Some(page.paging), Some(page.sorting), Some(page.subsetting)))
+    }
+
+    fn page<T: SearchResult>(
+        &self,
+        mut results: Vec<T>,
+        page_size: Option<usize>,
+    ) -> Result<Page<T>, RdapServerError> {
+        let page_size = page_size.unwrap_or(usize::MAX).max(1);
+        let sorts = parse_sort::<T>(self.params.sort.as_deref())?;
+        results.sort_by(|a, b| compare(a, b, &sorts));
+
+        let total_count = results.len();
+        let offset = self
+            .params
+            .cursor
+            .as_deref()
+            .map(decode_cursor)
+            .transpose()?
+            .unwrap_or_default();
+        let end = total_count.min(offset.saturating_add(page_size));
+        let results = if offset < end {
+            results.drain(offset..end).collect::<Vec<T>>()
+        } else {
+            vec![]
+        };
+
+        let field_set = self.params.field_set.as_deref().unwrap_or(FULL_FIELD_SET);
+        let results = match field_set {
+            FULL_FIELD_SET => results,
+            ID_FIELD_SET => subset(results, T::ID_FIELDS, &[])?,
+            BRIEF_FIELD_SET => subset(results, T::ID_FIELDS, &BRIEF_FIELDS)?,
+            _ => {
+                return Err(RdapServerError::InvalidArg(format!(
+                    "field set '{field_set}' is not supported"
+                )))
+            }
+        };
+
+        let next_links = (end < total_count).then(|| {
+            vec![self.link(
+                "next",
+                &[(CURSOR_PARAM, Some(encode_cursor(end)))],
+                Some("next page of results"),
+            )]
+        });
+        let paging = PagingMetadata::builder()
+            .and_total_count(
+                self.params
+                    .count
+                    .unwrap_or(false)
+                    .then_some(total_count as u64),
+            )
+            .page_size(results.len() as u64)
+            .page_number((offset / page_size) as u64 + 1)
+            .and_links(next_links)
+            .build();
+        let sorting = SortingMetadata::builder()
+            .and_current_sort(self.params.sort.clone())
+            .available_sorts(self.available_sorts::<T>())
+            .build();
+        let subsetting = SubsettingMetadata::builder()
+            .current_field_set(field_set)
+            .available_field_sets(self.available_field_sets())
+            .build();
+        Ok(Page {
+            results,
+            paging,
+            sorting,
+            subsetting,
+        })
+    }
+
+    fn available_sorts<T: SearchResult>(&self) -> Vec<AvailableSort> {
+        T::SORT_PROPERTIES
+            .iter()
+            .enumerate()
+            .map(|(i, property)| {
+                AvailableSort::builder()
+                    .property(property.name)
+                    .json_path(property.json_path)
+                    .default(i == 0)
+                    .links(vec![self.link(
+                        "alternate",
+                        &[
+                            (SORT_PARAM, Some(property.name.to_string())),
+                            (CURSOR_PARAM, None),
+                        ],
+                        None,
+                    )])
+                    .build()
+            })
+            .collect()
+    }
+
+    fn available_field_sets(&self) -> Vec<FieldSet> {
+        [
+            (ID_FIELD_SET, "Only the identifiers of each object."),
+            (
+                BRIEF_FIELD_SET,
+                "The identifiers, handle, status, and links of each object.",
+            ),
+            (FULL_FIELD_SET, "All of the information of each object."),
+        ]
+        .into_iter()
+        .map(|(name, description)| {
+            FieldSet::builder()
+                .name(name)
+                .description(description)
+                .default(name == FULL_FIELD_SET)
+                .links(vec![self.link(
+                    "alternate",
+                    &[(FIELD_SET_PARAM, Some(name.to_string()))],
+                    None,
+                )])
+                .build()
+        })
+        .collect()
+    }
+
+    /// Creates a link to the request URL with some query parameters replaced or removed.
+    fn link(&self, rel: &str, replace: &[(&str, Option<String>)], title: Option<&str>) -> Link {
+        let mut url = self.url.clone();
+        let pairs = self
+            .url
+            .query_pairs()
+            .filter(|(name, _)| !replace.iter().any(|(r, _)| r == name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<(String, String)>>();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .extend_pairs(
+                replace
+                    .iter()
+                    .filter_map(|(name, value)| value.as_ref().map(|v| (name, v))),
+            );
+        Link::builder()
+            .value(self.url.as_str())
+            .rel(rel)
+            .href(url.as_str())
+            .media_type(RDAP_MEDIA_TYPE)
+            .and_title(title.map(|t| t.to_string()))
+            .build()
+    }
+}
+
+/// The paging, sorting, and subsetting metadata of search results.
+type PageMetadata = (
+    Option<PagingMetadata>,
+    Option<SortingMetadata>,
+    Option<SubsettingMetadata>,
+);
+
+/// A page of search results and its metadata.
+struct Page<T> {
+    results: Vec<T>,
+    paging: PagingMetadata,
+    sorting: SortingMetadata,
+    subsetting: SubsettingMetadata,
+}
+
+/// A property by which search results may be sorted.
+struct SortProperty {
+    name: &'static str,
+    json_path: &'static str,
+}
+
+/// An object found in search results.
+trait SearchResult: Serialize + DeserializeOwned {
+    /// The properties by which the objects can be sorted. The first is the default.
+    const SORT_PROPERTIES: &'static [SortProperty];
+
+    /// The members of the object in the "id" field set.
+    const ID_FIELDS: &'static [&'static str];
+
+    fn object_common(&self) -> &ObjectCommon;
+
+    /// Gets the value of a sort property specific to the type of object.
+    fn own_sort_value(&self, property: &str) -> Option<String>;
+}
+
+impl SearchResult for Domain {
+    const SORT_PROPERTIES: &'static [SortProperty] = &[
+        SortProperty {
+            name: "name",
+            json_path: "$.domainSearchResults[*].ldhName",
+        },
+        SortProperty {
+            name: "handle",
+            json_path: "$.domainSearchResults[*].handle",
+        },
+        SortProperty {
+            name: "registrationDate",
+            json_path:
+                "$.domainSearchResults[*].events[?(@.eventAction==\"registration\")].eventDate",
+        },
+        SortProperty {
+            name: "lastChangedDate",
+            json_path:
+                "$.domainSearchResults[*].events[?(@.eventAction==\"last changed\")].eventDate",
+        },
+        SortProperty {
+            name: "expirationDate",
+            json_path:
+                "$.domainSearchResults[*].events[?(@.eventAction==\"expiration\")].eventDate",
+        },
+    ];
+
+    const ID_FIELDS: &'static [&'static str] = &["objectClassName", "ldhName", "unicodeName"];
+
+    fn object_common(&self) -> &ObjectCommon {
+        &self.object_common
+    }
+
+    fn own_sort_value(&self, property: &str) -> Option<String> {
+        match property {
+            "name" => self.ldh_name.as_ref().map(|n| n.to_lowercase()),
+            _ => None,
+        }
+    }
+}
+
+impl SearchResult for Nameserver {
+    const SORT_PROPERTIES: &'static [SortProperty] = &[
+        SortProperty {
+            name: "name",
+            json_path: "$.nameserverSearchResults[*].ldhName",
+        },
+        SortProperty {
+            name: "handle",
+            json_path: "$.nameserverSearchResults[*].handle",
+        },
+        SortProperty {
+            name: "registrationDate",
+            json_path:
+                "$.nameserverSearchResults[*].events[?(@.eventAction==\"registration\")].eventDate",
+        },
+        SortProperty {
+            name: "lastChangedDate",
+            json_path:
+                "$.nameserverSearchResults[*].events[?(@.eventAction==\"last changed\")].eventDate",
+        },
+    ];
+
+    const ID_FIELDS: &'static [&'static str] = &["objectClassName", "ldhName", "unicodeName"];
+
+    fn object_common(&self) -> &ObjectCommon {
+        &self.object_common
+    }
+
+    fn own_sort_value(&self, property: &str) -> Option<String> {
+        match property {
+            "name" => self.ldh_name.as_ref().map(|n| n.to_lowercase()),
+            _ => None,
+        }
+    }
+}
+
+impl SearchResult for Entity {
+    const SORT_PROPERTIES: &'static [SortProperty] = &[
+        SortProperty {
+            name: "handle",
+            json_path: "$.entitySearchResults[*].handle",
+        },
+        SortProperty {
+            name: "fn",
+            json_path: "$.entitySearchResults[*].vcardArray[1][?(@[0]==\"fn\")][3]",
+        },
+        SortProperty {
+            name: "registrationDate",
+            json_path:
+                "$.entitySearchResults[*].events[?(@.eventAction==\"registration\")].eventDate",
+        },
+        SortProperty {
+            name: "lastChangedDate",
+            json_path:
+                "$.entitySearchResults[*].events[?(@.eventAction==\"last changed\")].eventDate",
+        },
+    ];
+
+    const ID_FIELDS: &'static [&'static str] = &["objectClassName", "handle"];
+
+    fn object_common(&self) -> &ObjectCommon {
+        &self.object_common
+    }
+
+    fn own_sort_value(&self, property: &str) -> Option<String> {
+        match property {
+            "fn" => self
+                .contact()
+                .and_then(|c| c.full_name)
+                .map(|n| n.to_lowercase()),
+            _ => None,
+        }
+    }
+}
+
+/// Gets the value of an object for a sort property.
+fn sort_value<T: SearchResult>(result: &T, property: &str) -> Option<String> {
+    let event_action = match property {
+        "handle" => return result.object_common().handle.clone(),
+        "registrationDate" => "registration",
+        "lastChangedDate" => "last changed",
+        "expirationDate" => "expiration",
+        _ => return result.own_sort_value(property),
+    };
+    result
+        .object_common()
+        .events
+        .as_ref()?
+        .iter()
+        .find(|e| e.event_action.as_deref() == Some(event_action))
+        .and_then(|e| e.event_date.clone())
+}
+
+/// Parses the value of the sort parameter, which is a comma separated list of
+/// properties each optionally followed by ":a" (ascending) or ":d" (descending).
+/// Without a sort parameter, the results are sorted by the default property.
+fn parse_sort<T: SearchResult>(sort: Option<&str>) -> Result<Vec<(&str, bool)>, RdapServerError> {
+    let Some(sort) = sort else {
+        return Ok(vec![(T::SORT_PROPERTIES[0].name, true)]);
+    };
+    sort.split(',')
+        .map(|item| {
+            let (name, direction) = item.split_once(':').unwrap_or((item, "a"));
+            let ascending = match direction {
+                "a" => true,
+                "d" => false,
+                _ => {
+                    return Err(RdapServerError::InvalidArg(format!(
+                        "sort direction '{direction}' is invalid"
+                    )))
+                }
+            };
+            T::SORT_PROPERTIES
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| (p.name, ascending))
+                .ok_or_else(|| {
+                    RdapServerError::InvalidArg(format!("sort property '{name}' is not supported"))
+                })
+        })
+        .collect()
+}
+
+/// Compares two objects by sort properties. Objects without a value for a property
+/// are placed after those with a value.
+fn compare<T: SearchResult>(a: &T, b: &T, sorts: &[(&str, bool)]) -> Ordering {
+    for (property, ascending) in sorts {
+        let ordering = match (sort_value(a, property), sort_value(b, property)) {
+            (Some(a), Some(b)) if *ascending => a.cmp(&b),
+            (Some(a), Some(b)) => b.cmp(&a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Reduces objects to the id fields and any additional fields.
+fn subset<T: SearchResult>(
+    results: Vec<T>,
+    id_fields: &[&str],
+    fields: &[&str],
+) -> Result<Vec<T>, RdapServerError> {
+    results
+        .into_iter()
+        .map(|result| {
+            let mut value = serde_json::to_value(result)?;
+            if let Value::Object(object) = &mut value {
+                object.retain(|name, _| {
+                    id_fields.contains(&name.as_str()) || fields.contains(&name.as_str())
+                });
+            }
+            Ok(serde_json::from_value(value)?)
+        })
+        .collect()
+}
+
+/// Cursors are opaque to clients. This server uses the offset of the page in the results.
+fn encode_cursor(offset: usize) -> String {
+    format!("{offset:x}")
+}
+
+fn decode_cursor(cursor: &str) -> Result<usize, RdapServerError> {
+    usize::from_str_radix(cursor, 16)
+        .map_err(|_| RdapServerError::InvalidArg(format!("cursor '{cursor}' is invalid")))
+}
+
+fn add_extensions(common: &mut Common) {
+    let conformance = common.rdap_conformance.get_or_insert_with(Vec::new);
+    for id in [
+        ExtensionId::Paging,
+        ExtensionId::Sorting,
+        ExtensionId::Subsetting,
+    ] {
+        let extension = id.to_extension();
+        if !conformance.contains(&extension) {
+            conformance.push(extension);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use icann_rdap_common::response::{
+        domain::Domain, search::DomainSearchResults, types::Common, RdapResponse,
+    };
+    use url::Url;
+
+    use crate::error::RdapServerError;
+
+    use super::{
+        decode_cursor, encode_cursor, parse_sort, subset, SearchControlParams, SearchControls,
+    };
+
+    #[test]
+    fn GIVEN_no_sort_WHEN_parse_sort_THEN_default_property_ascending() {
+        // GIVEN no sort
+
+        // WHEN
+        let actual = parse_sort::<Domain>(None).expect("parsing sort");
+
+        // THEN
+        assert_eq!(actual, vec![("name", true)]);
+    }
+
+    #[test]
+    fn GIVEN_sort_with_directions_WHEN_parse_sort_THEN_properties_and_directions() {
+        // GIVEN
+        let sort = "handle:d,name";
+
+        // WHEN
+        let actual = parse_sort::<Domain>(Some(sort)).expect("parsing sort");
+
+        // THEN
+        assert_eq!(actual, vec![("handle", false), ("name", true)]);
+    }
+
+    #[test]
+    fn GIVEN_unknown_property_WHEN_parse_sort_THEN_invalid_arg() {
+        // GIVEN
+        let sort = "fn";
+
+        // WHEN
+        let actual = parse_sort::<Domain>(Some(sort));
+
+        // THEN
+        let Err(RdapServerError::InvalidArg(description)) = actual else {
+            panic!("sort by unknown property parsed")
+        };
+        assert!(description.contains("'fn'"));
+    }
+
+    #[test]
+    fn GIVEN_minus_prefix_WHEN_parse_sort_THEN_invalid_arg() {
+        // GIVEN descending as in other APIs instead of ":d"
+        let sort = "-name";
+
+        // WHEN
+        let actual = parse_sort::<Domain>(Some(sort));
+
+        // THEN
+        assert!(matches!(actual, Err(RdapServerError::InvalidArg(_))));
+    }
+
+    #[test]
+    fn GIVEN_offset_WHEN_encode_and_decode_cursor_THEN_same_offset() {
+        // GIVEN
+        let offset = 1234;
+
+        // WHEN
+        let actual = decode_cursor(&encode_cursor(offset)).expect("decoding cursor");
+
+        // THEN
+        assert_eq!(actual, offset);
+    }
+
+    #[test]
+    fn GIVEN_undecodable_cursor_WHEN_decode_cursor_THEN_invalid_arg() {
+        // GIVEN
+        let cursor = "not-a-cursor";
+
+        // WHEN
+        let actual = decode_cursor(cursor);
+
+        // THEN
+        assert!(matches!(actual, Err(RdapServerError::InvalidArg(_))));
+    }
+
+    #[test]
+    fn GIVEN_domain_WHEN_subset_THEN_only_id_and_given_fields_kept() {
+        // GIVEN
+        let domain = Domain::basic()
+            .ldh_name("foo.example")
+            .handle("FOO-1")
+            .status("active")
+            .port_43("whois.example".to_string())
+            .build();
+
+        // WHEN
+        let actual =
+            subset(vec![domain], &["objectClassName", "ldhName"], &["handle"]).expect("subsetting");
+
+        // THEN
+        let actual = &actual[0];
+        assert_eq!(actual.ldh_name.as_deref(), Some("foo.example"));
+        assert_eq!(actual.object_common.handle.as_deref(), Some("FOO-1"));
+        assert!(actual.object_common.status.is_none());
+        assert!(actual.object_common.port_43.is_none());
+    }
+
+    #[test]
+    fn GIVEN_unknown_sort_WHEN_apply_THEN_bad_request_describing_sort() {
+        // GIVEN
+        let controls = SearchControls {
+            params: SearchControlParams {
+                sort: Some("fn".to_string()),
+                ..Default::default()
+            },
+            url: Url::parse("http://localhost/rdap/domains?name=foo*").expect("parsing url"),
+        };
+        let search = RdapResponse::DomainSearchResults(
+            DomainSearchResults::builder()
+                .common(Common::new_level0(vec![], vec![]))
+                .results(vec![Domain::basic().ldh_name("foo.example").build()])
+                .build(),
+        );
+
+        // WHEN
+        let actual = controls.apply(search, None);
+
+        // THEN
+        let RdapResponse::ErrorResponse(error) = actual else {
+            panic!("not an error response")
+        };
+        assert_eq!(error.error_code, 400);
+        assert!(error
+            .description
+            .expect("no description")
+            .iter()
+            .any(|line| line.contains("'fn'")));
+    }
+}
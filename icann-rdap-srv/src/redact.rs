@@ -0,0 +1,489 @@
+use std::{path::Path, sync::Arc};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use icann_rdap_common::response::{
+    redacted::{Method, Name, Reason, Redacted},
+    types::ExtensionId,
+};
+use jsonpath_rust::{
+    parser::model::JsonPath,
+    path::{config::JsonPathConfig, json_path_instance},
+    JsonPathValue,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    auth::Principal,
+    error::RdapServerError,
+    rdap::response::{is_oversized_rdap_json, map_rdap_json, MAX_MAPPED_JSON_BYTES},
+};
+
+/// Stands in for removed values until they are pruned, because removing array elements
+/// one match at a time would shift the positions of later matches.
+const REMOVED: &str = "\u{0}removed by redaction\u{0}";
+
+/// The members of search results holding their objects, which are redacted individually.
+pub(crate) const SEARCH_RESULTS: [&str; 3] = [
+    "domainSearchResults",
+    "nameserverSearchResults",
+    "entitySearchResults",
+];
+
+/// A rule redacting the values at a JSONPath.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionRule {
+    /// The name of the redacted field in the `redacted` array.
+    pub name: Name,
+
+    /// The reason of the redaction in the `redacted` array.
+    pub reason: Option<Reason>,
+
+    /// If specified, the rule only applies to objects of this class.
+    #[serde(rename = "objectClassName")]
+    pub object_class_name: Option<String>,
+
+    /// The JSONPath of the values, relative to the object.
+    pub path: String,
+
+    /// How the values are redacted. Partial values are not supported.
+    #[serde(default)]
+    pub method: Method,
+
+    /// The value replacing the redacted values when the method is `replacementValue`.
+    pub replacement: Option<Value>,
+
+    /// Clients with any of these roles see the values.
+    #[serde(rename = "exemptRoles")]
+    #[serde(default)]
+    pub exempt_roles: Vec<String>,
+
+    /// The parsed `path`, set when the policy is read.
+    #[serde(skip)]
+    json_path: Option<JsonPath>,
+}
+
+/// A redaction policy, which removes or replaces values in RDAP objects
+/// depending on the roles of the client and describes what was redacted
+/// in RFC 9537 `redacted` arrays.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionPolicy {
+    pub rules: Vec<RedactionRule>,
+}
+
+impl RedactionPolicy {
+    /// Reads a policy from a JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RdapServerError> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_json(&contents)
+    }
+
+    /// Parses a policy from JSON, checking that each rule can be applied.
+    pub fn from_json(json: &str) -> Result<Self, RdapServerError> {
+        let mut policy = serde_json::from_str::<Self>(json)?;
+        for rule in &mut policy.rules {
+            rule.json_path = Some(JsonPath::try_from(rule.path.as_str()).map_err(|e| {
+                RdapServerError::Config(format!("invalid redaction path {}: {e}", rule.path))
+            })?);
+            match rule.method {
+                Method::PartialValue => {
+                    return Err(RdapServerError::Config(format!(
+                        "redaction of {} by partial value is not supported",
+                        rule.path
+                    )))
+                }
+                Method::ReplacementValue if rule.replacement.is_none() => {
+                    return Err(RdapServerError::Config(format!(
+                        "redaction of {} by replacement value has no replacement",
+                        rule.path
+                    )))
+                }
+                _ => {}
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Redacts an RDAP response for the principal. The objects of search results are
+    /// redacted individually.
+    ///
+    /// Fails if a value matched by a rule cannot be redacted, so that the response is not
+    /// sent with the value in it.
+    pub fn redact(
+        &self,
+        mut response: Value,
+        principal: &Principal,
+    ) -> Result<Value, RdapServerError> {
+        let mut redacted = false;
+        let search_results = SEARCH_RESULTS
+            .iter()
+            .find(|member| response.get(**member).is_some_and(Value::is_array));
+        if let Some(results) = search_results
+            .and_then(|member| response.get_mut(*member))
+            .and_then(Value::as_array_mut)
+        {
+            for result in results.iter_mut() {
+                redacted |= self.redact_object(result, principal)?;
+            }
+        } else {
+            redacted = self.redact_object(&mut response, principal)?;
+        }
+        if redacted {
+            add_conformance(&mut response);
+        }
+        Ok(response)
+    }
+
+    /// Redacts an object, returning true if anything was redacted.
+    fn redact_object(
+        &self,
+        object: &mut Value,
+        principal: &Principal,
+    ) -> Result<bool, RdapServerError> {
+        let object_class_name = object
+            .get("objectClassName")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let mut redactions = vec![];
+        for rule in &self.rules {
+            if principal.has_any_role(&rule.exempt_roles)
+                || rule
+                    .object_class_name
+                    .as_ref()
+                    .is_some_and(|class| Some(class) != object_class_name.as_ref())
+            {
+                continue;
+            }
+            if rule.apply(object)? {
+                redactions.push(rule.redacted());
+            }
+        }
+        if redactions.is_empty() {
+            return Ok(false);
+        }
+        prune_removed(object);
+        if let Some(object) = object.as_object_mut() {
+            let redacted = object
+                .entry("redacted")
+                .or_insert_with(|| Value::Array(vec![]));
+            if let Some(redacted) = redacted.as_array_mut() {
+                redacted.extend(
+                    redactions
+                        .iter()
+                        .filter_map(|redaction| serde_json::to_value(redaction).ok()),
+                );
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl RedactionRule {
+    /// Applies the rule to the object, returning true if any values were redacted.
+    fn apply(&self, object: &mut Value) -> Result<bool, RdapServerError> {
+        // rules not read with the policy are parsed here
+        let parsed;
+        let json_path = match &self.json_path {
+            Some(json_path) => json_path,
+            None => {
+                parsed = JsonPath::try_from(self.path.as_str())
+                    .map_err(|e| RdapServerError::Redaction(format!("{}: {e}", self.path)))?;
+                &parsed
+            }
+        };
+        let found_paths = json_path_instance(json_path, object, JsonPathConfig::default())
+            .find(JsonPathValue::from_root(object))
+            .into_iter()
+            .filter_map(JsonPathValue::to_path)
+            .collect::<Vec<String>>();
+        let mut redacted = false;
+        for found_path in &found_paths {
+            let Some(value) = json_pointer(found_path).and_then(|ptr| object.pointer_mut(&ptr))
+            else {
+                return Err(RdapServerError::Redaction(found_path.clone()));
+            };
+            *value = match self.method {
+                Method::EmptyValue => empty_value(value),
+                Method::ReplacementValue => self.replacement.clone().unwrap_or_default(),
+                _ => Value::String(REMOVED.to_string()),
+            };
+            redacted = true;
+        }
+        Ok(redacted)
+    }
+
+    fn redacted(&self) -> Redacted {
+        let path = Some(self.path.clone());
+        let (pre_path, post_path) = match self.method {
+            Method::Removal => (path, None),
+            _ => (None, path),
+        };
+        Redacted {
+            name: self.name.clone(),
+            reason: self.reason.clone(),
+            pre_path,
+            post_path,
+            path_lang: Some("jsonpath".to_string()),
+            replacement_path: None,
+            method: Some(self.method.clone()),
+        }
+    }
+}
+
+/// Converts a normalized path found by a JSONPath, such as `$.['entities'][0].['handle']`,
+/// into a JSON pointer.
+fn json_pointer(found_path: &str) -> Option<String> {
+    let mut rest = found_path.strip_prefix('$')?;
+    let mut pointer = String::new();
+    while !rest.is_empty() {
+        let token;
+        if let Some(key) = rest.strip_prefix(".['") {
+            let end = key.find("']")?;
+            token = &key[..end];
+            rest = &key[end + 2..];
+        } else {
+            let index = rest.strip_prefix('[')?;
+            let end = index.find(']')?;
+            token = &index[..end];
+            rest = &index[end + 1..];
+        }
+        pointer.push('/');
+        pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+    }
+    Some(pointer)
+}
+
+/// Gets the empty value of the same type as a value.
+fn empty_value(value: &Value) -> Value {
+    match value {
+        Value::String(_) => Value::String(String::default()),
+        Value::Array(_) => Value::Array(vec![]),
+        Value::Object(_) => Value::Object(serde_json::Map::new()),
+        _ => Value::Null,
+    }
+}
+
+/// Removes the values replaced by [REMOVED] from their arrays and objects.
+fn prune_removed(value: &mut Value) {
+    let is_removed = |value: &Value| value.as_str() == Some(REMOVED);
+    match value {
+        Value::Array(values) => {
+            values.retain(|value| !is_removed(value));
+            values.iter_mut().for_each(prune_removed);
+        }
+        Value::Object(members) => {
+            members.retain(|_, value| !is_removed(value));
+            members.values_mut().for_each(prune_removed);
+        }
+        _ => {}
+    }
+}
+
+/// Adds the redacted extension to the conformance of the response.
+fn add_conformance(response: &mut Value) {
+    let Some(response) = response.as_object_mut() else {
+        return;
+    };
+    let extension = Value::String(ExtensionId::Redacted.to_string());
+    let conformance = response
+        .entry("rdapConformance")
+        .or_insert_with(|| Value::Array(vec![]));
+    if let Some(conformance) = conformance.as_array_mut() {
+        if !conformance.contains(&extension) {
+            conformance.push(extension);
+        }
+    }
+}
+
+/// Middleware redacting successful RDAP responses according to the roles of the
+/// [Principal] of the request.
+pub(crate) async fn redact(
+    State(policy): State<Arc<RedactionPolicy>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let principal = request
+        .extensions()
+        .get::<Principal>()
+        .cloned()
+        .unwrap_or_else(Principal::anonymous);
+    let response = next.run(request).await;
+    // map_rdap_json passes oversized responses through, which must not be sent unredacted
+    if is_oversized_rdap_json(&response) {
+        warn!("unable to redact response larger than {MAX_MAPPED_JSON_BYTES} bytes");
+        return RdapServerError::Redaction("oversized response".to_string()).into_response();
+    }
+    map_rdap_json(response, "redact", |value| policy.redact(value, &principal)).await
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use serde_json::{json, Value};
+
+    use crate::auth::Principal;
+
+    use super::{json_pointer, RedactionPolicy};
+
+    fn new_policy() -> RedactionPolicy {
+        RedactionPolicy::from_json(
+            r#"
+            {
+              "rules": [
+                {
+                  "name": { "type": "Registry Domain ID" },
+                  "objectClassName": "domain",
+                  "path": "$.handle",
+                  "exemptRoles": ["law-enforcement"]
+                },
+                {
+                  "name": { "type": "Registrant Email" },
+                  "path": "$.entities[?(@.roles[0]=='registrant')].vcardArray[1][?(@[0]=='email')]",
+                  "method": "removal"
+                },
+                {
+                  "name": { "description": "Port 43 Server" },
+                  "path": "$.port43",
+                  "method": "replacementValue",
+                  "replacement": "whois.example"
+                }
+              ]
+            }
+            "#,
+        )
+        .expect("parsing policy")
+    }
+
+    fn new_domain() -> Value {
+        json!({
+          "objectClassName": "domain",
+          "ldhName": "foo.example",
+          "handle": "123",
+          "port43": "whois.foo.example",
+          "entities": [
+            {
+              "objectClassName": "entity",
+              "roles": ["registrant"],
+              "vcardArray": ["vcard", [
+                ["version", {}, "text", "4.0"],
+                ["email", {}, "text", "a@foo.example"],
+                ["email", {}, "text", "b@foo.example"]
+              ]]
+            }
+          ]
+        })
+    }
+
+    #[test]
+    fn GIVEN_anonymous_WHEN_redact_THEN_values_redacted_and_described() {
+        // GIVEN
+        let policy = new_policy();
+
+        // WHEN
+        let actual = policy
+            .redact(new_domain(), &Principal::anonymous())
+            .expect("redacting");
+
+        // THEN
+        assert!(actual.get("handle").is_none());
+        assert_eq!(actual["port43"], "whois.example");
+        assert_eq!(
+            actual["entities"][0]["vcardArray"][1],
+            json!([["version", {}, "text", "4.0"]])
+        );
+        let redacted = actual["redacted"].as_array().expect("no redacted array");
+        assert_eq!(redacted.len(), 3);
+        assert_eq!(redacted[0]["prePath"], "$.handle");
+        assert_eq!(redacted[2]["method"], "replacementValue");
+        assert_eq!(actual["rdapConformance"], json!(["redacted"]));
+    }
+
+    #[test]
+    fn GIVEN_exempt_role_WHEN_redact_THEN_value_not_redacted() {
+        // GIVEN
+        let policy = new_policy();
+        let principal = Principal::authenticated("alice", vec!["law-enforcement".to_string()]);
+
+        // WHEN
+        let actual = policy.redact(new_domain(), &principal).expect("redacting");
+
+        // THEN
+        assert_eq!(actual["handle"], "123");
+        assert_eq!(actual["redacted"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn GIVEN_search_results_WHEN_redact_THEN_each_result_redacted() {
+        // GIVEN
+        let policy = new_policy();
+        let results = json!({
+          "rdapConformance": ["rdap_level_0"],
+          "domainSearchResults": [new_domain(), new_domain()]
+        });
+
+        // WHEN
+        let actual = policy
+            .redact(results, &Principal::anonymous())
+            .expect("redacting");
+
+        // THEN
+        let results = actual["domainSearchResults"]
+            .as_array()
+            .expect("no search results");
+        assert!(results.iter().all(|result| result.get("handle").is_none()));
+        assert!(results
+            .iter()
+            .all(|result| result.get("redacted").is_some()));
+        assert_eq!(
+            actual["rdapConformance"],
+            json!(["rdap_level_0", "redacted"])
+        );
+    }
+
+    #[test]
+    fn GIVEN_value_without_json_pointer_WHEN_redact_THEN_error() {
+        // GIVEN
+        let policy = RedactionPolicy::from_json(
+            r#"{"rules": [{"name": {"type": "x"}, "path": "$.*", "method": "removal"}]}"#,
+        )
+        .expect("parsing policy");
+        let domain = json!({"objectClassName": "domain", "a']b": "secret"});
+
+        // WHEN
+        let actual = policy.redact(domain, &Principal::anonymous());
+
+        // THEN
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn GIVEN_found_path_WHEN_json_pointer_THEN_pointer_to_value() {
+        // GIVEN
+        let found_path = "$.['entities'][0].['vcardArray'][1][2].['a/b']";
+
+        // WHEN
+        let actual = json_pointer(found_path);
+
+        // THEN
+        assert_eq!(actual.as_deref(), Some("/entities/0/vcardArray/1/2/a~1b"));
+    }
+
+    #[test]
+    fn GIVEN_partial_value_rule_WHEN_parse_policy_THEN_error() {
+        // GIVEN
+        let json =
+            r#"{"rules": [{"name": {"type": "x"}, "path": "$.handle", "method": "partialValue"}]}"#;
+
+        // WHEN
+        let actual = RedactionPolicy::from_json(json);
+
+        // THEN
+        assert!(actual.is_err());
+    }
+}
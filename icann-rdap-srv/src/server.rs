@@ -1,33 +1,55 @@
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use async_trait::async_trait;
-use axum::{error_handling::HandleErrorLayer, Router};
+use axum::{error_handling::HandleErrorLayer, extract::Request, middleware, routing::get, Router};
+use axum_extra::typed_header::TypedHeader;
+use headers::Host;
 use http::{Method, StatusCode};
 use icann_rdap_common::VERSION;
 use tokio::net::TcpListener;
-use tower::{BoxError, ServiceBuilder};
+use tower::{BoxError, ServiceBuilder, ServiceExt};
 use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
 
 use crate::{
+    admin::{admin_router, authorize, AdminAccess},
+    auth::{authenticate, Authenticator},
     bootstrap::init_bootstrap,
-    config::{ListenConfig, ServiceConfig, StorageType},
+    caching::caching,
+    config::{
+        CacheConfig, LinkConfig, ListenConfig, RateLimitConfig, ServiceConfig, StorageType,
+        TlsConfig, VirtualHostConfig,
+    },
     error::RdapServerError,
+    links::rewrite_links,
+    metrics::{metrics, set_objects_loaded, track_metrics},
+    rate_limit::{self, RateLimiter},
     rdap::router::rdap_router,
+    redact::{redact, RedactionPolicy},
+    status::{health, ready, LoadKind, ServerStatus},
     storage::{
-        data::{load_data, reload_data},
-        mem::{config::MemConfig, ops::Mem},
+        data::{load_data_files, reload_data, DataFiles},
+        mem::{config::MemConfig, ops::Mem, snapshot::MemSnapshot},
         pg::{config::PgConfig, ops::Pg},
+        sqlite::{config::SqliteConfig, ops::Sqlite},
+        watch::DataWatcher,
         StoreOps,
     },
+    tls::serve_tls,
 };
 
 /// Holds information on the server listening.
 pub struct Listener {
     pub local_addr: SocketAddr,
     tcp_listener: TcpListener,
+    tls: Option<TlsConfig>,
 }
 
 /// Starts the RDAP service.
@@ -51,19 +73,21 @@ impl Listener {
         Ok(Self {
             local_addr,
             tcp_listener: listener,
+            tls: config.tls.clone(),
         })
     }
 
     pub fn rdap_base(&self) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
         if self.local_addr.is_ipv4() {
             format!(
-                "http://{}:{}/rdap",
+                "{scheme}://{}:{}/rdap",
                 self.local_addr.ip(),
                 self.local_addr.port()
             )
         } else {
             format!(
-                "http://[{}]:{}/rdap",
+                "{scheme}://[{}]:{}/rdap",
                 self.local_addr.ip(),
                 self.local_addr.port()
             )
@@ -74,12 +98,16 @@ impl Listener {
     /// This function will initiate any needed non-HTTP services and then call
     /// call [Listener::start_with_state], which initiates the HTTP service.
     pub async fn start_server(self, service_config: &ServiceConfig) -> Result<(), RdapServerError> {
-        init_bootstrap(service_config).await?;
+        let status = Arc::new(ServerStatus::default());
+        init_bootstrap(service_config, status.clone()).await?;
         if let StorageType::Memory(config) = &service_config.storage_type {
-            let app_state = AppState::new_mem(config.clone(), service_config).await?;
+            let app_state = AppState::new_mem(config.clone(), service_config, status).await?;
             self.start_with_state(app_state).await?;
         } else if let StorageType::Postgres(config) = &service_config.storage_type {
-            let app_state = AppState::new_pg(config.clone(), service_config).await?;
+            let app_state = AppState::new_pg(config.clone(), service_config, status).await?;
+            self.start_with_state(app_state).await?;
+        } else if let StorageType::Sqlite(config) = &service_config.storage_type {
+            let app_state = AppState::new_sqlite(config.clone(), service_config, status).await?;
             self.start_with_state(app_state).await?;
         };
         Ok(())
@@ -92,9 +120,13 @@ impl Listener {
         T: StoreOps + Clone + Send + Sync + 'static,
         AppState<T>: ServiceState,
     {
+        let status = app_state.status.clone();
         let app = app_router::<T>(app_state);
 
         tracing::debug!("listening on {}", self.local_addr);
+        if let Some(tls) = self.tls {
+            return serve_tls(self.tcp_listener, app, tls, &status).await;
+        }
         // axum::Server::from_tcp(self.tcp_listener)?
         //     .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         //     .await?;
@@ -107,25 +139,193 @@ impl Listener {
     }
 }
 
-async fn init_data(
+/// Loads the data directory in the background, so that the server may report that
+/// it is not ready until the load is finished, and then watches for reloads and updates.
+/// The initial load of the host must have begun in the status.
+///
+/// For the in-memory store, a snapshot may be restored in place of loading the data directory,
+/// and is written again after each reload, update, or change. The data directory is not
+/// loaded into storage whose objects are managed by the admin API.
+fn init_data(
     store: Box<dyn StoreOps>,
     config: &ServiceConfig,
-) -> Result<(), RdapServerError> {
-    load_data(config, &*store, false).await?;
-    if config.auto_reload {
-        tokio::spawn(reload_data(store, config.clone()));
+    status: Arc<ServerStatus>,
+    snapshot: Option<MemSnapshot>,
+    admin_managed: bool,
+) {
+    let config = config.clone();
+    // the watcher is started before the initial load so that no changes are missed
+    let watcher = config
+        .auto_reload
+        .then(|| DataWatcher::new(&config.data_dir))
+        .and_then(|watcher| {
+            watcher
+                .inspect_err(|e| {
+                    tracing::warn!("Cannot watch data directory, polling for triggers: {e}")
+                })
+                .ok()
+        });
+    tokio::spawn(async move {
+        // triggers written during the initial load are acted upon once it is finished
+        let load_time = SystemTime::now();
+        let loaded = match &snapshot {
+            Some(snapshot) => snapshot.load(&config).await,
+            None if admin_managed => Ok(DataFiles::default()),
+            None => load_data_files(&config, &*store, false).await,
+        };
+        let (data_files, result) = match loaded {
+            Ok(data_files) => (data_files, Ok(())),
+            Err(e) => (DataFiles::default(), Err(e)),
+        };
+        if let Err(e) = &result {
+            tracing::error!("Initial data load failed: {e}");
+        } else {
+            set_objects_loaded(config.host.as_deref(), &data_files.object_counts());
+        }
+        status.record_load(config.host.as_deref(), LoadKind::Initial, &result);
+        if let Some(watcher) = watcher {
+            watcher
+                .run(store, config, status, data_files, snapshot)
+                .await;
+        } else if config.auto_reload {
+            reload_data(store, config, status, load_time, snapshot).await;
+        }
+    });
+}
+
+fn new_authenticator(
+    config: &ServiceConfig,
+) -> Result<Option<Arc<Authenticator>>, RdapServerError> {
+    let Some(auth) = &config.auth else {
+        return Ok(None);
+    };
+    Ok(Some(Arc::new(Authenticator::new(auth)?)))
+}
+
+fn new_redaction_policy(
+    config: &ServiceConfig,
+) -> Result<Option<Arc<RedactionPolicy>>, RdapServerError> {
+    let Some(file) = &config.redaction_policy_file else {
+        return Ok(None);
+    };
+    Ok(Some(Arc::new(RedactionPolicy::from_file(file)?)))
+}
+
+fn new_admin_role(config: &ServiceConfig) -> Result<Option<String>, RdapServerError> {
+    if config.admin_role.is_some() && config.auth.is_none() {
+        return Err(RdapServerError::Config(
+            "the admin API requires authentication to be configured".to_string(),
+        ));
+    }
+    if config.admin_role.is_some() && config.auto_reload {
+        return Err(RdapServerError::Config(
+            "the admin API cannot be used with auto reload of the data directory".to_string(),
+        ));
+    }
+    Ok(config.admin_role.clone())
+}
+
+fn no_virtual_hosts(config: &ServiceConfig) -> Result<(), RdapServerError> {
+    if !config.virtual_hosts.is_empty() {
+        return Err(RdapServerError::Config(
+            "virtual hosts require the memory storage type".to_string(),
+        ));
     }
     Ok(())
 }
 
-fn app_router<T>(state: AppState<T>) -> Router
+/// Gets the configuration of the service of a virtual host, which loads the subdirectory
+/// of the data directory named after the host.
+fn virtual_host_service_config(config: &ServiceConfig, host: &VirtualHostConfig) -> ServiceConfig {
+    ServiceConfig {
+        data_dir: host.data_dir(&config.data_dir),
+        virtual_hosts: vec![],
+        host: Some(host.host.clone()),
+        ..config.clone()
+    }
+}
+
+/// Routes requests by their `Host` header to the router of the virtual host, or to the
+/// router of the state if the host is not a virtual host.
+fn app_router<T>(mut state: AppState<T>) -> Router
 where
     T: StoreOps + Clone + Send + Sync + 'static,
     AppState<T>: ServiceState,
 {
+    // the hosts share the limiter, so that the limits are for the server as a whole
+    let limiter = state
+        .rate_limit
+        .clone()
+        .map(|rate_limit| Arc::new(RateLimiter::new(rate_limit)));
+    let virtual_hosts = std::mem::take(&mut state.virtual_hosts)
+        .into_iter()
+        .map(|(host, state)| (host, host_router(state, limiter.clone())))
+        .collect::<HashMap<String, Router>>();
+    let default = host_router(state, limiter);
+    if virtual_hosts.is_empty() {
+        return default;
+    }
+    let virtual_hosts = Arc::new(virtual_hosts);
+    Router::new().fallback(move |host: Option<TypedHeader<Host>>, request: Request| {
+        let router = host
+            .and_then(|TypedHeader(host)| {
+                virtual_hosts
+                    .get(&host.hostname().to_ascii_lowercase())
+                    .cloned()
+            })
+            .unwrap_or_else(|| default.clone());
+        router.oneshot(request)
+    })
+}
+
+fn host_router<T>(state: AppState<T>, limiter: Option<Arc<RateLimiter>>) -> Router
+where
+    T: StoreOps + Clone + Send + Sync + 'static,
+    AppState<T>: ServiceState,
+{
+    let mut rdap = rdap_router();
+    if let Some(redaction) = &state.redaction {
+        rdap = rdap.layer(middleware::from_fn_with_state(redaction.clone(), redact));
+    }
+    if let Some(links) = &state.links {
+        rdap = rdap.layer(middleware::from_fn_with_state(
+            Arc::new(links.clone()),
+            rewrite_links,
+        ));
+    }
+    rdap = rdap.layer(middleware::from_fn_with_state(
+        state.cache.clone().map(Arc::new),
+        caching,
+    ));
+    if let Some(auth) = &state.auth {
+        rdap = rdap.layer(middleware::from_fn_with_state(auth.clone(), authenticate));
+    }
+    let mut router = Router::new().nest("/rdap", rdap);
+    if let (Some(role), Some(auth)) = (&state.admin_role, &state.auth) {
+        let access = Arc::new(AdminAccess {
+            authenticator: auth.clone(),
+            role: role.clone(),
+        });
+        let admin = admin_router()
+            .layer(middleware::from_fn_with_state(access, authorize))
+            .layer(middleware::from_fn_with_state(auth.clone(), authenticate));
+        router = router.nest("/admin", admin);
+    }
+    if let (Some(rate_limit), Some(limiter)) = (&state.rate_limit, limiter) {
+        router = router
+            .layer(middleware::from_fn_with_state(
+                limiter,
+                rate_limit::rate_limit,
+            ))
+            .layer(rate_limit.ip_source.clone().into_extension());
+    }
     let state = Arc::new(state) as DynServiceState;
-    Router::new()
-        .nest("/rdap", rdap_router())
+    router
+        .route("/metrics", get(metrics))
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        // outside of the other layers so that requests they reject are counted
+        .layer(middleware::from_fn(track_metrics))
         .layer(
             ServiceBuilder::new()
                 .layer(HandleErrorLayer::new(|error: BoxError| async move {
@@ -161,6 +361,12 @@ pub trait ServiceState: std::fmt::Debug {
     /// If returns true, this indicates the server has been configured to do
     /// bootstrapping.
     fn get_bootstrap(&self) -> bool;
+
+    /// Gets the maximum number of search results returned in a page.
+    fn get_search_page_size(&self) -> Option<usize>;
+
+    /// Gets the status of data loading and bootstrapping.
+    fn get_status(&self) -> &ServerStatus;
 }
 
 /// State that is passed to the HTTP service router and used by functions
@@ -169,20 +375,135 @@ pub trait ServiceState: std::fmt::Debug {
 pub struct AppState<T: StoreOps + Clone + Send + Sync + 'static> {
     pub storage: T,
     pub bootstrap: bool,
+    pub search_page_size: Option<usize>,
+    pub rate_limit: Option<RateLimitConfig>,
+    pub status: Arc<ServerStatus>,
+    pub auth: Option<Arc<Authenticator>>,
+    pub redaction: Option<Arc<RedactionPolicy>>,
+    pub cache: Option<CacheConfig>,
+    pub admin_role: Option<String>,
+    /// If specified, links to this server are rewritten at response time.
+    pub links: Option<LinkConfig>,
+    /// The states of the virtual hosts by host name, which serve requests with those
+    /// hosts in place of this state.
+    pub virtual_hosts: HashMap<String, AppState<T>>,
+}
+
+impl<T: StoreOps + Clone + Send + Sync + 'static> AppState<T> {
+    /// Creates a state serving the storage as it is, without the data directory and
+    /// without any of the optional features.
+    pub fn new(storage: T) -> Self {
+        Self {
+            storage,
+            bootstrap: false,
+            search_page_size: None,
+            rate_limit: None,
+            status: Arc::new(ServerStatus::new_ready()),
+            auth: None,
+            redaction: None,
+            cache: None,
+            admin_role: None,
+            links: None,
+            virtual_hosts: HashMap::new(),
+        }
+    }
+
+    /// Creates a state serving the storage as configured, and starts loading the data
+    /// directory into the storage unless its objects are managed by the admin API.
+    fn new_service(
+        storage: T,
+        service_config: &ServiceConfig,
+        status: Arc<ServerStatus>,
+        snapshot: Option<MemSnapshot>,
+        admin_managed: bool,
+    ) -> Result<Self, RdapServerError> {
+        let state = Self {
+            storage,
+            bootstrap: service_config.bootstrap,
+            search_page_size: service_config.search_page_size,
+            rate_limit: service_config.rate_limit.clone(),
+            status,
+            auth: new_authenticator(service_config)?,
+            redaction: new_redaction_policy(service_config)?,
+            cache: service_config.cache.clone(),
+            admin_role: new_admin_role(service_config)?,
+            links: service_config.links.clone(),
+            virtual_hosts: HashMap::new(),
+        };
+        init_data(
+            Box::new(state.storage.clone()),
+            service_config,
+            state.status.clone(),
+            snapshot,
+            admin_managed,
+        );
+        Ok(state)
+    }
 }
 
 impl AppState<Mem> {
     pub async fn new_mem(
         config: MemConfig,
         service_config: &ServiceConfig,
+        status: Arc<ServerStatus>,
     ) -> Result<Self, RdapServerError> {
-        let storage = Mem::new(config);
+        if service_config.admin_role.is_some() {
+            // the memory storage is loaded from the data directory, which changes are not
+            // written to
+            tracing::warn!(
+                "Admin API changes to memory storage are lost when the server is restarted"
+            );
+        }
+        status.begin_initial_loads(
+            std::iter::once(None).chain(
+                service_config
+                    .virtual_hosts
+                    .iter()
+                    .map(|virtual_host| Some(virtual_host.host.as_str())),
+            ),
+        );
+        let mut state = Self::new_mem_host(config.clone(), service_config, status.clone()).await?;
+        for virtual_host in &service_config.virtual_hosts {
+            // each host has its own store, and snapshot if the store is snapshotted
+            let config = MemConfig {
+                snapshot_file: config
+                    .snapshot_file
+                    .as_ref()
+                    .map(|snapshot_file| format!("{snapshot_file}.{}", virtual_host.host)),
+                ..config.clone()
+            };
+            let mut host_state = Self::new_mem_host(
+                config,
+                &virtual_host_service_config(service_config, virtual_host),
+                status.clone(),
+            )
+            .await?;
+            // the public base URL of the server is not that of the host
+            host_state.links = match &virtual_host.base_url {
+                Some(base_url) => Some(LinkConfig::builder().base_url(base_url).build()),
+                None => service_config
+                    .links
+                    .as_ref()
+                    .map(|_| LinkConfig::builder().host(&virtual_host.host).build()),
+            };
+            state
+                .virtual_hosts
+                .insert(virtual_host.host.clone(), host_state);
+        }
+        Ok(state)
+    }
+
+    async fn new_mem_host(
+        config: MemConfig,
+        service_config: &ServiceConfig,
+        status: Arc<ServerStatus>,
+    ) -> Result<Self, RdapServerError> {
+        let storage = Mem::new(config.clone());
         storage.init().await?;
-        init_data(Box::new(storage.clone()), service_config).await?;
-        Ok(Self {
-            storage,
-            bootstrap: service_config.bootstrap,
-        })
+        let snapshot = config
+            .snapshot_file
+            .map(|snapshot_file| MemSnapshot::new(snapshot_file, storage.clone()));
+        Self::new_service(storage, service_config, status, snapshot, false)
     }
 }
 
@@ -196,14 +517,19 @@ impl AppState<Pg> {
     pub async fn new_pg(
         config: PgConfig,
         service_config: &ServiceConfig,
+        status: Arc<ServerStatus>,
     ) -> Result<Self, RdapServerError> {
+        no_virtual_hosts(service_config)?;
         let storage = Pg::new(config).await?;
         storage.init().await?;
-        init_data(Box::new(storage.clone()), service_config).await?;
-        Ok(Self {
+        status.begin_initial_loads([None]);
+        Self::new_service(
             storage,
-            bootstrap: service_config.bootstrap,
-        })
+            service_config,
+            status,
+            None,
+            service_config.admin_role.is_some(),
+        )
     }
 }
 
@@ -213,6 +539,32 @@ impl std::fmt::Debug for AppState<Pg> {
     }
 }
 
+impl AppState<Sqlite> {
+    pub async fn new_sqlite(
+        config: SqliteConfig,
+        service_config: &ServiceConfig,
+        status: Arc<ServerStatus>,
+    ) -> Result<Self, RdapServerError> {
+        no_virtual_hosts(service_config)?;
+        let storage = Sqlite::new(config).await?;
+        storage.init().await?;
+        status.begin_initial_loads([None]);
+        Self::new_service(
+            storage,
+            service_config,
+            status,
+            None,
+            service_config.admin_role.is_some(),
+        )
+    }
+}
+
+impl std::fmt::Debug for AppState<Sqlite> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppState<Sqlite>").finish()
+    }
+}
+
 #[async_trait]
 impl ServiceState for AppState<Pg> {
     async fn get_storage(&self) -> Result<&dyn StoreOps, RdapServerError> {
@@ -222,6 +574,14 @@ impl ServiceState for AppState<Pg> {
     fn get_bootstrap(&self) -> bool {
         self.bootstrap
     }
+
+    fn get_search_page_size(&self) -> Option<usize> {
+        self.search_page_size
+    }
+
+    fn get_status(&self) -> &ServerStatus {
+        &self.status
+    }
 }
 
 #[async_trait]
@@ -233,4 +593,31 @@ impl ServiceState for AppState<Mem> {
     fn get_bootstrap(&self) -> bool {
         self.bootstrap
     }
+
+    fn get_search_page_size(&self) -> Option<usize> {
+        self.search_page_size
+    }
+
+    fn get_status(&self) -> &ServerStatus {
+        &self.status
+    }
+}
+
+#[async_trait]
+impl ServiceState for AppState<Sqlite> {
+    async fn get_storage(&self) -> Result<&dyn StoreOps, RdapServerError> {
+        Ok(&self.storage)
+    }
+
+    fn get_bootstrap(&self) -> bool {
+        self.bootstrap
+    }
+
+    fn get_search_page_size(&self) -> Option<usize> {
+        self.search_page_size
+    }
+
+    fn get_status(&self) -> &ServerStatus {
+        &self.status
+    }
 }
@@ -0,0 +1,337 @@
+use std::{collections::BTreeMap, sync::RwLock};
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+use tokio::sync::watch;
+
+use crate::{error::RdapServerError, server::DynServiceState};
+
+/// The kind of load of the data directory.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum LoadKind {
+    /// The load when the server starts.
+    Initial,
+    /// A load triggered by the `update` file, which adds to the existing data.
+    Update,
+    /// A load triggered by the `reload` file, which replaces the existing data.
+    Reload,
+    /// A load of the data files that were added, changed, or removed.
+    Change,
+}
+
+/// The outcome of a background task.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    /// When the task finished, in RFC 3339 format.
+    pub time: String,
+
+    pub success: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TaskStatus {
+    fn new(result: &Result<(), RdapServerError>) -> Self {
+        Self {
+            time: Utc::now().to_rfc3339(),
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// The outcome of a load of the data directory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadStatus {
+    pub kind: LoadKind,
+
+    #[serde(flatten)]
+    pub status: TaskStatus,
+}
+
+/// The status of the loading of the data of a host.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostStatus {
+    /// True if data of the host has been successfully loaded.
+    pub ready: bool,
+
+    /// True if the load of the data of the host when the server started has finished,
+    /// whether or not it succeeded.
+    #[serde(rename = "initialLoadFinished")]
+    pub initial_load_finished: bool,
+
+    #[serde(rename = "lastLoad")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_load: Option<LoadStatus>,
+}
+
+/// The status reported by the readiness endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusReport {
+    /// True if data has been successfully loaded for every host, in which case the server
+    /// is ready for traffic.
+    pub ready: bool,
+
+    /// True if the loads of the data directory and those of the virtual hosts when the
+    /// server started have all finished, whether or not they succeeded.
+    #[serde(rename = "initialLoadFinished")]
+    pub initial_load_finished: bool,
+
+    /// The last load of the data directory, other than those of the virtual hosts.
+    #[serde(rename = "lastLoad")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_load: Option<LoadStatus>,
+
+    /// The status of each virtual host.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, HostStatus>,
+
+    /// The outcome of the last IANA bootstrap refresh, if bootstrapping is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bootstrap: Option<TaskStatus>,
+}
+
+/// Holds the status of the loading of data and of bootstrapping.
+#[derive(Debug)]
+pub struct ServerStatus {
+    /// The status of each host, which is `None` for the data directory itself.
+    hosts: RwLock<BTreeMap<Option<String>, HostStatus>>,
+    bootstrap: RwLock<Option<TaskStatus>>,
+    /// True if the storage is populated without loading the data directory.
+    preloaded: bool,
+    reloads: watch::Sender<()>,
+}
+
+impl Default for ServerStatus {
+    fn default() -> Self {
+        Self {
+            hosts: Default::default(),
+            bootstrap: Default::default(),
+            preloaded: false,
+            reloads: watch::Sender::new(()),
+        }
+    }
+}
+
+impl ServerStatus {
+    /// Creates a status for a server whose storage is populated without loading
+    /// the data directory.
+    pub fn new_ready() -> Self {
+        Self {
+            preloaded: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn report(&self) -> StatusReport {
+        let hosts = self.hosts.read().expect("server status poisoned");
+        StatusReport {
+            ready: self.preloaded || (!hosts.is_empty() && hosts.values().all(|host| host.ready)),
+            initial_load_finished: !hosts.is_empty()
+                && hosts.values().all(|host| host.initial_load_finished),
+            last_load: hosts.get(&None).and_then(|host| host.last_load.clone()),
+            hosts: hosts
+                .iter()
+                .filter_map(|(host, status)| Some((host.clone()?, status.clone())))
+                .collect(),
+            bootstrap: self
+                .bootstrap
+                .read()
+                .expect("server status poisoned")
+                .clone(),
+        }
+    }
+
+    /// Records that the initial loads of the hosts have begun, which must be done for every
+    /// host before any of them is loaded so that the server is not ready until all are.
+    pub(crate) fn begin_initial_loads<'a>(&self, hosts: impl IntoIterator<Item = Option<&'a str>>) {
+        let mut statuses = self.hosts.write().expect("server status poisoned");
+        for host in hosts {
+            statuses.entry(host.map(str::to_owned)).or_default();
+        }
+    }
+
+    pub(crate) fn record_load(
+        &self,
+        host: Option<&str>,
+        kind: LoadKind,
+        result: &Result<(), RdapServerError>,
+    ) {
+        let mut hosts = self.hosts.write().expect("server status poisoned");
+        let status = hosts.entry(host.map(str::to_owned)).or_default();
+        if kind == LoadKind::Initial {
+            status.initial_load_finished = true;
+        }
+        status.ready |= result.is_ok();
+        status.last_load = Some(LoadStatus {
+            kind,
+            status: TaskStatus::new(result),
+        });
+        if kind == LoadKind::Reload {
+            self.reloads.send_replace(());
+        }
+    }
+
+    /// Subscribes to the loads triggered by the `reload` file, whether or not they succeed.
+    pub(crate) fn subscribe_reloads(&self) -> watch::Receiver<()> {
+        self.reloads.subscribe()
+    }
+
+    pub(crate) fn record_bootstrap(&self, result: &Result<(), RdapServerError>) {
+        *self.bootstrap.write().expect("server status poisoned") = Some(TaskStatus::new(result));
+    }
+}
+
+/// Reports that the server is running.
+pub(crate) async fn health() -> Response {
+    Json(serde_json::json!({"status": "ok"})).into_response()
+}
+
+/// Reports the status of the server, with a 503 status code if it is not ready for traffic.
+pub(crate) async fn ready(State(state): State<DynServiceState>) -> Response {
+    let report = state.get_status().report();
+    let status_code = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status_code, Json(report)).into_response()
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use crate::error::RdapServerError;
+
+    use super::{LoadKind, ServerStatus};
+
+    #[test]
+    fn GIVEN_failed_initial_load_WHEN_update_succeeds_THEN_ready() {
+        // GIVEN
+        let status = ServerStatus::default();
+        status.record_load(
+            None,
+            LoadKind::Initial,
+            &Err(RdapServerError::NonJsonFile("foo.json".to_string())),
+        );
+        assert!(!status.report().ready);
+
+        // WHEN
+        status.record_load(None, LoadKind::Update, &Ok(()));
+
+        // THEN
+        let actual = status.report();
+        assert!(actual.ready);
+        assert!(actual.initial_load_finished);
+        let last_load = actual.last_load.expect("no last load");
+        assert_eq!(last_load.kind, LoadKind::Update);
+        assert!(last_load.status.success);
+    }
+
+    #[test]
+    fn GIVEN_two_hosts_WHEN_one_initial_load_finished_THEN_not_ready() {
+        // GIVEN
+        let status = ServerStatus::default();
+        status.begin_initial_loads([None, Some("foo.example")]);
+
+        // WHEN
+        status.record_load(None, LoadKind::Initial, &Ok(()));
+
+        // THEN
+        let actual = status.report();
+        assert!(!actual.ready);
+        assert!(!actual.initial_load_finished);
+        status.record_load(Some("foo.example"), LoadKind::Initial, &Ok(()));
+        let actual = status.report();
+        assert!(actual.ready);
+        assert!(actual.initial_load_finished);
+    }
+
+    #[test]
+    fn GIVEN_two_hosts_WHEN_host_load_fails_THEN_failure_reported_for_host() {
+        // GIVEN
+        let status = ServerStatus::default();
+        status.begin_initial_loads([None, Some("foo.example")]);
+        status.record_load(None, LoadKind::Initial, &Ok(()));
+
+        // WHEN
+        status.record_load(
+            Some("foo.example"),
+            LoadKind::Initial,
+            &Err(RdapServerError::NonJsonFile("foo.json".to_string())),
+        );
+
+        // THEN
+        let actual = status.report();
+        assert!(!actual.ready);
+        assert!(actual.initial_load_finished);
+        assert!(actual.last_load.expect("no last load").status.success);
+        let host = actual.hosts.get("foo.example").expect("no host status");
+        assert!(!host.ready);
+        assert!(
+            !host
+                .last_load
+                .as_ref()
+                .expect("no last load")
+                .status
+                .success
+        );
+    }
+
+    #[test]
+    fn GIVEN_ready_WHEN_reload_fails_THEN_still_ready_with_error() {
+        // GIVEN
+        let status = ServerStatus::default();
+        status.record_load(None, LoadKind::Initial, &Ok(()));
+
+        // WHEN
+        status.record_load(
+            None,
+            LoadKind::Reload,
+            &Err(RdapServerError::NonJsonFile("foo.json".to_string())),
+        );
+
+        // THEN
+        let actual = status.report();
+        assert!(actual.ready);
+        let last_load = actual.last_load.expect("no last load");
+        assert!(!last_load.status.success);
+        assert_eq!(
+            last_load.status.error.as_deref(),
+            Some("file at foo.json is not JSON")
+        );
+    }
+
+    #[test]
+    fn GIVEN_reload_subscriber_WHEN_loads_recorded_THEN_only_reload_notified() {
+        // GIVEN
+        let status = ServerStatus::new_ready();
+        let reloads = status.subscribe_reloads();
+
+        // WHEN
+        status.record_load(None, LoadKind::Update, &Ok(()));
+        let after_update = reloads.has_changed().expect("reloads closed");
+        status.record_load(
+            None,
+            LoadKind::Reload,
+            &Err(RdapServerError::NonJsonFile("foo.json".to_string())),
+        );
+        let after_reload = reloads.has_changed().expect("reloads closed");
+
+        // THEN
+        assert!(!after_update);
+        assert!(after_reload);
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::MultiGzDecoder;
+use icann_rdap_common::response::RdapResponse;
+use serde_json::Value;
+use tokio::{
+    sync::mpsc::{channel, Sender},
+    task::spawn_blocking,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    error::RdapServerError,
+    storage::{
+        data::{help_host, Compression, DataFileKind, Document, ObjectId},
+        TxHandle,
+    },
+};
+
+/// How many parsed documents may wait to be put into storage.
+const CHANNEL_SIZE: usize = 1024;
+
+/// How many objects are loaded between progress messages.
+const PROGRESS_INTERVAL: usize = 100_000;
+
+/// A document read from a bulk data file.
+struct BulkItem {
+    /// Where the document is, such as `file.jsonl:12` or `file.tar/domain.json`.
+    location: String,
+    document: Result<Document, RdapServerError>,
+}
+
+/// Loads a JSON Lines file, which has an RDAP object or template on each line.
+pub(crate) async fn load_json_lines(
+    path: &Path,
+    compression: Compression,
+    tx: &mut Box<dyn TxHandle>,
+) -> Result<Vec<ObjectId>, RdapServerError> {
+    load_documents(path, tx, move |path, sender| {
+        read_json_lines_file(path, compression, sender)
+    })
+    .await
+}
+
+/// Loads a tar archive of `.json`, `.template`, `.help`, and `.jsonl` files.
+pub(crate) async fn load_archive(
+    path: &Path,
+    compression: Compression,
+    tx: &mut Box<dyn TxHandle>,
+) -> Result<Vec<ObjectId>, RdapServerError> {
+    load_documents(path, tx, move |path, sender| {
+        read_archive_file(path, compression, sender)
+    })
+    .await
+}
+
+/// Checks a JSON Lines file without putting it into storage. Each document that could be
+/// loaded is given to `verify` with its location, which returns `true` if it finds errors.
+///
+/// Returns `true` if any document cannot be loaded or has errors.
+pub async fn verify_json_lines<V>(
+    path: &Path,
+    compression: Compression,
+    verify: V,
+) -> Result<bool, RdapServerError>
+where
+    V: FnMut(&str, Document) -> bool,
+{
+    verify_documents(
+        path,
+        move |path, sender| read_json_lines_file(path, compression, sender),
+        verify,
+    )
+    .await
+}
+
+/// Checks a tar archive without putting it into storage, in the same way as
+/// [verify_json_lines].
+pub async fn verify_archive<V>(
+    path: &Path,
+    compression: Compression,
+    verify: V,
+) -> Result<bool, RdapServerError>
+where
+    V: FnMut(&str, Document) -> bool,
+{
+    verify_documents(
+        path,
+        move |path, sender| read_archive_file(path, compression, sender),
+        verify,
+    )
+    .await
+}
+
+/// Puts the documents of a bulk data file into storage as they are read, so that the file
+/// never has to fit in memory.
+///
+/// The file is read on a blocking thread. Documents that cannot be loaded are logged with
+/// their location and skipped, and the load fails once the whole file has been read so that
+/// all the bad documents are reported at once.
+async fn load_documents<F>(
+    path: &Path,
+    tx: &mut Box<dyn TxHandle>,
+    read: F,
+) -> Result<Vec<ObjectId>, RdapServerError>
+where
+    F: FnOnce(&Path, &Sender<BulkItem>) -> Result<(), RdapServerError> + Send + 'static,
+{
+    let path_name = path.to_string_lossy().to_string();
+    debug!("loading {path_name} into storage");
+    let (sender, mut receiver) = channel(CHANNEL_SIZE);
+    let reader_path = PathBuf::from(path);
+    let reader = spawn_blocking(move || read(&reader_path, &sender));
+
+    let mut ids = vec![];
+    let mut bad_documents: usize = 0;
+    let mut next_progress = PROGRESS_INTERVAL;
+    while let Some(item) = receiver.recv().await {
+        let checked = item.document.and_then(|document| {
+            document.object_ids()?;
+            Ok(document)
+        });
+        match checked {
+            Ok(document) => ids.extend(document.add(tx).await?),
+            Err(e) => {
+                error!("{}: {e}", item.location);
+                bad_documents += 1;
+            }
+        }
+        if ids.len() >= next_progress {
+            info!("{} objects loaded from {path_name}", ids.len());
+            next_progress += PROGRESS_INTERVAL;
+        }
+    }
+    reader.await.map_err(std::io::Error::from)??;
+
+    if bad_documents > 0 {
+        return Err(RdapServerError::BadDocuments(path_name, bad_documents));
+    }
+    info!("{} objects loaded from {path_name}", ids.len());
+    Ok(ids)
+}
+
+/// Reads the documents of a bulk data file as they would be loaded, and gives them to
+/// `verify` instead of putting them into storage.
+async fn verify_documents<F, V>(
+    path: &Path,
+    read: F,
+    mut verify: V,
+) -> Result<bool, RdapServerError>
+where
+    F: FnOnce(&Path, &Sender<BulkItem>) -> Result<(), RdapServerError> + Send + 'static,
+    V: FnMut(&str, Document) -> bool,
+{
+    debug!("verifying {}", path.to_string_lossy());
+    let (sender, mut receiver) = channel(CHANNEL_SIZE);
+    let reader_path = PathBuf::from(path);
+    let reader = spawn_blocking(move || read(&reader_path, &sender));
+
+    let mut errors_found = false;
+    while let Some(item) = receiver.recv().await {
+        let checked = item.document.and_then(|document| {
+            document.object_ids()?;
+            Ok(document)
+        });
+        match checked {
+            Ok(document) => errors_found |= verify(&item.location, document),
+            Err(e) => {
+                error!("{}: {e}", item.location);
+                errors_found = true;
+            }
+        }
+    }
+    reader.await.map_err(std::io::Error::from)??;
+    Ok(errors_found)
+}
+
+fn read_json_lines_file(
+    path: &Path,
+    compression: Compression,
+    sender: &Sender<BulkItem>,
+) -> Result<(), RdapServerError> {
+    let location = path.to_string_lossy();
+    let reader = decompress(File::open(path)?, compression)?;
+    read_lines(reader, &location, sender)?;
+    Ok(())
+}
+
+fn read_archive_file(
+    path: &Path,
+    compression: Compression,
+    sender: &Sender<BulkItem>,
+) -> Result<(), RdapServerError> {
+    let location = path.to_string_lossy();
+    let reader = decompress(File::open(path)?, compression)?;
+    read_archive(reader, &location, sender)
+}
+
+fn decompress<'a>(
+    reader: impl Read + 'a,
+    compression: Compression,
+) -> Result<Box<dyn Read + 'a>, RdapServerError> {
+    let reader = BufReader::new(reader);
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+    })
+}
+
+/// Sends the document on each line, skipping blank lines. Returns `false` if loading
+/// has stopped.
+fn read_lines(
+    reader: impl Read,
+    location: &str,
+    sender: &Sender<BulkItem>,
+) -> Result<bool, RdapServerError> {
+    for (index, line) in BufReader::new(reader).split(b'\n').enumerate() {
+        let line = line?;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let item = BulkItem {
+            location: format!("{location}:{}", index + 1),
+            document: Document::parse(&line),
+        };
+        if sender.blocking_send(item).is_err() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Sends the documents of the data files in a tar archive. Other files are ignored.
+fn read_archive(
+    reader: impl Read,
+    location: &str,
+    sender: &Sender<BulkItem>,
+) -> Result<(), RdapServerError> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_path = entry.path()?.into_owned();
+        let entry_location = format!("{location}/{}", entry_path.to_string_lossy());
+        let document = match DataFileKind::of(&entry_path) {
+            Some(DataFileKind::Json | DataFileKind::Template) => {
+                let mut json = vec![];
+                entry.read_to_end(&mut json)?;
+                Document::parse(&json)
+            }
+            Some(DataFileKind::Help) => {
+                let mut json = vec![];
+                entry.read_to_end(&mut json)?;
+                parse_help(&json, &entry_path, &entry_location)
+            }
+            Some(DataFileKind::JsonLines(compression)) => {
+                let reader = decompress(&mut entry, compression)?;
+                if !read_lines(reader, &entry_location, sender)? {
+                    return Ok(());
+                }
+                continue;
+            }
+            Some(DataFileKind::Archive(_)) => {
+                warn!("{entry_location}: archives within archives are not loaded");
+                continue;
+            }
+            None => continue,
+        };
+        let item = BulkItem {
+            location: entry_location,
+            document,
+        };
+        if sender.blocking_send(item).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Parses a `.help` file, which is named for its host.
+fn parse_help(json: &[u8], entry_path: &Path, location: &str) -> Result<Document, RdapServerError> {
+    let host = entry_path
+        .file_name()
+        .and_then(|name| help_host(&name.to_string_lossy()))
+        .ok_or_else(|| RdapServerError::NonRdapJsonFile(location.to_string()))?;
+    let value = serde_json::from_slice::<Value>(json)?;
+    match RdapResponse::try_from(value)? {
+        RdapResponse::Help(help) => Ok(Document::SrvHelp { host, help }),
+        _ => Err(RdapServerError::NonRdapJsonFile(location.to_string())),
+    }
+}
@@ -1,6 +1,9 @@
 use std::{
+    collections::HashMap,
     net::IpAddr,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
     time::{Duration, SystemTime},
 };
 
@@ -9,6 +12,7 @@ use icann_rdap_common::response::{
     autnum::Autnum,
     domain::Domain,
     entity::Entity,
+    help::Help,
     nameserver::Nameserver,
     network::{Cidr0Cidr, Network, V4Cidr, V6Cidr},
     GetSelfLink, RdapResponse, SelfLink,
@@ -18,17 +22,26 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use strum_macros::Display;
 use tokio::time::sleep;
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     config::ServiceConfig,
     error::RdapServerError,
-    storage::{StoreOps, TxHandle},
+    metrics::set_objects_loaded,
+    status::{LoadKind, ServerStatus},
+    storage::{
+        bulk::{load_archive, load_json_lines},
+        mem::snapshot::MemSnapshot,
+        StoreOps, TxHandle,
+    },
 };
 
 pub const UPDATE: &str = "update";
 pub const RELOAD: &str = "reload";
 
+/// Counts of objects loaded, keyed by object class name.
+type ObjectCounts = HashMap<&'static str, usize>;
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Display)]
 #[serde(untagged)]
 pub enum Template {
@@ -94,7 +107,7 @@ pub enum NetworkOrError {
     ErrorResponse(icann_rdap_common::response::error::Error),
 }
 
-#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq, Hash)]
 pub struct DomainId {
     #[serde(rename = "ldhName")]
     pub ldh_name: String,
@@ -103,12 +116,12 @@ pub struct DomainId {
     pub unicode_name: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq, Hash)]
 pub struct EntityId {
     pub handle: String,
 }
 
-#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq, Hash)]
 pub struct NameserverId {
     #[serde(rename = "ldhName")]
     pub ldh_name: String,
@@ -117,7 +130,7 @@ pub struct NameserverId {
     pub unicode_name: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq, Hash)]
 pub struct AutnumId {
     #[serde(rename = "startAutnum")]
     pub start_autnum: u32,
@@ -125,13 +138,13 @@ pub struct AutnumId {
     pub end_autnum: u32,
 }
 
-#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Builder, Debug, PartialEq, Eq, Hash)]
 pub struct NetworkId {
     #[serde(rename = "networkId")]
     pub network_id: NetworkIdType,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 #[serde(untagged)]
 pub enum NetworkIdType {
     Cidr(IpNet),
@@ -143,6 +156,99 @@ pub enum NetworkIdType {
     },
 }
 
+impl NetworkId {
+    /// Gets the CIDRs under which the network is stored.
+    pub fn cidrs(&self) -> Result<Vec<IpNet>, RdapServerError> {
+        match &self.network_id {
+            NetworkIdType::Cidr(cidr) => Ok(vec![cidr.trunc()]),
+            NetworkIdType::Range {
+                start_address,
+                end_address,
+            } => range_cidrs(start_address, end_address),
+        }
+    }
+}
+
+/// Identifies an object in storage.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum ObjectId {
+    Domain(DomainId),
+    Entity(EntityId),
+    Nameserver(NameserverId),
+    Autnum(AutnumId),
+    Network(NetworkId),
+    /// Server help for a host, or the default server help.
+    SrvHelp(Option<String>),
+}
+
+impl ObjectId {
+    /// Creates the ID of a domain stored under an LDH name.
+    pub fn domain(ldh_name: String) -> Self {
+        Self::Domain(DomainId {
+            ldh_name,
+            unicode_name: None,
+        })
+    }
+
+    /// Creates the ID of a nameserver stored under an LDH name.
+    pub fn nameserver(ldh_name: String) -> Self {
+        Self::Nameserver(NameserverId {
+            ldh_name,
+            unicode_name: None,
+        })
+    }
+
+    /// Creates the ID of a network covering an address range.
+    ///
+    /// The addresses are normalized, so that the IDs of a network written as a CIDR or
+    /// as a range in different notations are equal.
+    pub fn network_range(start_address: &str, end_address: &str) -> Result<Self, RdapServerError> {
+        Ok(Self::Network(NetworkId {
+            network_id: NetworkIdType::Range {
+                start_address: IpAddr::from_str(start_address)?.to_string(),
+                end_address: IpAddr::from_str(end_address)?.to_string(),
+            },
+        }))
+    }
+
+    /// Creates the ID of a network from the ID of a template.
+    pub fn network(network_id: &NetworkId) -> Result<Self, RdapServerError> {
+        match &network_id.network_id {
+            NetworkIdType::Cidr(cidr) => {
+                Self::network_range(&cidr.network().to_string(), &cidr.broadcast().to_string())
+            }
+            NetworkIdType::Range {
+                start_address,
+                end_address,
+            } => Self::network_range(start_address, end_address),
+        }
+    }
+
+    /// Gets the object class name of the object, or `help` for server help.
+    pub fn class(&self) -> &'static str {
+        match self {
+            Self::Domain(_) => "domain",
+            Self::Entity(_) => "entity",
+            Self::Nameserver(_) => "nameserver",
+            Self::Autnum(_) => "autnum",
+            Self::Network(_) => "ip network",
+            Self::SrvHelp(_) => "help",
+        }
+    }
+
+    /// Removes the object from storage.
+    pub async fn remove(&self, tx: &mut Box<dyn TxHandle>) -> Result<(), RdapServerError> {
+        match self {
+            Self::Domain(domain_id) => tx.remove_domain(domain_id).await,
+            Self::Entity(entity_id) => tx.remove_entity(entity_id).await,
+            Self::Nameserver(nameserver_id) => tx.remove_nameserver(nameserver_id).await,
+            Self::Autnum(autnum_id) => tx.remove_autnum(autnum_id).await,
+            Self::Network(network_id) => tx.remove_network(network_id).await,
+            Self::SrvHelp(host) => tx.remove_srv_help(host.as_deref()).await,
+        }
+    }
+}
+
 /// Loads files from the data directory into memory.
 ///
 /// There are 2 types of files that will be selected. Files ending with a `.json` extension
@@ -170,14 +276,32 @@ pub enum NetworkIdType {
 /// ```
 /// In this example, 2 domains will be created for "foo.example" and "bar.exaple" using
 /// the template.
+///
+/// For large amounts of data, files ending with a `.jsonl` extension hold one RDAP object
+/// or template per line (JSON Lines), and tar archives ending with `.tar` hold files of all
+/// of these types. Both may be compressed with gzip (`.jsonl.gz`, `.tar.gz`, `.tgz`) or
+/// zstd (`.jsonl.zst`, `.tar.zst`, `.tzst`). These are streamed rather than read into memory.
 pub async fn load_data(
     config: &ServiceConfig,
     store: &dyn StoreOps,
     truncate: bool,
 ) -> Result<(), RdapServerError> {
+    load_data_files(config, store, truncate).await?;
+    Ok(())
+}
+
+/// Loads the files of the data directory as [load_data] does, and gets the index of the
+/// objects loaded from each file.
+pub(crate) async fn load_data_files(
+    config: &ServiceConfig,
+    store: &dyn StoreOps,
+    truncate: bool,
+) -> Result<DataFiles, RdapServerError> {
     let mut json_count: usize = 0;
     let mut template_count: usize = 0;
     let mut srvhelp_count: usize = 0;
+    let mut bulk_count: usize = 0;
+    let mut data_files = DataFiles::default();
     let mut tx = if truncate {
         store.new_truncate_tx().await?
     } else {
@@ -189,42 +313,276 @@ pub async fn load_data(
             "Directory {} does not exist or is not a directory. Server has no content to serve.",
             path.to_string_lossy()
         );
-        return Ok(());
+        return Ok(data_files);
     }
 
     let mut entries = tokio::fs::read_dir(path).await?;
     while let Some(entry) = entries.next_entry().await? {
         let entry_path = entry.path();
-        let contents = tokio::fs::read_to_string(&entry_path).await?;
-        if entry_path
-            .extension()
-            .map_or(false, |ext| ext == "template")
-        {
-            load_rdap_template(&contents, &entry_path.to_string_lossy(), &mut tx).await?;
-            template_count += 1;
-        } else if entry_path.extension().map_or(false, |ext| ext == "json") {
-            load_rdap(&contents, &entry_path.to_string_lossy(), &mut tx).await?;
-            json_count += 1;
-        } else if entry_path.extension().map_or(false, |ext| ext == "help") {
-            load_srvhelp(
-                &contents,
-                &entry_path.to_string_lossy(),
-                &entry.file_name().to_string_lossy(),
-                &mut tx,
-            )
-            .await?;
-            srvhelp_count += 1;
+        let Some(kind) = DataFileKind::of(&entry_path) else {
+            continue;
+        };
+        let stamp = FileStamp::of(&entry_path).await?;
+        let ids = load_file(kind, &entry_path, &mut tx).await?;
+        data_files.insert(PathBuf::from(entry.file_name()), stamp, ids);
+        match kind {
+            DataFileKind::Json => json_count += 1,
+            DataFileKind::Template => template_count += 1,
+            DataFileKind::Help => srvhelp_count += 1,
+            DataFileKind::JsonLines(_) | DataFileKind::Archive(_) => bulk_count += 1,
         }
     }
 
     info!("{json_count} RDAP JSON files loaded.");
     info!("{template_count} RDAP template files loaded.");
     info!("{srvhelp_count} RDAP server help files loaded.");
-    if json_count == 0 && template_count == 0 && srvhelp_count == 0 {
+    info!("{bulk_count} RDAP JSON Lines and archive files loaded.");
+    if json_count == 0 && template_count == 0 && srvhelp_count == 0 && bulk_count == 0 {
         warn!("No data loaded. Server has no content to serve.");
     }
     tx.commit().await?;
-    Ok(())
+    Ok(data_files)
+}
+
+/// The kinds of files in the data directory holding objects, which are told apart
+/// by their file extensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFileKind {
+    /// A `.json` file holding one RDAP response.
+    Json,
+    /// A `.template` file creating RDAP objects from a template.
+    Template,
+    /// A `.help` file holding the server help of a host.
+    Help,
+    /// A `.jsonl` file holding an RDAP object or template on each line.
+    JsonLines(Compression),
+    /// A `.tar` archive of data files.
+    Archive(Compression),
+}
+
+/// The compression of a data file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl DataFileKind {
+    /// Gets the kind of a data file, or `None` if the file does not hold objects.
+    pub fn of(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        let (stem, compression) = if let Some(stem) = file_name.strip_suffix(".gz") {
+            (stem, Compression::Gzip)
+        } else if let Some(stem) = file_name.strip_suffix(".zst") {
+            (stem, Compression::Zstd)
+        } else {
+            (file_name, Compression::None)
+        };
+        let extension = Path::new(stem).extension()?.to_str()?;
+        match (extension, compression) {
+            ("json", Compression::None) => Some(Self::Json),
+            ("template", Compression::None) => Some(Self::Template),
+            ("help", Compression::None) => Some(Self::Help),
+            ("jsonl", _) => Some(Self::JsonLines(compression)),
+            ("tar", _) => Some(Self::Archive(compression)),
+            ("tgz", Compression::None) => Some(Self::Archive(Compression::Gzip)),
+            ("tzst", Compression::None) => Some(Self::Archive(Compression::Zstd)),
+            _ => None,
+        }
+    }
+}
+
+/// Loads a data file into storage, and gets the IDs of the objects loaded.
+pub(crate) async fn load_file(
+    kind: DataFileKind,
+    path: &Path,
+    tx: &mut Box<dyn TxHandle>,
+) -> Result<Vec<ObjectId>, RdapServerError> {
+    let path_name = path.to_string_lossy();
+    let mut ids = vec![];
+    match kind {
+        DataFileKind::Json => {
+            let contents = tokio::fs::read_to_string(path).await?;
+            load_rdap(&contents, &path_name, tx, &mut ids).await?
+        }
+        DataFileKind::Template => {
+            let contents = tokio::fs::read_to_string(path).await?;
+            load_rdap_template(&contents, &path_name, tx, &mut ids).await?
+        }
+        DataFileKind::Help => {
+            let contents = tokio::fs::read_to_string(path).await?;
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            load_srvhelp(&contents, &path_name, &file_name, tx, &mut ids).await?
+        }
+        DataFileKind::JsonLines(compression) => {
+            ids = load_json_lines(path, compression, tx).await?
+        }
+        DataFileKind::Archive(compression) => ids = load_archive(path, compression, tx).await?,
+    };
+    Ok(ids)
+}
+
+/// A document holding objects, such as a line of a JSON Lines file.
+pub enum Document {
+    Object(RdapResponse),
+    Template(Template),
+    SrvHelp { host: String, help: Help },
+}
+
+impl Document {
+    /// Parses an RDAP object or a template, which are told apart by the `objectClassName`
+    /// of RDAP objects.
+    pub(crate) fn parse(json: &[u8]) -> Result<Self, RdapServerError> {
+        let value = serde_json::from_slice::<Value>(json)?;
+        if value.get("objectClassName").is_some() {
+            let rdap = RdapResponse::try_from(value)?;
+            if object_id(&rdap)?.is_none() {
+                return Err(RdapServerError::NonObjectDocument);
+            }
+            Ok(Self::Object(rdap))
+        } else {
+            Ok(Self::Template(serde_json::from_value(value)?))
+        }
+    }
+
+    /// Gets the IDs of the objects of the document, which also checks that they can be stored.
+    pub(crate) fn object_ids(&self) -> Result<Vec<ObjectId>, RdapServerError> {
+        match self {
+            Self::Object(rdap) => Ok(object_id(rdap)?.into_iter().collect()),
+            Self::Template(template) => template_object_ids(template),
+            Self::SrvHelp { host, .. } => Ok(vec![ObjectId::SrvHelp(Some(host.clone()))]),
+        }
+    }
+
+    /// Puts the objects of the document into storage, and gets their IDs.
+    pub(crate) async fn add(
+        self,
+        tx: &mut Box<dyn TxHandle>,
+    ) -> Result<Vec<ObjectId>, RdapServerError> {
+        match self {
+            Self::Object(rdap) => Ok(add_object(rdap, tx).await?.into_iter().collect()),
+            Self::Template(template) => add_template(template, tx).await,
+            Self::SrvHelp { host, help } => {
+                tx.add_srv_help(&help, Some(&host)).await?;
+                Ok(vec![ObjectId::SrvHelp(Some(host))])
+            }
+        }
+    }
+}
+
+/// Gets the host of the server help in a `.help` file, which is the file name
+/// with underscores in place of dots.
+pub(crate) fn help_host(file_name: &str) -> Option<String> {
+    Some(file_name.strip_suffix(".help")?.replace('_', "."))
+}
+
+/// The size and modification time of a data file, taken before the file is loaded, which
+/// tell if the file has changed since it was loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FileStamp {
+    len: u64,
+    modified: SystemTime,
+}
+
+impl FileStamp {
+    pub(crate) async fn of(path: &Path) -> Result<Self, std::io::Error> {
+        let metadata = tokio::fs::metadata(path).await?;
+        Ok(Self {
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+/// An index of the objects loaded from each file of the data directory, keyed by file name.
+///
+/// Objects are counted by the number of files loading them, so that changing or removing
+/// one file only removes the objects no other file loads. When several files load the same
+/// object, it keeps the contents last loaded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DataFiles {
+    files: HashMap<PathBuf, (FileStamp, Vec<ObjectId>)>,
+    references: HashMap<ObjectId, usize>,
+}
+
+impl DataFiles {
+    /// Records the objects loaded from a file, and gets the objects previously loaded from
+    /// the file which are no longer loaded by any file.
+    pub(crate) fn insert(
+        &mut self,
+        file_name: PathBuf,
+        stamp: FileStamp,
+        ids: Vec<ObjectId>,
+    ) -> Vec<ObjectId> {
+        for id in &ids {
+            *self.references.entry(id.clone()).or_default() += 1;
+        }
+        let previous = self
+            .files
+            .insert(file_name, (stamp, ids))
+            .map(|(_, ids)| ids)
+            .unwrap_or_default();
+        self.release(previous)
+    }
+
+    /// Forgets a file, and gets the objects it loaded which are no longer loaded by any file.
+    pub(crate) fn remove(&mut self, file_name: &Path) -> Vec<ObjectId> {
+        let previous = self
+            .files
+            .remove(file_name)
+            .map(|(_, ids)| ids)
+            .unwrap_or_default();
+        self.release(previous)
+    }
+
+    /// Gets the files, their stamps when loaded, and the objects loaded from them.
+    pub(crate) fn files(&self) -> impl Iterator<Item = (&PathBuf, &FileStamp, &Vec<ObjectId>)> {
+        self.files
+            .iter()
+            .map(|(file_name, (stamp, ids))| (file_name, stamp, ids))
+    }
+
+    /// Gets the objects loaded from a file, which are none if it has not been loaded.
+    pub(crate) fn ids(&self, file_name: &Path) -> &[ObjectId] {
+        self.files
+            .get(file_name)
+            .map_or(&[], |(_, ids)| ids.as_slice())
+    }
+
+    /// Gets the stamp of a file when its objects were loaded, or `None` if they have not been.
+    pub(crate) fn stamp(&self, file_name: &Path) -> Option<FileStamp> {
+        self.files.get(file_name).map(|(stamp, _)| *stamp)
+    }
+
+    fn release(&mut self, ids: Vec<ObjectId>) -> Vec<ObjectId> {
+        ids.into_iter()
+            .filter(|id| {
+                let Some(count) = self.references.get_mut(id) else {
+                    return false;
+                };
+                *count -= 1;
+                if *count == 0 {
+                    self.references.remove(id);
+                    true
+                } else {
+                    false
+                }
+            })
+            .collect()
+    }
+
+    /// Gets the counts of the objects loaded by object class.
+    pub(crate) fn object_counts(&self) -> ObjectCounts {
+        let mut object_counts = ObjectCounts::new();
+        for id in self.references.keys() {
+            *object_counts.entry(id.class()).or_default() += 1;
+        }
+        object_counts
+    }
 }
 
 /// Loads the RDAP JSON files and puts them in storage.
@@ -232,6 +590,7 @@ async fn load_rdap(
     contents: &str,
     path_name: &str,
     tx: &mut Box<dyn TxHandle>,
+    ids: &mut Vec<ObjectId>,
 ) -> Result<(), RdapServerError> {
     debug!("loading {path_name} into storage");
     let json = serde_json::from_str::<Value>(contents);
@@ -242,29 +601,75 @@ async fn load_rdap(
     let Ok(rdap) = rdap else {
         return Err(RdapServerError::NonRdapJsonFile(path_name.to_owned()));
     };
-    match rdap {
-        RdapResponse::Entity(entity) => tx.add_entity(&entity).await,
-        RdapResponse::Domain(domain) => tx.add_domain(&domain).await,
-        RdapResponse::Nameserver(nameserver) => tx.add_nameserver(&nameserver).await,
-        RdapResponse::Autnum(autnum) => tx.add_autnum(&autnum).await,
-        RdapResponse::Network(network) => tx.add_network(&network).await,
-        _ => return Err(RdapServerError::NonRdapJsonFile(path_name.to_owned())),
-    }?;
+    let Some(id) = add_object(rdap, tx).await? else {
+        return Err(RdapServerError::NonRdapJsonFile(path_name.to_owned()));
+    };
+    ids.push(id);
     Ok(())
 }
 
+/// Gets the ID under which an RDAP object is stored. RDAP responses which are not objects,
+/// such as search results, have no ID.
+pub(crate) fn object_id(rdap: &RdapResponse) -> Result<Option<ObjectId>, RdapServerError> {
+    let id = match rdap {
+        RdapResponse::Entity(entity) => ObjectId::Entity(EntityId {
+            handle: required(&entity.object_common.handle, "handle")?.to_owned(),
+        }),
+        RdapResponse::Domain(domain) => {
+            ObjectId::domain(required(&domain.ldh_name, "ldhName")?.to_owned())
+        }
+        RdapResponse::Nameserver(nameserver) => {
+            ObjectId::nameserver(required(&nameserver.ldh_name, "ldhName")?.to_owned())
+        }
+        RdapResponse::Autnum(autnum) => ObjectId::Autnum(AutnumId {
+            start_autnum: *required(&autnum.start_autnum, "startNum")?,
+            end_autnum: *required(&autnum.end_autnum, "endNum")?,
+        }),
+        RdapResponse::Network(network) => ObjectId::network_range(
+            required(&network.start_address, "startAddress")?,
+            required(&network.end_address, "endAddress")?,
+        )?,
+        _ => return Ok(None),
+    };
+    Ok(Some(id))
+}
+
+fn required<'a, T>(value: &'a Option<T>, name: &str) -> Result<&'a T, RdapServerError> {
+    value
+        .as_ref()
+        .ok_or_else(|| RdapServerError::EmptyIndexData(name.to_string()))
+}
+
+/// Puts an RDAP object into storage, and gets the ID it is stored under. RDAP responses
+/// which are not objects are not put into storage.
+pub(crate) async fn add_object(
+    rdap: RdapResponse,
+    tx: &mut Box<dyn TxHandle>,
+) -> Result<Option<ObjectId>, RdapServerError> {
+    let id = object_id(&rdap)?;
+    match rdap {
+        RdapResponse::Entity(entity) => tx.add_entity(&entity).await?,
+        RdapResponse::Domain(domain) => tx.add_domain(&domain).await?,
+        RdapResponse::Nameserver(nameserver) => tx.add_nameserver(&nameserver).await?,
+        RdapResponse::Autnum(autnum) => tx.add_autnum(&autnum).await?,
+        RdapResponse::Network(network) => tx.add_network(&network).await?,
+        _ => {}
+    };
+    Ok(id)
+}
+
 /// Loads the RDAP HELP files and puts them in storage.
 async fn load_srvhelp(
     contents: &str,
     path_name: &str,
     file_name: &str,
     tx: &mut Box<dyn TxHandle>,
+    ids: &mut Vec<ObjectId>,
 ) -> Result<(), RdapServerError> {
     debug!("loading {path_name} into storage");
-    let Some(host) = file_name.strip_suffix(".help") else {
+    let Some(host) = help_host(file_name) else {
         return Err(RdapServerError::NonRdapJsonFile(path_name.to_string()));
     };
-    let host = host.replace('_', ".");
     let json = serde_json::from_str::<Value>(contents);
     let Ok(value) = json else {
         return Err(RdapServerError::NonJsonFile(path_name.to_owned()));
@@ -277,6 +682,7 @@ async fn load_srvhelp(
         RdapResponse::Help(srvhelp) => tx.add_srv_help(&srvhelp, Some(&host)).await,
         _ => return Err(RdapServerError::NonRdapJsonFile(path_name.to_owned())),
     }?;
+    ids.push(ObjectId::SrvHelp(Some(host)));
     Ok(())
 }
 
@@ -286,120 +692,227 @@ async fn load_rdap_template(
     contents: &str,
     path_name: &str,
     tx: &mut Box<dyn TxHandle>,
+    ids: &mut Vec<ObjectId>,
 ) -> Result<(), RdapServerError> {
     debug!("processing {path_name} template");
     let json = serde_json::from_str::<Template>(contents);
-    if let Ok(value) = json {
-        match value {
-            Template::Domain { domain, ids } => {
-                for id in ids {
-                    debug!("adding domain from template for {id:?}");
-                    match &domain {
-                        DomainOrError::DomainObject(domain) => {
-                            let domain = make_domain_from_template(domain, id);
-                            tx.add_domain(&domain).await?;
-                        }
-                        DomainOrError::ErrorResponse(error) => {
-                            tx.add_domain_err(&id, error).await?;
-                        }
-                    };
-                }
+    let Ok(template) = json else {
+        return Err(RdapServerError::NonJsonFile(path_name.to_owned()));
+    };
+    ids.extend(add_template(template, tx).await?);
+    Ok(())
+}
+
+/// Gets the IDs under which the objects created from a template are stored.
+pub(crate) fn template_object_ids(template: &Template) -> Result<Vec<ObjectId>, RdapServerError> {
+    let object_ids = match template {
+        Template::Domain { ids, .. } => ids
+            .iter()
+            .map(|id| ObjectId::domain(id.ldh_name.clone()))
+            .collect(),
+        Template::Entity { ids, .. } => ids.iter().cloned().map(ObjectId::Entity).collect(),
+        Template::Nameserver { ids, .. } => ids
+            .iter()
+            .map(|id| ObjectId::nameserver(id.ldh_name.clone()))
+            .collect(),
+        Template::Autnum { ids, .. } => ids.iter().cloned().map(ObjectId::Autnum).collect(),
+        Template::Network { ids, .. } => ids
+            .iter()
+            .map(ObjectId::network)
+            .collect::<Result<_, _>>()?,
+    };
+    Ok(object_ids)
+}
+
+/// Creates RDAP objects from a template and puts them into storage, and gets the IDs
+/// they are stored under.
+pub(crate) async fn add_template(
+    template: Template,
+    tx: &mut Box<dyn TxHandle>,
+) -> Result<Vec<ObjectId>, RdapServerError> {
+    let object_ids = template_object_ids(&template)?;
+    match template {
+        Template::Domain { domain, ids } => {
+            for id in ids {
+                debug!("adding domain from template for {id:?}");
+                match &domain {
+                    DomainOrError::DomainObject(domain) => {
+                        let domain = make_domain_from_template(domain, id);
+                        tx.add_domain(&domain).await?;
+                    }
+                    DomainOrError::ErrorResponse(error) => {
+                        tx.add_domain_err(&id, error).await?;
+                    }
+                };
             }
-            Template::Entity { entity, ids } => {
-                for id in ids {
-                    debug!("adding entity from template for {id:?}");
-                    match &entity {
-                        EntityOrError::EntityObject(entity) => {
-                            let entity = make_entity_from_template(entity, id);
-                            tx.add_entity(&entity).await?;
-                        }
-                        EntityOrError::ErrorResponse(error) => {
-                            tx.add_entity_err(&id, error).await?;
-                        }
-                    };
-                }
+        }
+        Template::Entity { entity, ids } => {
+            for id in ids {
+                debug!("adding entity from template for {id:?}");
+                match &entity {
+                    EntityOrError::EntityObject(entity) => {
+                        let entity = make_entity_from_template(entity, id);
+                        tx.add_entity(&entity).await?;
+                    }
+                    EntityOrError::ErrorResponse(error) => {
+                        tx.add_entity_err(&id, error).await?;
+                    }
+                };
             }
-            Template::Nameserver { nameserver, ids } => {
-                for id in ids {
-                    debug!("adding nameserver from template for {id:?}");
-                    match &nameserver {
-                        NameserverOrError::NameserverObject(nameserver) => {
-                            let nameserver = make_nameserver_from_template(nameserver, id);
-                            tx.add_nameserver(&nameserver).await?;
-                        }
-                        NameserverOrError::ErrorResponse(error) => {
-                            tx.add_nameserver_err(&id, error).await?;
-                        }
-                    };
-                }
+        }
+        Template::Nameserver { nameserver, ids } => {
+            for id in ids {
+                debug!("adding nameserver from template for {id:?}");
+                match &nameserver {
+                    NameserverOrError::NameserverObject(nameserver) => {
+                        let nameserver = make_nameserver_from_template(nameserver, id);
+                        tx.add_nameserver(&nameserver).await?;
+                    }
+                    NameserverOrError::ErrorResponse(error) => {
+                        tx.add_nameserver_err(&id, error).await?;
+                    }
+                };
             }
-            Template::Autnum { autnum, ids } => {
-                for id in ids {
-                    debug!("adding autnum from template for {id:?}");
-                    match &autnum {
-                        AutnumOrError::AutnumObject(autnum) => {
-                            let autnum = make_autnum_from_template(autnum, id);
-                            tx.add_autnum(&autnum).await?;
-                        }
-                        AutnumOrError::ErrorResponse(error) => {
-                            tx.add_autnum_err(&id, error).await?;
-                        }
-                    };
-                }
+        }
+        Template::Autnum { autnum, ids } => {
+            for id in ids {
+                debug!("adding autnum from template for {id:?}");
+                match &autnum {
+                    AutnumOrError::AutnumObject(autnum) => {
+                        let autnum = make_autnum_from_template(autnum, id);
+                        tx.add_autnum(&autnum).await?;
+                    }
+                    AutnumOrError::ErrorResponse(error) => {
+                        tx.add_autnum_err(&id, error).await?;
+                    }
+                };
             }
-            Template::Network { network, ids } => {
-                for id in ids {
-                    debug!("adding network from template for {id:?}");
-                    match &network {
-                        NetworkOrError::NetworkObject(network) => {
-                            let network = make_network_from_template(network, id)?;
-                            tx.add_network(&network).await?;
-                        }
-                        NetworkOrError::ErrorResponse(error) => {
-                            tx.add_network_err(&id, error).await?;
-                        }
-                    };
-                }
+        }
+        Template::Network { network, ids } => {
+            for id in ids {
+                debug!("adding network from template for {id:?}");
+                match &network {
+                    NetworkOrError::NetworkObject(network) => {
+                        let network = make_network_from_template(network, id)?;
+                        tx.add_network(&network).await?;
+                    }
+                    NetworkOrError::ErrorResponse(error) => {
+                        tx.add_network_err(&id, error).await?;
+                    }
+                };
             }
-        };
-    } else {
-        return Err(RdapServerError::NonJsonFile(path_name.to_owned()));
-    }
-    Ok(())
+        }
+    };
+    Ok(object_ids)
 }
 
+/// Polls for modifications of the `update` and `reload` files in the data directory
+/// after `last_time` and loads the data directory when they are modified. Failures are
+/// logged and recorded in the server status, and polling continues.
+///
+/// This is only used when the data directory cannot be watched for changes.
 pub(crate) async fn reload_data(
     store: Box<dyn StoreOps>,
     config: ServiceConfig,
-) -> Result<(), RdapServerError> {
+    status: Arc<ServerStatus>,
+    mut last_time: SystemTime,
+    snapshot: Option<MemSnapshot>,
+) {
     let update_path = PathBuf::from(&config.data_dir);
     let update_path = update_path.join(UPDATE);
     let reload_path = PathBuf::from(&config.data_dir);
     let reload_path = reload_path.join(RELOAD);
-    let mut last_time = SystemTime::now();
     loop {
         sleep(Duration::from_millis(1000)).await;
-        let update_meta = tokio::fs::metadata(&update_path).await;
-        if update_meta.is_ok() {
-            let modified = update_meta.unwrap().modified()?;
-            if modified > last_time {
-                last_time = modified;
-                info!("Data being updated.");
-                load_data(&config, &*store, false).await?;
-            }
+        let update_modified = modified_time(&update_path).await;
+        if update_modified.is_some_and(|modified| modified > last_time) {
+            last_time = update_modified.unwrap_or(last_time);
+            info!("Data being updated.");
+            let result = load_data_files(&config, &*store, false).await;
+            finish_load(
+                &status,
+                LoadKind::Update,
+                snapshot.as_ref(),
+                &config,
+                result,
+            )
+            .await;
         };
-        let reload_meta = tokio::fs::metadata(&reload_path).await;
-        if reload_meta.is_ok() {
-            let modified = reload_meta.unwrap().modified()?;
-            if modified > last_time {
-                last_time = modified;
-                info!("Data being reloaded.");
-                load_data(&config, &*store, true).await?;
-            }
+        let reload_modified = modified_time(&reload_path).await;
+        if reload_modified.is_some_and(|modified| modified > last_time) {
+            last_time = reload_modified.unwrap_or(last_time);
+            info!("Data being reloaded.");
+            let result = load_data_files(&config, &*store, true).await;
+            finish_load(
+                &status,
+                LoadKind::Reload,
+                snapshot.as_ref(),
+                &config,
+                result,
+            )
+            .await;
         };
     }
 }
 
+/// Records the outcome of a load in the server status and, after a successful load, the
+/// objects loaded. The snapshot of the store, if any, is then written in the background.
+/// Gets the files loaded if the load succeeded.
+pub(crate) async fn finish_load(
+    status: &ServerStatus,
+    kind: LoadKind,
+    snapshot: Option<&MemSnapshot>,
+    config: &ServiceConfig,
+    loaded: Result<DataFiles, RdapServerError>,
+) -> Option<DataFiles> {
+    let data_files = match loaded {
+        Ok(data_files) => data_files,
+        Err(e) => {
+            error!("Data {kind} failed: {e}");
+            status.record_load(config.host.as_deref(), kind, &Err(e));
+            return None;
+        }
+    };
+    set_objects_loaded(config.host.as_deref(), &data_files.object_counts());
+    status.record_load(config.host.as_deref(), kind, &Ok(()));
+    if let Some(snapshot) = snapshot {
+        snapshot.write_later(&config.data_dir, &data_files).await;
+    }
+    Some(data_files)
+}
+
+/// Gets the modification time of a file, or `None` if it does not exist or cannot be read.
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// Gets the CIDRs that cover an address range.
+pub(crate) fn range_cidrs(
+    start_address: &str,
+    end_address: &str,
+) -> Result<Vec<IpNet>, RdapServerError> {
+    let start_addr = IpAddr::from_str(start_address)?;
+    let end_addr = IpAddr::from_str(end_address)?;
+    match (start_addr, end_addr) {
+        (IpAddr::V4(start_addr), IpAddr::V4(end_addr)) => {
+            Ok(Ipv4Subnets::new(start_addr, end_addr, 0)
+                .map(IpNet::V4)
+                .collect())
+        }
+        (IpAddr::V6(start_addr), IpAddr::V6(end_addr)) => {
+            Ok(Ipv6Subnets::new(start_addr, end_addr, 0)
+                .map(IpNet::V6)
+                .collect())
+        }
+        _ => Err(RdapServerError::EmptyIndexData(
+            "mismatch ip version".to_string(),
+        )),
+    }
+}
+
 pub async fn trigger_reload(data_dir: &str) -> Result<(), RdapServerError> {
     let reload_path = PathBuf::from(&data_dir);
     let reload_path = reload_path.join(RELOAD);
@@ -559,6 +1072,7 @@ fn make_network_from_template(
 mod tests {
 
     use icann_rdap_common::response::{domain::Domain, types::Link};
+    use rstest::rstest;
 
     use super::*;
 
@@ -1001,4 +1515,107 @@ mod tests {
             "http://reg.example/ip/11.0.0.0/24"
         );
     }
+
+    fn stamp() -> FileStamp {
+        FileStamp {
+            len: 0,
+            modified: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn GIVEN_object_in_two_files_WHEN_remove_one_file_THEN_object_not_stale() {
+        // GIVEN
+        let mut data_files = DataFiles::default();
+        let foo = ObjectId::domain("foo.example".to_string());
+        let bar = ObjectId::domain("bar.example".to_string());
+        data_files.insert(PathBuf::from("a.json"), stamp(), vec![foo.clone()]);
+        data_files.insert(
+            PathBuf::from("b.template"),
+            stamp(),
+            vec![foo.clone(), bar.clone()],
+        );
+
+        // WHEN
+        let stale_b = data_files.remove(Path::new("b.template"));
+        let stale_a = data_files.remove(Path::new("a.json"));
+
+        // THEN
+        assert_eq!(stale_b, vec![bar]);
+        assert_eq!(stale_a, vec![foo]);
+        assert!(data_files.object_counts().is_empty());
+    }
+
+    #[test]
+    fn GIVEN_file_WHEN_insert_changed_file_THEN_dropped_objects_stale() {
+        // GIVEN
+        let mut data_files = DataFiles::default();
+        let foo = ObjectId::domain("foo.example".to_string());
+        let bar = ObjectId::domain("bar.example".to_string());
+        data_files.insert(
+            PathBuf::from("a.template"),
+            stamp(),
+            vec![foo.clone(), bar.clone()],
+        );
+
+        // WHEN
+        let stale = data_files.insert(
+            PathBuf::from("a.template"),
+            stamp(),
+            vec![foo, ObjectId::SrvHelp(None)],
+        );
+
+        // THEN
+        assert_eq!(stale, vec![bar]);
+        let counts = data_files.object_counts();
+        assert_eq!(counts.get("domain"), Some(&1));
+        assert_eq!(counts.get("help"), Some(&1));
+    }
+
+    #[test]
+    fn GIVEN_network_as_cidr_and_range_WHEN_object_ids_THEN_equal() {
+        // GIVEN
+        let cidr = NetworkId::builder()
+            .network_id(NetworkIdType::Cidr(
+                "2001:db8::/64".parse().expect("parsing cidr"),
+            ))
+            .build();
+
+        // WHEN
+        let from_cidr = ObjectId::network(&cidr).expect("cidr object id");
+        let from_range = ObjectId::network_range("2001:0db8::", "2001:db8:0:0:ffff:ffff:ffff:ffff")
+            .expect("range object id");
+
+        // THEN
+        assert_eq!(from_cidr, from_range);
+    }
+
+    #[rstest]
+    #[case("foo.json", Some(DataFileKind::Json))]
+    #[case("foo.template", Some(DataFileKind::Template))]
+    #[case("foo_example.help", Some(DataFileKind::Help))]
+    #[case("foo.jsonl", Some(DataFileKind::JsonLines(Compression::None)))]
+    #[case("foo.jsonl.gz", Some(DataFileKind::JsonLines(Compression::Gzip)))]
+    #[case("foo.jsonl.zst", Some(DataFileKind::JsonLines(Compression::Zstd)))]
+    #[case("foo.tar", Some(DataFileKind::Archive(Compression::None)))]
+    #[case("foo.tar.gz", Some(DataFileKind::Archive(Compression::Gzip)))]
+    #[case("foo.tgz", Some(DataFileKind::Archive(Compression::Gzip)))]
+    #[case("foo.tar.zst", Some(DataFileKind::Archive(Compression::Zstd)))]
+    #[case("foo.tzst", Some(DataFileKind::Archive(Compression::Zstd)))]
+    #[case("foo.json.gz", None)]
+    #[case("update", None)]
+    #[case("foo.txt", None)]
+    fn GIVEN_file_name_WHEN_data_file_kind_THEN_kind_from_extension(
+        #[case] file_name: &str,
+        #[case] expected: Option<DataFileKind>,
+    ) {
+        // GIVEN
+        let path = Path::new("/data").join(file_name);
+
+        // WHEN
+        let actual = DataFileKind::of(&path);
+
+        // THEN
+        assert_eq!(actual, expected);
+    }
 }
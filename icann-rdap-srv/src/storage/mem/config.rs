@@ -5,4 +5,7 @@ use crate::storage::CommonConfig;
 #[derive(Debug, Builder, Clone)]
 pub struct MemConfig {
     pub common_config: CommonConfig,
+    /// If specified, the store is snapshotted to this file after the data directory is
+    /// loaded at startup, and restored from it at the next startup.
+    pub snapshot_file: Option<String>,
 }
@@ -0,0 +1,313 @@
+use std::{collections::HashSet, sync::Arc};
+
+use icann_rdap_common::{
+    contact::Contact,
+    response::{entity::Entity, RdapResponse},
+};
+
+use crate::{
+    error::RdapServerError,
+    storage::{search::strings::matches, ReverseSearchConditions},
+};
+
+use super::string_search::SearchStrings;
+
+/// An entity related to an object, such as the registrant of a domain.
+pub(crate) struct RelatedEntity {
+    object: Arc<RdapResponse>,
+    handle: Option<String>,
+    contact: Option<Contact>,
+    roles: Vec<String>,
+}
+
+impl RelatedEntity {
+    /// Determines if this related entity meets all of the conditions of a reverse search.
+    fn meets(&self, conditions: &ReverseSearchConditions) -> Result<bool, RdapServerError> {
+        if let Some(search) = &conditions.full_name {
+            let full_name = self.contact.as_ref().and_then(|c| c.full_name.as_deref());
+            if !full_name.map_or(Ok(false), |f| matches(search, f))? {
+                return Ok(false);
+            }
+        }
+        if let Some(search) = &conditions.handle {
+            if !self
+                .handle
+                .as_deref()
+                .map_or(Ok(false), |h| matches(search, h))?
+            {
+                return Ok(false);
+            }
+        }
+        if let Some(search) = &conditions.email {
+            let emails = self
+                .contact
+                .as_ref()
+                .and_then(|c| c.emails.as_ref())
+                .map(|emails| emails.as_slice())
+                .unwrap_or_default();
+            let mut found = false;
+            for email in emails {
+                if matches(search, &email.email)? {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Ok(false);
+            }
+        }
+        if let Some(role) = &conditions.role {
+            if !self.roles.iter().any(|r| r.eq_ignore_ascii_case(role)) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// An index of objects by the contact information of their related entities,
+/// used for RFC 9536 reverse searches.
+#[derive(Clone)]
+pub struct ContactIndex {
+    by_fn: SearchStrings<Arc<RelatedEntity>>,
+    by_handle: SearchStrings<Arc<RelatedEntity>>,
+    by_email: SearchStrings<Arc<RelatedEntity>>,
+}
+
+impl Default for ContactIndex {
+    fn default() -> Self {
+        Self {
+            by_fn: SearchStrings::builder().build(),
+            by_handle: SearchStrings::builder().build(),
+            by_email: SearchStrings::builder().build(),
+        }
+    }
+}
+
+impl ContactIndex {
+    /// Insert an object by the entities related to it. The key identifies the object,
+    /// such as the LDH name of a domain.
+    pub(crate) fn insert(&mut self, key: &str, object: &Arc<RdapResponse>, entities: &[Entity]) {
+        for (i, entity) in entities.iter().enumerate() {
+            let id = related_id(key, i);
+            let related = Arc::new(RelatedEntity {
+                object: object.clone(),
+                handle: entity.object_common.handle.clone(),
+                contact: entity.contact(),
+                roles: entity.roles.clone().unwrap_or_default(),
+            });
+            if let Some(handle) = &related.handle {
+                self.by_handle.insert(handle, &id, related.clone());
+            }
+            if let Some(contact) = &related.contact {
+                if let Some(full_name) = &contact.full_name {
+                    self.by_fn.insert(full_name, &id, related.clone());
+                }
+                for email in contact.emails.iter().flatten() {
+                    self.by_email.insert(&email.email, &id, related.clone());
+                }
+            }
+        }
+    }
+
+    /// Remove an object inserted with the key and the entities related to it.
+    pub(crate) fn remove(&mut self, key: &str, entities: &[Entity]) {
+        for (i, entity) in entities.iter().enumerate() {
+            let id = related_id(key, i);
+            if let Some(handle) = &entity.object_common.handle {
+                self.by_handle.remove(handle, &id);
+            }
+            if let Some(contact) = entity.contact() {
+                if let Some(full_name) = &contact.full_name {
+                    self.by_fn.remove(full_name, &id);
+                }
+                for email in contact.emails.iter().flatten() {
+                    self.by_email.remove(&email.email, &id);
+                }
+            }
+        }
+    }
+
+    /// Search for the objects with a related entity meeting the conditions. At least one
+    /// condition other than the role must be given.
+    pub(crate) fn search(
+        &self,
+        conditions: &ReverseSearchConditions,
+    ) -> Result<Vec<Arc<RdapResponse>>, RdapServerError> {
+        let candidates = if let Some(full_name) = &conditions.full_name {
+            self.by_fn.search(full_name)?
+        } else if let Some(handle) = &conditions.handle {
+            self.by_handle.search(handle)?
+        } else if let Some(email) = &conditions.email {
+            self.by_email.search(email)?
+        } else {
+            return Err(RdapServerError::InvalidArg(
+                "Reverse search requires a condition other than role".to_string(),
+            ));
+        };
+        let mut seen = HashSet::new();
+        let mut objects = vec![];
+        for related in candidates {
+            if related.meets(conditions)? && seen.insert(Arc::as_ptr(&related.object)) {
+                objects.push(related.object.clone());
+            }
+        }
+        Ok(objects)
+    }
+}
+
+/// Identifies a related entity by the key of its object and its place among the entities
+/// of the object, as an object may have more than one entity with the same contact.
+fn related_id(key: &str, i: usize) -> String {
+    format!("{key}#{i}")
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use icann_rdap_common::{
+        contact::{Contact, Email},
+        response::{domain::Domain, entity::Entity, RdapResponse},
+    };
+
+    use crate::storage::ReverseSearchConditions;
+
+    use super::ContactIndex;
+
+    fn new_index() -> ContactIndex {
+        let mut index = ContactIndex::default();
+        let registrant = Entity::basic()
+            .handle("bob-1")
+            .role("registrant")
+            .contact(
+                Contact::builder()
+                    .full_name("Bob Smurd")
+                    .emails(vec![Email::builder().email("bob@example.com").build()])
+                    .build(),
+            )
+            .build();
+        let technical = Entity::basic()
+            .handle("alice-1")
+            .role("technical")
+            .contact(Contact::builder().full_name("Alice Smurd").build())
+            .build();
+        let billing = Entity::basic()
+            .handle("bobby-1")
+            .role("billing")
+            .contact(Contact::builder().full_name("Bobby Smurd").build())
+            .build();
+        let domain = Arc::new(RdapResponse::Domain(
+            Domain::basic().ldh_name("foo.example").build(),
+        ));
+        index.insert("foo.example", &domain, &[registrant, technical, billing]);
+        index
+    }
+
+    #[test]
+    fn GIVEN_index_WHEN_remove_object_THEN_object_not_found() {
+        // GIVEN
+        let mut index = new_index();
+        let registrant = Entity::basic()
+            .handle("bob-1")
+            .role("registrant")
+            .contact(
+                Contact::builder()
+                    .full_name("Bob Smurd")
+                    .emails(vec![Email::builder().email("bob@example.com").build()])
+                    .build(),
+            )
+            .build();
+        let bar = Arc::new(RdapResponse::Domain(
+            Domain::basic().ldh_name("bar.example").build(),
+        ));
+        index.insert("bar.example", &bar, std::slice::from_ref(&registrant));
+
+        // WHEN
+        index.remove("bar.example", &[registrant]);
+
+        // THEN
+        let conditions = ReverseSearchConditions::builder().handle("bob-1").build();
+        let actual = index.search(&conditions).expect("search is invalid");
+        assert_eq!(actual.len(), 1);
+        let RdapResponse::Domain(domain) = actual[0].as_ref() else {
+            panic!("not a domain")
+        };
+        assert_eq!(domain.ldh_name.as_deref(), Some("foo.example"));
+    }
+
+    #[test]
+    fn GIVEN_index_WHEN_search_by_fn_matching_two_entities_THEN_object_found_once() {
+        // GIVEN
+        let index = new_index();
+        let conditions = ReverseSearchConditions::builder().full_name("bob*").build();
+
+        // WHEN
+        let actual = index.search(&conditions).expect("search is invalid");
+
+        // THEN
+        assert_eq!(actual.len(), 1);
+    }
+
+    #[test]
+    fn GIVEN_index_WHEN_search_with_role_of_other_entity_THEN_no_object_found() {
+        // GIVEN
+        let index = new_index();
+        let conditions = ReverseSearchConditions::builder()
+            .full_name("Bob Smurd")
+            .role("technical")
+            .build();
+
+        // WHEN
+        let actual = index.search(&conditions).expect("search is invalid");
+
+        // THEN
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn GIVEN_index_WHEN_search_without_prefix_THEN_error() {
+        // GIVEN
+        let index = new_index();
+        let conditions = ReverseSearchConditions::builder().full_name("*").build();
+
+        // WHEN
+        let actual = index.search(&conditions);
+
+        // THEN
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn GIVEN_index_WHEN_search_by_email_and_role_THEN_object_found() {
+        // GIVEN
+        let index = new_index();
+        let conditions = ReverseSearchConditions::builder()
+            .email("BOB@example.com")
+            .role("registrant")
+            .build();
+
+        // WHEN
+        let actual = index.search(&conditions).expect("search is invalid");
+
+        // THEN
+        assert_eq!(actual.len(), 1);
+    }
+
+    #[test]
+    fn GIVEN_index_WHEN_search_by_role_only_THEN_error() {
+        // GIVEN
+        let index = new_index();
+        let conditions = ReverseSearchConditions::builder()
+            .role("registrant")
+            .build();
+
+        // WHEN
+        let actual = index.search(&conditions);
+
+        // THEN
+        assert!(actual.is_err());
+    }
+}
@@ -1,99 +1,98 @@
-use std::collections::HashMap;
-
-use ab_radix_trie::Trie;
 use buildstructor::Builder;
+use im::{HashMap, OrdMap};
 
-use crate::error::RdapServerError;
+use crate::{
+    error::RdapServerError,
+    storage::search::labels::{matches_labels, split_partial_search, split_search},
+};
 
 /// A structure for searching DNS labels as specified in RFC 9082.
 /// For RDAP, type T is likely RdapResponse or Arc<RdapResponse>.
-#[derive(Builder)]
+///
+/// Names are indexed by each of their label suffixes, and under each suffix by the labels
+/// preceding it, so that a search is a range of the names under one suffix. The maps are
+/// persistent, making clones cheap and letting names be inserted and removed without
+/// rebuilding the index.
+#[derive(Builder, Clone)]
 pub struct SearchLabels<T: Clone> {
-    label_suffixes: HashMap<String, Trie<T>>,
+    label_suffixes: HashMap<String, OrdMap<String, T>>,
 }
 
 impl<T: Clone> SearchLabels<T> {
-    /// Insert a value based on a domain name.
+    /// Insert a value based on a domain name, replacing any value of the same name.
     pub(crate) fn insert(&mut self, text: &str, value: T) {
-        // char_indices gets the UTF8 indices as well as the character
-        for (i, char) in text.char_indices() {
-            if char == '.' && i != 0 {
-                let prefix = &text[..i];
-                // find the next UTF8 character index
-                let mut next_i = i + 1;
-                while !text.is_char_boundary(next_i) {
-                    next_i += 1;
+        for (prefix, suffix) in label_splits(text) {
+            self.label_suffixes
+                .entry(suffix.to_owned())
+                .or_default()
+                .insert(prefix.to_owned(), value.clone());
+        }
+    }
+
+    /// Remove the value of a domain name.
+    pub(crate) fn remove(&mut self, text: &str) {
+        for (prefix, suffix) in label_splits(text) {
+            if let Some(prefixes) = self.label_suffixes.get_mut(suffix) {
+                prefixes.remove(prefix);
+                if prefixes.is_empty() {
+                    self.label_suffixes.remove(suffix);
                 }
-                let suffix = &text[next_i..];
-                self.label_suffixes
-                    .entry(suffix.to_owned())
-                    .or_insert(Trie::new())
-                    .insert(prefix, Some(value.clone()));
             }
         }
-        // the root
-        self.label_suffixes
-            .entry(String::default())
-            .or_insert(Trie::new())
-            .insert(text, Some(value.clone()));
     }
 
     /// Search values based on a label search
     pub(crate) fn search(&self, search: &str) -> Result<Vec<T>, RdapServerError> {
-        // search string is invalid if it doesn't have only one asterisk ('*')
-        if search.chars().filter(|c| *c == '*').count() != 1 {
-            return Err(RdapServerError::InvalidArg(
-                "Search string must contain one and only one asterisk ('*')".to_string(),
-            ));
-        }
-        // asterisk must not be followed by a character other than dot ('.')
-        let star = search
-            .find('*')
-            .expect("internal error. previous check should have caught this");
-        if star != search.chars().count() - 1
-            && search
-                .chars()
-                .nth(star + 1)
-                .expect("should have been short circuited")
-                != '.'
-        {
-            return Err(RdapServerError::InvalidArg(
-                "Search string asterisk ('*') must terminate domain label".to_string(),
-            ));
-        }
-
-        let parts = search
-            .split_once('*')
-            .expect("internal error. previous check should insure there is an asterisk");
-
-        // this is a limitation of the trie in that it requires a prefix
-        if parts.0.is_empty() {
-            return Err(RdapServerError::InvalidArg(
-                "Search string must have a prefix".to_string(),
-            ));
-        }
+        let (prefix, suffix) = split_search(search)?;
+        Ok(self.find(prefix, suffix).map(|(_, v)| v.clone()).collect())
+    }
 
-        if let Some(trie) = self.label_suffixes.get(parts.1.trim_start_matches('.')) {
-            if let Some(entries) = trie.get_suffixes_values(parts.0) {
-                if !entries.is_empty() {
-                    let values = entries
-                        .iter()
-                        .filter_map(|e| e.val.clone())
-                        .collect::<Vec<T>>();
-                    return Ok(values);
-                }
-            }
-        }
+    /// Search values based on a label search in which more than one label may be partial,
+    /// such as `exa*.c*`. When there is more than one asterisk, an asterisk by itself
+    /// matches exactly one label.
+    pub(crate) fn search_partial_labels(&self, search: &str) -> Result<Vec<T>, RdapServerError> {
+        let (prefix, suffix) = split_partial_search(search)?;
+        let labels = search[..search.len() - suffix.len()].trim_end_matches('.');
+        let is_partial = labels.matches('*').count() > 1;
+        Ok(self
+            .find(prefix, suffix)
+            .filter(|(name, _)| !is_partial || matches_labels(labels, name))
+            .map(|(_, v)| v.clone())
+            .collect())
+    }
 
-        Ok(vec![])
+    /// Finds the names under a label suffix beginning with a prefix, without the suffix.
+    fn find<'a>(
+        &'a self,
+        prefix: &'a str,
+        suffix: &str,
+    ) -> impl Iterator<Item = (&'a String, &'a T)> + 'a {
+        self.label_suffixes
+            .get(suffix)
+            .into_iter()
+            .flat_map(move |prefixes| {
+                prefixes
+                    .range(prefix.to_owned()..)
+                    .take_while(move |(k, _)| k.starts_with(prefix))
+            })
     }
 }
 
+/// Splits a domain name into the labels preceding each label suffix and that suffix (without
+/// the leading dot), including the whole name under the root (empty) suffix.
+fn label_splits(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    // char_indices gets the UTF8 indices as well as the character
+    text.char_indices()
+        .filter(|(i, char)| *char == '.' && *i != 0)
+        .map(|(i, _)| (&text[..i], &text[i + 1..]))
+        .chain(std::iter::once((text, "")))
+}
+
 #[cfg(test)]
 #[allow(non_snake_case)]
 mod tests {
 
-    use ab_radix_trie::{Entry, Trie};
+    use im::OrdMap;
 
     use super::SearchLabels;
 
@@ -113,69 +112,75 @@ mod tests {
         assert_eq!(search.label_suffixes.len(), 5);
         // root
         let root = search.label_suffixes.get("").expect("no root");
-        assert_trie(
+        assert_prefixes(
             root,
             "foo.example.",
             &["foo.example.com", "foo.example.net"],
             &["bar.example.com", "bar.example.net"],
         );
-        assert_trie(
+        assert_prefixes(
             root,
             "bar.example.",
             &["bar.example.com", "bar.example.net"],
             &["foo.example.com", "foo.example.net"],
         );
         // com
-        let com = search.label_suffixes.get("com").expect("no trie");
-        assert_trie(
+        let com = search.label_suffixes.get("com").expect("no prefixes");
+        assert_prefixes(
             com,
             "foo.example",
             &["foo.example.com"],
             &["bar.example.com", "bar.example.net", "foo.example.net"],
         );
-        assert_trie(
+        assert_prefixes(
             com,
             "bar.example",
             &["bar.example.com"],
             &["foo.example.com", "foo.example.net", "bar.example.net"],
         );
         // net
-        let net = search.label_suffixes.get("net").expect("no trie");
-        assert_trie(
+        let net = search.label_suffixes.get("net").expect("no prefixes");
+        assert_prefixes(
             net,
             "foo.example",
             &["foo.example.net"],
             &["bar.example.net", "bar.example.com", "foo.example.com"],
         );
-        assert_trie(
+        assert_prefixes(
             net,
             "bar.example",
             &["bar.example.net"],
             &["foo.example.com", "foo.example.net", "bar.example.com"],
         );
         // example.com
-        let example_com = search.label_suffixes.get("example.com").expect("no trie");
-        assert_trie(
+        let example_com = search
+            .label_suffixes
+            .get("example.com")
+            .expect("no prefixes");
+        assert_prefixes(
             example_com,
             "foo",
             &["foo.example.com"],
             &["bar.example.com", "bar.example.net", "foo.example.net"],
         );
-        assert_trie(
+        assert_prefixes(
             example_com,
             "bar",
             &["bar.example.com"],
             &["foo.example.com", "foo.example.net", "bar.example.net"],
         );
         // example.net
-        let example_net = search.label_suffixes.get("example.net").expect("no trie");
-        assert_trie(
+        let example_net = search
+            .label_suffixes
+            .get("example.net")
+            .expect("no prefixes");
+        assert_prefixes(
             example_net,
             "foo",
             &["foo.example.net"],
             &["bar.example.net", "bar.example.com", "foo.example.com"],
         );
-        assert_trie(
+        assert_prefixes(
             example_net,
             "bar",
             &["bar.example.net"],
@@ -183,25 +188,41 @@ mod tests {
         );
     }
 
-    fn assert_trie(trie: &Trie<String>, suffix: &str, must_have: &[&str], must_not_have: &[&str]) {
-        let entries = trie
-            .get_suffixes_values(suffix)
-            .expect("no values in entries");
+    fn assert_prefixes(
+        prefixes: &OrdMap<String, String>,
+        prefix: &str,
+        must_have: &[&str],
+        must_not_have: &[&str],
+    ) {
+        let values = prefixes
+            .iter()
+            .filter(|(k, _)| k.starts_with(prefix))
+            .map(|(_, v)| v.as_str())
+            .collect::<Vec<&str>>();
         for s in must_have {
-            assert!(
-                trie_contains(&entries, s),
-                "suffix = {suffix} did not find {s}"
-            );
+            assert!(values.contains(s), "prefix = {prefix} did not find {s}");
         }
         for s in must_not_have {
-            assert!(!trie_contains(&entries, s), "suffix = {suffix} found {s}");
+            assert!(!values.contains(s), "prefix = {prefix} found {s}");
         }
     }
 
-    fn trie_contains(entries: &[Entry<'_, String>], value: &str) -> bool {
-        entries
-            .iter()
-            .any(|e| e.val.as_ref().expect("no entry value") == value)
+    #[test]
+    fn GIVEN_domain_names_WHEN_removing_THEN_suffixes_of_name_removed() {
+        // GIVEN
+        let mut search = SearchLabels::builder().build();
+        search.insert("foo.example.com", "foo.example.com".to_owned());
+        search.insert("foo.example.net", "foo.example.net".to_owned());
+
+        // WHEN
+        search.remove("foo.example.com");
+
+        // THEN
+        assert_eq!(search.label_suffixes.len(), 3);
+        assert!(search.label_suffixes.get("com").is_none());
+        assert!(search.label_suffixes.get("example.com").is_none());
+        let actual = search.search("foo.example.*").expect("search is invalid");
+        assert_eq!(actual, vec!["foo.example.net".to_string()]);
     }
 
     #[test]
@@ -363,4 +384,63 @@ mod tests {
         dbg!(&actual);
         assert!(actual.is_err());
     }
+
+    #[test]
+    fn GIVEN_labels_WHEN_partial_sld_and_tld_search_THEN_correct_values_found() {
+        // GIVEN
+        let mut labels = SearchLabels::builder().build();
+        labels.insert("foo.example.com", "foo.example.com".to_owned());
+        labels.insert("foo.example.net", "foo.example.net".to_owned());
+        labels.insert("foo.sample.com", "foo.sample.com".to_owned());
+        labels.insert("foo.bar.example.com", "foo.bar.example.com".to_owned());
+
+        // WHEN
+        let actual = labels
+            .search_partial_labels("foo.ex*.c*")
+            .expect("search is invalid");
+
+        // THEN
+        assert_eq!(actual, vec!["foo.example.com".to_string()]);
+    }
+
+    #[test]
+    fn GIVEN_labels_WHEN_partial_search_with_asterisk_label_THEN_one_label_matched() {
+        // GIVEN
+        let mut labels = SearchLabels::builder().build();
+        labels.insert("foo.example.com", "foo.example.com".to_owned());
+        labels.insert("foo.bar.example.com", "foo.bar.example.com".to_owned());
+
+        // WHEN
+        let actual = labels
+            .search_partial_labels("fo*.*.com")
+            .expect("search is invalid");
+
+        // THEN
+        assert_eq!(actual, vec!["foo.example.com".to_string()]);
+    }
+
+    #[test]
+    fn GIVEN_two_asterisks_WHEN_search_without_partial_labels_THEN_error() {
+        // GIVEN
+        let mut labels = SearchLabels::builder().build();
+        labels.insert("foo.example.com", "foo.example.com".to_owned());
+
+        // WHEN
+        let actual = labels.search("foo.ex*.c*");
+
+        // THEN
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn GIVEN_inner_asterisk_WHEN_search_partial_labels_THEN_error() {
+        // GIVEN
+        let labels: SearchLabels<String> = SearchLabels::builder().build();
+
+        // WHEN
+        let actual = labels.search_partial_labels("foo.*ex*.com");
+
+        // THEN
+        assert!(actual.is_err());
+    }
 }
@@ -1,6 +1,11 @@
 #![allow(dead_code)] // TODO remove
 
 pub mod config;
-mod label_search;
+pub(crate) mod contact_index;
+pub(crate) mod label_search;
 pub mod ops;
+pub(crate) mod resource_index;
+pub(crate) mod snapshot;
+pub(crate) mod state;
+pub(crate) mod string_search;
 pub mod tx;
@@ -1,48 +1,41 @@
-use std::{collections::HashMap, net::IpAddr, str::FromStr, sync::Arc};
+use std::{collections::HashSet, net::IpAddr, str::FromStr, sync::Arc};
 
 use async_trait::async_trait;
-use btree_range_map::RangeMap;
 use icann_rdap_common::response::{
-    domain::Domain, search::DomainSearchResults, types::Common, RdapResponse,
+    domain::Domain,
+    entity::Entity,
+    nameserver::Nameserver,
+    search::{DomainSearchResults, EntitySearchResults, NameserverSearchResults},
+    types::{Common, ExtensionId},
+    RdapResponse,
 };
 use ipnet::{IpNet, Ipv4Net, Ipv6Net};
-use prefix_trie::PrefixMap;
 use tokio::sync::RwLock;
 
 use crate::{
     error::RdapServerError,
     rdap::response::{NOT_FOUND, NOT_IMPLEMENTED},
-    storage::{CommonConfig, StoreOps, TxHandle},
+    storage::{
+        search::labels::unicode_search, CommonConfig, ReverseSearchConditions, StoreOps, TxHandle,
+    },
 };
 
-use super::{config::MemConfig, label_search::SearchLabels, tx::MemTx};
+use super::{config::MemConfig, state::MemState, tx::MemTx};
 
+/// The in-memory store.
+///
+/// The objects and search indexes are held in one state, which transactions replace as a
+/// whole on commit. See [MemState] for how this avoids copying the store on each transaction.
 #[derive(Clone)]
 pub struct Mem {
-    pub(crate) autnums: Arc<RwLock<RangeMap<u32, Arc<RdapResponse>>>>,
-    pub(crate) ip4: Arc<RwLock<PrefixMap<Ipv4Net, Arc<RdapResponse>>>>,
-    pub(crate) ip6: Arc<RwLock<PrefixMap<Ipv6Net, Arc<RdapResponse>>>>,
-    pub(crate) domains: Arc<RwLock<HashMap<String, Arc<RdapResponse>>>>,
-    pub(crate) domains_by_name: Arc<RwLock<SearchLabels<Arc<RdapResponse>>>>,
-    pub(crate) idns: Arc<RwLock<HashMap<String, Arc<RdapResponse>>>>,
-    pub(crate) nameservers: Arc<RwLock<HashMap<String, Arc<RdapResponse>>>>,
-    pub(crate) entities: Arc<RwLock<HashMap<String, Arc<RdapResponse>>>>,
-    pub(crate) srvhelps: Arc<RwLock<HashMap<String, Arc<RdapResponse>>>>,
+    pub(crate) state: Arc<RwLock<MemState>>,
     pub(crate) config: MemConfig,
 }
 
 impl Mem {
     pub fn new(config: MemConfig) -> Self {
         Self {
-            autnums: <_>::default(),
-            ip4: <_>::default(),
-            ip6: <_>::default(),
-            domains: <_>::default(),
-            domains_by_name: Arc::new(RwLock::new(SearchLabels::builder().build())),
-            idns: <_>::default(),
-            nameservers: <_>::default(),
-            entities: <_>::default(),
-            srvhelps: <_>::default(),
+            state: <_>::default(),
             config,
         }
     }
@@ -73,7 +66,8 @@ impl StoreOps for Mem {
     }
 
     async fn get_domain_by_ldh(&self, ldh: &str) -> Result<RdapResponse, RdapServerError> {
-        let domains = self.domains.read().await;
+        let state = self.state.read().await;
+        let domains = &state.domains;
         let result = domains.get(ldh);
         match result {
             Some(domain) => Ok(RdapResponse::clone(domain)),
@@ -82,7 +76,8 @@ impl StoreOps for Mem {
     }
 
     async fn get_domain_by_unicode(&self, unicode: &str) -> Result<RdapResponse, RdapServerError> {
-        let idns = self.idns.read().await;
+        let state = self.state.read().await;
+        let idns = &state.idns;
         let result = idns.get(unicode);
         match result {
             Some(domain) => Ok(RdapResponse::clone(domain)),
@@ -91,7 +86,8 @@ impl StoreOps for Mem {
     }
 
     async fn get_entity_by_handle(&self, handle: &str) -> Result<RdapResponse, RdapServerError> {
-        let entities = self.entities.read().await;
+        let state = self.state.read().await;
+        let entities = &state.entities;
         let result = entities.get(handle);
         match result {
             Some(entity) => Ok(RdapResponse::clone(entity)),
@@ -100,7 +96,8 @@ impl StoreOps for Mem {
     }
 
     async fn get_nameserver_by_ldh(&self, ldh: &str) -> Result<RdapResponse, RdapServerError> {
-        let nameservers = self.nameservers.read().await;
+        let state = self.state.read().await;
+        let nameservers = &state.nameservers;
         let result = nameservers.get(ldh);
         match result {
             Some(nameserver) => Ok(RdapResponse::clone(nameserver)),
@@ -109,7 +106,8 @@ impl StoreOps for Mem {
     }
 
     async fn get_autnum_by_num(&self, num: u32) -> Result<RdapResponse, RdapServerError> {
-        let autnums = self.autnums.read().await;
+        let state = self.state.read().await;
+        let autnums = &state.autnums;
         let result = autnums.get(num);
         match result {
             Some(autnum) => Ok(RdapResponse::clone(autnum)),
@@ -122,7 +120,8 @@ impl StoreOps for Mem {
         match addr {
             IpAddr::V4(v4) => {
                 let slash32 = Ipv4Net::new(v4, 32)?;
-                let ip4s = self.ip4.read().await;
+                let state = self.state.read().await;
+                let ip4s = &state.ip4;
                 let result = ip4s.get_lpm(&slash32);
                 match result {
                     Some(network) => Ok(RdapResponse::clone(network.1)),
@@ -131,7 +130,8 @@ impl StoreOps for Mem {
             }
             IpAddr::V6(v6) => {
                 let slash128 = Ipv6Net::new(v6, 128)?;
-                let ip6s = self.ip6.read().await;
+                let state = self.state.read().await;
+                let ip6s = &state.ip6;
                 let result = ip6s.get_lpm(&slash128);
                 match result {
                     Some(network) => Ok(RdapResponse::clone(network.1)),
@@ -145,7 +145,8 @@ impl StoreOps for Mem {
         let net = IpNet::from_str(cidr)?;
         match net {
             IpNet::V4(ipv4net) => {
-                let ip4s = self.ip4.read().await;
+                let state = self.state.read().await;
+                let ip4s = &state.ip4;
                 let result = ip4s.get_lpm(&ipv4net);
                 match result {
                     Some(network) => Ok(RdapResponse::clone(network.1)),
@@ -153,7 +154,8 @@ impl StoreOps for Mem {
                 }
             }
             IpNet::V6(ipv6net) => {
-                let ip6s = self.ip6.read().await;
+                let state = self.state.read().await;
+                let ip6s = &state.ip6;
                 let result = ip6s.get_lpm(&ipv6net);
                 match result {
                     Some(network) => Ok(RdapResponse::clone(network.1)),
@@ -165,7 +167,8 @@ impl StoreOps for Mem {
 
     async fn get_srv_help(&self, host: Option<&str>) -> Result<RdapResponse, RdapServerError> {
         let host = host.unwrap_or("..default");
-        let srvhelps = self.srvhelps.read().await;
+        let state = self.state.read().await;
+        let srvhelps = &state.srvhelps;
         let result = srvhelps.get(host);
         match result {
             Some(srvhelp) => Ok(RdapResponse::clone(srvhelp)),
@@ -178,23 +181,234 @@ impl StoreOps for Mem {
             return Ok(NOT_IMPLEMENTED.clone());
         }
         //else
-        let domains_by_name = self.domains_by_name.read().await;
-        let results = domains_by_name
-            .search(name)
-            .unwrap_or_default()
-            .into_iter()
-            .map(Arc::<RdapResponse>::unwrap_or_clone)
-            .filter_map(|d| match d {
-                RdapResponse::Domain(d) => Some(d),
-                _ => None,
-            })
-            .collect::<Vec<Domain>>();
-        let response = RdapResponse::DomainSearchResults(
-            DomainSearchResults::builder()
-                .common(Common::new_level0(vec![], vec![]))
-                .results(results)
-                .build(),
-        );
-        Ok(response)
+        let name = unicode_search(name);
+        let state = self.state.read().await;
+        let domains_by_name = &state.domains_by_name;
+        let results = if self.config.common_config.partial_label_search_enable {
+            domains_by_name.search_partial_labels(&name)
+        } else {
+            domains_by_name.search(&name)
+        }
+        .unwrap_or_default();
+        Ok(domain_search_results(results))
+    }
+
+    async fn search_domains_by_ns_ldh_name(
+        &self,
+        ns_ldh_name: &str,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self
+            .config
+            .common_config
+            .domain_search_by_ns_ldh_name_enable
+        {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let ns_ldh_name = ns_ldh_name.to_lowercase();
+        let state = self.state.read().await;
+        let ns_ldh_names = if ns_ldh_name.contains('*') {
+            state
+                .domains_by_ns_ldh_label
+                .search(&ns_ldh_name)
+                .unwrap_or_default()
+        } else {
+            vec![ns_ldh_name]
+        };
+        let mut results = vec![];
+        for ns_ldh_name in ns_ldh_names {
+            if let Some(domains) = state.domains_by_ns_ldh_name.get(&ns_ldh_name) {
+                results.extend(domains.values().cloned());
+            }
+        }
+        Ok(domain_search_results(results))
+    }
+
+    async fn search_domains_by_ns_ip(&self, ns_ip: &str) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.domain_search_by_ns_ip_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let addr = ns_ip.parse::<IpAddr>()?;
+        let state = self.state.read().await;
+        let domains_by_ns_ip = &state.domains_by_ns_ip;
+        let results = domains_by_ns_ip
+            .get(&addr)
+            .map(|domains| domains.values().cloned().collect())
+            .unwrap_or_default();
+        Ok(domain_search_results(results))
+    }
+
+    async fn search_nameservers_by_name(
+        &self,
+        name: &str,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.nameserver_search_by_name_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let name = unicode_search(name);
+        let state = self.state.read().await;
+        let nameservers_by_name = &state.nameservers_by_name;
+        let results = if self.config.common_config.partial_label_search_enable {
+            nameservers_by_name.search_partial_labels(&name)
+        } else {
+            nameservers_by_name.search(&name)
+        }
+        .unwrap_or_default();
+        Ok(nameserver_search_results(results))
+    }
+
+    async fn search_nameservers_by_ip(&self, ip: &str) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.nameserver_search_by_ip_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let addr = ip.parse::<IpAddr>()?;
+        let state = self.state.read().await;
+        let nameservers_by_ip = &state.nameservers_by_ip;
+        let results = nameservers_by_ip
+            .get(&addr)
+            .map(|nameservers| nameservers.values().cloned().collect())
+            .unwrap_or_default();
+        Ok(nameserver_search_results(results))
+    }
+
+    async fn search_entities_by_fn(&self, fn_: &str) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.entity_search_by_fn_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let state = self.state.read().await;
+        let entities_by_fn = &state.entities_by_fn;
+        let results = entities_by_fn.search(fn_).unwrap_or_default();
+        Ok(entity_search_results(results))
+    }
+
+    async fn search_entities_by_handle(
+        &self,
+        handle: &str,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.entity_search_by_handle_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let state = self.state.read().await;
+        let entities_by_handle = &state.entities_by_handle;
+        let results = entities_by_handle.search(handle).unwrap_or_default();
+        Ok(entity_search_results(results))
     }
+
+    async fn reverse_search_domains(
+        &self,
+        conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.reverse_search_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let state = self.state.read().await;
+        let domains_by_contact = &state.domains_by_contact;
+        let results = domains_by_contact.search(conditions).unwrap_or_default();
+        Ok(reverse_search_results(domain_search_results(results)))
+    }
+
+    async fn reverse_search_nameservers(
+        &self,
+        conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.reverse_search_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let state = self.state.read().await;
+        let nameservers_by_contact = &state.nameservers_by_contact;
+        let results = nameservers_by_contact
+            .search(conditions)
+            .unwrap_or_default();
+        Ok(reverse_search_results(nameserver_search_results(results)))
+    }
+
+    async fn reverse_search_entities(
+        &self,
+        conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.reverse_search_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let state = self.state.read().await;
+        let entities_by_contact = &state.entities_by_contact;
+        let results = entities_by_contact.search(conditions).unwrap_or_default();
+        Ok(reverse_search_results(entity_search_results(results)))
+    }
+}
+
+/// Adds the reverse search extension to the conformance of search results.
+fn reverse_search_results(mut results: RdapResponse) -> RdapResponse {
+    let common = match &mut results {
+        RdapResponse::DomainSearchResults(r) => &mut r.common,
+        RdapResponse::NameserverSearchResults(r) => &mut r.common,
+        RdapResponse::EntitySearchResults(r) => &mut r.common,
+        _ => return results,
+    };
+    *common = Common::new_level0(vec![ExtensionId::ReverseSearch.to_extension()], vec![]);
+    results
+}
+
+/// Creates domain search results, removing the duplicates that occur when
+/// more than one nameserver of a domain matches the search.
+fn domain_search_results(results: Vec<Arc<RdapResponse>>) -> RdapResponse {
+    let mut seen = HashSet::new();
+    let results = results
+        .into_iter()
+        .filter(|d| seen.insert(Arc::as_ptr(d)))
+        .map(Arc::<RdapResponse>::unwrap_or_clone)
+        .filter_map(|d| match d {
+            RdapResponse::Domain(d) => Some(d),
+            _ => None,
+        })
+        .collect::<Vec<Domain>>();
+    RdapResponse::DomainSearchResults(
+        DomainSearchResults::builder()
+            .common(Common::new_level0(vec![], vec![]))
+            .results(results)
+            .build(),
+    )
+}
+
+fn nameserver_search_results(results: Vec<Arc<RdapResponse>>) -> RdapResponse {
+    let mut seen = HashSet::new();
+    let results = results
+        .into_iter()
+        .filter(|n| seen.insert(Arc::as_ptr(n)))
+        .map(Arc::<RdapResponse>::unwrap_or_clone)
+        .filter_map(|n| match n {
+            RdapResponse::Nameserver(n) => Some(n),
+            _ => None,
+        })
+        .collect::<Vec<Nameserver>>();
+    RdapResponse::NameserverSearchResults(
+        NameserverSearchResults::builder()
+            .common(Common::new_level0(vec![], vec![]))
+            .results(results)
+            .build(),
+    )
+}
+
+fn entity_search_results(results: Vec<Arc<RdapResponse>>) -> RdapResponse {
+    let results = results
+        .into_iter()
+        .map(Arc::<RdapResponse>::unwrap_or_clone)
+        .filter_map(|e| match e {
+            RdapResponse::Entity(e) => Some(e),
+            _ => None,
+        })
+        .collect::<Vec<Entity>>();
+    RdapResponse::EntitySearchResults(
+        EntitySearchResults::builder()
+            .common(Common::new_level0(vec![], vec![]))
+            .results(results)
+            .build(),
+    )
 }
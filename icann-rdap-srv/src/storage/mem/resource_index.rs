@@ -0,0 +1,388 @@
+use std::{hash::Hash, ops::RangeInclusive};
+
+use im::{HashMap, OrdMap};
+use ipnet::{Ipv4Net, Ipv6Net};
+
+/// A persistent map of ranges of numbers, such as autnums, to values.
+///
+/// Ranges are kept whole, so they may nest or overlap, and a number is looked up to the
+/// smallest range containing it. Removing a nested range makes the range containing it
+/// answer again, and removing the containing range leaves the nested ones in place.
+#[derive(Clone)]
+pub(crate) struct RangeIndex<V: Clone> {
+    /// The values, by the start and end of their ranges.
+    values: OrdMap<(u32, u32), V>,
+    /// The numbers covered by any range split into segments covered by the same ranges.
+    /// Each segment has its end and the ranges covering it, smallest first, by its start.
+    segments: OrdMap<u32, (u32, Vec<(u32, u32)>)>,
+}
+
+impl<V: Clone> Default for RangeIndex<V> {
+    fn default() -> Self {
+        Self {
+            values: OrdMap::new(),
+            segments: OrdMap::new(),
+        }
+    }
+}
+
+impl<V: Clone> RangeIndex<V> {
+    pub(crate) fn insert(&mut self, range: RangeInclusive<u32>, value: V) {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        if self.values.insert((start, end), value).is_some() {
+            return;
+        }
+        self.split_at(start);
+        if let Some(after) = end.checked_add(1) {
+            self.split_at(after);
+        }
+        let covered = self
+            .segments
+            .range(start..=end)
+            .map(|(segment_start, (segment_end, _))| (*segment_start, *segment_end))
+            .collect::<Vec<_>>();
+        let mut next = Some(start);
+        for (segment_start, segment_end) in covered {
+            if let Some(gap_start) = next.filter(|gap_start| *gap_start < segment_start) {
+                self.segments
+                    .insert(gap_start, (segment_start - 1, vec![(start, end)]));
+            }
+            if let Some((_, ranges)) = self.segments.get_mut(&segment_start) {
+                let at = ranges
+                    .binary_search_by_key(&size_order(&(start, end)), size_order)
+                    .unwrap_or_else(|at| at);
+                ranges.insert(at, (start, end));
+            }
+            next = segment_end.checked_add(1);
+        }
+        if let Some(gap_start) = next.filter(|gap_start| *gap_start <= end) {
+            self.segments.insert(gap_start, (end, vec![(start, end)]));
+        }
+    }
+
+    pub(crate) fn remove(&mut self, range: RangeInclusive<u32>) {
+        let (start, end) = range.into_inner();
+        if self.values.remove(&(start, end)).is_none() {
+            return;
+        }
+        let covered = self
+            .segments
+            .range(start..=end)
+            .map(|(segment_start, _)| *segment_start)
+            .collect::<Vec<_>>();
+        for segment_start in covered {
+            let Some((_, ranges)) = self.segments.get_mut(&segment_start) else {
+                continue;
+            };
+            ranges.retain(|covering| *covering != (start, end));
+            if ranges.is_empty() {
+                self.segments.remove(&segment_start);
+            }
+        }
+        self.join_at(start);
+        if let Some(after) = end.checked_add(1) {
+            self.join_at(after);
+        }
+    }
+
+    /// Gets the value of the smallest range containing the number.
+    pub(crate) fn get(&self, num: u32) -> Option<&V> {
+        self.segments
+            .get_prev(&num)
+            .filter(|(_, (end, _))| *end >= num)
+            .and_then(|(_, (_, ranges))| ranges.first())
+            .and_then(|range| self.values.get(range))
+    }
+
+    /// Iterates over the ranges in order, as (start, end, value).
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (u32, u32, &V)> {
+        self.values
+            .iter()
+            .map(|((start, end), value)| (*start, *end, value))
+    }
+
+    /// Splits the segment containing the number so that a segment starts at it.
+    fn split_at(&mut self, num: u32) {
+        let Some((segment_start, (segment_end, ranges))) = self
+            .segments
+            .get_prev(&num)
+            .filter(|(segment_start, (segment_end, _))| {
+                **segment_start < num && *segment_end >= num
+            })
+            .map(|(segment_start, segment)| (*segment_start, segment.clone()))
+        else {
+            return;
+        };
+        self.segments
+            .insert(segment_start, (num - 1, ranges.clone()));
+        self.segments.insert(num, (segment_end, ranges));
+    }
+
+    /// Joins the segment starting at the number to the one before it if they are covered
+    /// by the same ranges.
+    fn join_at(&mut self, num: u32) {
+        let Some(before) = num.checked_sub(1) else {
+            return;
+        };
+        let Some((segment_end, ranges)) = self.segments.get(&num).cloned() else {
+            return;
+        };
+        let Some(previous_start) = self
+            .segments
+            .get_prev(&before)
+            .filter(|(_, (previous_end, previous_ranges))| {
+                *previous_end == before && *previous_ranges == ranges
+            })
+            .map(|(previous_start, _)| *previous_start)
+        else {
+            return;
+        };
+        self.segments.remove(&num);
+        self.segments.insert(previous_start, (segment_end, ranges));
+    }
+}
+
+/// Orders ranges smallest first, then by their start.
+fn size_order(range: &(u32, u32)) -> (u32, u32) {
+    (range.1 - range.0, range.0)
+}
+
+/// An IP network which can be shortened to the networks containing it.
+pub(crate) trait Prefix: Copy + Eq + Hash {
+    fn prefix_len(&self) -> u8;
+
+    /// Gets the network of the prefix length containing this one.
+    fn supernet_of_len(&self, prefix_len: u8) -> Self;
+}
+
+impl Prefix for Ipv4Net {
+    fn prefix_len(&self) -> u8 {
+        Ipv4Net::prefix_len(self)
+    }
+
+    fn supernet_of_len(&self, prefix_len: u8) -> Self {
+        Ipv4Net::new(self.addr(), prefix_len)
+            .map(|net| net.trunc())
+            .unwrap_or(*self)
+    }
+}
+
+impl Prefix for Ipv6Net {
+    fn prefix_len(&self) -> u8 {
+        Ipv6Net::prefix_len(self)
+    }
+
+    fn supernet_of_len(&self, prefix_len: u8) -> Self {
+        Ipv6Net::new(self.addr(), prefix_len)
+            .map(|net| net.trunc())
+            .unwrap_or(*self)
+    }
+}
+
+/// A persistent map of IP networks to values, with longest prefix match lookups.
+///
+/// Only the prefix lengths in use are tried when looking up a network, which are few in
+/// practice.
+#[derive(Clone)]
+pub(crate) struct PrefixIndex<N: Prefix, V: Clone> {
+    nets: HashMap<N, V>,
+    /// The number of networks of each prefix length.
+    prefix_lens: OrdMap<u8, usize>,
+}
+
+impl<N: Prefix, V: Clone> Default for PrefixIndex<N, V> {
+    fn default() -> Self {
+        Self {
+            nets: HashMap::new(),
+            prefix_lens: OrdMap::new(),
+        }
+    }
+}
+
+impl<N: Prefix, V: Clone> PrefixIndex<N, V> {
+    pub(crate) fn insert(&mut self, net: N, value: V) {
+        let net = net.supernet_of_len(net.prefix_len());
+        if self.nets.insert(net, value).is_none() {
+            *self.prefix_lens.entry(net.prefix_len()).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, net: &N) {
+        let net = net.supernet_of_len(net.prefix_len());
+        if self.nets.remove(&net).is_some() {
+            let prefix_len = net.prefix_len();
+            match self.prefix_lens.get(&prefix_len) {
+                Some(1) | None => {
+                    self.prefix_lens.remove(&prefix_len);
+                }
+                Some(count) => {
+                    self.prefix_lens.insert(prefix_len, count - 1);
+                }
+            }
+        }
+    }
+
+    /// Gets the most specific network containing the network, and its value.
+    pub(crate) fn get_lpm(&self, net: &N) -> Option<(N, &V)> {
+        self.prefix_lens
+            .range(..=net.prefix_len())
+            .rev()
+            .find_map(|(prefix_len, _)| {
+                let supernet = net.supernet_of_len(*prefix_len);
+                self.nets.get(&supernet).map(|value| (supernet, value))
+            })
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&N, &V)> {
+        self.nets.iter()
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use ipnet::Ipv4Net;
+
+    use super::{PrefixIndex, RangeIndex};
+
+    #[test]
+    fn GIVEN_nested_range_WHEN_get_THEN_smallest_containing_range() {
+        // GIVEN
+        let mut index = RangeIndex::default();
+        index.insert(100..=199, "block");
+
+        // WHEN
+        index.insert(120..=129, "assignment");
+
+        // THEN
+        assert_eq!(index.get(99), None);
+        assert_eq!(index.get(100), Some(&"block"));
+        assert_eq!(index.get(119), Some(&"block"));
+        assert_eq!(index.get(120), Some(&"assignment"));
+        assert_eq!(index.get(129), Some(&"assignment"));
+        assert_eq!(index.get(130), Some(&"block"));
+        assert_eq!(index.get(199), Some(&"block"));
+        assert_eq!(index.get(200), None);
+        assert_eq!(index.iter().count(), 2);
+    }
+
+    #[test]
+    fn GIVEN_nested_range_WHEN_remove_inner_THEN_outer_range_found() {
+        // GIVEN
+        let mut index = RangeIndex::default();
+        index.insert(100..=199, "block");
+        index.insert(120..=129, "assignment");
+
+        // WHEN
+        index.remove(120..=129);
+
+        // THEN
+        assert_eq!(index.get(120), Some(&"block"));
+        assert_eq!(index.get(129), Some(&"block"));
+        assert_eq!(index.iter().count(), 1);
+        assert_eq!(index.segments.len(), 1);
+    }
+
+    #[test]
+    fn GIVEN_nested_range_WHEN_remove_outer_THEN_inner_range_kept() {
+        // GIVEN
+        let mut index = RangeIndex::default();
+        index.insert(100..=199, "block");
+        index.insert(120..=129, "assignment");
+
+        // WHEN
+        index.remove(100..=199);
+
+        // THEN
+        assert_eq!(index.get(100), None);
+        assert_eq!(index.get(120), Some(&"assignment"));
+        assert_eq!(index.get(129), Some(&"assignment"));
+        assert_eq!(index.get(130), None);
+        assert_eq!(index.iter().count(), 1);
+    }
+
+    #[test]
+    fn GIVEN_ranges_WHEN_remove_other_range_THEN_nothing_removed() {
+        // GIVEN
+        let mut index = RangeIndex::default();
+        index.insert(100..=199, "block");
+        index.insert(200..=299, "next");
+
+        // WHEN
+        index.remove(150..=249);
+
+        // THEN
+        assert_eq!(index.get(150), Some(&"block"));
+        assert_eq!(index.get(249), Some(&"next"));
+        assert_eq!(index.iter().count(), 2);
+    }
+
+    #[test]
+    fn GIVEN_overlapping_ranges_WHEN_get_THEN_smaller_range_in_overlap() {
+        // GIVEN
+        let mut index = RangeIndex::default();
+        index.insert(100..=199, "wide");
+        index.insert(150..=209, "narrow");
+
+        // WHEN
+        let before = index.get(149);
+        let overlap = index.get(150);
+        let after = index.get(205);
+
+        // THEN
+        assert_eq!(before, Some(&"wide"));
+        assert_eq!(overlap, Some(&"narrow"));
+        assert_eq!(after, Some(&"narrow"));
+    }
+
+    #[test]
+    fn GIVEN_range_WHEN_insert_same_range_THEN_value_replaced() {
+        // GIVEN
+        let mut index = RangeIndex::default();
+        index.insert(0..=u32::MAX, "old");
+
+        // WHEN
+        index.insert(0..=u32::MAX, "new");
+
+        // THEN
+        assert_eq!(index.get(u32::MAX), Some(&"new"));
+        assert_eq!(index.iter().count(), 1);
+    }
+
+    #[test]
+    fn GIVEN_nested_networks_WHEN_get_lpm_THEN_most_specific() {
+        // GIVEN
+        let mut index = PrefixIndex::default();
+        index.insert("10.0.0.0/8".parse::<Ipv4Net>().expect("net"), "eight");
+        index.insert("10.1.0.0/16".parse::<Ipv4Net>().expect("net"), "sixteen");
+
+        // WHEN
+        let in_sixteen = index.get_lpm(&"10.1.2.3/32".parse().expect("net"));
+        let in_eight = index.get_lpm(&"10.2.2.3/32".parse().expect("net"));
+        let wider = index.get_lpm(&"10.0.0.0/7".parse().expect("net"));
+
+        // THEN
+        assert_eq!(in_sixteen.map(|(_, value)| *value), Some("sixteen"));
+        assert_eq!(in_eight.map(|(_, value)| *value), Some("eight"));
+        assert!(wider.is_none());
+    }
+
+    #[test]
+    fn GIVEN_removed_network_WHEN_get_lpm_THEN_containing_network() {
+        // GIVEN
+        let mut index = PrefixIndex::default();
+        index.insert("10.0.0.0/8".parse::<Ipv4Net>().expect("net"), "eight");
+        index.insert("10.1.0.0/16".parse::<Ipv4Net>().expect("net"), "sixteen");
+
+        // WHEN
+        index.remove(&"10.1.0.0/16".parse().expect("net"));
+
+        // THEN
+        let actual = index.get_lpm(&"10.1.2.3/32".parse().expect("net"));
+        assert_eq!(actual.map(|(_, value)| *value), Some("eight"));
+        assert_eq!(index.iter().count(), 1);
+    }
+}
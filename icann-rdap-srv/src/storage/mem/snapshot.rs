@@ -0,0 +1,684 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use icann_rdap_common::response::{
+    autnum::Autnum, domain::Domain, entity::Entity, error::Error, help::Help,
+    nameserver::Nameserver, network::Network, RdapResponse,
+};
+use ipnet::{Ipv4Net, Ipv6Net};
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::ServiceConfig,
+    error::RdapServerError,
+    storage::{
+        data::{load_data_files, DataFileKind, DataFiles, FileStamp, ObjectId},
+        watch::load_changes,
+        CommonConfig,
+    },
+};
+
+use super::{ops::Mem, state::MemState};
+
+/// The version of the snapshot format. Snapshots of other versions are not restored.
+const SNAPSHOT_VERSION: u32 = 3;
+
+/// A snapshot file of the in-memory store, which saves parsing the data directory at
+/// startup.
+///
+/// The snapshot is a zstd compressed MessagePack file holding every object of the store
+/// once, the maps of names, autnum ranges, and networks to those objects, and the
+/// objects loaded from each data file. The search indexes are rebuilt from the objects
+/// when the snapshot is restored, so that they follow the search configuration of the
+/// server rather than that of the server writing the snapshot.
+#[derive(Clone)]
+pub(crate) struct MemSnapshot {
+    path: PathBuf,
+    mem: Mem,
+    writes: Arc<Mutex<Writes>>,
+}
+
+/// The background writes of a snapshot, shared by its clones. A write asked for while
+/// another is in progress waits for it, and replaces any write already waiting, so that
+/// back-to-back loads write the snapshot once.
+#[derive(Default)]
+struct Writes {
+    waiting: Option<(SnapshotHeader, MemState)>,
+    writing: bool,
+}
+
+impl MemSnapshot {
+    pub(crate) fn new(path: impl Into<PathBuf>, mem: Mem) -> Self {
+        Self {
+            path: path.into(),
+            mem,
+            writes: Default::default(),
+        }
+    }
+
+    /// Restores the store from the snapshot and loads the data files changed or removed
+    /// since the snapshot was written, or loads the whole data directory if there is no
+    /// usable snapshot. A new snapshot is written if anything was loaded.
+    pub(crate) async fn load(&self, config: &ServiceConfig) -> Result<DataFiles, RdapServerError> {
+        let restored = match self.restore(&config.data_dir).await {
+            Ok(restored) => restored,
+            Err(e) => {
+                warn!("Loading the data directory, as the snapshot cannot be restored: {e}");
+                None
+            }
+        };
+        let data_files = match restored {
+            Some(data_files) => {
+                let paths = changed_paths(&config.data_dir, &data_files).await?;
+                if paths.is_empty() {
+                    return Ok(data_files);
+                }
+                info!("{} data files changed since snapshot.", paths.len());
+                load_changes(&self.mem, &config.data_dir, &data_files, &paths).await?
+            }
+            None => load_data_files(config, &self.mem, false).await?,
+        };
+        self.write_later(&config.data_dir, &data_files).await;
+        Ok(data_files)
+    }
+
+    /// Writes the state of the store, which is that after loading the data files, in the
+    /// background. Failures are logged, as the store is usable without a snapshot.
+    pub(crate) async fn write_later(&self, data_dir: &str, data_files: &DataFiles) {
+        let state = self.mem.state.read().await.clone();
+        let header = SnapshotHeader {
+            version: SNAPSHOT_VERSION,
+            data_dir: data_dir.to_owned(),
+            files: data_files
+                .files()
+                .map(|(file_name, stamp, ids)| (file_name.clone(), *stamp, ids.clone()))
+                .collect(),
+        };
+        {
+            let mut writes = self.writes.lock().expect("snapshot writes poisoned");
+            if writes.waiting.replace((header, state)).is_some() {
+                debug!("snapshot write replaced by a later one");
+            }
+            if writes.writing {
+                return;
+            }
+            writes.writing = true;
+        }
+        let snapshot = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let waiting = {
+                    let mut writes = snapshot.writes.lock().expect("snapshot writes poisoned");
+                    let waiting = writes.waiting.take();
+                    writes.writing = waiting.is_some();
+                    waiting
+                };
+                let Some((header, state)) = waiting else {
+                    break;
+                };
+                snapshot.write(header, state).await;
+            }
+        });
+    }
+
+    async fn write(&self, header: SnapshotHeader, state: MemState) {
+        let path = self.path.clone();
+        let written = match spawn_blocking(move || write_file(&path, &header, &state)).await {
+            Ok(written) => written,
+            Err(e) => Err(std::io::Error::from(e).into()),
+        };
+        match written {
+            Ok(()) => info!("Snapshot {} written.", self.path.to_string_lossy()),
+            Err(e) => warn!("Snapshot {} not written: {e}", self.path.to_string_lossy()),
+        }
+    }
+
+    /// Replaces the state of the store with the snapshot, and gets the objects loaded from
+    /// each data file. Gets `None` if there is no snapshot.
+    pub(crate) async fn restore(
+        &self,
+        data_dir: &str,
+    ) -> Result<Option<DataFiles>, RdapServerError> {
+        let path = self.path.clone();
+        let data_dir = data_dir.to_owned();
+        let config = self.mem.config.common_config;
+        let restored = spawn_blocking(move || read_file(&path, &data_dir, &config))
+            .await
+            .map_err(std::io::Error::from)??;
+        let Some((header, state)) = restored else {
+            debug!("no snapshot at {}", self.path.to_string_lossy());
+            return Ok(None);
+        };
+        let old = std::mem::replace(&mut *self.mem.state.write().await, state);
+        drop(old);
+
+        let mut data_files = DataFiles::default();
+        for (file_name, stamp, ids) in header.files {
+            data_files.insert(file_name, stamp, ids);
+        }
+        info!("Snapshot {} restored.", self.path.to_string_lossy());
+        Ok(Some(data_files))
+    }
+}
+
+/// Gets the data files whose size or modification time differ from when they were loaded
+/// or which are not in `data_files`, and the files in `data_files` which no longer exist.
+async fn changed_paths(
+    data_dir: &str,
+    data_files: &DataFiles,
+) -> Result<HashSet<PathBuf>, RdapServerError> {
+    let mut paths = HashSet::new();
+    let data_dir = Path::new(data_dir);
+    if data_dir.is_dir() {
+        let mut entries = tokio::fs::read_dir(data_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if DataFileKind::of(&path).is_none() {
+                continue;
+            }
+            let stamp = FileStamp::of(&path).await?;
+            if data_files.stamp(Path::new(&entry.file_name())) != Some(stamp) {
+                paths.insert(path);
+            }
+        }
+    }
+    for (file_name, _, _) in data_files.files() {
+        let path = data_dir.join(file_name);
+        if !path.exists() {
+            paths.insert(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// What a snapshot is of, which is read before the rest of the snapshot.
+#[derive(Serialize, Deserialize)]
+struct SnapshotHeader {
+    version: u32,
+    data_dir: String,
+    /// The data files loaded, with their stamps when loaded. Files whose stamps have
+    /// changed are loaded again.
+    files: Vec<(PathBuf, FileStamp, Vec<ObjectId>)>,
+}
+
+/// The objects of the store, each once, and the maps to them by their index in `objects`.
+#[derive(Serialize, Deserialize)]
+struct SnapshotBody<'a> {
+    objects: Vec<SnapshotObject<'a>>,
+    domains: Vec<(Cow<'a, str>, usize)>,
+    idns: Vec<(Cow<'a, str>, usize)>,
+    nameservers: Vec<(Cow<'a, str>, usize)>,
+    entities: Vec<(Cow<'a, str>, usize)>,
+    autnums: Vec<(u32, u32, usize)>,
+    ip4: Vec<(Ipv4Net, usize)>,
+    ip6: Vec<(Ipv6Net, usize)>,
+    srvhelps: Vec<(Cow<'a, str>, usize)>,
+}
+
+/// An object of the store. Unlike [RdapResponse], this is deserialized without first
+/// being read as a JSON value.
+#[derive(Serialize, Deserialize)]
+enum SnapshotObject<'a> {
+    Domain(Box<Cow<'a, Domain>>),
+    Entity(Box<Cow<'a, Entity>>),
+    Nameserver(Box<Cow<'a, Nameserver>>),
+    Autnum(Box<Cow<'a, Autnum>>),
+    Network(Box<Cow<'a, Network>>),
+    Help(Box<Cow<'a, Help>>),
+    Error(Box<Cow<'a, Error>>),
+}
+
+impl<'a> SnapshotObject<'a> {
+    fn of(rdap: &'a RdapResponse) -> Result<Self, RdapServerError> {
+        Ok(match rdap {
+            RdapResponse::Domain(domain) => Self::Domain(Box::new(Cow::Borrowed(domain))),
+            RdapResponse::Entity(entity) => Self::Entity(Box::new(Cow::Borrowed(entity))),
+            RdapResponse::Nameserver(nameserver) => {
+                Self::Nameserver(Box::new(Cow::Borrowed(nameserver)))
+            }
+            RdapResponse::Autnum(autnum) => Self::Autnum(Box::new(Cow::Borrowed(autnum))),
+            RdapResponse::Network(network) => Self::Network(Box::new(Cow::Borrowed(network))),
+            RdapResponse::Help(help) => Self::Help(Box::new(Cow::Borrowed(help))),
+            RdapResponse::ErrorResponse(error) => Self::Error(Box::new(Cow::Borrowed(error))),
+            _ => return Err(RdapServerError::NonObjectDocument),
+        })
+    }
+
+    fn into_response(self) -> RdapResponse {
+        match self {
+            Self::Domain(domain) => RdapResponse::Domain(domain.into_owned()),
+            Self::Entity(entity) => RdapResponse::Entity(entity.into_owned()),
+            Self::Nameserver(nameserver) => RdapResponse::Nameserver(nameserver.into_owned()),
+            Self::Autnum(autnum) => RdapResponse::Autnum(autnum.into_owned()),
+            Self::Network(network) => RdapResponse::Network(network.into_owned()),
+            Self::Help(help) => RdapResponse::Help(help.into_owned()),
+            Self::Error(error) => RdapResponse::ErrorResponse(error.into_owned()),
+        }
+    }
+}
+
+/// Numbers the objects of the store, so that objects shared by several maps are
+/// written once.
+#[derive(Default)]
+struct ObjectTable<'a> {
+    objects: Vec<SnapshotObject<'a>>,
+    indexes: HashMap<*const RdapResponse, usize>,
+}
+
+impl<'a> ObjectTable<'a> {
+    fn index(&mut self, rdap: &'a Arc<RdapResponse>) -> Result<usize, RdapServerError> {
+        if let Some(index) = self.indexes.get(&Arc::as_ptr(rdap)) {
+            return Ok(*index);
+        }
+        self.objects.push(SnapshotObject::of(rdap)?);
+        let index = self.objects.len() - 1;
+        self.indexes.insert(Arc::as_ptr(rdap), index);
+        Ok(index)
+    }
+
+    fn names(
+        &mut self,
+        map: impl IntoIterator<Item = (&'a String, &'a Arc<RdapResponse>)>,
+    ) -> Result<Vec<(Cow<'a, str>, usize)>, RdapServerError> {
+        map.into_iter()
+            .map(|(name, rdap)| Ok((Cow::Borrowed(name.as_str()), self.index(rdap)?)))
+            .collect()
+    }
+}
+
+impl<'a> SnapshotBody<'a> {
+    fn of(state: &'a MemState) -> Result<Self, RdapServerError> {
+        let mut table = ObjectTable::default();
+        let domains = table.names(&state.domains)?;
+        let idns = table.names(&state.idns)?;
+        let nameservers = table.names(&state.nameservers)?;
+        let entities = table.names(&state.entities)?;
+        let srvhelps = table.names(&state.srvhelps)?;
+        let mut autnums = vec![];
+        for (start, end, rdap) in state.autnums.iter() {
+            autnums.push((start, end, table.index(rdap)?));
+        }
+        let ip4 = state
+            .ip4
+            .iter()
+            .map(|(net, rdap)| Ok((*net, table.index(rdap)?)))
+            .collect::<Result<_, RdapServerError>>()?;
+        let ip6 = state
+            .ip6
+            .iter()
+            .map(|(net, rdap)| Ok((*net, table.index(rdap)?)))
+            .collect::<Result<_, RdapServerError>>()?;
+        Ok(Self {
+            objects: table.objects,
+            domains,
+            idns,
+            nameservers,
+            entities,
+            autnums,
+            ip4,
+            ip6,
+            srvhelps,
+        })
+    }
+
+    /// Makes the state of the store, indexing the objects as configured.
+    fn into_state(self, config: &CommonConfig) -> Result<MemState, RdapServerError> {
+        let objects = self
+            .objects
+            .into_iter()
+            .map(|object| Arc::new(object.into_response()))
+            .collect::<Vec<_>>();
+        let object = |index: usize| {
+            objects
+                .get(index)
+                .cloned()
+                .ok_or_else(|| RdapServerError::EmptyIndexData(format!("snapshot object {index}")))
+        };
+
+        let mut state = MemState::default();
+        for (ldh_name, index) in self.domains {
+            state.put_domain(&ldh_name, object(index)?, config);
+        }
+        // which domain has a unicode name is kept as it was rather than as indexed
+        state.idns = self
+            .idns
+            .into_iter()
+            .map(|(unicode_name, index)| Ok((unicode_name.into_owned(), object(index)?)))
+            .collect::<Result<_, RdapServerError>>()?;
+        for (ldh_name, index) in self.nameservers {
+            state.put_nameserver(&ldh_name, object(index)?, config);
+        }
+        for (handle, index) in self.entities {
+            state.put_entity(&handle, object(index)?, config);
+        }
+        let autnums = &mut state.autnums;
+        for (start, end, index) in self.autnums {
+            autnums.insert(start..=end, object(index)?);
+        }
+        let ip4 = &mut state.ip4;
+        for (net, index) in self.ip4 {
+            ip4.insert(net, object(index)?);
+        }
+        let ip6 = &mut state.ip6;
+        for (net, index) in self.ip6 {
+            ip6.insert(net, object(index)?);
+        }
+        state.srvhelps = self
+            .srvhelps
+            .into_iter()
+            .map(|(host, index)| Ok((host.into_owned(), object(index)?)))
+            .collect::<Result<_, RdapServerError>>()?;
+        Ok(state)
+    }
+}
+
+/// Writes the snapshot to a temporary file which then replaces the snapshot, so that a
+/// snapshot is never partly written.
+fn write_file(
+    path: &Path,
+    header: &SnapshotHeader,
+    state: &MemState,
+) -> Result<(), RdapServerError> {
+    let body = SnapshotBody::of(state)?;
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    let mut encoder = zstd::Encoder::new(BufWriter::new(File::create(&temp_path)?), 0)?;
+    rmp_serde::encode::write_named(&mut encoder, header)?;
+    rmp_serde::encode::write_named(&mut encoder, &body)?;
+    encoder.finish()?.flush()?;
+    std::fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+fn read_file(
+    path: &Path,
+    data_dir: &str,
+    config: &CommonConfig,
+) -> Result<Option<(SnapshotHeader, MemState)>, RdapServerError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let path_name = path.to_string_lossy().to_string();
+    let mut decoder = zstd::Decoder::new(file)?;
+    let header: SnapshotHeader = rmp_serde::decode::from_read(&mut decoder)?;
+    if header.version != SNAPSHOT_VERSION {
+        return Err(RdapServerError::Snapshot(
+            path_name,
+            format!("version {} is not {SNAPSHOT_VERSION}", header.version),
+        ));
+    }
+    if header.data_dir != data_dir {
+        return Err(RdapServerError::Snapshot(
+            path_name,
+            format!("it is of data directory {}", header.data_dir),
+        ));
+    }
+    let body: SnapshotBody = rmp_serde::decode::from_read(&mut decoder)?;
+    Ok(Some((header, body.into_state(config)?)))
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use std::fs::File;
+
+    use icann_rdap_common::{
+        contact::Contact,
+        response::{
+            autnum::Autnum, domain::Domain, entity::Entity, nameserver::Nameserver,
+            network::Network, RdapResponse,
+        },
+    };
+    use serde::Serialize;
+    use test_dir::{DirBuilder, TestDir};
+
+    use crate::{
+        config::{ServiceConfig, StorageType},
+        storage::{
+            data::DataFiles,
+            mem::{config::MemConfig, ops::Mem},
+            CommonConfig, StoreOps,
+        },
+    };
+
+    use super::MemSnapshot;
+
+    fn write_json(dir: &TestDir, file_name: &str, value: &impl Serialize) {
+        std::fs::write(
+            dir.path(file_name),
+            serde_json::to_string(value).expect("serializing json"),
+        )
+        .expect("writing file");
+    }
+
+    fn domain(ldh_name: &str) -> Domain {
+        Domain::basic()
+            .ldh_name(ldh_name)
+            .unicode_name(format!("{ldh_name}-unicode"))
+            .nameservers(vec![Nameserver::basic()
+                .ldh_name("ns1.example.net")
+                .build()
+                .expect("building nameserver")])
+            .entity(
+                Entity::basic()
+                    .handle("registrant")
+                    .role("registrant")
+                    .contact(Contact::builder().full_name("Bob Smurd").build())
+                    .build(),
+            )
+            .build()
+    }
+
+    fn new_mem(snapshot_file: &str) -> Mem {
+        Mem::new(
+            MemConfig::builder()
+                .common_config(CommonConfig::default())
+                .snapshot_file(snapshot_file)
+                .build(),
+        )
+    }
+
+    /// Loads the store with its snapshot as done at startup.
+    async fn load(data_dir: &TestDir, snapshot_dir: &TestDir) -> Mem {
+        let snapshot_file = snapshot_dir.path("rdap.snapshot");
+        let mem = new_mem(&snapshot_file.to_string_lossy());
+        let config = ServiceConfig::non_server()
+            .data_dir(data_dir.root().to_string_lossy())
+            .storage_type(StorageType::Memory(mem.config.clone()))
+            .build()
+            .expect("building service config");
+        let snapshot = MemSnapshot::new(snapshot_file, mem.clone());
+        snapshot.load(&config).await.expect("loading");
+        wait_written(&snapshot).await;
+        mem
+    }
+
+    async fn wait_written(snapshot: &MemSnapshot) {
+        while snapshot
+            .writes
+            .lock()
+            .expect("snapshot writes poisoned")
+            .writing
+        {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Overwrites a file with whitespace, which is not JSON, keeping its size and
+    /// modification time.
+    fn overwrite_keeping_stamp(dir: &TestDir, file_name: &str) {
+        let metadata = std::fs::metadata(dir.path(file_name)).expect("getting metadata");
+        let modified = metadata.modified().expect("getting modification time");
+        std::fs::write(dir.path(file_name), " ".repeat(metadata.len() as usize))
+            .expect("writing file");
+        File::options()
+            .write(true)
+            .open(dir.path(file_name))
+            .and_then(|file| file.set_modified(modified))
+            .expect("setting modification time");
+    }
+
+    fn is_found(response: Result<RdapResponse, crate::error::RdapServerError>) -> bool {
+        !matches!(
+            response.expect("getting response"),
+            RdapResponse::ErrorResponse(_)
+        )
+    }
+
+    #[tokio::test]
+    async fn GIVEN_snapshot_WHEN_loaded_THEN_objects_and_indexes_restored() {
+        // GIVEN
+        let data_dir = TestDir::temp();
+        let snapshot_dir = TestDir::temp();
+        write_json(&data_dir, "foo.json", &domain("foo.example"));
+        write_json(
+            &data_dir,
+            "autnum.json",
+            &Autnum::basic().autnum_range(700..710).build(),
+        );
+        write_json(
+            &data_dir,
+            "net.json",
+            &Network::basic()
+                .cidr("10.0.0.0/24")
+                .build()
+                .expect("building network"),
+        );
+        load(&data_dir, &snapshot_dir).await;
+        // changing the files without changing their size or modification time shows they
+        // are not read
+        for file_name in ["foo.json", "autnum.json", "net.json"] {
+            overwrite_keeping_stamp(&data_dir, file_name);
+        }
+
+        // WHEN
+        let mem = load(&data_dir, &snapshot_dir).await;
+
+        // THEN
+        assert_eq!(
+            mem.get_domain_by_ldh("foo.example")
+                .await
+                .expect("getting domain"),
+            RdapResponse::Domain(domain("foo.example"))
+        );
+        assert!(is_found(
+            mem.get_domain_by_unicode("foo.example-unicode").await
+        ));
+        let RdapResponse::DomainSearchResults(results) = mem
+            .search_domains_by_ns_ldh_name("ns1.example.net")
+            .await
+            .expect("searching domains")
+        else {
+            panic!("not domain search results")
+        };
+        assert_eq!(results.results.len(), 1);
+        assert!(is_found(mem.get_autnum_by_num(705).await));
+        assert!(is_found(mem.get_network_by_ipaddr("10.0.0.1").await));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_snapshot_WHEN_files_added_and_removed_THEN_changes_loaded() {
+        // GIVEN
+        let data_dir = TestDir::temp();
+        let snapshot_dir = TestDir::temp();
+        write_json(&data_dir, "foo.json", &domain("foo.example"));
+        write_json(&data_dir, "bar.json", &domain("bar.example"));
+        load(&data_dir, &snapshot_dir).await;
+        std::fs::remove_file(data_dir.path("foo.json")).expect("removing file");
+        write_json(&data_dir, "baz.json", &domain("baz.example"));
+
+        // WHEN
+        let mem = load(&data_dir, &snapshot_dir).await;
+
+        // THEN
+        assert!(!is_found(mem.get_domain_by_ldh("foo.example").await));
+        assert!(is_found(mem.get_domain_by_ldh("bar.example").await));
+        assert!(is_found(mem.get_domain_by_ldh("baz.example").await));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_snapshot_WHEN_file_changed_with_same_modification_time_THEN_file_loaded() {
+        // GIVEN
+        let data_dir = TestDir::temp();
+        let snapshot_dir = TestDir::temp();
+        write_json(&data_dir, "foo.json", &domain("foo.example"));
+        load(&data_dir, &snapshot_dir).await;
+        let modified = std::fs::metadata(data_dir.path("foo.json"))
+            .and_then(|meta| meta.modified())
+            .expect("getting modification time");
+        write_json(&data_dir, "foo.json", &domain("foo-longer-name.example"));
+        File::options()
+            .write(true)
+            .open(data_dir.path("foo.json"))
+            .and_then(|file| file.set_modified(modified))
+            .expect("setting modification time");
+
+        // WHEN
+        let mem = load(&data_dir, &snapshot_dir).await;
+
+        // THEN
+        assert!(!is_found(mem.get_domain_by_ldh("foo.example").await));
+        assert!(is_found(
+            mem.get_domain_by_ldh("foo-longer-name.example").await
+        ));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_snapshot_of_other_data_dir_WHEN_loaded_THEN_data_dir_loaded() {
+        // GIVEN
+        let other_dir = TestDir::temp();
+        let data_dir = TestDir::temp();
+        let snapshot_dir = TestDir::temp();
+        write_json(&other_dir, "foo.json", &domain("foo.example"));
+        write_json(&data_dir, "bar.json", &domain("bar.example"));
+        load(&other_dir, &snapshot_dir).await;
+
+        // WHEN
+        let mem = load(&data_dir, &snapshot_dir).await;
+
+        // THEN
+        assert!(!is_found(mem.get_domain_by_ldh("foo.example").await));
+        assert!(is_found(mem.get_domain_by_ldh("bar.example").await));
+    }
+
+    #[tokio::test]
+    async fn GIVEN_writes_back_to_back_WHEN_written_THEN_last_state_in_snapshot() {
+        // GIVEN
+        let snapshot_dir = TestDir::temp();
+        let snapshot_file = snapshot_dir.path("rdap.snapshot");
+        let mem = new_mem(&snapshot_file.to_string_lossy());
+        let snapshot = MemSnapshot::new(&snapshot_file, mem.clone());
+
+        // WHEN
+        for ldh_name in ["foo.example", "bar.example"] {
+            let mut tx = mem.new_tx().await.expect("new transaction");
+            tx.add_domain(&domain(ldh_name))
+                .await
+                .expect("adding domain");
+            tx.commit().await.expect("committing");
+            snapshot.write_later("data", &DataFiles::default()).await;
+        }
+        wait_written(&snapshot).await;
+
+        // THEN
+        let restored = new_mem(&snapshot_file.to_string_lossy());
+        MemSnapshot::new(&snapshot_file, restored.clone())
+            .restore("data")
+            .await
+            .expect("restoring snapshot")
+            .expect("snapshot written");
+        assert!(is_found(restored.get_domain_by_ldh("foo.example").await));
+        assert!(is_found(restored.get_domain_by_ldh("bar.example").await));
+    }
+}
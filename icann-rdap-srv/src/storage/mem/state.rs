@@ -0,0 +1,436 @@
+use std::{net::IpAddr, sync::Arc};
+
+use icann_rdap_common::response::RdapResponse;
+use im::{HashMap, OrdMap};
+use ipnet::{Ipv4Net, Ipv6Net};
+
+use crate::storage::{
+    search::{ip_addrs, labels::search_names},
+    CommonConfig,
+};
+
+use super::{
+    contact_index::ContactIndex,
+    label_search::SearchLabels,
+    resource_index::{PrefixIndex, RangeIndex},
+    string_search::SearchStrings,
+};
+
+/// Objects by their LDH name or handle.
+pub(crate) type ResponseMap = HashMap<String, Arc<RdapResponse>>;
+
+/// A set of objects, such as the domains of a nameserver, keyed by their LDH name or handle.
+pub(crate) type ResponseSet = OrdMap<String, Arc<RdapResponse>>;
+
+/// The objects of the in-memory store and their search indexes.
+///
+/// Cloning the state is cheap, as all of its maps are persistent and share their
+/// structure with their clones. This lets a transaction work on its own version of the state
+/// and have it replace the state of the store on commit. The search indexes are kept
+/// up to date as objects are put and removed rather than rebuilt.
+#[derive(Clone)]
+pub(crate) struct MemState {
+    pub(crate) autnums: RangeIndex<Arc<RdapResponse>>,
+    pub(crate) ip4: PrefixIndex<Ipv4Net, Arc<RdapResponse>>,
+    pub(crate) ip6: PrefixIndex<Ipv6Net, Arc<RdapResponse>>,
+    pub(crate) domains: ResponseMap,
+    pub(crate) domains_by_name: SearchLabels<Arc<RdapResponse>>,
+    pub(crate) domains_by_ns_ldh_name: HashMap<String, ResponseSet>,
+    /// The LDH names of the nameservers in [MemState::domains_by_ns_ldh_name].
+    pub(crate) domains_by_ns_ldh_label: SearchLabels<String>,
+    pub(crate) domains_by_ns_ip: HashMap<IpAddr, ResponseSet>,
+    pub(crate) idns: ResponseMap,
+    pub(crate) nameservers: ResponseMap,
+    pub(crate) nameservers_by_name: SearchLabels<Arc<RdapResponse>>,
+    pub(crate) nameservers_by_ip: HashMap<IpAddr, ResponseSet>,
+    pub(crate) entities: ResponseMap,
+    pub(crate) entities_by_fn: SearchStrings<Arc<RdapResponse>>,
+    pub(crate) entities_by_handle: SearchStrings<Arc<RdapResponse>>,
+    pub(crate) domains_by_contact: ContactIndex,
+    pub(crate) nameservers_by_contact: ContactIndex,
+    pub(crate) entities_by_contact: ContactIndex,
+    pub(crate) srvhelps: ResponseMap,
+}
+
+impl Default for MemState {
+    fn default() -> Self {
+        Self {
+            autnums: <_>::default(),
+            ip4: <_>::default(),
+            ip6: <_>::default(),
+            domains: <_>::default(),
+            domains_by_name: SearchLabels::builder().build(),
+            domains_by_ns_ldh_name: <_>::default(),
+            domains_by_ns_ldh_label: SearchLabels::builder().build(),
+            domains_by_ns_ip: <_>::default(),
+            idns: <_>::default(),
+            nameservers: <_>::default(),
+            nameservers_by_name: SearchLabels::builder().build(),
+            nameservers_by_ip: <_>::default(),
+            entities: <_>::default(),
+            entities_by_fn: SearchStrings::builder().build(),
+            entities_by_handle: SearchStrings::builder().build(),
+            domains_by_contact: <_>::default(),
+            nameservers_by_contact: <_>::default(),
+            entities_by_contact: <_>::default(),
+            srvhelps: <_>::default(),
+        }
+    }
+}
+
+impl MemState {
+    /// Puts a domain (or the error in place of a domain) by its LDH name, replacing the
+    /// domain of that name in the indexes.
+    pub(crate) fn put_domain(
+        &mut self,
+        ldh_name: &str,
+        value: Arc<RdapResponse>,
+        config: &CommonConfig,
+    ) {
+        if let Some(old) = self.domains.insert(ldh_name.to_owned(), value.clone()) {
+            self.unindex_domain(ldh_name, &old, config);
+        }
+        self.index_domain(ldh_name, &value, config);
+    }
+
+    /// Removes a domain by its LDH name.
+    pub(crate) fn remove_domain(&mut self, ldh_name: &str, config: &CommonConfig) {
+        if let Some(old) = self.domains.remove(ldh_name) {
+            self.unindex_domain(ldh_name, &old, config);
+        }
+    }
+
+    fn index_domain(&mut self, ldh_name: &str, value: &Arc<RdapResponse>, config: &CommonConfig) {
+        if config.domain_search_by_name_enable {
+            for name in domain_search_names(ldh_name, value) {
+                self.domains_by_name.insert(&name, value.clone());
+            }
+        }
+        let RdapResponse::Domain(domain) = value.as_ref() else {
+            return;
+        };
+        if let Some(unicode_name) = &domain.unicode_name {
+            self.idns.insert(unicode_name.to_owned(), value.clone());
+        }
+        for nameserver in domain.nameservers.iter().flatten() {
+            if config.domain_search_by_ns_ldh_name_enable {
+                if let Some(ns_ldh_name) = &nameserver.ldh_name {
+                    let ns_ldh_name = ns_ldh_name.to_lowercase();
+                    let domains = self
+                        .domains_by_ns_ldh_name
+                        .entry(ns_ldh_name.clone())
+                        .or_default();
+                    if domains.is_empty() {
+                        self.domains_by_ns_ldh_label
+                            .insert(&ns_ldh_name, ns_ldh_name.clone());
+                    }
+                    domains.insert(ldh_name.to_owned(), value.clone());
+                }
+            }
+            if config.domain_search_by_ns_ip_enable {
+                for addr in ip_addrs(nameserver) {
+                    self.domains_by_ns_ip
+                        .entry(addr)
+                        .or_default()
+                        .insert(ldh_name.to_owned(), value.clone());
+                }
+            }
+        }
+        if config.reverse_search_enable {
+            if let Some(entities) = &domain.object_common.entities {
+                self.domains_by_contact.insert(ldh_name, value, entities);
+            }
+        }
+    }
+
+    fn unindex_domain(&mut self, ldh_name: &str, old: &Arc<RdapResponse>, config: &CommonConfig) {
+        if config.domain_search_by_name_enable {
+            for name in domain_search_names(ldh_name, old) {
+                self.domains_by_name.remove(&name);
+            }
+        }
+        let RdapResponse::Domain(domain) = old.as_ref() else {
+            return;
+        };
+        if let Some(unicode_name) = &domain.unicode_name {
+            // the IDN may have been taken over by another domain
+            if self
+                .idns
+                .get(unicode_name)
+                .is_some_and(|idn| Arc::ptr_eq(idn, old))
+            {
+                self.idns.remove(unicode_name);
+            }
+        }
+        for nameserver in domain.nameservers.iter().flatten() {
+            if config.domain_search_by_ns_ldh_name_enable {
+                if let Some(ns_ldh_name) = &nameserver.ldh_name {
+                    let ns_ldh_name = ns_ldh_name.to_lowercase();
+                    if remove_from_set(&mut self.domains_by_ns_ldh_name, &ns_ldh_name, ldh_name) {
+                        self.domains_by_ns_ldh_label.remove(&ns_ldh_name);
+                    }
+                }
+            }
+            if config.domain_search_by_ns_ip_enable {
+                for addr in ip_addrs(nameserver) {
+                    remove_from_set(&mut self.domains_by_ns_ip, &addr, ldh_name);
+                }
+            }
+        }
+        if config.reverse_search_enable {
+            if let Some(entities) = &domain.object_common.entities {
+                self.domains_by_contact.remove(ldh_name, entities);
+            }
+        }
+    }
+
+    /// Puts a nameserver (or the error in place of a nameserver) by its LDH name, replacing
+    /// the nameserver of that name in the indexes.
+    pub(crate) fn put_nameserver(
+        &mut self,
+        ldh_name: &str,
+        value: Arc<RdapResponse>,
+        config: &CommonConfig,
+    ) {
+        if let Some(old) = self.nameservers.insert(ldh_name.to_owned(), value.clone()) {
+            self.unindex_nameserver(ldh_name, &old, config);
+        }
+        self.index_nameserver(ldh_name, &value, config);
+    }
+
+    /// Removes a nameserver by its LDH name.
+    pub(crate) fn remove_nameserver(&mut self, ldh_name: &str, config: &CommonConfig) {
+        if let Some(old) = self.nameservers.remove(ldh_name) {
+            self.unindex_nameserver(ldh_name, &old, config);
+        }
+    }
+
+    fn index_nameserver(
+        &mut self,
+        ldh_name: &str,
+        value: &Arc<RdapResponse>,
+        config: &CommonConfig,
+    ) {
+        let RdapResponse::Nameserver(nameserver) = value.as_ref() else {
+            return;
+        };
+        if config.nameserver_search_by_name_enable {
+            for name in search_names(ldh_name, nameserver.unicode_name.as_deref()) {
+                self.nameservers_by_name.insert(&name, value.clone());
+            }
+        }
+        if config.nameserver_search_by_ip_enable {
+            for addr in ip_addrs(nameserver) {
+                self.nameservers_by_ip
+                    .entry(addr)
+                    .or_default()
+                    .insert(ldh_name.to_owned(), value.clone());
+            }
+        }
+        if config.reverse_search_enable {
+            if let Some(entities) = &nameserver.object_common.entities {
+                self.nameservers_by_contact
+                    .insert(ldh_name, value, entities);
+            }
+        }
+    }
+
+    fn unindex_nameserver(
+        &mut self,
+        ldh_name: &str,
+        old: &Arc<RdapResponse>,
+        config: &CommonConfig,
+    ) {
+        let RdapResponse::Nameserver(nameserver) = old.as_ref() else {
+            return;
+        };
+        if config.nameserver_search_by_name_enable {
+            for name in search_names(ldh_name, nameserver.unicode_name.as_deref()) {
+                self.nameservers_by_name.remove(&name);
+            }
+        }
+        if config.nameserver_search_by_ip_enable {
+            for addr in ip_addrs(nameserver) {
+                remove_from_set(&mut self.nameservers_by_ip, &addr, ldh_name);
+            }
+        }
+        if config.reverse_search_enable {
+            if let Some(entities) = &nameserver.object_common.entities {
+                self.nameservers_by_contact.remove(ldh_name, entities);
+            }
+        }
+    }
+
+    /// Puts an entity (or the error in place of an entity) by its handle, replacing the
+    /// entity of that handle in the indexes.
+    pub(crate) fn put_entity(
+        &mut self,
+        handle: &str,
+        value: Arc<RdapResponse>,
+        config: &CommonConfig,
+    ) {
+        if let Some(old) = self.entities.insert(handle.to_owned(), value.clone()) {
+            self.unindex_entity(handle, &old, config);
+        }
+        self.index_entity(handle, &value, config);
+    }
+
+    /// Removes an entity by its handle.
+    pub(crate) fn remove_entity(&mut self, handle: &str, config: &CommonConfig) {
+        if let Some(old) = self.entities.remove(handle) {
+            self.unindex_entity(handle, &old, config);
+        }
+    }
+
+    fn index_entity(&mut self, handle: &str, value: &Arc<RdapResponse>, config: &CommonConfig) {
+        let RdapResponse::Entity(entity) = value.as_ref() else {
+            return;
+        };
+        if config.entity_search_by_handle_enable {
+            self.entities_by_handle
+                .insert(handle, handle, value.clone());
+        }
+        if config.entity_search_by_fn_enable {
+            if let Some(full_name) = entity.contact().and_then(|c| c.full_name) {
+                self.entities_by_fn
+                    .insert(&full_name, handle, value.clone());
+            }
+        }
+        if config.reverse_search_enable {
+            if let Some(entities) = &entity.object_common.entities {
+                self.entities_by_contact.insert(handle, value, entities);
+            }
+        }
+    }
+
+    fn unindex_entity(&mut self, handle: &str, old: &Arc<RdapResponse>, config: &CommonConfig) {
+        let RdapResponse::Entity(entity) = old.as_ref() else {
+            return;
+        };
+        if config.entity_search_by_handle_enable {
+            self.entities_by_handle.remove(handle, handle);
+        }
+        if config.entity_search_by_fn_enable {
+            if let Some(full_name) = entity.contact().and_then(|c| c.full_name) {
+                self.entities_by_fn.remove(&full_name, handle);
+            }
+        }
+        if config.reverse_search_enable {
+            if let Some(entities) = &entity.object_common.entities {
+                self.entities_by_contact.remove(handle, entities);
+            }
+        }
+    }
+}
+
+/// Removes an object from the set under a key, and removes the set if it is then empty.
+/// Returns true if the set was removed.
+fn remove_from_set<K>(sets: &mut HashMap<K, ResponseSet>, key: &K, object_key: &str) -> bool
+where
+    K: std::hash::Hash + Eq + Clone,
+{
+    let Some(set) = sets.get_mut(key) else {
+        return false;
+    };
+    set.remove(object_key);
+    if set.is_empty() {
+        sets.remove(key);
+        true
+    } else {
+        false
+    }
+}
+
+/// Gets the names by which a domain (or the error in place of a domain) is found in a
+/// search by name.
+fn domain_search_names(ldh_name: &str, value: &RdapResponse) -> Vec<String> {
+    let unicode_name = match value {
+        RdapResponse::Domain(domain) => domain.unicode_name.as_deref(),
+        _ => None,
+    };
+    search_names(ldh_name, unicode_name)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use icann_rdap_common::response::{domain::Domain, nameserver::Nameserver, RdapResponse};
+
+    use crate::storage::CommonConfig;
+
+    use super::MemState;
+
+    fn domain(ldh_name: &str, ns_ldh_name: &str) -> Arc<RdapResponse> {
+        Arc::new(RdapResponse::Domain(
+            Domain::basic()
+                .ldh_name(ldh_name)
+                .nameservers(vec![Nameserver::basic()
+                    .ldh_name(ns_ldh_name)
+                    .build()
+                    .expect("building nameserver")])
+                .build(),
+        ))
+    }
+
+    #[test]
+    fn GIVEN_domain_WHEN_replaced_with_other_nameserver_THEN_only_new_nameserver_indexed() {
+        // GIVEN
+        let config = CommonConfig::default();
+        let mut state = MemState::default();
+        state.put_domain("foo.example", domain("foo.example", "ns1.example"), &config);
+        state.put_domain("bar.example", domain("bar.example", "ns1.example"), &config);
+
+        // WHEN
+        state.put_domain("foo.example", domain("foo.example", "ns2.example"), &config);
+
+        // THEN
+        let ns1 = state
+            .domains_by_ns_ldh_name
+            .get("ns1.example")
+            .expect("no domains of ns1");
+        assert_eq!(ns1.keys().collect::<Vec<_>>(), vec!["bar.example"]);
+        let ns2 = state
+            .domains_by_ns_ldh_label
+            .search("ns*.example")
+            .expect("search is invalid");
+        assert_eq!(ns2, vec!["ns1.example", "ns2.example"]);
+        let ns2 = state
+            .domains_by_ns_ldh_name
+            .get("ns2.example")
+            .expect("no domains of ns2");
+        assert!(ns2.contains_key("foo.example"));
+    }
+
+    #[test]
+    fn GIVEN_cloned_state_WHEN_domain_removed_THEN_clone_unchanged() {
+        // GIVEN
+        let config = CommonConfig::default();
+        let mut state = MemState::default();
+        state.put_domain("foo.example", domain("foo.example", "ns1.example"), &config);
+        let clone = state.clone();
+
+        // WHEN
+        state.remove_domain("foo.example", &config);
+
+        // THEN
+        assert!(state.domains.is_empty());
+        assert!(state.domains_by_ns_ldh_name.is_empty());
+        assert!(state
+            .domains_by_name
+            .search("foo*")
+            .expect("search is invalid")
+            .is_empty());
+        assert_eq!(clone.domains.len(), 1);
+        assert_eq!(
+            clone
+                .domains_by_name
+                .search("foo*")
+                .expect("search is invalid")
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use buildstructor::Builder;
+use im::OrdMap;
+
+use crate::{error::RdapServerError, storage::search::strings::split_search};
+
+/// A structure for searching strings, such as entity handles and names, as specified in RFC 9082.
+/// Matching is case-insensitive.
+/// For RDAP, type T is likely RdapResponse or Arc<RdapResponse>.
+///
+/// Values are kept under an ID unique to each string, such as the handle of an entity, so that
+/// they can be removed. The maps are persistent, making clones cheap.
+#[derive(Builder, Clone)]
+pub struct SearchStrings<T: Clone> {
+    strings: OrdMap<String, OrdMap<String, T>>,
+}
+
+impl<T: Clone> SearchStrings<T> {
+    /// Insert a value based on a string, replacing any value with the same ID.
+    pub(crate) fn insert(&mut self, text: &str, id: &str, value: T) {
+        self.strings
+            .entry(text.to_lowercase())
+            .or_default()
+            .insert(id.to_owned(), value);
+    }
+
+    /// Remove the value with an ID from a string.
+    pub(crate) fn remove(&mut self, text: &str, id: &str) {
+        let text = text.to_lowercase();
+        if let Some(values) = self.strings.get_mut(&text) {
+            values.remove(id);
+            if values.is_empty() {
+                self.strings.remove(&text);
+            }
+        }
+    }
+
+    /// Search values based on a string search. Without an asterisk ('*') the search must
+    /// match exactly. With a trailing asterisk the search matches all strings beginning
+    /// with the text preceding the asterisk.
+    pub(crate) fn search(&self, search: &str) -> Result<Vec<T>, RdapServerError> {
+        let (text, is_prefix) = split_search(search)?;
+        let values = if is_prefix {
+            self.strings
+                .range(text.clone()..)
+                .take_while(|(k, _)| k.starts_with(&text))
+                .flat_map(|(_, v)| v.values().cloned())
+                .collect()
+        } else {
+            self.strings
+                .get(&text)
+                .map(|v| v.values().cloned().collect())
+                .unwrap_or_default()
+        };
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use super::SearchStrings;
+
+    fn new_strings() -> SearchStrings<String> {
+        let mut strings = SearchStrings::builder().build();
+        strings.insert("Bob Smurd", "bob", "bob".to_owned());
+        strings.insert("Bobby Smurd", "bobby", "bobby".to_owned());
+        strings.insert("Alice Smurd", "alice", "alice".to_owned());
+        strings
+    }
+
+    #[test]
+    fn GIVEN_strings_WHEN_exact_search_THEN_only_exact_value_found() {
+        // GIVEN
+        let strings = new_strings();
+
+        // WHEN
+        let actual = strings.search("bob smurd").expect("search is invalid");
+
+        // THEN
+        assert_eq!(actual, vec!["bob".to_string()]);
+    }
+
+    #[test]
+    fn GIVEN_strings_WHEN_prefix_search_THEN_correct_values_found() {
+        // GIVEN
+        let strings = new_strings();
+
+        // WHEN
+        let actual = strings.search("BOB*").expect("search is invalid");
+
+        // THEN
+        assert_eq!(actual.len(), 2);
+        assert!(actual.contains(&"bob".to_string()));
+        assert!(actual.contains(&"bobby".to_string()));
+    }
+
+    #[test]
+    fn GIVEN_strings_WHEN_search_not_matching_THEN_no_values_found() {
+        // GIVEN
+        let strings = new_strings();
+
+        // WHEN
+        let actual = strings.search("carol*").expect("search is invalid");
+
+        // THEN
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn GIVEN_search_string_with_no_prefix_WHEN_search_THEN_error() {
+        // GIVEN
+        let strings = new_strings();
+
+        // WHEN
+        let actual = strings.search("*");
+
+        // THEN
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn GIVEN_search_string_with_inner_asterisk_WHEN_search_THEN_error() {
+        // GIVEN
+        let strings = new_strings();
+
+        // WHEN
+        let actual = strings.search("bob*smurd");
+
+        // THEN
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn GIVEN_strings_with_same_text_WHEN_remove_one_THEN_other_found() {
+        // GIVEN
+        let mut strings = new_strings();
+        strings.insert("Bob Smurd", "robert", "robert".to_owned());
+
+        // WHEN
+        strings.remove("BOB SMURD", "bob");
+
+        // THEN
+        let actual = strings.search("bob smurd").expect("search is invalid");
+        assert_eq!(actual, vec!["robert".to_string()]);
+    }
+}
@@ -1,77 +1,50 @@
-use std::{collections::HashMap, net::IpAddr, str::FromStr, sync::Arc};
+use std::{net::IpAddr, str::FromStr, sync::Arc};
 
 use async_trait::async_trait;
-use btree_range_map::RangeMap;
 use icann_rdap_common::response::{
     autnum::Autnum, domain::Domain, entity::Entity, help::Help, nameserver::Nameserver,
     network::Network, RdapResponse,
 };
-use ipnet::{IpSubnets, Ipv4Net, Ipv4Subnets, Ipv6Net, Ipv6Subnets};
-use prefix_trie::PrefixMap;
+use ipnet::{IpNet, IpSubnets, Ipv4Subnets, Ipv6Subnets};
 
 use crate::{
     error::RdapServerError,
     storage::{
         data::{AutnumId, DomainId, EntityId, NameserverId, NetworkId},
-        TxHandle,
+        CommonConfig, TxHandle,
     },
 };
 
-use super::{label_search::SearchLabels, ops::Mem};
+use super::{ops::Mem, state::MemState};
 
+/// A transaction of the in-memory store.
+///
+/// The transaction changes its own version of the state of the store, which is a cheap
+/// clone of the state when the transaction began (or an empty state when truncating),
+/// and replaces the state of the store with it on commit.
 pub struct MemTx {
     mem: Mem,
-    autnums: RangeMap<u32, Arc<RdapResponse>>,
-    ip4: PrefixMap<Ipv4Net, Arc<RdapResponse>>,
-    ip6: PrefixMap<Ipv6Net, Arc<RdapResponse>>,
-    domains: HashMap<String, Arc<RdapResponse>>,
-    domains_by_name: SearchLabels<Arc<RdapResponse>>,
-    idns: HashMap<String, Arc<RdapResponse>>,
-    nameservers: HashMap<String, Arc<RdapResponse>>,
-    entities: HashMap<String, Arc<RdapResponse>>,
-    srvhelps: HashMap<String, Arc<RdapResponse>>,
+    state: MemState,
 }
 
 impl MemTx {
     pub async fn new(mem: &Mem) -> Self {
-        let domains = Arc::clone(&mem.domains).read_owned().await.clone();
-        let mut domains_by_name = SearchLabels::builder().build();
-
-        // only do load up domain search labels if search by domain names is supported
-        if mem.config.common_config.domain_search_by_name_enable {
-            for (name, value) in domains.iter() {
-                domains_by_name.insert(name, value.clone());
-            }
-        }
-
         Self {
             mem: mem.clone(),
-            autnums: Arc::clone(&mem.autnums).read_owned().await.clone(),
-            ip4: Arc::clone(&mem.ip4).read_owned().await.clone(),
-            ip6: Arc::clone(&mem.ip6).read_owned().await.clone(),
-            domains,
-            domains_by_name,
-            idns: Arc::clone(&mem.idns).read_owned().await.clone(),
-            nameservers: Arc::clone(&mem.nameservers).read_owned().await.clone(),
-            entities: Arc::clone(&mem.entities).read_owned().await.clone(),
-            srvhelps: Arc::clone(&mem.srvhelps).read_owned().await.clone(),
+            state: mem.state.read().await.clone(),
         }
     }
 
     pub fn new_truncate(mem: &Mem) -> Self {
         Self {
             mem: mem.clone(),
-            autnums: RangeMap::new(),
-            ip4: PrefixMap::new(),
-            ip6: PrefixMap::new(),
-            domains: HashMap::new(),
-            domains_by_name: SearchLabels::builder().build(),
-            idns: HashMap::new(),
-            nameservers: HashMap::new(),
-            entities: HashMap::new(),
-            srvhelps: HashMap::new(),
+            state: MemState::default(),
         }
     }
+
+    fn config(&self) -> &CommonConfig {
+        &self.mem.config.common_config
+    }
 }
 
 #[async_trait]
@@ -82,9 +55,11 @@ impl TxHandle for MemTx {
             .handle
             .as_ref()
             .ok_or_else(|| RdapServerError::EmptyIndexData("handle".to_string()))?;
-        self.entities.insert(
-            handle.to_owned(),
+        let config = *self.config();
+        self.state.put_entity(
+            handle,
             Arc::new(RdapResponse::Entity(entity.clone())),
+            &config,
         );
         Ok(())
     }
@@ -94,34 +69,27 @@ impl TxHandle for MemTx {
         entity_id: &EntityId,
         error: &icann_rdap_common::response::error::Error,
     ) -> Result<(), RdapServerError> {
-        self.entities.insert(
-            entity_id.handle.to_owned(),
+        let config = *self.config();
+        self.state.put_entity(
+            &entity_id.handle,
             Arc::new(RdapResponse::ErrorResponse(error.clone())),
+            &config,
         );
         Ok(())
     }
 
     async fn add_domain(&mut self, domain: &Domain) -> Result<(), RdapServerError> {
-        let domain_response = Arc::new(RdapResponse::Domain(domain.clone()));
-
-        // add the domain as LDH, which is required.
+        // add the domain as LDH, which is required, and by unicodeName.
         let ldh_name = domain
             .ldh_name
             .as_ref()
             .ok_or_else(|| RdapServerError::EmptyIndexData("ldhName".to_string()))?;
-        self.domains
-            .insert(ldh_name.to_owned(), domain_response.clone());
-
-        // add the domain by unicodeName
-        if let Some(unicode_name) = domain.unicode_name.as_ref() {
-            self.idns
-                .insert(unicode_name.to_owned(), domain_response.clone());
-        };
-
-        if self.mem.config.common_config.domain_search_by_name_enable {
-            self.domains_by_name.insert(ldh_name, domain_response);
-        }
-
+        let config = *self.config();
+        self.state.put_domain(
+            ldh_name,
+            Arc::new(RdapResponse::Domain(domain.clone())),
+            &config,
+        );
         Ok(())
     }
 
@@ -130,9 +98,11 @@ impl TxHandle for MemTx {
         domain_id: &DomainId,
         error: &icann_rdap_common::response::error::Error,
     ) -> Result<(), RdapServerError> {
-        self.domains.insert(
-            domain_id.ldh_name.to_owned(),
+        let config = *self.config();
+        self.state.put_domain(
+            &domain_id.ldh_name,
             Arc::new(RdapResponse::ErrorResponse(error.clone())),
+            &config,
         );
         Ok(())
     }
@@ -142,9 +112,11 @@ impl TxHandle for MemTx {
             .ldh_name
             .as_ref()
             .ok_or_else(|| RdapServerError::EmptyIndexData("ldhName".to_string()))?;
-        self.nameservers.insert(
-            ldh_name.to_owned(),
+        let config = *self.config();
+        self.state.put_nameserver(
+            ldh_name,
             Arc::new(RdapResponse::Nameserver(nameserver.clone())),
+            &config,
         );
         Ok(())
     }
@@ -154,9 +126,11 @@ impl TxHandle for MemTx {
         nameserver_id: &NameserverId,
         error: &icann_rdap_common::response::error::Error,
     ) -> Result<(), RdapServerError> {
-        self.nameservers.insert(
-            nameserver_id.ldh_name.to_owned(),
+        let config = *self.config();
+        self.state.put_nameserver(
+            &nameserver_id.ldh_name,
             Arc::new(RdapResponse::ErrorResponse(error.clone())),
+            &config,
         );
         Ok(())
     }
@@ -170,7 +144,7 @@ impl TxHandle for MemTx {
             .end_autnum
             .as_ref()
             .ok_or_else(|| RdapServerError::EmptyIndexData("endNum".to_string()))?;
-        self.autnums.insert(
+        self.state.autnums.insert(
             (*start_num)..=(*end_num),
             Arc::new(RdapResponse::Autnum(autnum.clone())),
         );
@@ -182,7 +156,7 @@ impl TxHandle for MemTx {
         autnum_id: &AutnumId,
         error: &icann_rdap_common::response::error::Error,
     ) -> Result<(), RdapServerError> {
-        self.autnums.insert(
+        self.state.autnums.insert(
             (autnum_id.start_autnum)..=(autnum_id.end_autnum),
             Arc::new(RdapResponse::ErrorResponse(error.clone())),
         );
@@ -205,15 +179,15 @@ impl TxHandle for MemTx {
         let is_v4 = ip_type.eq_ignore_ascii_case("v4");
         if is_v4 {
             let subnets = Ipv4Subnets::new(start_addr.parse()?, end_addr.parse()?, 0);
+            let ip4 = &mut self.state.ip4;
             for net in subnets {
-                self.ip4
-                    .insert(net, Arc::new(RdapResponse::Network(network.clone())));
+                ip4.insert(net, Arc::new(RdapResponse::Network(network.clone())));
             }
         } else {
             let subnets = Ipv6Subnets::new(start_addr.parse()?, end_addr.parse()?, 0);
+            let ip6 = &mut self.state.ip6;
             for net in subnets {
-                self.ip6
-                    .insert(net, Arc::new(RdapResponse::Network(network.clone())));
+                ip6.insert(net, Arc::new(RdapResponse::Network(network.clone())));
             }
         };
         Ok(())
@@ -257,15 +231,15 @@ impl TxHandle for MemTx {
         };
         match subnets {
             IpSubnets::V4(subnets) => {
+                let ip4 = &mut self.state.ip4;
                 for net in subnets {
-                    self.ip4
-                        .insert(net, Arc::new(RdapResponse::ErrorResponse(error.clone())));
+                    ip4.insert(net, Arc::new(RdapResponse::ErrorResponse(error.clone())));
                 }
             }
             IpSubnets::V6(subnets) => {
+                let ip6 = &mut self.state.ip6;
                 for net in subnets {
-                    self.ip6
-                        .insert(net, Arc::new(RdapResponse::ErrorResponse(error.clone())));
+                    ip6.insert(net, Arc::new(RdapResponse::ErrorResponse(error.clone())));
                 }
             }
         }
@@ -278,48 +252,60 @@ impl TxHandle for MemTx {
         host: Option<&str>,
     ) -> Result<(), RdapServerError> {
         let host = host.unwrap_or("..default");
-        self.srvhelps
+        self.state
+            .srvhelps
             .insert(host.to_string(), Arc::new(RdapResponse::Help(help.clone())));
         Ok(())
     }
 
-    async fn commit(mut self: Box<Self>) -> Result<(), RdapServerError> {
-        // autnums
-        let mut autnum_g = self.mem.autnums.write().await;
-        std::mem::swap(&mut self.autnums, &mut autnum_g);
-
-        // ip4
-        let mut ip4_g = self.mem.ip4.write().await;
-        std::mem::swap(&mut self.ip4, &mut ip4_g);
-
-        // ip6
-        let mut ip6_g = self.mem.ip6.write().await;
-        std::mem::swap(&mut self.ip6, &mut ip6_g);
-
-        // domains
-        let mut domains_g = self.mem.domains.write().await;
-        std::mem::swap(&mut self.domains, &mut domains_g);
+    async fn remove_domain(&mut self, domain_id: &DomainId) -> Result<(), RdapServerError> {
+        let config = *self.config();
+        self.state.remove_domain(&domain_id.ldh_name, &config);
+        Ok(())
+    }
 
-        //domains by name
-        let mut domains_by_name_g = self.mem.domains_by_name.write().await;
-        std::mem::swap(&mut self.domains_by_name, &mut domains_by_name_g);
+    async fn remove_entity(&mut self, entity_id: &EntityId) -> Result<(), RdapServerError> {
+        let config = *self.config();
+        self.state.remove_entity(&entity_id.handle, &config);
+        Ok(())
+    }
 
-        //idns
-        let mut idns_g = self.mem.idns.write().await;
-        std::mem::swap(&mut self.idns, &mut idns_g);
+    async fn remove_nameserver(
+        &mut self,
+        nameserver_id: &NameserverId,
+    ) -> Result<(), RdapServerError> {
+        let config = *self.config();
+        self.state
+            .remove_nameserver(&nameserver_id.ldh_name, &config);
+        Ok(())
+    }
 
-        // nameservers
-        let mut nameservers_g = self.mem.nameservers.write().await;
-        std::mem::swap(&mut self.nameservers, &mut nameservers_g);
+    async fn remove_autnum(&mut self, autnum_id: &AutnumId) -> Result<(), RdapServerError> {
+        self.state
+            .autnums
+            .remove((autnum_id.start_autnum)..=(autnum_id.end_autnum));
+        Ok(())
+    }
 
-        // entities
-        let mut entities_g = self.mem.entities.write().await;
-        std::mem::swap(&mut self.entities, &mut entities_g);
+    async fn remove_network(&mut self, network_id: &NetworkId) -> Result<(), RdapServerError> {
+        for cidr in network_id.cidrs()? {
+            match cidr {
+                IpNet::V4(net) => self.state.ip4.remove(&net),
+                IpNet::V6(net) => self.state.ip6.remove(&net),
+            };
+        }
+        Ok(())
+    }
 
-        //srvhelps
-        let mut srvhelps_g = self.mem.srvhelps.write().await;
-        std::mem::swap(&mut self.srvhelps, &mut srvhelps_g);
+    async fn remove_srv_help(&mut self, host: Option<&str>) -> Result<(), RdapServerError> {
+        self.state.srvhelps.remove(host.unwrap_or("..default"));
+        Ok(())
+    }
 
+    async fn commit(self: Box<Self>) -> Result<(), RdapServerError> {
+        let old = std::mem::replace(&mut *self.mem.state.write().await, self.state);
+        // the objects only in the old state are freed after readers are let back in.
+        drop(old);
         Ok(())
     }
 
@@ -9,9 +9,14 @@ use crate::error::RdapServerError;
 
 use self::data::{AutnumId, DomainId, EntityId, NameserverId, NetworkId};
 
+pub mod bulk;
 pub mod data;
 pub mod mem;
 pub mod pg;
+/// Search helpers which do not depend on the type of storage.
+pub(crate) mod search;
+pub mod sqlite;
+pub(crate) mod watch;
 
 pub type DynStoreOps = dyn StoreOps + Send + Sync;
 
@@ -56,6 +61,49 @@ pub trait StoreOps: Send + Sync {
 
     /// Search for domains by name.
     async fn search_domains_by_name(&self, name: &str) -> Result<RdapResponse, RdapServerError>;
+
+    /// Search for domains by the name of one of their nameservers.
+    async fn search_domains_by_ns_ldh_name(
+        &self,
+        ns_ldh_name: &str,
+    ) -> Result<RdapResponse, RdapServerError>;
+
+    /// Search for domains by the IP address of one of their nameservers.
+    async fn search_domains_by_ns_ip(&self, ns_ip: &str) -> Result<RdapResponse, RdapServerError>;
+
+    /// Search for nameservers by name.
+    async fn search_nameservers_by_name(&self, name: &str)
+        -> Result<RdapResponse, RdapServerError>;
+
+    /// Search for nameservers by IP address.
+    async fn search_nameservers_by_ip(&self, ip: &str) -> Result<RdapResponse, RdapServerError>;
+
+    /// Search for entities by full name (the 'fn' property of the jCard).
+    async fn search_entities_by_fn(&self, fn_: &str) -> Result<RdapResponse, RdapServerError>;
+
+    /// Search for entities by handle.
+    async fn search_entities_by_handle(
+        &self,
+        handle: &str,
+    ) -> Result<RdapResponse, RdapServerError>;
+
+    /// Reverse search (RFC 9536) for domains by the properties of their related entities.
+    async fn reverse_search_domains(
+        &self,
+        conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError>;
+
+    /// Reverse search (RFC 9536) for nameservers by the properties of their related entities.
+    async fn reverse_search_nameservers(
+        &self,
+        conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError>;
+
+    /// Reverse search (RFC 9536) for entities by the properties of their related entities.
+    async fn reverse_search_entities(
+        &self,
+        conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError>;
 }
 
 /// Represents a handle to a transaction.
@@ -119,6 +167,28 @@ pub trait TxHandle: Send {
         host: Option<&str>,
     ) -> Result<(), RdapServerError>;
 
+    /// Remove a domain, or an error added as a domain, from storage.
+    /// Removing a domain that is not in storage is not an error.
+    async fn remove_domain(&mut self, domain_id: &DomainId) -> Result<(), RdapServerError>;
+
+    /// Remove an entity, or an error added as an entity, from storage.
+    async fn remove_entity(&mut self, entity_id: &EntityId) -> Result<(), RdapServerError>;
+
+    /// Remove a nameserver, or an error added as a nameserver, from storage.
+    async fn remove_nameserver(
+        &mut self,
+        nameserver_id: &NameserverId,
+    ) -> Result<(), RdapServerError>;
+
+    /// Remove an autnum, or an error added as an autnum, from storage.
+    async fn remove_autnum(&mut self, autnum_id: &AutnumId) -> Result<(), RdapServerError>;
+
+    /// Remove a network, or an error added as a network, from storage.
+    async fn remove_network(&mut self, network_id: &NetworkId) -> Result<(), RdapServerError>;
+
+    /// Remove server help from storage.
+    async fn remove_srv_help(&mut self, host: Option<&str>) -> Result<(), RdapServerError>;
+
     /// Commit the transaction.
     async fn commit(self: Box<Self>) -> Result<(), RdapServerError>;
 
@@ -126,16 +196,90 @@ pub trait TxHandle: Send {
     async fn rollback(self: Box<Self>) -> Result<(), RdapServerError>;
 }
 
+/// The conditions of an RFC 9536 reverse search, all of which must be met
+/// by the same related entity.
+#[derive(Debug, Clone, Default, Builder)]
+pub struct ReverseSearchConditions {
+    /// The full name ("fn") of the related entity's contact.
+    pub full_name: Option<String>,
+
+    /// The handle of the related entity.
+    pub handle: Option<String>,
+
+    /// An email address of the related entity's contact.
+    pub email: Option<String>,
+
+    /// A role of the related entity. This is matched exactly, and cannot be the only condition.
+    pub role: Option<String>,
+}
+
 /// Common configuration for storage back ends.
-#[derive(Debug, Clone, Copy, Builder)]
+#[derive(Debug, Clone, Copy)]
 pub struct CommonConfig {
     pub domain_search_by_name_enable: bool,
+    pub domain_search_by_ns_ldh_name_enable: bool,
+    pub domain_search_by_ns_ip_enable: bool,
+    pub nameserver_search_by_name_enable: bool,
+    pub nameserver_search_by_ip_enable: bool,
+    pub entity_search_by_fn_enable: bool,
+    pub entity_search_by_handle_enable: bool,
+    pub reverse_search_enable: bool,
+    /// Allows domain and nameserver name searches with more than one partial label,
+    /// such as `exa*.c*`.
+    pub partial_label_search_enable: bool,
+}
+
+#[buildstructor::buildstructor]
+impl CommonConfig {
+    /// Creates a new common configuration. Any search not given is as in [Self::default].
+    #[builder]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        domain_search_by_name_enable: Option<bool>,
+        domain_search_by_ns_ldh_name_enable: Option<bool>,
+        domain_search_by_ns_ip_enable: Option<bool>,
+        nameserver_search_by_name_enable: Option<bool>,
+        nameserver_search_by_ip_enable: Option<bool>,
+        entity_search_by_fn_enable: Option<bool>,
+        entity_search_by_handle_enable: Option<bool>,
+        reverse_search_enable: Option<bool>,
+        partial_label_search_enable: Option<bool>,
+    ) -> Self {
+        let default = Self::default();
+        CommonConfig {
+            domain_search_by_name_enable: domain_search_by_name_enable
+                .unwrap_or(default.domain_search_by_name_enable),
+            domain_search_by_ns_ldh_name_enable: domain_search_by_ns_ldh_name_enable
+                .unwrap_or(default.domain_search_by_ns_ldh_name_enable),
+            domain_search_by_ns_ip_enable: domain_search_by_ns_ip_enable
+                .unwrap_or(default.domain_search_by_ns_ip_enable),
+            nameserver_search_by_name_enable: nameserver_search_by_name_enable
+                .unwrap_or(default.nameserver_search_by_name_enable),
+            nameserver_search_by_ip_enable: nameserver_search_by_ip_enable
+                .unwrap_or(default.nameserver_search_by_ip_enable),
+            entity_search_by_fn_enable: entity_search_by_fn_enable
+                .unwrap_or(default.entity_search_by_fn_enable),
+            entity_search_by_handle_enable: entity_search_by_handle_enable
+                .unwrap_or(default.entity_search_by_handle_enable),
+            reverse_search_enable: reverse_search_enable.unwrap_or(default.reverse_search_enable),
+            partial_label_search_enable: partial_label_search_enable
+                .unwrap_or(default.partial_label_search_enable),
+        }
+    }
 }
 
 impl Default for CommonConfig {
     fn default() -> Self {
         CommonConfig {
             domain_search_by_name_enable: true,
+            domain_search_by_ns_ldh_name_enable: true,
+            domain_search_by_ns_ip_enable: true,
+            nameserver_search_by_name_enable: true,
+            nameserver_search_by_ip_enable: true,
+            entity_search_by_fn_enable: true,
+            entity_search_by_handle_enable: true,
+            reverse_search_enable: true,
+            partial_label_search_enable: false,
         }
     }
 }
@@ -1,12 +1,31 @@
-#![allow(clippy::diverging_sub_expression)]
+use std::{net::IpAddr, str::FromStr};
+
 use async_trait::async_trait;
-use icann_rdap_common::response::RdapResponse;
-use sqlx::{query, PgPool};
+use icann_rdap_common::response::{
+    domain::Domain,
+    entity::Entity,
+    nameserver::Nameserver,
+    search::{DomainSearchResults, EntitySearchResults, NameserverSearchResults},
+    types::Common,
+    RdapResponse,
+};
+use ipnet::IpNet;
+use sqlx::{query, query_scalar, types::Json, PgPool};
 use tracing::{debug, info};
 
 use crate::{
     error::RdapServerError,
-    storage::{StoreOps, TxHandle},
+    rdap::response::{NOT_FOUND, NOT_IMPLEMENTED},
+    storage::{
+        search::{
+            labels::{
+                escape_like, is_like_match, like_pattern, partial_like_pattern, search_names,
+                unicode_search,
+            },
+            strings,
+        },
+        ReverseSearchConditions, StoreOps, TxHandle,
+    },
 };
 
 use super::{config::PgConfig, tx::PgTx};
@@ -14,15 +33,75 @@ use super::{config::PgConfig, tx::PgTx};
 #[derive(Clone)]
 pub struct Pg {
     pg_pool: PgPool,
+    config: PgConfig,
 }
 
 impl Pg {
     pub async fn new(config: PgConfig) -> Result<Self, RdapServerError> {
         let pg_pool = PgPool::connect(&config.db_url).await?;
-        Ok(Self { pg_pool })
+        Ok(Self { pg_pool, config })
+    }
+}
+
+/// Turns an optional row into the RDAP response, or 404 if there was no row.
+fn found_or_not(row: Option<Json<RdapResponse>>) -> RdapResponse {
+    match row {
+        Some(Json(rdap)) => rdap,
+        None => NOT_FOUND.clone(),
     }
 }
 
+/// Turns the rows of a domain search into domain search results.
+fn domain_results(rows: Vec<Json<RdapResponse>>) -> RdapResponse {
+    let results = rows
+        .into_iter()
+        .filter_map(|Json(d)| match d {
+            RdapResponse::Domain(d) => Some(d),
+            _ => None,
+        })
+        .collect::<Vec<Domain>>();
+    RdapResponse::DomainSearchResults(
+        DomainSearchResults::builder()
+            .common(Common::new_level0(vec![], vec![]))
+            .results(results)
+            .build(),
+    )
+}
+
+/// Turns the rows of a nameserver search into nameserver search results.
+fn nameserver_results(rows: Vec<Json<RdapResponse>>) -> RdapResponse {
+    let results = rows
+        .into_iter()
+        .filter_map(|Json(n)| match n {
+            RdapResponse::Nameserver(n) => Some(n),
+            _ => None,
+        })
+        .collect::<Vec<Nameserver>>();
+    RdapResponse::NameserverSearchResults(
+        NameserverSearchResults::builder()
+            .common(Common::new_level0(vec![], vec![]))
+            .results(results)
+            .build(),
+    )
+}
+
+/// Turns the rows of an entity search into entity search results.
+fn entity_results(rows: Vec<Json<RdapResponse>>) -> RdapResponse {
+    let results = rows
+        .into_iter()
+        .filter_map(|Json(e)| match e {
+            RdapResponse::Entity(e) => Some(e),
+            _ => None,
+        })
+        .collect::<Vec<Entity>>();
+    RdapResponse::EntitySearchResults(
+        EntitySearchResults::builder()
+            .common(Common::new_level0(vec![], vec![]))
+            .results(results)
+            .build(),
+    )
+}
+
 #[async_trait]
 impl StoreOps for Pg {
     async fn init(&self) -> Result<(), RdapServerError> {
@@ -30,6 +109,11 @@ impl StoreOps for Pg {
         let mut conn = self.pg_pool.acquire().await?;
         query("select 1").fetch_one(&mut *conn).await?;
         info!("Database connection test is successful.");
+        debug!("Running database migrations.");
+        sqlx::migrate!("./migrations/postgres")
+            .run(&self.pg_pool)
+            .await?;
+        info!("Database schema is up to date.");
         Ok(())
     }
 
@@ -41,36 +125,284 @@ impl StoreOps for Pg {
         Ok(Box::new(PgTx::new_truncate(&self.pg_pool).await?))
     }
 
-    async fn get_domain_by_ldh(&self, _ldh: &str) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+    async fn get_domain_by_ldh(&self, ldh: &str) -> Result<RdapResponse, RdapServerError> {
+        let row = query_scalar("select rdap from domain where ldh_name = $1")
+            .bind(ldh)
+            .fetch_optional(&self.pg_pool)
+            .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn get_domain_by_unicode(&self, unicode: &str) -> Result<RdapResponse, RdapServerError> {
+        let row = query_scalar("select rdap from domain where unicode_name = $1 limit 1")
+            .bind(unicode)
+            .fetch_optional(&self.pg_pool)
+            .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn get_entity_by_handle(&self, handle: &str) -> Result<RdapResponse, RdapServerError> {
+        let row = query_scalar("select rdap from entity where handle = $1")
+            .bind(handle)
+            .fetch_optional(&self.pg_pool)
+            .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn get_nameserver_by_ldh(&self, ldh: &str) -> Result<RdapResponse, RdapServerError> {
+        let row = query_scalar("select rdap from nameserver where ldh_name = $1")
+            .bind(ldh)
+            .fetch_optional(&self.pg_pool)
+            .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn get_autnum_by_num(&self, num: u32) -> Result<RdapResponse, RdapServerError> {
+        // the most specific (smallest) range containing the number wins.
+        let row = query_scalar(
+            "select rdap from autnum where start_autnum <= $1 and end_autnum >= $1 \
+             order by end_autnum - start_autnum, start_autnum limit 1",
+        )
+        .bind(num as i64)
+        .fetch_optional(&self.pg_pool)
+        .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn get_network_by_ipaddr(&self, ipaddr: &str) -> Result<RdapResponse, RdapServerError> {
+        let addr = ipaddr.parse::<IpAddr>()?;
+        let row = query_scalar(
+            "select rdap from network where cidr >>= $1::inet \
+             order by masklen(cidr) desc limit 1",
+        )
+        .bind(addr.to_string())
+        .fetch_optional(&self.pg_pool)
+        .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn get_network_by_cidr(&self, cidr: &str) -> Result<RdapResponse, RdapServerError> {
+        let net = IpNet::from_str(cidr)?;
+        let row = query_scalar(
+            "select rdap from network where cidr >>= $1::inet \
+             order by masklen(cidr) desc limit 1",
+        )
+        .bind(net.trunc().to_string())
+        .fetch_optional(&self.pg_pool)
+        .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn get_srv_help(&self, host: Option<&str>) -> Result<RdapResponse, RdapServerError> {
+        let host = host.unwrap_or("..default");
+        let row = query_scalar("select rdap from srv_help where host = $1")
+            .bind(host)
+            .fetch_optional(&self.pg_pool)
+            .await?;
+        Ok(found_or_not(row))
+    }
+
+    async fn search_domains_by_name(&self, name: &str) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.domain_search_by_name_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let name = unicode_search(name);
+        let pattern = if self.config.common_config.partial_label_search_enable {
+            partial_like_pattern(&name)
+        } else {
+            like_pattern(&name)
+        };
+        let rows: Vec<Json<RdapResponse>> = if let Ok(pattern) = pattern {
+            let rows: Vec<Json<RdapResponse>> = query_scalar(
+                "select rdap from domain where ldh_name in \
+                 (select ldh_name from domain_name where name like $1) \
+                 and rdap->>'objectClassName' = 'domain'",
+            )
+            .bind(pattern)
+            .fetch_all(&self.pg_pool)
+            .await?;
+            rows.into_iter()
+                .filter(|Json(rdap)| match rdap {
+                    RdapResponse::Domain(d) => d.ldh_name.as_deref().is_some_and(|ldh_name| {
+                        search_names(ldh_name, d.unicode_name.as_deref())
+                            .iter()
+                            .any(|search_name| is_like_match(&name, search_name))
+                    }),
+                    _ => false,
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        Ok(domain_results(rows))
+    }
+
+    async fn search_domains_by_ns_ldh_name(
+        &self,
+        ns_ldh_name: &str,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self
+            .config
+            .common_config
+            .domain_search_by_ns_ldh_name_enable
+        {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let ns_ldh_name = ns_ldh_name.to_lowercase();
+        let pattern = if ns_ldh_name.contains('*') {
+            like_pattern(&ns_ldh_name)
+        } else {
+            Ok(escape_like(&ns_ldh_name))
+        };
+        let rows: Vec<Json<RdapResponse>> = if let Ok(pattern) = pattern {
+            query_scalar(
+                "select rdap from domain where rdap->>'objectClassName' = 'domain' \
+                 and ldh_name in (select ldh_name from domain_nameserver \
+                 where ns_ldh_name like $1)",
+            )
+            .bind(pattern)
+            .fetch_all(&self.pg_pool)
+            .await?
+        } else {
+            vec![]
+        };
+        Ok(domain_results(rows))
     }
 
-    async fn get_domain_by_unicode(&self, _unicode: &str) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+    async fn search_domains_by_ns_ip(&self, ns_ip: &str) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.domain_search_by_ns_ip_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let addr = ns_ip.parse::<IpAddr>()?;
+        let rows: Vec<Json<RdapResponse>> = query_scalar(
+            "select rdap from domain where rdap->>'objectClassName' = 'domain' \
+             and ldh_name in (select ldh_name from domain_ns_ip where addr = $1::inet)",
+        )
+        .bind(addr.to_string())
+        .fetch_all(&self.pg_pool)
+        .await?;
+        Ok(domain_results(rows))
     }
 
-    async fn get_entity_by_handle(&self, _handle: &str) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+    async fn search_nameservers_by_name(
+        &self,
+        name: &str,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.nameserver_search_by_name_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let name = unicode_search(name);
+        let pattern = if self.config.common_config.partial_label_search_enable {
+            partial_like_pattern(&name)
+        } else {
+            like_pattern(&name)
+        };
+        let rows: Vec<Json<RdapResponse>> = if let Ok(pattern) = pattern {
+            let rows: Vec<Json<RdapResponse>> = query_scalar(
+                "select rdap from nameserver where ldh_name in \
+                 (select ldh_name from nameserver_name where name like $1) \
+                 and rdap->>'objectClassName' = 'nameserver'",
+            )
+            .bind(pattern)
+            .fetch_all(&self.pg_pool)
+            .await?;
+            rows.into_iter()
+                .filter(|Json(rdap)| match rdap {
+                    RdapResponse::Nameserver(n) => n.ldh_name.as_deref().is_some_and(|ldh_name| {
+                        search_names(ldh_name, n.unicode_name.as_deref())
+                            .iter()
+                            .any(|search_name| is_like_match(&name, search_name))
+                    }),
+                    _ => false,
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        Ok(nameserver_results(rows))
     }
 
-    async fn get_nameserver_by_ldh(&self, _ldh: &str) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+    async fn search_nameservers_by_ip(&self, ip: &str) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.nameserver_search_by_ip_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let addr = ip.parse::<IpAddr>()?;
+        let rows: Vec<Json<RdapResponse>> = query_scalar(
+            "select rdap from nameserver where rdap->>'objectClassName' = 'nameserver' \
+             and ldh_name in (select ldh_name from nameserver_ip where addr = $1::inet)",
+        )
+        .bind(addr.to_string())
+        .fetch_all(&self.pg_pool)
+        .await?;
+        Ok(nameserver_results(rows))
     }
 
-    async fn get_autnum_by_num(&self, _num: u32) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+    async fn search_entities_by_fn(&self, fn_: &str) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.entity_search_by_fn_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let rows: Vec<Json<RdapResponse>> = if let Ok(pattern) = strings::like_pattern(fn_) {
+            query_scalar(
+                "select rdap from entity where full_name like $1 \
+                 and rdap->>'objectClassName' = 'entity'",
+            )
+            .bind(pattern)
+            .fetch_all(&self.pg_pool)
+            .await?
+        } else {
+            vec![]
+        };
+        Ok(entity_results(rows))
     }
 
-    async fn get_network_by_ipaddr(&self, _ipaddr: &str) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+    async fn search_entities_by_handle(
+        &self,
+        handle: &str,
+    ) -> Result<RdapResponse, RdapServerError> {
+        if !self.config.common_config.entity_search_by_handle_enable {
+            return Ok(NOT_IMPLEMENTED.clone());
+        }
+        //else
+        let rows: Vec<Json<RdapResponse>> = if let Ok(pattern) = strings::like_pattern(handle) {
+            query_scalar(
+                "select rdap from entity where lower(handle) like $1 \
+                 and rdap->>'objectClassName' = 'entity'",
+            )
+            .bind(pattern)
+            .fetch_all(&self.pg_pool)
+            .await?
+        } else {
+            vec![]
+        };
+        Ok(entity_results(rows))
     }
-    async fn get_network_by_cidr(&self, _cidr: &str) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+
+    // reverse search is refused for this storage when configured from the environment,
+    // see StorageType::new_from_env.
+    async fn reverse_search_domains(
+        &self,
+        _conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError> {
+        Ok(NOT_IMPLEMENTED.clone())
     }
-    async fn get_srv_help(&self, _host: Option<&str>) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+
+    async fn reverse_search_nameservers(
+        &self,
+        _conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError> {
+        Ok(NOT_IMPLEMENTED.clone())
     }
-    async fn search_domains_by_name(&self, _name: &str) -> Result<RdapResponse, RdapServerError> {
-        todo!()
+
+    async fn reverse_search_entities(
+        &self,
+        _conditions: &ReverseSearchConditions,
+    ) -> Result<RdapResponse, RdapServerError> {
+        Ok(NOT_IMPLEMENTED.clone())
     }
 }
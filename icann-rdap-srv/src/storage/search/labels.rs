@@ -0,0 +1,171 @@
+use crate::error::RdapServerError;
+
+/// Validates a domain label search string and splits it into the prefix preceding
+/// the asterisk and the label suffix following it (without the leading dot).
+pub(crate) fn split_search(search: &str) -> Result<(&str, &str), RdapServerError> {
+    // search string is invalid if it doesn't have only one asterisk ('*')
+    if search.chars().filter(|c| *c == '*').count() != 1 {
+        return Err(RdapServerError::InvalidArg(
+            "Search string must contain one and only one asterisk ('*')".to_string(),
+        ));
+    }
+    split_partial_search(search)
+}
+
+/// Validates a domain label search string in which more than one label may be partial
+/// and splits it into the prefix preceding the first asterisk and the label suffix
+/// following the last asterisk (without the leading dot).
+pub(crate) fn split_partial_search(search: &str) -> Result<(&str, &str), RdapServerError> {
+    let (Some(first), Some(last)) = (search.find('*'), search.rfind('*')) else {
+        return Err(RdapServerError::InvalidArg(
+            "Search string must contain an asterisk ('*')".to_string(),
+        ));
+    };
+
+    // asterisks must not be followed by a character other than dot ('.')
+    if search
+        .match_indices('*')
+        .any(|(i, _)| !matches!(search[i + 1..].chars().next(), None | Some('.')))
+    {
+        return Err(RdapServerError::InvalidArg(
+            "Search string asterisk ('*') must terminate domain label".to_string(),
+        ));
+    }
+
+    // this is a limitation of the trie in that it requires a prefix
+    let prefix = &search[..first];
+    if prefix.is_empty() {
+        return Err(RdapServerError::InvalidArg(
+            "Search string must have a prefix".to_string(),
+        ));
+    }
+
+    Ok((prefix, search[last + 1..].trim_start_matches('.')))
+}
+
+/// Determines if a domain name matches the labels of a search, each of which either
+/// equals the label of the name or, ending with an asterisk, is a prefix of it.
+pub(crate) fn matches_labels(search: &str, name: &str) -> bool {
+    search.split('.').count() == name.split('.').count()
+        && search
+            .split('.')
+            .zip(name.split('.'))
+            .all(|(search, label)| match search.strip_suffix('*') {
+                Some(prefix) => label.starts_with(prefix),
+                None => search == label,
+            })
+}
+
+/// Converts the whole labels of a domain label search to U-labels using IDNA, so that
+/// searches with A-labels or U-labels find the same names. Partial labels are only
+/// lowercased, as the start of a U-label cannot be converted to the start of its A-label.
+pub(crate) fn unicode_search(search: &str) -> String {
+    search
+        .split('.')
+        .map(|label| {
+            if label.contains('*') {
+                return label.to_lowercase();
+            }
+            match idna::domain_to_unicode(label) {
+                (unicode, Ok(())) => unicode,
+                _ => label.to_lowercase(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Gets the names by which a domain or nameserver is found in a label search: its
+/// LDH name, the U-label form of its LDH name, and its unicode name.
+pub(crate) fn search_names(ldh_name: &str, unicode_name: Option<&str>) -> Vec<String> {
+    let mut names = vec![ldh_name.to_owned()];
+    for name in std::iter::once(ldh_name).chain(unicode_name) {
+        if let (unicode, Ok(())) = idna::domain_to_unicode(name) {
+            if !names.contains(&unicode) {
+                names.push(unicode);
+            }
+        }
+    }
+    names
+}
+
+/// Converts a domain label search into an SQL `like` pattern matching the same
+/// names as [SearchLabels::search](crate::storage::mem::label_search::SearchLabels::search). Backslash is the escape character.
+pub(crate) fn like_pattern(search: &str) -> Result<String, RdapServerError> {
+    split_search(search)?;
+    Ok(to_like(search))
+}
+
+/// Converts a domain label search in which more than one label may be partial into an
+/// SQL `like` pattern. As the pattern may match names with more labels than the search,
+/// the names found must also be checked with [matches_labels].
+pub(crate) fn partial_like_pattern(search: &str) -> Result<String, RdapServerError> {
+    split_partial_search(search)?;
+    Ok(to_like(search))
+}
+
+/// Determines if a name found with the `like` pattern of a search matches the search,
+/// which is only in doubt when more than one label of the search is partial.
+pub(crate) fn is_like_match(search: &str, name: &str) -> bool {
+    search.matches('*').count() < 2 || matches_labels(search, &name.to_lowercase())
+}
+
+fn to_like(search: &str) -> String {
+    search
+        .split('*')
+        .map(escape_like)
+        .collect::<Vec<_>>()
+        .join("%")
+}
+
+/// Escapes the SQL `like` wildcards in text using backslash as the escape character.
+pub(crate) fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use super::{is_like_match, partial_like_pattern, search_names, unicode_search};
+
+    #[test]
+    fn GIVEN_a_label_WHEN_unicode_search_THEN_u_label() {
+        // GIVEN
+        let search = "FOO.xn--caf-dma.*";
+
+        // WHEN
+        let actual = unicode_search(search);
+
+        // THEN
+        assert_eq!(actual, "foo.café.*");
+    }
+
+    #[test]
+    fn GIVEN_partial_search_WHEN_like_pattern_THEN_asterisks_are_wildcards() {
+        // GIVEN
+        let search = "fo_*.ex*.c*";
+
+        // WHEN
+        let actual = partial_like_pattern(search).expect("search is invalid");
+
+        // THEN
+        assert_eq!(actual, "fo\\_%.ex%.c%");
+        assert!(is_like_match(search, "fo_o.example.com"));
+        assert!(!is_like_match(search, "fo_o.bar.example.com"));
+    }
+
+    #[test]
+    fn GIVEN_idn_WHEN_search_names_THEN_ldh_and_unicode_names() {
+        // GIVEN
+        let ldh_name = "xn--caf-dma.example";
+
+        // WHEN
+        let actual = search_names(ldh_name, Some("Café.example"));
+
+        // THEN
+        assert_eq!(actual, vec!["xn--caf-dma.example", "café.example"]);
+    }
+}
@@ -0,0 +1,17 @@
+use std::{net::IpAddr, str::FromStr};
+
+use icann_rdap_common::response::nameserver::Nameserver;
+
+pub(crate) mod labels;
+pub(crate) mod strings;
+
+/// Gets the parsable IP addresses of a nameserver, by which it is found in searches by
+/// IP address.
+pub(crate) fn ip_addrs(nameserver: &Nameserver) -> impl Iterator<Item = IpAddr> + '_ {
+    nameserver
+        .ip_addresses
+        .iter()
+        .flat_map(|ip_addresses| ip_addresses.v4.iter().chain(ip_addresses.v6.iter()))
+        .flatten()
+        .filter_map(|addr| IpAddr::from_str(addr).ok())
+}
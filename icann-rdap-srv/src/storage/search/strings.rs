@@ -0,0 +1,46 @@
+use crate::error::RdapServerError;
+
+use super::labels::escape_like;
+
+/// Validates a string search and returns the lowercased text to match and if the
+/// match is on the prefix (the search ended with an asterisk).
+pub(crate) fn split_search(search: &str) -> Result<(String, bool), RdapServerError> {
+    let search = search.to_lowercase();
+    let Some((prefix, rest)) = search.split_once('*') else {
+        return Ok((search, false));
+    };
+    if !rest.is_empty() {
+        return Err(RdapServerError::InvalidArg(
+            "Search string asterisk ('*') must be the last character".to_string(),
+        ));
+    }
+    if prefix.is_empty() {
+        return Err(RdapServerError::InvalidArg(
+            "Search string must have a prefix".to_string(),
+        ));
+    }
+    Ok((prefix.to_string(), true))
+}
+
+/// Determines if text matches a string search in the same manner as
+/// [SearchStrings::search](crate::storage::mem::string_search::SearchStrings::search).
+pub(crate) fn matches(search: &str, text: &str) -> Result<bool, RdapServerError> {
+    let (search, is_prefix) = split_search(search)?;
+    let text = text.to_lowercase();
+    if is_prefix {
+        Ok(text.starts_with(&search))
+    } else {
+        Ok(text == search)
+    }
+}
+
+/// Converts a string search into an SQL `like` pattern matching the same lowercased
+/// strings as [SearchStrings::search](crate::storage::mem::string_search::SearchStrings::search). Backslash is the escape character.
+pub(crate) fn like_pattern(search: &str) -> Result<String, RdapServerError> {
+    let (text, is_prefix) = split_search(search)?;
+    if is_prefix {
+        Ok(format!("{}%", escape_like(&text)))
+    } else {
+        Ok(escape_like(&text))
+    }
+}
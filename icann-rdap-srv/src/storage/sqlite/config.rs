@@ -0,0 +1,9 @@
+use buildstructor::Builder;
+
+use crate::storage::CommonConfig;
+
+#[derive(Debug, Builder, Clone)]
+pub struct SqliteConfig {
+    pub db_file: String,
+    pub common_config: CommonConfig,
+}
@@ -0,0 +1,476 @@
+use async_trait::async_trait;
+use icann_rdap_common::response::{
+    autnum::Autnum, domain::Domain, entity::Entity, help::Help, nameserver::Nameserver,
+    network::Network, RdapResponse,
+};
+use ipnet::IpNet;
+use sqlx::{query, types::Json, SqlitePool};
+
+use crate::{
+    error::RdapServerError,
+    storage::{
+        data::{range_cidrs, AutnumId, DomainId, EntityId, NameserverId, NetworkId},
+        search::{ip_addrs, labels::search_names},
+        TxHandle,
+    },
+};
+
+use super::ops::addr_key;
+
+pub struct SqliteTx<'a> {
+    db_tx: sqlx::Transaction<'a, sqlx::Sqlite>,
+}
+
+impl<'a> SqliteTx<'a> {
+    pub async fn new(pool: &SqlitePool) -> Result<Self, RdapServerError> {
+        let db_tx = pool.begin().await?;
+        Ok(Self { db_tx })
+    }
+
+    pub async fn new_truncate(pool: &SqlitePool) -> Result<Self, RdapServerError> {
+        let mut db_tx = pool.begin().await?;
+        for table in [
+            "domain",
+            "entity",
+            "nameserver",
+            "autnum",
+            "network",
+            "srv_help",
+            "nameserver_ip",
+            "nameserver_name",
+            "domain_nameserver",
+            "domain_ns_ip",
+            "domain_name",
+        ] {
+            query(&format!("delete from {table}"))
+                .execute(&mut *db_tx)
+                .await?;
+        }
+        Ok(Self { db_tx })
+    }
+
+    async fn upsert_domain(
+        &mut self,
+        ldh_name: &str,
+        unicode_name: Option<&str>,
+        rdap: &RdapResponse,
+    ) -> Result<(), RdapServerError> {
+        query(
+            "insert into domain (ldh_name, unicode_name, rdap) values (?1, ?2, ?3) \
+             on conflict (ldh_name) do update \
+             set unicode_name = excluded.unicode_name, rdap = excluded.rdap",
+        )
+        .bind(ldh_name)
+        .bind(unicode_name)
+        .bind(Json(rdap))
+        .execute(&mut *self.db_tx)
+        .await?;
+        self.index_domain(ldh_name, rdap).await
+    }
+
+    /// Deletes the rows indexing the names and addresses of a domain and the names and addresses of its nameservers.
+    async fn unindex_domain(&mut self, ldh_name: &str) -> Result<(), RdapServerError> {
+        for table in ["domain_name", "domain_nameserver", "domain_ns_ip"] {
+            query(&format!("delete from {table} where ldh_name = ?1"))
+                .bind(ldh_name)
+                .execute(&mut *self.db_tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the rows indexing the names of a domain and the names and addresses
+    /// of its nameservers.
+    async fn index_domain(
+        &mut self,
+        ldh_name: &str,
+        rdap: &RdapResponse,
+    ) -> Result<(), RdapServerError> {
+        self.unindex_domain(ldh_name).await?;
+        let RdapResponse::Domain(domain) = rdap else {
+            return Ok(());
+        };
+        self.index_names("domain_name", ldh_name, domain.unicode_name.as_deref())
+            .await?;
+        for nameserver in domain.nameservers.iter().flatten() {
+            if let Some(ns_ldh_name) = &nameserver.ldh_name {
+                query(
+                    "insert or ignore into domain_nameserver (ns_ldh_name, ldh_name) \
+                     values (?1, ?2)",
+                )
+                .bind(ns_ldh_name.to_lowercase())
+                .bind(ldh_name)
+                .execute(&mut *self.db_tx)
+                .await?;
+            }
+            for addr in ip_addrs(nameserver) {
+                query(
+                    "insert or ignore into domain_ns_ip (addr, ldh_name) \
+                     values (?1, ?2)",
+                )
+                .bind(addr_key(addr).1)
+                .bind(ldh_name)
+                .execute(&mut *self.db_tx)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn upsert_entity(
+        &mut self,
+        handle: &str,
+        rdap: &RdapResponse,
+    ) -> Result<(), RdapServerError> {
+        let full_name = match rdap {
+            RdapResponse::Entity(entity) => entity
+                .contact()
+                .and_then(|contact| contact.full_name)
+                .map(|full_name| full_name.to_lowercase()),
+            _ => None,
+        };
+        query(
+            "insert into entity (handle, full_name, rdap) values (?1, ?2, ?3) \
+             on conflict (handle) do update \
+             set full_name = excluded.full_name, rdap = excluded.rdap",
+        )
+        .bind(handle)
+        .bind(full_name)
+        .bind(Json(rdap))
+        .execute(&mut *self.db_tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_nameserver(
+        &mut self,
+        ldh_name: &str,
+        rdap: &RdapResponse,
+    ) -> Result<(), RdapServerError> {
+        query(
+            "insert into nameserver (ldh_name, rdap) values (?1, ?2) \
+             on conflict (ldh_name) do update set rdap = excluded.rdap",
+        )
+        .bind(ldh_name)
+        .bind(Json(rdap))
+        .execute(&mut *self.db_tx)
+        .await?;
+        self.index_nameserver(ldh_name, rdap).await
+    }
+
+    /// Deletes the rows indexing the names and addresses of a nameserver.
+    async fn unindex_nameserver(&mut self, ldh_name: &str) -> Result<(), RdapServerError> {
+        for table in ["nameserver_name", "nameserver_ip"] {
+            query(&format!("delete from {table} where ldh_name = ?1"))
+                .bind(ldh_name)
+                .execute(&mut *self.db_tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the rows indexing the names and addresses of a nameserver.
+    async fn index_nameserver(
+        &mut self,
+        ldh_name: &str,
+        rdap: &RdapResponse,
+    ) -> Result<(), RdapServerError> {
+        self.unindex_nameserver(ldh_name).await?;
+        let RdapResponse::Nameserver(nameserver) = rdap else {
+            return Ok(());
+        };
+        self.index_names(
+            "nameserver_name",
+            ldh_name,
+            nameserver.unicode_name.as_deref(),
+        )
+        .await?;
+        for addr in ip_addrs(nameserver) {
+            query(
+                "insert or ignore into nameserver_ip (addr, ldh_name) \
+                 values (?1, ?2)",
+            )
+            .bind(addr_key(addr).1)
+            .bind(ldh_name)
+            .execute(&mut *self.db_tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the lowercased names by which a domain or nameserver is found in a search
+    /// by name into a name index table.
+    async fn index_names(
+        &mut self,
+        table: &str,
+        ldh_name: &str,
+        unicode_name: Option<&str>,
+    ) -> Result<(), RdapServerError> {
+        for name in search_names(ldh_name, unicode_name) {
+            query(&format!(
+                "insert or ignore into {table} (name, ldh_name) values (?1, ?2)"
+            ))
+            .bind(name.to_lowercase())
+            .bind(ldh_name)
+            .execute(&mut *self.db_tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn upsert_autnum(
+        &mut self,
+        start_autnum: u32,
+        end_autnum: u32,
+        rdap: &RdapResponse,
+    ) -> Result<(), RdapServerError> {
+        query(
+            "insert into autnum (start_autnum, end_autnum, rdap) values (?1, ?2, ?3) \
+             on conflict (start_autnum, end_autnum) do update set rdap = excluded.rdap",
+        )
+        .bind(start_autnum as i64)
+        .bind(end_autnum as i64)
+        .bind(Json(rdap))
+        .execute(&mut *self.db_tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_networks(
+        &mut self,
+        cidrs: Vec<IpNet>,
+        rdap: &RdapResponse,
+    ) -> Result<(), RdapServerError> {
+        for cidr in cidrs {
+            let (ip_version, start_addr) = addr_key(cidr.network());
+            let (_, end_addr) = addr_key(cidr.broadcast());
+            query(
+                "insert into network (ip_version, start_addr, end_addr, prefix_len, rdap) \
+                 values (?1, ?2, ?3, ?4, ?5) \
+                 on conflict (ip_version, start_addr, prefix_len) do update set rdap = excluded.rdap",
+            )
+            .bind(ip_version)
+            .bind(start_addr)
+            .bind(end_addr)
+            .bind(cidr.prefix_len())
+            .bind(Json(rdap))
+            .execute(&mut *self.db_tx)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TxHandle for SqliteTx<'_> {
+    async fn add_entity(&mut self, entity: &Entity) -> Result<(), RdapServerError> {
+        let handle = entity
+            .object_common
+            .handle
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("handle".to_string()))?;
+        self.upsert_entity(handle, &RdapResponse::Entity(entity.clone()))
+            .await
+    }
+
+    async fn add_entity_err(
+        &mut self,
+        entity_id: &EntityId,
+        error: &icann_rdap_common::response::error::Error,
+    ) -> Result<(), RdapServerError> {
+        self.upsert_entity(
+            &entity_id.handle,
+            &RdapResponse::ErrorResponse(error.clone()),
+        )
+        .await
+    }
+
+    async fn add_domain(&mut self, domain: &Domain) -> Result<(), RdapServerError> {
+        let ldh_name = domain
+            .ldh_name
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("ldhName".to_string()))?;
+        self.upsert_domain(
+            ldh_name,
+            domain.unicode_name.as_deref(),
+            &RdapResponse::Domain(domain.clone()),
+        )
+        .await
+    }
+
+    async fn add_domain_err(
+        &mut self,
+        domain_id: &DomainId,
+        error: &icann_rdap_common::response::error::Error,
+    ) -> Result<(), RdapServerError> {
+        self.upsert_domain(
+            &domain_id.ldh_name,
+            None,
+            &RdapResponse::ErrorResponse(error.clone()),
+        )
+        .await
+    }
+
+    async fn add_nameserver(&mut self, nameserver: &Nameserver) -> Result<(), RdapServerError> {
+        let ldh_name = nameserver
+            .ldh_name
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("ldhName".to_string()))?;
+        self.upsert_nameserver(ldh_name, &RdapResponse::Nameserver(nameserver.clone()))
+            .await
+    }
+
+    async fn add_nameserver_err(
+        &mut self,
+        nameserver_id: &NameserverId,
+        error: &icann_rdap_common::response::error::Error,
+    ) -> Result<(), RdapServerError> {
+        self.upsert_nameserver(
+            &nameserver_id.ldh_name,
+            &RdapResponse::ErrorResponse(error.clone()),
+        )
+        .await
+    }
+
+    async fn add_autnum(&mut self, autnum: &Autnum) -> Result<(), RdapServerError> {
+        let start_num = autnum
+            .start_autnum
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("startNum".to_string()))?;
+        let end_num = autnum
+            .end_autnum
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("endNum".to_string()))?;
+        self.upsert_autnum(*start_num, *end_num, &RdapResponse::Autnum(autnum.clone()))
+            .await
+    }
+
+    async fn add_autnum_err(
+        &mut self,
+        autnum_id: &AutnumId,
+        error: &icann_rdap_common::response::error::Error,
+    ) -> Result<(), RdapServerError> {
+        self.upsert_autnum(
+            autnum_id.start_autnum,
+            autnum_id.end_autnum,
+            &RdapResponse::ErrorResponse(error.clone()),
+        )
+        .await
+    }
+
+    async fn add_network(&mut self, network: &Network) -> Result<(), RdapServerError> {
+        let start_addr = network
+            .start_address
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("startAddress".to_string()))?;
+        let end_addr = network
+            .end_address
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("endAddress".to_string()))?;
+        network
+            .ip_version
+            .as_ref()
+            .ok_or_else(|| RdapServerError::EmptyIndexData("ipVersion".to_string()))?;
+        let cidrs = range_cidrs(start_addr, end_addr)?;
+        self.upsert_networks(cidrs, &RdapResponse::Network(network.clone()))
+            .await
+    }
+
+    async fn add_network_err(
+        &mut self,
+        network_id: &NetworkId,
+        error: &icann_rdap_common::response::error::Error,
+    ) -> Result<(), RdapServerError> {
+        self.upsert_networks(
+            network_id.cidrs()?,
+            &RdapResponse::ErrorResponse(error.clone()),
+        )
+        .await
+    }
+
+    async fn add_srv_help(
+        &mut self,
+        help: &Help,
+        host: Option<&str>,
+    ) -> Result<(), RdapServerError> {
+        let host = host.unwrap_or("..default");
+        query(
+            "insert into srv_help (host, rdap) values (?1, ?2) \
+             on conflict (host) do update set rdap = excluded.rdap",
+        )
+        .bind(host)
+        .bind(Json(RdapResponse::Help(help.clone())))
+        .execute(&mut *self.db_tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_domain(&mut self, domain_id: &DomainId) -> Result<(), RdapServerError> {
+        query("delete from domain where ldh_name = ?1")
+            .bind(&domain_id.ldh_name)
+            .execute(&mut *self.db_tx)
+            .await?;
+        self.unindex_domain(&domain_id.ldh_name).await
+    }
+
+    async fn remove_entity(&mut self, entity_id: &EntityId) -> Result<(), RdapServerError> {
+        query("delete from entity where handle = ?1")
+            .bind(&entity_id.handle)
+            .execute(&mut *self.db_tx)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_nameserver(
+        &mut self,
+        nameserver_id: &NameserverId,
+    ) -> Result<(), RdapServerError> {
+        query("delete from nameserver where ldh_name = ?1")
+            .bind(&nameserver_id.ldh_name)
+            .execute(&mut *self.db_tx)
+            .await?;
+        self.unindex_nameserver(&nameserver_id.ldh_name).await
+    }
+
+    async fn remove_autnum(&mut self, autnum_id: &AutnumId) -> Result<(), RdapServerError> {
+        query("delete from autnum where start_autnum = ?1 and end_autnum = ?2")
+            .bind(autnum_id.start_autnum as i64)
+            .bind(autnum_id.end_autnum as i64)
+            .execute(&mut *self.db_tx)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_network(&mut self, network_id: &NetworkId) -> Result<(), RdapServerError> {
+        for cidr in network_id.cidrs()? {
+            let (ip_version, start_addr) = addr_key(cidr.network());
+            query(
+                "delete from network where ip_version = ?1 and start_addr = ?2 and prefix_len = ?3",
+            )
+            .bind(ip_version)
+            .bind(start_addr)
+            .bind(cidr.prefix_len())
+            .execute(&mut *self.db_tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn remove_srv_help(&mut self, host: Option<&str>) -> Result<(), RdapServerError> {
+        query("delete from srv_help where host = ?1")
+            .bind(host.unwrap_or("..default"))
+            .execute(&mut *self.db_tx)
+            .await?;
+        Ok(())
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), RdapServerError> {
+        self.db_tx.commit().await?;
+        Ok(())
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), RdapServerError> {
+        self.db_tx.rollback().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    collections::HashSet,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use notify::{
+    event::{AccessKind, AccessMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    time::sleep,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    config::ServiceConfig,
+    error::RdapServerError,
+    status::{LoadKind, ServerStatus},
+    storage::{
+        data::{
+            finish_load, load_data_files, load_file, DataFileKind, DataFiles, FileStamp, RELOAD,
+            UPDATE,
+        },
+        mem::snapshot::MemSnapshot,
+        StoreOps,
+    },
+};
+
+/// How long to wait for more events after the first event of a change, as writing a
+/// file is usually reported as several events.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// How many times a failed change is tried again, as a file may have been read before it
+/// was completely written.
+const CHANGE_RETRIES: u32 = 3;
+
+/// Watches the data directory for changes to files.
+///
+/// Data files that are added, changed, or removed are applied to storage as upserts and
+/// deletes of the objects in them, without reading the other files. Modifying the `update`
+/// or `reload` files loads the entire data directory. The snapshot of the store, if any, is
+/// written after each successful load.
+pub(crate) struct DataWatcher {
+    // events are only sent while the watcher lives
+    _watcher: RecommendedWatcher,
+    events: UnboundedReceiver<notify::Result<Event>>,
+}
+
+impl DataWatcher {
+    /// Starts watching the data directory. Changes are queued until [DataWatcher::run] is called.
+    pub(crate) fn new(data_dir: &str) -> Result<Self, RdapServerError> {
+        let (sender, events) = unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // the receiver is only gone when the server is shutting down
+            let _ = sender.send(event);
+        })?;
+        watcher.watch(Path::new(data_dir), RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Applies changes to the data directory until the watcher stops. Failures are logged and
+    /// recorded in the server status, and watching continues.
+    pub(crate) async fn run(
+        mut self,
+        store: Box<dyn StoreOps>,
+        config: ServiceConfig,
+        status: Arc<ServerStatus>,
+        mut data_files: DataFiles,
+        snapshot: Option<MemSnapshot>,
+    ) {
+        while let Some(event) = self.events.recv().await {
+            let mut changes = Changes::default();
+            changes.collect(event);
+            sleep(DEBOUNCE).await;
+            self.collect_queued(&mut changes);
+
+            let mut retries = 0;
+            let load = loop {
+                let (kind, result) = if changes.rescan || changes.is_trigger(RELOAD) {
+                    info!("Data being reloaded.");
+                    (
+                        LoadKind::Reload,
+                        load_data_files(&config, &*store, true).await,
+                    )
+                } else if changes.is_trigger(UPDATE) {
+                    info!("Data being updated.");
+                    (
+                        LoadKind::Update,
+                        load_data_files(&config, &*store, false).await,
+                    )
+                } else if changes
+                    .paths
+                    .iter()
+                    .any(|path| DataFileKind::of(path).is_some())
+                {
+                    info!("Data files changed.");
+                    (
+                        LoadKind::Change,
+                        load_changes(&*store, &config.data_dir, &data_files, &changes.paths).await,
+                    )
+                } else {
+                    break None;
+                };
+                match result {
+                    // the changes failed as a whole, so they are all tried again along with
+                    // any that happened since
+                    Err(e) if kind == LoadKind::Change && retries < CHANGE_RETRIES => {
+                        retries += 1;
+                        warn!("Data {kind} failed, trying again: {e}");
+                        sleep(DEBOUNCE * retries).await;
+                        self.collect_queued(&mut changes);
+                    }
+                    result => break Some((kind, result)),
+                }
+            };
+            let Some((kind, result)) = load else {
+                continue;
+            };
+            if let Some(loaded) =
+                finish_load(&status, kind, snapshot.as_ref(), &config, result).await
+            {
+                data_files = loaded;
+            }
+        }
+    }
+
+    /// Adds the changes of the events already queued.
+    fn collect_queued(&mut self, changes: &mut Changes) {
+        while let Ok(event) = self.events.try_recv() {
+            changes.collect(event);
+        }
+    }
+}
+
+/// The changes to the data directory reported by a batch of events.
+#[derive(Default)]
+struct Changes {
+    /// Paths of files that may have changed.
+    paths: HashSet<PathBuf>,
+    /// Events may have been lost, so the entire data directory must be read.
+    rescan: bool,
+}
+
+impl Changes {
+    /// Adds the changes of an event.
+    fn collect(&mut self, event: notify::Result<Event>) {
+        match event {
+            Ok(event) => {
+                debug!("data directory event {event:?}");
+                if event.need_rescan() {
+                    warn!("Data directory events were lost, reading all data files.");
+                    self.rescan = true;
+                }
+                let changed = match event.kind {
+                    EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+                    EventKind::Access(_) => false,
+                    _ => true,
+                };
+                if changed {
+                    self.paths.extend(event.paths);
+                }
+            }
+            Err(e) => {
+                error!("Watching data directory failed, reading all data files: {e}");
+                self.rescan = true;
+            }
+        }
+    }
+
+    fn is_trigger(&self, trigger: &str) -> bool {
+        self.paths
+            .iter()
+            .any(|path| path.file_name().is_some_and(|name| name == trigger))
+    }
+}
+
+/// Loads the changed data files in one transaction, and removes the objects no longer in
+/// any file. Files that no longer exist are treated as removed.
+///
+/// The objects previously loaded from a file are removed before the file is loaded again,
+/// so an object whose ID changes in place (such as an autnum range being narrowed) does
+/// not have its new version removed along with the old one. Objects still loaded by an
+/// unchanged file are not removed, and that file is loaded again if the changed files no
+/// longer load them, so that they have its contents as after a full load.
+pub(crate) async fn load_changes(
+    store: &dyn StoreOps,
+    data_dir: &str,
+    data_files: &DataFiles,
+    paths: &HashSet<PathBuf>,
+) -> Result<DataFiles, RdapServerError> {
+    let mut data_files = data_files.clone();
+    let mut tx = store.new_tx().await?;
+    let mut changed = HashSet::new();
+    let mut shared = HashSet::new();
+    for path in paths {
+        let (Some(kind), Some(file_name)) = (DataFileKind::of(path), path.file_name()) else {
+            continue;
+        };
+        changed.insert(PathBuf::from(file_name));
+        let previous = data_files.ids(Path::new(file_name)).to_vec();
+        let removed = data_files.remove(Path::new(file_name));
+        shared.extend(previous.into_iter().filter(|id| !removed.contains(id)));
+        for id in removed {
+            id.remove(&mut tx).await?;
+        }
+        let loaded = match FileStamp::of(path).await {
+            Ok(stamp) => load_file(kind, path, &mut tx).await.map(|ids| (stamp, ids)),
+            Err(e) => Err(e.into()),
+        };
+        match loaded {
+            Ok((stamp, ids)) => {
+                data_files.insert(PathBuf::from(file_name), stamp, ids);
+            }
+            Err(RdapServerError::IO(e)) if e.kind() == ErrorKind::NotFound => {
+                debug!("removed objects of {}", path.to_string_lossy());
+            }
+            Err(e) => return Err(e),
+        };
+    }
+    // objects the changed files load again keep the contents they were loaded with
+    for file_name in &changed {
+        for id in data_files.ids(file_name) {
+            shared.remove(id);
+        }
+    }
+    let owners = data_files
+        .files()
+        .filter(|(file_name, _, ids)| {
+            !changed.contains(*file_name) && ids.iter().any(|id| shared.contains(id))
+        })
+        .map(|(file_name, _, _)| file_name.clone())
+        .collect::<Vec<_>>();
+    for file_name in owners {
+        let path = Path::new(data_dir).join(&file_name);
+        let Some(kind) = DataFileKind::of(&path) else {
+            continue;
+        };
+        debug!("reloading shared objects of {}", path.to_string_lossy());
+        let stamp = FileStamp::of(&path).await?;
+        let ids = load_file(kind, &path, &mut tx).await?;
+        for id in data_files.insert(file_name, stamp, ids) {
+            id.remove(&mut tx).await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(data_files)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use icann_rdap_common::response::{autnum::Autnum, domain::Domain, RdapResponse};
+    use notify::{
+        event::{Flag, ModifyKind},
+        Event, EventKind,
+    };
+    use test_dir::{DirBuilder, TestDir};
+
+    use crate::{
+        config::{ServiceConfig, StorageType},
+        status::ServerStatus,
+        storage::{
+            data::{load_data_files, DataFiles},
+            mem::{ops::Mem, snapshot::MemSnapshot},
+            StoreOps,
+        },
+    };
+
+    use super::{load_changes, Changes, DataWatcher};
+
+    #[test]
+    fn GIVEN_modify_event_WHEN_collect_THEN_path_without_rescan() {
+        // GIVEN
+        let event = Event::new(EventKind::Modify(ModifyKind::Any)).add_path("foo.json".into());
+
+        // WHEN
+        let mut changes = Changes::default();
+        changes.collect(Ok(event));
+
+        // THEN
+        assert!(changes.paths.contains(std::path::Path::new("foo.json")));
+        assert!(!changes.rescan);
+    }
+
+    #[test]
+    fn GIVEN_rescan_event_WHEN_collect_THEN_rescan() {
+        // GIVEN
+        let event = Event::new(EventKind::Other).set_flag(Flag::Rescan);
+
+        // WHEN
+        let mut changes = Changes::default();
+        changes.collect(Ok(event));
+
+        // THEN
+        assert!(changes.rescan);
+    }
+
+    #[test]
+    fn GIVEN_watch_error_WHEN_collect_THEN_rescan() {
+        // GIVEN
+        let error = notify::Error::generic("queue overflow");
+
+        // WHEN
+        let mut changes = Changes::default();
+        changes.collect(Err(error));
+
+        // THEN
+        assert!(changes.rescan);
+    }
+
+    #[tokio::test]
+    async fn GIVEN_watched_data_dir_with_snapshot_WHEN_file_added_THEN_snapshot_written() {
+        // GIVEN
+        let data_dir = TestDir::temp();
+        let snapshot_dir = TestDir::temp();
+        let mem = Mem::default();
+        let config = ServiceConfig::non_server()
+            .data_dir(data_dir.root().to_string_lossy())
+            .storage_type(StorageType::Memory(mem.config.clone()))
+            .build()
+            .expect("building service config");
+        let snapshot = MemSnapshot::new(snapshot_dir.path("rdap.snapshot"), mem.clone());
+        let watcher = DataWatcher::new(&config.data_dir).expect("watching data directory");
+        tokio::spawn(watcher.run(
+            Box::new(mem),
+            config,
+            Arc::new(ServerStatus::default()),
+            DataFiles::default(),
+            Some(snapshot),
+        ));
+
+        // WHEN
+        let domain = Domain::basic().ldh_name("foo.example").build();
+        std::fs::write(
+            data_dir.path("foo.json"),
+            serde_json::to_string(&domain).expect("serializing domain"),
+        )
+        .expect("writing file");
+
+        // THEN
+        for _ in 0..100 {
+            let restored = Mem::default();
+            let data_files = MemSnapshot::new(snapshot_dir.path("rdap.snapshot"), restored.clone())
+                .restore(&data_dir.root().to_string_lossy())
+                .await
+                .expect("restoring snapshot");
+            if data_files.is_some() {
+                let actual = restored
+                    .get_domain_by_ldh("foo.example")
+                    .await
+                    .expect("getting domain");
+                assert!(matches!(actual, RdapResponse::Domain(_)));
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("snapshot not written")
+    }
+
+    #[tokio::test]
+    async fn GIVEN_loaded_autnum_WHEN_range_changed_in_place_THEN_new_range_found() {
+        // GIVEN
+        let data_dir = TestDir::temp();
+        let mem = Mem::default();
+        let config = ServiceConfig::non_server()
+            .data_dir(data_dir.root().to_string_lossy())
+            .storage_type(StorageType::Memory(mem.config.clone()))
+            .build()
+            .expect("building service config");
+        let write_autnum = |autnum: Autnum| {
+            std::fs::write(
+                data_dir.path("autnum.json"),
+                serde_json::to_string(&autnum).expect("serializing autnum"),
+            )
+            .expect("writing file")
+        };
+        write_autnum(Autnum::basic().autnum_range(64496..64511).build());
+        let data_files = load_data_files(&config, &mem, false)
+            .await
+            .expect("loading data files");
+
+        // WHEN
+        write_autnum(Autnum::basic().autnum_range(64496..64500).build());
+        let paths = [data_dir.path("autnum.json")].into_iter().collect();
+        let data_files = load_changes(&mem, &config.data_dir, &data_files, &paths)
+            .await
+            .expect("loading changes");
+
+        // THEN
+        let actual = mem.get_autnum_by_num(64496).await.expect("getting autnum");
+        let RdapResponse::Autnum(autnum) = actual else {
+            panic!("autnum not found")
+        };
+        assert_eq!(autnum.end_autnum, Some(64500));
+        let actual = mem.get_autnum_by_num(64505).await.expect("getting autnum");
+        assert!(matches!(actual, RdapResponse::ErrorResponse(_)));
+        assert_eq!(data_files.files().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn GIVEN_domain_in_two_files_WHEN_one_file_drops_it_THEN_other_file_contents_kept() {
+        // GIVEN
+        let data_dir = TestDir::temp();
+        let mem = Mem::default();
+        let config = ServiceConfig::non_server()
+            .data_dir(data_dir.root().to_string_lossy())
+            .storage_type(StorageType::Memory(mem.config.clone()))
+            .build()
+            .expect("building service config");
+        let write_domain = |file_name: &str, domain: Domain| {
+            std::fs::write(
+                data_dir.path(file_name),
+                serde_json::to_string(&domain).expect("serializing domain"),
+            )
+            .expect("writing file")
+        };
+        let foo = |handle: &str| {
+            Domain::basic()
+                .ldh_name("foo.example")
+                .handle(handle)
+                .build()
+        };
+        write_domain("a.json", foo("A"));
+        write_domain("b.json", foo("B"));
+        // a.json is loaded last, so the domain has its contents
+        let mut data_files = DataFiles::default();
+        for file_name in ["b.json", "a.json"] {
+            let paths = [data_dir.path(file_name)].into_iter().collect();
+            data_files = load_changes(&mem, &config.data_dir, &data_files, &paths)
+                .await
+                .expect("loading file");
+        }
+
+        // WHEN
+        write_domain("a.json", Domain::basic().ldh_name("bar.example").build());
+        let paths = [data_dir.path("a.json")].into_iter().collect();
+        let data_files = load_changes(&mem, &config.data_dir, &data_files, &paths)
+            .await
+            .expect("loading changes");
+
+        // THEN
+        let actual = mem
+            .get_domain_by_ldh("foo.example")
+            .await
+            .expect("getting domain");
+        let RdapResponse::Domain(domain) = actual else {
+            panic!("domain not found")
+        };
+        assert_eq!(domain.object_common.handle.as_deref(), Some("B"));
+        let actual = mem
+            .get_domain_by_ldh("bar.example")
+            .await
+            .expect("getting domain");
+        assert!(matches!(actual, RdapResponse::Domain(_)));
+        assert_eq!(data_files.object_counts().get("domain"), Some(&2));
+    }
+}
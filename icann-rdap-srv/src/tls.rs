@@ -0,0 +1,185 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use axum::{extract::connect_info::IntoMakeServiceWithConnectInfo, Extension, Router};
+use http::uri::Scheme;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use tokio::{
+    net::TcpListener,
+    sync::watch,
+    time::{sleep, timeout},
+};
+use tokio_rustls::{
+    rustls::{crypto::ring::default_provider, ServerConfig},
+    TlsAcceptor,
+};
+use tower::Service;
+use tracing::{debug, error, info, warn};
+
+use crate::{config::TlsConfig, error::RdapServerError, status::ServerStatus};
+
+/// The time a client has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A TLS server configuration that is replaced when the certificate is reloaded.
+type SharedServerConfig = Arc<RwLock<Arc<ServerConfig>>>;
+
+/// Loads the certificate chain and private key into a TLS server configuration.
+pub(crate) fn load_server_config(tls: &TlsConfig) -> Result<Arc<ServerConfig>, RdapServerError> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&tls.cert_file)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(RdapServerError::Config(format!(
+            "no certificates found in {}",
+            tls.cert_file
+        )));
+    }
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(&tls.key_file)?))?
+        .ok_or_else(|| {
+            RdapServerError::Config(format!("no private key found in {}", tls.key_file))
+        })?;
+    let mut config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// Serves HTTPS connections. The certificate and private key are reloaded when the
+/// data directory is reloaded with the `reload` file.
+pub(crate) async fn serve_tls(
+    tcp_listener: TcpListener,
+    app: Router,
+    tls: TlsConfig,
+    status: &ServerStatus,
+) -> Result<(), RdapServerError> {
+    let server_config = Arc::new(RwLock::new(load_server_config(&tls)?));
+    tokio::spawn(reload_tls(
+        tls,
+        server_config.clone(),
+        status.subscribe_reloads(),
+    ));
+    let mut make_service: IntoMakeServiceWithConnectInfo<Router, SocketAddr> = app
+        .layer(Extension(Scheme::HTTPS))
+        .into_make_service_with_connect_info::<SocketAddr>();
+    loop {
+        let (stream, remote_addr) = match tcp_listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                // as with axum::serve, errors of one connection are skipped and others,
+                // such as running out of file descriptors, are waited out.
+                if !is_connection_error(&e) {
+                    error!("accept error: {e}");
+                    sleep(Duration::from_secs(1)).await;
+                }
+                continue;
+            }
+        };
+        let acceptor = TlsAcceptor::from(
+            server_config
+                .read()
+                .expect("TLS server config poisoned")
+                .clone(),
+        );
+        let Ok(tower_service) = make_service.call(remote_addr).await;
+        tokio::spawn(async move {
+            let stream = match timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    debug!("TLS handshake with {remote_addr} failed: {e}");
+                    return;
+                }
+                Err(_) => {
+                    debug!("TLS handshake with {remote_addr} timed out");
+                    return;
+                }
+            };
+            let hyper_service = TowerToHyperService::new(tower_service);
+            if let Err(e) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), hyper_service)
+                .await
+            {
+                debug!("connection with {remote_addr} failed: {e}");
+            }
+        });
+    }
+}
+
+fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    )
+}
+
+/// Reloads the certificate and private key on every reload of the data directory.
+async fn reload_tls(
+    tls: TlsConfig,
+    server_config: SharedServerConfig,
+    mut reloads: watch::Receiver<()>,
+) {
+    while reloads.changed().await.is_ok() {
+        match load_server_config(&tls) {
+            Ok(config) => {
+                *server_config.write().expect("TLS server config poisoned") = config;
+                info!("TLS certificate reloaded.");
+            }
+            Err(e) => warn!("unable to reload TLS certificate: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use crate::{config::TlsConfig, error::RdapServerError};
+
+    use super::load_server_config;
+
+    fn test_tls_file(name: &str) -> String {
+        format!("{}/resources/test-tls/{name}", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    #[test]
+    fn GIVEN_cert_and_key_WHEN_load_server_config_THEN_alpn_includes_h2() {
+        // GIVEN
+        let tls = TlsConfig::builder()
+            .cert_file(test_tls_file("cert.pem"))
+            .key_file(test_tls_file("key.pem"))
+            .build();
+
+        // WHEN
+        let actual = load_server_config(&tls).expect("loading TLS config");
+
+        // THEN
+        assert!(actual.alpn_protocols.contains(&b"h2".to_vec()));
+    }
+
+    #[test]
+    fn GIVEN_cert_as_key_WHEN_load_server_config_THEN_config_error() {
+        // GIVEN
+        let tls = TlsConfig::builder()
+            .cert_file(test_tls_file("cert.pem"))
+            .key_file(test_tls_file("cert.pem"))
+            .build();
+
+        // WHEN
+        let actual = load_server_config(&tls);
+
+        // THEN
+        assert!(matches!(actual, Err(RdapServerError::Config(_))));
+    }
+}
@@ -1,8 +1,9 @@
 #![allow(non_snake_case)]
 
+use icann_rdap_common::response::domain::Domain;
 use test_dir::DirBuilder;
 
-use crate::test_jig::RdapSrvStoreTestJig;
+use crate::test_jig::{tar_archive, RdapSrvStoreTestJig};
 
 #[test]
 fn GIVEN_source_dir_same_as_data_dir_WHEN_invoked_THEN_error() {
@@ -16,3 +17,81 @@ fn GIVEN_source_dir_same_as_data_dir_WHEN_invoked_THEN_error() {
     let assert = test_jig.cmd.assert();
     assert.failure();
 }
+
+fn domain_line(ldh_name: &str) -> String {
+    let domain = Domain::basic().ldh_name(ldh_name).build();
+    format!(
+        "{}\n",
+        serde_json::to_string(&domain).expect("serializing domain")
+    )
+}
+
+#[test]
+fn GIVEN_json_lines_WHEN_invoked_THEN_moved() {
+    // GIVEN
+    let mut test_jig = RdapSrvStoreTestJig::new();
+    let lines = format!(
+        "{}{}",
+        domain_line("foo.example"),
+        domain_line("bar.example")
+    );
+    std::fs::write(test_jig.source_dir.path("example.jsonl"), lines).expect("writing file");
+
+    // WHEN
+    test_jig.cmd.arg(test_jig.source_dir.root());
+
+    // THEN
+    let assert = test_jig.cmd.assert();
+    assert.success();
+    assert!(test_jig.data_dir.path("example.jsonl").exists());
+}
+
+#[test]
+fn GIVEN_json_lines_with_bad_line_WHEN_invoked_THEN_error_and_not_moved() {
+    // GIVEN
+    let mut test_jig = RdapSrvStoreTestJig::new();
+    let lines = format!("{}not json\n", domain_line("foo.example"));
+    std::fs::write(test_jig.source_dir.path("example.jsonl"), lines).expect("writing file");
+
+    // WHEN
+    test_jig.cmd.arg(test_jig.source_dir.root());
+
+    // THEN
+    let assert = test_jig.cmd.assert();
+    assert.failure();
+    assert!(!test_jig.data_dir.path("example.jsonl").exists());
+}
+
+#[test]
+fn GIVEN_archive_with_bad_file_WHEN_invoked_THEN_error_and_not_moved() {
+    // GIVEN
+    let mut test_jig = RdapSrvStoreTestJig::new();
+    let archive = tar_archive(&[
+        ("data/example.jsonl", domain_line("foo.example").as_bytes()),
+        ("data/bar_example.json", b"not json"),
+    ]);
+    std::fs::write(test_jig.source_dir.path("example.tar"), archive).expect("writing file");
+
+    // WHEN
+    test_jig.cmd.arg(test_jig.source_dir.root());
+
+    // THEN
+    let assert = test_jig.cmd.assert();
+    assert.failure();
+    assert!(!test_jig.data_dir.path("example.tar").exists());
+}
+
+#[test]
+fn GIVEN_file_of_unknown_type_WHEN_invoked_THEN_error_and_not_moved() {
+    // GIVEN
+    let mut test_jig = RdapSrvStoreTestJig::new();
+    std::fs::write(test_jig.source_dir.path("example.txt"), "not data").expect("writing file");
+
+    // WHEN
+    test_jig.cmd.arg(test_jig.source_dir.root());
+
+    // THEN
+    let assert = test_jig.cmd.assert();
+    assert.failure();
+    assert!(!test_jig.data_dir.path("example.txt").exists());
+}
@@ -0,0 +1,243 @@
+#![allow(non_snake_case)]
+
+use std::sync::Arc;
+
+use icann_rdap_common::response::{domain::Domain, RdapResponse};
+use icann_rdap_srv::{
+    auth::Authenticator,
+    config::{AuthConfig, ServiceConfig, StorageType},
+    error::RdapServerError,
+    server::AppState,
+    status::ServerStatus,
+    storage::{mem::config::MemConfig, sqlite::config::SqliteConfig, CommonConfig, StoreOps},
+};
+use serde_json::json;
+use test_dir::{DirBuilder, TestDir};
+
+use crate::test_jig::{service_config, wait_for_initial_load, SrvTestJig};
+
+async fn new_admin_srv() -> (SrvTestJig, TestDir) {
+    let temp = TestDir::temp();
+    std::fs::write(
+        temp.path("tokens"),
+        "admin-token:provisioning\nother-token:law-enforcement\n",
+    )
+    .expect("writing tokens");
+    let config = AuthConfig::builder()
+        .tokens_file(temp.path("tokens").to_string_lossy().to_string())
+        .build();
+    let auth = Authenticator::new(&config).expect("creating authenticator");
+    let test_srv = SrvTestJig::new_admin(auth, "provisioning").await;
+    (test_srv, temp)
+}
+
+/// Creates the configuration of a service with the admin API over the storage, with a
+/// domain in its data directory.
+fn admin_service_config(temp: &TestDir, storage_type: StorageType) -> ServiceConfig {
+    std::fs::write(temp.path("tokens"), "admin-token:provisioning\n").expect("writing tokens");
+    std::fs::create_dir(temp.path("data")).expect("creating data directory");
+    std::fs::write(
+        temp.path("data").join("foo.json"),
+        serde_json::to_string(&Domain::basic().ldh_name("foo.example").build())
+            .expect("serializing domain"),
+    )
+    .expect("writing file");
+    ServiceConfig {
+        storage_type,
+        auth: Some(
+            AuthConfig::builder()
+                .tokens_file(temp.path("tokens").to_string_lossy().to_string())
+                .build(),
+        ),
+        admin_role: Some("provisioning".to_string()),
+        ..service_config(&temp.path("data"))
+    }
+}
+
+fn objects_url(test_srv: &SrvTestJig) -> String {
+    test_srv.rdap_base.replace("/rdap", "/admin/objects")
+}
+
+async fn domain_status(test_srv: &SrvTestJig, ldh_name: &str) -> u16 {
+    reqwest::get(format!("{}/domain/{ldh_name}", test_srv.rdap_base))
+        .await
+        .expect("querying domain")
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn GIVEN_admin_token_WHEN_put_domain_THEN_domain_served() {
+    // GIVEN
+    let (test_srv, _temp) = new_admin_srv().await;
+    let domain = Domain::basic().ldh_name("foo.example").build();
+
+    // WHEN
+    let actual = reqwest::Client::new()
+        .put(objects_url(&test_srv))
+        .bearer_auth("admin-token")
+        .json(&domain)
+        .send()
+        .await
+        .expect("putting domain");
+
+    // THEN
+    assert_eq!(actual.status(), 204);
+    assert_eq!(domain_status(&test_srv, "foo.example").await, 200);
+}
+
+#[tokio::test]
+async fn GIVEN_domains_put_by_template_WHEN_delete_domain_THEN_only_that_domain_removed() {
+    // GIVEN
+    let (test_srv, _temp) = new_admin_srv().await;
+    let client = reqwest::Client::new();
+    let template = json!({
+        "domain": { "object": { "objectClassName": "domain", "ldhName": "example" } },
+        "ids": [ { "ldhName": "foo.example" }, { "ldhName": "bar.example" } ]
+    });
+    client
+        .put(objects_url(&test_srv))
+        .bearer_auth("admin-token")
+        .json(&template)
+        .send()
+        .await
+        .expect("putting template");
+
+    // WHEN
+    let actual = client
+        .delete(objects_url(&test_srv))
+        .bearer_auth("admin-token")
+        .json(&Domain::basic().ldh_name("foo.example").build())
+        .send()
+        .await
+        .expect("deleting domain");
+
+    // THEN
+    assert_eq!(actual.status(), 204);
+    assert_eq!(domain_status(&test_srv, "foo.example").await, 404);
+    assert_eq!(domain_status(&test_srv, "bar.example").await, 200);
+}
+
+#[tokio::test]
+async fn GIVEN_clients_without_admin_role_WHEN_put_domain_THEN_rejected() {
+    // GIVEN
+    let (test_srv, _temp) = new_admin_srv().await;
+    let client = reqwest::Client::new();
+    let domain = Domain::basic().ldh_name("foo.example").build();
+
+    // WHEN
+    let anonymous = client
+        .put(objects_url(&test_srv))
+        .json(&domain)
+        .send()
+        .await
+        .expect("putting domain");
+    let other = client
+        .put(objects_url(&test_srv))
+        .bearer_auth("other-token")
+        .json(&domain)
+        .send()
+        .await
+        .expect("putting domain");
+
+    // THEN
+    assert_eq!(anonymous.status(), 401);
+    assert!(anonymous.headers().contains_key("www-authenticate"));
+    assert_eq!(other.status(), 403);
+    assert_eq!(domain_status(&test_srv, "foo.example").await, 404);
+}
+
+#[tokio::test]
+async fn GIVEN_domain_without_ldh_name_WHEN_put_THEN_status_code_400() {
+    // GIVEN
+    let (test_srv, _temp) = new_admin_srv().await;
+
+    // WHEN
+    let actual = reqwest::Client::new()
+        .put(objects_url(&test_srv))
+        .bearer_auth("admin-token")
+        .json(&json!({ "objectClassName": "domain" }))
+        .send()
+        .await
+        .expect("putting domain");
+
+    // THEN
+    assert_eq!(actual.status(), 400);
+}
+
+#[tokio::test]
+async fn GIVEN_admin_role_and_data_file_WHEN_new_mem_THEN_data_file_loaded() {
+    // GIVEN
+    let temp = TestDir::temp();
+    let mem_config = MemConfig::builder()
+        .common_config(CommonConfig::default())
+        .build();
+    let service_config = admin_service_config(&temp, StorageType::Memory(mem_config.clone()));
+    let status = Arc::new(ServerStatus::default());
+
+    // WHEN
+    let app_state = AppState::new_mem(mem_config, &service_config, status.clone())
+        .await
+        .expect("creating app state");
+
+    // THEN
+    assert!(wait_for_initial_load(&status).await.ready);
+    assert_eq!(app_state.admin_role.as_deref(), Some("provisioning"));
+    let actual = app_state
+        .storage
+        .get_domain_by_ldh("foo.example")
+        .await
+        .expect("getting domain");
+    assert!(matches!(actual, RdapResponse::Domain(_)));
+}
+
+#[tokio::test]
+async fn GIVEN_admin_role_and_auto_reload_WHEN_new_sqlite_THEN_config_error() {
+    // GIVEN
+    let temp = TestDir::temp();
+    let sqlite_config = SqliteConfig::builder()
+        .db_file(temp.path("rdap.sqlite").to_string_lossy())
+        .common_config(CommonConfig::default())
+        .build();
+    let service_config = ServiceConfig {
+        auto_reload: true,
+        ..admin_service_config(&temp, StorageType::Sqlite(sqlite_config.clone()))
+    };
+
+    // WHEN
+    let actual = AppState::new_sqlite(
+        sqlite_config,
+        &service_config,
+        Arc::new(ServerStatus::default()),
+    )
+    .await;
+
+    // THEN
+    assert!(matches!(actual, Err(RdapServerError::Config(_))));
+}
+
+#[tokio::test]
+async fn GIVEN_admin_role_and_data_file_WHEN_new_sqlite_THEN_data_file_not_loaded() {
+    // GIVEN
+    let temp = TestDir::temp();
+    let sqlite_config = SqliteConfig::builder()
+        .db_file(temp.path("rdap.sqlite").to_string_lossy())
+        .common_config(CommonConfig::default())
+        .build();
+    let service_config = admin_service_config(&temp, StorageType::Sqlite(sqlite_config.clone()));
+    let status = Arc::new(ServerStatus::default());
+
+    // WHEN
+    let app_state = AppState::new_sqlite(sqlite_config, &service_config, status.clone())
+        .await
+        .expect("creating app state");
+
+    // THEN
+    assert!(wait_for_initial_load(&status).await.ready);
+    let actual = app_state
+        .storage
+        .get_domain_by_ldh("foo.example")
+        .await
+        .expect("getting domain");
+    assert!(matches!(actual, RdapResponse::ErrorResponse(_)));
+}
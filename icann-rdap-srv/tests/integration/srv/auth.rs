@@ -0,0 +1,204 @@
+#![allow(non_snake_case)]
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use icann_rdap_common::response::domain::Domain;
+use icann_rdap_srv::{
+    auth::Authenticator, config::AuthConfig, redact::RedactionPolicy, storage::StoreOps,
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::{json, Value};
+use test_dir::{DirBuilder, TestDir};
+
+use crate::test_jig::SrvTestJig;
+
+const JWT_SECRET: &[u8] = b"not-so-secret";
+
+const POLICY: &str = r#"
+{
+  "rules": [
+    {
+      "name": { "type": "Registry Domain ID" },
+      "objectClassName": "domain",
+      "path": "$.handle",
+      "exemptRoles": ["law-enforcement"]
+    }
+  ]
+}
+"#;
+
+fn new_authenticator(temp: &TestDir) -> Authenticator {
+    let hash = bcrypt::hash("secret", 4).expect("hashing password");
+    std::fs::write(temp.path("tokens"), "le-token:law-enforcement\n").expect("writing tokens");
+    std::fs::write(
+        temp.path("htpasswd"),
+        format!("alice:{hash}:law-enforcement\n"),
+    )
+    .expect("writing htpasswd");
+    let jwks = json!({
+        "keys": [{
+            "kty": "oct",
+            "kid": "test",
+            "alg": "HS256",
+            "k": URL_SAFE_NO_PAD.encode(JWT_SECRET)
+        }]
+    });
+    std::fs::write(temp.path("jwks.json"), jwks.to_string()).expect("writing jwks");
+    let path = |name: &str| temp.path(name).to_string_lossy().to_string();
+    let config = AuthConfig::builder()
+        .tokens_file(path("tokens"))
+        .htpasswd_file(path("htpasswd"))
+        .jwks_file(path("jwks.json"))
+        .build();
+    Authenticator::new(&config).expect("creating authenticator")
+}
+
+async fn new_access_srv() -> (SrvTestJig, TestDir) {
+    let temp = TestDir::temp();
+    let auth = new_authenticator(&temp);
+    let redaction = RedactionPolicy::from_json(POLICY).expect("parsing policy");
+    let test_srv = SrvTestJig::new_access(auth, redaction).await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(
+        &Domain::basic()
+            .ldh_name("foo.example")
+            .handle("foo-1")
+            .build(),
+    )
+    .await
+    .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+    (test_srv, temp)
+}
+
+async fn get_domain(test_srv: &SrvTestJig, authorization: Option<String>) -> reqwest::Response {
+    let mut request =
+        reqwest::Client::new().get(format!("{}/domain/foo.example", test_srv.rdap_base));
+    if let Some(authorization) = authorization {
+        request = request.header("authorization", authorization);
+    }
+    request.send().await.expect("querying domain")
+}
+
+#[tokio::test]
+async fn GIVEN_anonymous_client_WHEN_query_domain_THEN_handle_redacted() {
+    // GIVEN
+    let (test_srv, _temp) = new_access_srv().await;
+
+    // WHEN
+    let actual = get_domain(&test_srv, None).await;
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+    let domain: Value = actual.json().await.expect("parsing domain");
+    assert!(domain.get("handle").is_none());
+    assert_eq!(domain["redacted"][0]["name"]["type"], "Registry Domain ID");
+    assert_eq!(domain["redacted"][0]["prePath"], "$.handle");
+    assert!(domain["rdapConformance"]
+        .as_array()
+        .expect("no rdapConformance")
+        .contains(&json!("redacted")));
+}
+
+#[tokio::test]
+async fn GIVEN_domain_too_large_to_redact_WHEN_query_domain_THEN_error() {
+    // GIVEN
+    let (test_srv, _temp) = new_access_srv().await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(
+        &Domain::basic()
+            .ldh_name("foo.example")
+            .handle("foo-1")
+            .port_43("x".repeat(5_000_000))
+            .build(),
+    )
+    .await
+    .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    let actual = get_domain(&test_srv, None).await;
+
+    // THEN
+    assert_eq!(actual.status(), 500);
+    let body = actual.text().await.expect("reading body");
+    assert!(!body.contains("foo-1"));
+}
+
+#[tokio::test]
+async fn GIVEN_exempt_bearer_token_WHEN_query_domain_THEN_handle_not_redacted() {
+    // GIVEN
+    let (test_srv, _temp) = new_access_srv().await;
+
+    // WHEN
+    let actual = get_domain(&test_srv, Some("Bearer le-token".to_string())).await;
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+    let domain: Value = actual.json().await.expect("parsing domain");
+    assert_eq!(domain["handle"], "foo-1");
+    assert!(domain.get("redacted").is_none());
+}
+
+#[tokio::test]
+async fn GIVEN_exempt_basic_user_WHEN_query_domain_THEN_handle_not_redacted() {
+    // GIVEN
+    let (test_srv, _temp) = new_access_srv().await;
+    let credentials = STANDARD.encode("alice:secret");
+
+    // WHEN
+    let actual = get_domain(&test_srv, Some(format!("Basic {credentials}"))).await;
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+    let domain: Value = actual.json().await.expect("parsing domain");
+    assert_eq!(domain["handle"], "foo-1");
+}
+
+#[tokio::test]
+async fn GIVEN_jwt_with_exempt_role_WHEN_query_domain_THEN_handle_not_redacted() {
+    // GIVEN
+    let (test_srv, _temp) = new_access_srv().await;
+    let header = Header {
+        kid: Some("test".to_string()),
+        ..Default::default()
+    };
+    let claims = json!({
+        "sub": "officer",
+        "exp": chrono::Utc::now().timestamp() + 600,
+        "roles": ["law-enforcement"]
+    });
+    let token =
+        encode(&header, &claims, &EncodingKey::from_secret(JWT_SECRET)).expect("encoding JWT");
+
+    // WHEN
+    let actual = get_domain(&test_srv, Some(format!("Bearer {token}"))).await;
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+    let domain: Value = actual.json().await.expect("parsing domain");
+    assert_eq!(domain["handle"], "foo-1");
+}
+
+#[tokio::test]
+async fn GIVEN_bad_credentials_WHEN_query_domain_THEN_status_code_401_with_challenge() {
+    // GIVEN
+    let (test_srv, _temp) = new_access_srv().await;
+    let credentials = STANDARD.encode("alice:wrong");
+
+    // WHEN
+    let actual = get_domain(&test_srv, Some(format!("Basic {credentials}"))).await;
+
+    // THEN
+    assert_eq!(actual.status(), 401);
+    let challenge = actual
+        .headers()
+        .get("www-authenticate")
+        .expect("no www-authenticate header")
+        .to_str()
+        .expect("header is not a string");
+    assert!(challenge.contains("Basic"));
+    assert!(challenge.contains("Bearer"));
+}
@@ -0,0 +1,176 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_common::response::{
+    domain::Domain,
+    help::Help,
+    types::{Event, Notice, NoticeOrRemark},
+};
+use icann_rdap_srv::{config::CacheConfig, storage::StoreOps};
+
+use crate::test_jig::SrvTestJig;
+
+async fn new_cache_srv() -> SrvTestJig {
+    let cache = CacheConfig::builder()
+        .max_age(60)
+        .class_max_age("domain".to_string(), 3600)
+        .build();
+    let test_srv = SrvTestJig::new_cache(cache).await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(
+        &Domain::basic()
+            .ldh_name("foo.example")
+            .event(
+                Event::builder()
+                    .event_action("last changed")
+                    .event_date("2024-01-01T00:00:00Z")
+                    .build(),
+            )
+            .build(),
+    )
+    .await
+    .expect("add domain in tx");
+    let srvhelp = Help::basic()
+        .notice(Notice(
+            NoticeOrRemark::builder()
+                .description_entry("foo".to_string())
+                .build(),
+        ))
+        .build()
+        .expect("building help");
+    tx.add_srv_help(&srvhelp, None)
+        .await
+        .expect("adding srv help");
+    tx.commit().await.expect("tx commit");
+    test_srv
+}
+
+fn domain_url(test_srv: &SrvTestJig) -> String {
+    format!("{}/domain/foo.example", test_srv.rdap_base)
+}
+
+#[tokio::test]
+async fn GIVEN_cache_config_WHEN_query_domain_THEN_caching_headers_for_object_class() {
+    // GIVEN
+    let test_srv = new_cache_srv().await;
+
+    // WHEN
+    let actual = reqwest::get(domain_url(&test_srv))
+        .await
+        .expect("querying domain");
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+    let headers = actual.headers();
+    assert_eq!(
+        headers.get("cache-control").expect("no cache-control"),
+        "public, max-age=3600"
+    );
+    assert!(headers.contains_key("expires"));
+    assert!(headers.contains_key("etag"));
+    assert_eq!(
+        headers.get("last-modified").expect("no last-modified"),
+        "Mon, 01 Jan 2024 00:00:00 GMT"
+    );
+}
+
+#[tokio::test]
+async fn GIVEN_cache_config_WHEN_query_help_THEN_default_max_age() {
+    // GIVEN
+    let test_srv = new_cache_srv().await;
+
+    // WHEN
+    let actual = reqwest::get(format!("{}/help", test_srv.rdap_base))
+        .await
+        .expect("querying help");
+
+    // THEN
+    assert_eq!(
+        actual
+            .headers()
+            .get("cache-control")
+            .expect("no cache-control"),
+        "public, max-age=60"
+    );
+}
+
+#[tokio::test]
+async fn GIVEN_etag_WHEN_query_with_if_none_match_THEN_status_code_304() {
+    // GIVEN
+    let test_srv = new_cache_srv().await;
+    let first = reqwest::get(domain_url(&test_srv))
+        .await
+        .expect("querying domain");
+    let etag = first.headers().get("etag").expect("no etag").clone();
+
+    // WHEN
+    let actual = reqwest::Client::new()
+        .get(domain_url(&test_srv))
+        .header("if-none-match", etag.clone())
+        .send()
+        .await
+        .expect("querying domain");
+
+    // THEN
+    assert_eq!(actual.status(), 304);
+    assert_eq!(actual.headers().get("etag"), Some(&etag));
+    assert!(actual.headers().contains_key("cache-control"));
+    assert!(actual.bytes().await.expect("reading body").is_empty());
+}
+
+#[tokio::test]
+async fn GIVEN_other_etag_WHEN_query_with_if_none_match_THEN_status_code_200() {
+    // GIVEN
+    let test_srv = new_cache_srv().await;
+
+    // WHEN
+    let actual = reqwest::Client::new()
+        .get(domain_url(&test_srv))
+        .header("if-none-match", "\"other\"")
+        .send()
+        .await
+        .expect("querying domain");
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+}
+
+#[tokio::test]
+async fn GIVEN_last_changed_WHEN_query_with_if_modified_since_THEN_compared_to_last_changed() {
+    // GIVEN
+    let test_srv = new_cache_srv().await;
+    let client = reqwest::Client::new();
+
+    // WHEN
+    let not_modified = client
+        .get(domain_url(&test_srv))
+        .header("if-modified-since", "Tue, 02 Jan 2024 00:00:00 GMT")
+        .send()
+        .await
+        .expect("querying domain");
+    let modified = client
+        .get(domain_url(&test_srv))
+        .header("if-modified-since", "Sun, 31 Dec 2023 00:00:00 GMT")
+        .send()
+        .await
+        .expect("querying domain");
+
+    // THEN
+    assert_eq!(not_modified.status(), 304);
+    assert_eq!(modified.status(), 200);
+}
+
+#[tokio::test]
+async fn GIVEN_cache_config_WHEN_domain_not_found_THEN_no_caching_headers() {
+    // GIVEN
+    let test_srv = new_cache_srv().await;
+
+    // WHEN
+    let actual = reqwest::get(format!("{}/domain/bar.example", test_srv.rdap_base))
+        .await
+        .expect("querying domain");
+
+    // THEN
+    assert_eq!(actual.status(), 404);
+    assert!(!actual.headers().contains_key("cache-control"));
+    assert!(!actual.headers().contains_key("etag"));
+}
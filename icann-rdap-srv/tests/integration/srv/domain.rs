@@ -4,9 +4,8 @@ use icann_rdap_client::{
     http::create_client,
     http::ClientConfig,
     rdap::{rdap_request, QueryType},
-    RdapClientError,
 };
-use icann_rdap_common::response::domain::Domain;
+use icann_rdap_common::response::{domain::Domain, RdapResponse};
 use icann_rdap_srv::storage::{CommonConfig, StoreOps};
 
 use crate::test_jig::SrvTestJig;
@@ -89,18 +88,15 @@ async fn GIVEN_server_with_domain_and_search_disabled_WHEN_query_domain_THEN_sta
     let response = rdap_request(&test_srv.rdap_base, &query, &client).await;
 
     // THEN
-    let RdapClientError::Client(error) = response.expect_err("not an error response") else {
-        panic!("the error was not an HTTP error")
-    };
-    assert_eq!(error.status().expect("no status code"), 501);
+    let response = response.expect("querying server");
+    assert_eq!(response.http_data.status_code, 501);
+    assert!(matches!(response.rdap, RdapResponse::ErrorResponse(_)));
 }
 
 #[tokio::test]
 async fn GIVEN_server_with_domain_and_search_enabled_WHEN_query_domain_THEN_status_code_200() {
     // GIVEN
-    let common_config = CommonConfig::builder()
-        .domain_search_by_name_enable(true)
-        .build();
+    let common_config = CommonConfig::default();
     let test_srv = SrvTestJig::new_common_config(common_config).await;
     let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
     tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
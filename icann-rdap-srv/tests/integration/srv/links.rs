@@ -0,0 +1,203 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_common::response::{domain::Domain, types::Link};
+use icann_rdap_srv::config::{LinkConfig, ServiceConfig};
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use test_dir::{DirBuilder, TestDir};
+
+use crate::test_jig::{service_config, wait_for_initial_load, SrvTestJig};
+
+/// The host of the requests to the test server, which listens on all addresses.
+const HOST: &str = "[::]";
+
+fn link(rel: &str, href: &str) -> Link {
+    Link::builder()
+        .href(href)
+        .value("https://old.example/rdap/domain/foo.example")
+        .rel(rel)
+        .build()
+}
+
+async fn new_links_srv(links: LinkConfig) -> (TestDir, SrvTestJig) {
+    let temp = TestDir::temp();
+    let domain = Domain::basic()
+        .ldh_name("foo.example")
+        .links(vec![
+            link("self", "https://old.example/rdap/domain/foo.example"),
+            link("up", "https://old.example/rdap/domain/example"),
+            link(
+                "related",
+                "https://registrar.example/rdap/domain/foo.example",
+            ),
+        ])
+        .build();
+    std::fs::write(
+        temp.path("foo.example.json"),
+        serde_json::to_string(&domain).expect("serializing domain"),
+    )
+    .expect("writing file");
+    std::fs::write(
+        temp.path("foo2.example.json"),
+        serde_json::to_string(&Domain::basic().ldh_name("foo2.example").build())
+            .expect("serializing domain"),
+    )
+    .expect("writing file");
+    let service_config = ServiceConfig {
+        search_page_size: Some(1),
+        links: Some(links),
+        ..service_config(temp.root())
+    };
+    let test_srv = SrvTestJig::new_service_config(service_config).await;
+    wait_for_initial_load(&test_srv.status).await;
+    (temp, test_srv)
+}
+
+async fn get_links(test_srv: &SrvTestJig) -> Vec<Value> {
+    get_links_with_headers(test_srv, &[]).await.1
+}
+
+async fn get_links_with_headers(
+    test_srv: &SrvTestJig,
+    headers: &[(&str, &str)],
+) -> (HeaderMap, Vec<Value>) {
+    let mut request =
+        reqwest::Client::new().get(format!("{}/domain/foo.example", test_srv.rdap_base));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = request.send().await.expect("querying domain");
+    let headers = response.headers().clone();
+    let domain: Value = response.json().await.expect("parsing domain");
+    (
+        headers,
+        domain["links"].as_array().expect("no links").clone(),
+    )
+}
+
+#[tokio::test]
+async fn GIVEN_link_rewriting_WHEN_query_THEN_links_rewritten_to_request_host() {
+    // GIVEN
+    let (_temp, test_srv) = new_links_srv(LinkConfig::builder().host(HOST).build()).await;
+
+    // WHEN
+    let (headers, links) = get_links_with_headers(&test_srv, &[]).await;
+
+    // THEN
+    let vary = headers
+        .get_all("vary")
+        .iter()
+        .map(|value| value.to_str().expect("vary is not text"))
+        .collect::<Vec<&str>>()
+        .join(",");
+    assert!(vary.contains("host"));
+    let base = &test_srv.rdap_base;
+    assert_eq!(links[0]["href"], format!("{base}/domain/foo.example"));
+    assert_eq!(links[0]["value"], format!("{base}/domain/foo.example"));
+    assert_eq!(links[1]["href"], format!("{base}/domain/example"));
+    assert_eq!(
+        links[2]["href"],
+        "https://registrar.example/rdap/domain/foo.example"
+    );
+}
+
+#[tokio::test]
+async fn GIVEN_public_base_url_WHEN_query_THEN_links_rewritten_to_base_url() {
+    // GIVEN
+    let links = LinkConfig::builder()
+        .base_url("https://rdap.example.com/rdap/")
+        .build();
+    let (_temp, test_srv) = new_links_srv(links).await;
+
+    // WHEN
+    let links = get_links(&test_srv).await;
+
+    // THEN
+    assert_eq!(
+        links[0]["href"],
+        "https://rdap.example.com/rdap/domain/foo.example"
+    );
+    assert_eq!(
+        links[1]["href"],
+        "https://rdap.example.com/rdap/domain/example"
+    );
+}
+
+#[tokio::test]
+async fn GIVEN_link_rewriting_WHEN_query_with_other_host_THEN_links_not_rewritten() {
+    // GIVEN
+    let (_temp, test_srv) = new_links_srv(LinkConfig::builder().host(HOST).build()).await;
+
+    // WHEN
+    let (_, links) = get_links_with_headers(&test_srv, &[("host", "attacker.example")]).await;
+
+    // THEN
+    assert_eq!(
+        links[0]["href"],
+        "https://old.example/rdap/domain/foo.example"
+    );
+}
+
+#[tokio::test]
+async fn GIVEN_link_rewriting_WHEN_query_with_forwarded_proto_THEN_links_rewritten_to_scheme() {
+    // GIVEN
+    let (_temp, test_srv) = new_links_srv(LinkConfig::builder().host(HOST).build()).await;
+
+    // WHEN
+    let (_, links) = get_links_with_headers(&test_srv, &[("x-forwarded-proto", "https")]).await;
+
+    // THEN
+    let base = test_srv.rdap_base.replace("http://", "https://");
+    assert_eq!(links[0]["href"], format!("{base}/domain/foo.example"));
+}
+
+async fn get_next_link(test_srv: &SrvTestJig, headers: &[(&str, &str)]) -> String {
+    let mut request =
+        reqwest::Client::new().get(format!("{}/domains?name=foo*", test_srv.rdap_base));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let results: Value = request
+        .send()
+        .await
+        .expect("searching domains")
+        .json()
+        .await
+        .expect("parsing search results");
+    results["paging_metadata"]["links"]
+        .as_array()
+        .expect("no paging links")
+        .iter()
+        .find(|link| link["rel"] == "next")
+        .and_then(|link| link["href"].as_str())
+        .expect("no next link")
+        .to_string()
+}
+
+#[tokio::test]
+async fn GIVEN_public_base_url_WHEN_search_THEN_next_link_on_base_url() {
+    // GIVEN
+    let links = LinkConfig::builder()
+        .base_url("https://rdap.example.com/rdap/")
+        .build();
+    let (_temp, test_srv) = new_links_srv(links).await;
+
+    // WHEN
+    let actual = get_next_link(&test_srv, &[]).await;
+
+    // THEN
+    assert!(actual.starts_with("https://rdap.example.com/rdap/domains?"));
+}
+
+#[tokio::test]
+async fn GIVEN_link_rewriting_WHEN_search_with_forwarded_proto_THEN_next_link_has_scheme() {
+    // GIVEN
+    let (_temp, test_srv) = new_links_srv(LinkConfig::builder().host(HOST).build()).await;
+
+    // WHEN
+    let actual = get_next_link(&test_srv, &[("x-forwarded-proto", "https")]).await;
+
+    // THEN
+    let base = test_srv.rdap_base.replace("http://", "https://");
+    assert!(actual.starts_with(&format!("{base}/domains?")));
+}
@@ -0,0 +1,73 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_common::response::domain::Domain;
+use icann_rdap_srv::{config::RateLimitConfig, storage::StoreOps};
+
+use crate::test_jig::SrvTestJig;
+
+#[tokio::test]
+async fn GIVEN_server_WHEN_domain_queries_THEN_metrics_count_requests_by_status() {
+    // GIVEN
+    let test_srv = SrvTestJig::new().await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+        .await
+        .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+    reqwest::get(format!("{}/domain/foo.example", test_srv.rdap_base))
+        .await
+        .expect("querying domain");
+    reqwest::get(format!("{}/domain/bar.example", test_srv.rdap_base))
+        .await
+        .expect("querying missing domain");
+
+    // WHEN
+    let metrics_url = test_srv.rdap_base.replace("/rdap", "/metrics");
+    let actual = reqwest::get(metrics_url).await.expect("querying metrics");
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+    let body = actual.text().await.expect("metrics body");
+    assert!(body.contains(r#"rdap_srv_requests_total{class="domain",status="200"}"#));
+    assert!(body.contains(r#"rdap_srv_requests_total{class="domain",status="404"}"#));
+    assert!(body.contains(r#"rdap_srv_request_duration_seconds_bucket{class="domain""#));
+}
+
+#[tokio::test]
+async fn GIVEN_rate_limited_server_WHEN_limit_exceeded_THEN_metrics_count_429() {
+    // GIVEN
+    let rate_limit = RateLimitConfig::builder().rate(1.0).burst(1).build();
+    let test_srv = SrvTestJig::new_rate_limit(rate_limit).await;
+    let url = format!("{}/domain/foo.example", test_srv.rdap_base);
+    reqwest::get(&url).await.expect("first request");
+    let limited = reqwest::get(&url).await.expect("second request");
+    assert_eq!(limited.status(), 429);
+
+    // WHEN
+    let metrics_url = test_srv.rdap_base.replace("/rdap", "/metrics");
+    let actual = reqwest::get(metrics_url).await.expect("querying metrics");
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+    let body = actual.text().await.expect("metrics body");
+    assert!(body.contains(r#"rdap_srv_requests_total{class="domain",status="429"}"#));
+}
+
+#[tokio::test]
+async fn GIVEN_server_WHEN_unknown_path_queried_THEN_metrics_count_unmatched() {
+    // GIVEN
+    let test_srv = SrvTestJig::new().await;
+    let unknown_url = test_srv.rdap_base.replace("/rdap", "/no/such/path");
+    let unknown = reqwest::get(unknown_url)
+        .await
+        .expect("querying unknown path");
+    assert_eq!(unknown.status(), 404);
+
+    // WHEN
+    let metrics_url = test_srv.rdap_base.replace("/rdap", "/metrics");
+    let actual = reqwest::get(metrics_url).await.expect("querying metrics");
+
+    // THEN
+    let body = actual.text().await.expect("metrics body");
+    assert!(body.contains(r#"rdap_srv_requests_total{class="unmatched",status="404"}"#));
+}
@@ -1,4 +1,15 @@
+mod admin;
+mod auth;
 mod bootstrap;
+mod caching;
 mod domain;
+mod links;
+mod metrics;
+mod paging;
+mod rate_limit;
 mod redirect;
+mod search;
 mod srvhelp;
+mod status;
+mod tls;
+mod virtual_host;
@@ -0,0 +1,191 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_client::{
+    http::create_client,
+    http::ClientConfig,
+    rdap::{rdap_all_pages_request, rdap_request, QueryType},
+};
+use icann_rdap_common::response::{
+    domain::Domain, search::DomainSearchResults, types::ExtensionId, RdapResponse,
+};
+use icann_rdap_srv::storage::{CommonConfig, StoreOps};
+use rstest::rstest;
+
+use crate::test_jig::SrvTestJig;
+
+async fn new_paging_srv() -> SrvTestJig {
+    new_search_srv(Some(2)).await
+}
+
+async fn new_search_srv(search_page_size: Option<usize>) -> SrvTestJig {
+    let common_config = CommonConfig::default();
+    let test_srv = SrvTestJig::new_search_page_size(common_config, search_page_size).await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    for name in ["foo2.example", "foo1.example", "foo3.example"] {
+        tx.add_domain(&Domain::basic().ldh_name(name).handle(name).build())
+            .await
+            .expect("add domain in tx");
+    }
+    tx.commit().await.expect("tx commit");
+    test_srv
+}
+
+async fn search_domains(test_srv: &SrvTestJig, query: &str) -> DomainSearchResults {
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::Url(format!("{}/domains?{query}", test_srv.rdap_base));
+    let response = rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("quering server");
+    let RdapResponse::DomainSearchResults(results) = response.rdap else {
+        panic!("response was not domain search results")
+    };
+    results
+}
+
+fn ldh_names(results: &DomainSearchResults) -> Vec<&str> {
+    results
+        .results
+        .iter()
+        .map(|d| d.ldh_name.as_deref().expect("no ldh name"))
+        .collect()
+}
+
+#[tokio::test]
+async fn GIVEN_more_results_than_page_size_WHEN_search_THEN_first_page_with_next_link_returned() {
+    // GIVEN
+    let test_srv = new_paging_srv().await;
+
+    // WHEN
+    let results = search_domains(&test_srv, "name=foo*&count=true").await;
+
+    // THEN
+    assert_eq!(ldh_names(&results), vec!["foo1.example", "foo2.example"]);
+    let paging = results
+        .paging_metadata
+        .as_ref()
+        .expect("no paging metadata");
+    assert_eq!(paging.total_count, Some(3));
+    assert_eq!(paging.page_number, Some(1));
+    assert!(paging.next_link().is_some());
+    let conformance = results.common.rdap_conformance.expect("no conformance");
+    assert!(conformance.contains(&ExtensionId::Paging.to_extension()));
+    assert!(conformance.contains(&ExtensionId::Sorting.to_extension()));
+    assert!(conformance.contains(&ExtensionId::Subsetting.to_extension()));
+}
+
+#[tokio::test]
+async fn GIVEN_no_page_size_WHEN_search_THEN_all_results_returned() {
+    // GIVEN
+    let test_srv = new_search_srv(None).await;
+
+    // WHEN
+    let results = search_domains(&test_srv, "name=foo*").await;
+
+    // THEN
+    assert_eq!(
+        ldh_names(&results),
+        vec!["foo1.example", "foo2.example", "foo3.example"]
+    );
+    let paging = results
+        .paging_metadata
+        .as_ref()
+        .expect("no paging metadata");
+    assert!(paging.next_link().is_none());
+}
+
+#[tokio::test]
+async fn GIVEN_more_results_than_page_size_WHEN_fetch_all_pages_THEN_all_results_returned() {
+    // GIVEN
+    let test_srv = new_paging_srv().await;
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::DomainNameSearch("foo*".to_string());
+    let response = rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("quering server");
+    let response = rdap_all_pages_request(response, &client)
+        .await
+        .expect("fetching pages");
+
+    // THEN
+    let RdapResponse::DomainSearchResults(results) = response.rdap else {
+        panic!("response was not domain search results")
+    };
+    assert_eq!(
+        ldh_names(&results),
+        vec!["foo1.example", "foo2.example", "foo3.example"]
+    );
+    let paging = results
+        .paging_metadata
+        .as_ref()
+        .expect("no paging metadata");
+    assert_eq!(paging.page_number, Some(2));
+    assert!(paging.next_link().is_none());
+}
+
+#[tokio::test]
+async fn GIVEN_results_WHEN_search_sorted_descending_THEN_results_in_descending_order() {
+    // GIVEN
+    let test_srv = new_paging_srv().await;
+
+    // WHEN
+    let results = search_domains(&test_srv, "name=foo*&sort=name:d").await;
+
+    // THEN
+    assert_eq!(ldh_names(&results), vec!["foo3.example", "foo2.example"]);
+    let sorting = results.sorting_metadata.expect("no sorting metadata");
+    assert_eq!(sorting.current_sort.as_deref(), Some("name:d"));
+}
+
+#[tokio::test]
+async fn GIVEN_results_WHEN_search_with_id_field_set_THEN_only_ids_returned() {
+    // GIVEN
+    let test_srv = new_paging_srv().await;
+
+    // WHEN
+    let results = search_domains(&test_srv, "name=foo*&fieldSet=id").await;
+
+    // THEN
+    assert_eq!(ldh_names(&results), vec!["foo1.example", "foo2.example"]);
+    assert!(results
+        .results
+        .iter()
+        .all(|d| d.object_common.handle.is_none()));
+    let subsetting = results.subsetting_metadata.expect("no subsetting metadata");
+    assert_eq!(subsetting.current_field_set, "id");
+}
+
+#[rstest]
+#[case("name=foo*&sort=fn")]
+#[case("name=foo*&sort=name:x")]
+#[case("name=foo*&cursor=foo")]
+#[case("name=foo*&fieldSet=foo")]
+#[case("name=foo*&count=foo")]
+#[tokio::test]
+async fn GIVEN_server_WHEN_search_with_invalid_controls_THEN_status_code_400(#[case] query: &str) {
+    // GIVEN
+    let test_srv = new_paging_srv().await;
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::Url(format!("{}/domains?{query}", test_srv.rdap_base));
+    let response = rdap_request(&test_srv.rdap_base, &query, &client).await;
+
+    // THEN
+    let response = response.expect("querying server");
+    assert_eq!(response.http_data.status_code, 400);
+    assert!(matches!(response.rdap, RdapResponse::ErrorResponse(_)));
+}
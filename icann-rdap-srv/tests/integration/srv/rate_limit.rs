@@ -0,0 +1,93 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_client::{
+    http::{create_client, ClientConfig},
+    rdap::{rdap_request, QueryType},
+};
+use icann_rdap_common::response::{domain::Domain, RdapResponse};
+use icann_rdap_srv::{config::RateLimitConfig, storage::StoreOps};
+
+use crate::test_jig::SrvTestJig;
+
+async fn new_rate_limit_srv() -> SrvTestJig {
+    let rate_limit = RateLimitConfig::builder().rate(1.0).burst(1).build();
+    let test_srv = SrvTestJig::new_rate_limit(rate_limit).await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+        .await
+        .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+    test_srv
+}
+
+#[tokio::test]
+async fn GIVEN_rate_limited_server_WHEN_limit_exceeded_THEN_status_code_429_with_retry_after() {
+    // GIVEN
+    let test_srv = new_rate_limit_srv().await;
+    let url = format!("{}/domain/foo.example", test_srv.rdap_base);
+    let first = reqwest::get(&url).await.expect("first request");
+    assert_eq!(first.status(), 200);
+
+    // WHEN
+    let actual = reqwest::get(&url).await.expect("second request");
+
+    // THEN
+    assert_eq!(actual.status(), 429);
+    let retry_after = actual
+        .headers()
+        .get("retry-after")
+        .expect("no retry-after header");
+    assert_eq!(retry_after, "1");
+    let body: serde_json::Value = actual.json().await.expect("body is not JSON");
+    assert_eq!(body["errorCode"], 429);
+}
+
+#[tokio::test]
+async fn GIVEN_rate_limited_server_WHEN_client_does_not_retry_THEN_status_code_429() {
+    // GIVEN
+    let test_srv = new_rate_limit_srv().await;
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .max_retries(0)
+        .max_retry_secs(0)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::domain("foo.example").expect("invalid domain name");
+    rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("first query");
+
+    // WHEN
+    let response = rdap_request(&test_srv.rdap_base, &query, &client).await;
+
+    // THEN
+    let response = response.expect("querying server");
+    assert_eq!(response.http_data.status_code, 429);
+    assert!(matches!(response.rdap, RdapResponse::ErrorResponse(_)));
+}
+
+#[tokio::test]
+async fn GIVEN_rate_limited_server_WHEN_client_retries_THEN_status_code_200() {
+    // GIVEN
+    let test_srv = new_rate_limit_srv().await;
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .max_retries(1)
+        .def_retry_secs(1)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::domain("foo.example").expect("invalid domain name");
+    rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("first query");
+
+    // WHEN
+    let response = rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("quering server");
+
+    // THEN
+    assert_eq!(response.http_data.status_code, 200);
+}
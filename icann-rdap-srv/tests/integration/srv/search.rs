@@ -0,0 +1,274 @@
+#![allow(non_snake_case)]
+
+use std::net::IpAddr;
+
+use icann_rdap_client::{
+    http::create_client,
+    http::ClientConfig,
+    rdap::{rdap_request, QueryType},
+};
+use icann_rdap_common::{
+    contact::Contact,
+    response::{domain::Domain, entity::Entity, nameserver::Nameserver, RdapResponse},
+};
+use icann_rdap_srv::storage::{CommonConfig, StoreOps};
+use rstest::rstest;
+
+use crate::test_jig::SrvTestJig;
+
+async fn new_search_srv() -> SrvTestJig {
+    let test_srv = SrvTestJig::new().await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_nameserver(
+        &Nameserver::basic()
+            .ldh_name("ns.foo.example")
+            .address("10.0.0.1")
+            .build()
+            .unwrap(),
+    )
+    .await
+    .expect("add nameserver in tx");
+    tx.add_entity(
+        &Entity::basic()
+            .handle("foo-1")
+            .contact(Contact::builder().full_name("Bob Smurd").build())
+            .build(),
+    )
+    .await
+    .expect("add entity in tx");
+    tx.commit().await.expect("tx commit");
+    test_srv
+}
+
+#[rstest]
+#[case(QueryType::NameserverNameSearch("ns.foo.*".to_string()))]
+#[case(QueryType::NameserverIpSearch(IpAddr::from([10, 0, 0, 1])))]
+#[tokio::test]
+async fn GIVEN_server_with_nameserver_WHEN_search_nameservers_THEN_nameserver_returned(
+    #[case] query: QueryType,
+) {
+    // GIVEN
+    let test_srv = new_search_srv().await;
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let response = rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("quering server");
+
+    // THEN
+    assert_eq!(response.http_data.status_code, 200);
+    let RdapResponse::NameserverSearchResults(results) = response.rdap else {
+        panic!("response was not nameserver search results")
+    };
+    assert_eq!(results.results.len(), 1);
+}
+
+#[rstest]
+#[case(QueryType::EntityNameSearch("bob*".to_string()))]
+#[case(QueryType::EntityHandleSearch("foo-1".to_string()))]
+#[tokio::test]
+async fn GIVEN_server_with_entity_WHEN_search_entities_THEN_entity_returned(
+    #[case] query: QueryType,
+) {
+    // GIVEN
+    let test_srv = new_search_srv().await;
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let response = rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("quering server");
+
+    // THEN
+    assert_eq!(response.http_data.status_code, 200);
+    let RdapResponse::EntitySearchResults(results) = response.rdap else {
+        panic!("response was not entity search results")
+    };
+    assert_eq!(results.results.len(), 1);
+}
+
+#[tokio::test]
+async fn GIVEN_server_with_entity_and_search_disabled_WHEN_search_entities_THEN_status_code_501() {
+    // GIVEN
+    let common_config = CommonConfig::builder()
+        .entity_search_by_fn_enable(false)
+        .build();
+    let test_srv = SrvTestJig::new_common_config(common_config).await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_entity(
+        &Entity::basic()
+            .handle("foo-1")
+            .contact(Contact::builder().full_name("Bob Smurd").build())
+            .build(),
+    )
+    .await
+    .expect("add entity in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::EntityNameSearch("bob*".to_string());
+    let response = rdap_request(&test_srv.rdap_base, &query, &client).await;
+
+    // THEN
+    let response = response.expect("querying server");
+    assert_eq!(response.http_data.status_code, 501);
+    assert!(matches!(response.rdap, RdapResponse::ErrorResponse(_)));
+}
+
+#[tokio::test]
+async fn GIVEN_server_WHEN_search_nameservers_with_bad_ip_THEN_status_code_400() {
+    // GIVEN
+    let test_srv = new_search_srv().await;
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::Url(format!("{}/nameservers?ip=foo", test_srv.rdap_base));
+    let response = rdap_request(&test_srv.rdap_base, &query, &client).await;
+
+    // THEN
+    let response = response.expect("querying server");
+    assert_eq!(response.http_data.status_code, 400);
+    assert!(matches!(response.rdap, RdapResponse::ErrorResponse(_)));
+}
+
+#[rstest]
+#[case(QueryType::DomainNsNameSearch("ns1.example.net".to_string()))]
+#[case(QueryType::DomainNsIpSearch(IpAddr::from([10, 0, 0, 1])))]
+#[tokio::test]
+async fn GIVEN_server_with_domain_WHEN_search_domains_by_nameserver_THEN_domain_returned(
+    #[case] query: QueryType,
+) {
+    // GIVEN
+    let test_srv = SrvTestJig::new().await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(
+        &Domain::basic()
+            .ldh_name("foo.example")
+            .nameservers(vec![Nameserver::basic()
+                .ldh_name("ns1.example.net")
+                .address("10.0.0.1")
+                .build()
+                .unwrap()])
+            .build(),
+    )
+    .await
+    .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let response = rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("quering server");
+
+    // THEN
+    assert_eq!(response.http_data.status_code, 200);
+    let RdapResponse::DomainSearchResults(results) = response.rdap else {
+        panic!("response was not domain search results")
+    };
+    assert_eq!(results.results.len(), 1);
+}
+
+#[rstest]
+#[case(QueryType::domain_reverse_search("fn=Bob*&role=registrant").expect("invalid conditions"))]
+#[case(QueryType::ns_reverse_search("handle=foo-1").expect("invalid conditions"))]
+#[case(QueryType::entity_reverse_search("fn=Bob Smurd").expect("invalid conditions"))]
+#[tokio::test]
+async fn GIVEN_server_with_related_entity_WHEN_reverse_search_THEN_object_returned(
+    #[case] query: QueryType,
+) {
+    // GIVEN
+    let test_srv = SrvTestJig::new().await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    let entity = Entity::basic()
+        .handle("foo-1")
+        .role("registrant")
+        .contact(Contact::builder().full_name("Bob Smurd").build())
+        .build();
+    tx.add_domain(
+        &Domain::basic()
+            .ldh_name("foo.example")
+            .entity(entity.clone())
+            .build(),
+    )
+    .await
+    .expect("add domain in tx");
+    tx.add_nameserver(
+        &Nameserver::basic()
+            .ldh_name("ns.foo.example")
+            .entity(entity.clone())
+            .build()
+            .unwrap(),
+    )
+    .await
+    .expect("add nameserver in tx");
+    tx.add_entity(&Entity::basic().handle("bar-1").entity(entity).build())
+        .await
+        .expect("add entity in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let response = rdap_request(&test_srv.rdap_base, &query, &client)
+        .await
+        .expect("quering server");
+
+    // THEN
+    assert_eq!(response.http_data.status_code, 200);
+    let results = match response.rdap {
+        RdapResponse::DomainSearchResults(r) => r.results.len(),
+        RdapResponse::NameserverSearchResults(r) => r.results.len(),
+        RdapResponse::EntitySearchResults(r) => r.results.len(),
+        _ => panic!("response was not search results"),
+    };
+    assert_eq!(results, 1);
+}
+
+#[tokio::test]
+async fn GIVEN_server_WHEN_reverse_search_with_only_role_THEN_status_code_400() {
+    // GIVEN
+    let test_srv = new_search_srv().await;
+
+    // WHEN
+    let client_config = ClientConfig::builder()
+        .https_only(false)
+        .follow_redirects(false)
+        .build();
+    let client = create_client(&client_config).expect("creating client");
+    let query = QueryType::Url(format!(
+        "{}/domains/reverse_search/entity?role=registrant",
+        test_srv.rdap_base
+    ));
+    let response = rdap_request(&test_srv.rdap_base, &query, &client).await;
+
+    // THEN
+    let response = response.expect("querying server");
+    assert_eq!(response.http_data.status_code, 400);
+    assert!(matches!(response.rdap, RdapResponse::ErrorResponse(_)));
+}
@@ -0,0 +1,174 @@
+#![allow(non_snake_case)]
+
+use std::time::Duration;
+
+use icann_rdap_common::response::domain::Domain;
+use icann_rdap_srv::{config::ServiceConfig, status::LoadKind};
+use test_dir::{DirBuilder, TestDir};
+
+use crate::test_jig::{service_config, wait_for_initial_load, wait_for_status, SrvTestJig};
+
+fn status_url(test_srv: &SrvTestJig, path: &str) -> String {
+    test_srv.rdap_base.replace("/rdap", path)
+}
+
+/// Polls a domain until it is served with the status code.
+async fn wait_for_domain(test_srv: &SrvTestJig, ldh_name: &str, status_code: u16) {
+    for _ in 0..100 {
+        let response = reqwest::get(format!("{}/domain/{ldh_name}", test_srv.rdap_base))
+            .await
+            .expect("querying domain");
+        if response.status().as_u16() == status_code {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("timed out waiting for {ldh_name} with status code {status_code}")
+}
+
+fn new_service_config(temp: &TestDir) -> ServiceConfig {
+    ServiceConfig {
+        auto_reload: true,
+        ..service_config(temp.root())
+    }
+}
+
+fn write_domain(temp: &TestDir, ldh_name: &str) {
+    let domain = Domain::basic().ldh_name(ldh_name).build();
+    std::fs::write(
+        temp.path(&format!("{ldh_name}.json")),
+        serde_json::to_string(&domain).expect("serializing domain"),
+    )
+    .expect("writing file");
+}
+
+#[tokio::test]
+async fn GIVEN_server_WHEN_health_THEN_status_code_200() {
+    // GIVEN
+    let test_srv = SrvTestJig::new().await;
+
+    // WHEN
+    let actual = reqwest::get(status_url(&test_srv, "/health"))
+        .await
+        .expect("querying health");
+
+    // THEN
+    assert_eq!(actual.status(), 200);
+}
+
+#[tokio::test]
+async fn GIVEN_data_dir_WHEN_initial_load_finished_THEN_ready() {
+    // GIVEN
+    let temp = TestDir::temp();
+    write_domain(&temp, "foo.example");
+
+    // WHEN
+    let test_srv = SrvTestJig::new_service_config(new_service_config(&temp)).await;
+
+    // THEN
+    wait_for_initial_load(&test_srv.status).await;
+    let (status_code, report) = test_srv.ready_report().await;
+    assert_eq!(status_code, 200);
+    assert!(report.ready);
+    let last_load = report.last_load.expect("no last load");
+    assert_eq!(last_load.kind, LoadKind::Initial);
+    assert!(last_load.status.success);
+}
+
+#[tokio::test]
+async fn GIVEN_data_dir_with_bad_file_WHEN_initial_load_finished_THEN_not_ready() {
+    // GIVEN
+    let temp = TestDir::temp();
+    std::fs::write(temp.path("bad.json"), "not json").expect("writing file");
+
+    // WHEN
+    let test_srv = SrvTestJig::new_service_config(new_service_config(&temp)).await;
+
+    // THEN
+    wait_for_initial_load(&test_srv.status).await;
+    let (status_code, report) = test_srv.ready_report().await;
+    assert_eq!(status_code, 503);
+    assert!(!report.ready);
+    let last_load = report.last_load.expect("no last load");
+    assert!(!last_load.status.success);
+    assert!(last_load.status.error.is_some());
+}
+
+#[tokio::test]
+async fn GIVEN_failed_reload_WHEN_update_triggered_THEN_reload_loop_still_loads_data() {
+    // GIVEN
+    let temp = TestDir::temp();
+    write_domain(&temp, "foo.example");
+    let test_srv = SrvTestJig::new_service_config(new_service_config(&temp)).await;
+    wait_for_initial_load(&test_srv.status).await;
+    std::fs::write(temp.path("bad.json"), "not json").expect("writing file");
+    std::fs::write(temp.path("reload"), "").expect("writing reload trigger");
+    wait_for_status(&test_srv.status, |report| {
+        report
+            .last_load
+            .as_ref()
+            .is_some_and(|load| load.kind == LoadKind::Reload)
+    })
+    .await;
+    let (status_code, report) = test_srv.ready_report().await;
+    assert_eq!(status_code, 200);
+    assert!(!report.last_load.expect("no last load").status.success);
+
+    std::fs::remove_file(temp.path("bad.json")).expect("removing file");
+    write_domain(&temp, "bar.example");
+    // the changes are loaded before the trigger is written so that the trigger is the last load
+    wait_for_domain(&test_srv, "bar.example", 200).await;
+    wait_for_status(&test_srv.status, |report| {
+        report
+            .last_load
+            .as_ref()
+            .is_some_and(|load| load.kind == LoadKind::Change && load.status.success)
+    })
+    .await;
+
+    // WHEN
+    std::fs::write(temp.path("update"), "").expect("writing update trigger");
+
+    // THEN
+    wait_for_status(&test_srv.status, |report| {
+        report
+            .last_load
+            .as_ref()
+            .is_some_and(|load| load.kind == LoadKind::Update)
+    })
+    .await;
+    let (status_code, report) = test_srv.ready_report().await;
+    assert_eq!(status_code, 200);
+    assert!(report.last_load.expect("no last load").status.success);
+    wait_for_domain(&test_srv, "bar.example", 200).await;
+}
+
+#[tokio::test]
+async fn GIVEN_watched_data_dir_WHEN_file_added_and_removed_THEN_domain_added_and_removed() {
+    // GIVEN
+    let temp = TestDir::temp();
+    write_domain(&temp, "foo.example");
+    let test_srv = SrvTestJig::new_service_config(new_service_config(&temp)).await;
+    wait_for_initial_load(&test_srv.status).await;
+
+    // WHEN
+    write_domain(&temp, "bar.example");
+
+    // THEN
+    wait_for_domain(&test_srv, "bar.example", 200).await;
+    let report = wait_for_status(&test_srv.status, |report| {
+        report
+            .last_load
+            .as_ref()
+            .is_some_and(|load| load.kind == LoadKind::Change)
+    })
+    .await;
+    assert!(report.last_load.expect("no last load").status.success);
+
+    // WHEN
+    std::fs::remove_file(temp.path("bar.example.json")).expect("removing file");
+
+    // THEN
+    wait_for_domain(&test_srv, "bar.example", 404).await;
+    wait_for_domain(&test_srv, "foo.example", 200).await;
+}
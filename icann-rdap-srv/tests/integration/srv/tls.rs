@@ -0,0 +1,42 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_common::response::domain::Domain;
+use icann_rdap_srv::{config::TlsConfig, storage::StoreOps};
+
+use crate::test_jig::SrvTestJig;
+
+fn test_tls_file(name: &str) -> String {
+    format!("{}/resources/test-tls/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+#[tokio::test]
+async fn GIVEN_tls_server_WHEN_query_with_test_ca_THEN_status_code_200() {
+    // GIVEN
+    let tls = TlsConfig::builder()
+        .cert_file(test_tls_file("cert.pem"))
+        .key_file(test_tls_file("key.pem"))
+        .build();
+    let test_srv = SrvTestJig::new_tls(tls).await;
+    let mut tx = test_srv.mem.new_tx().await.expect("new transaction");
+    tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+        .await
+        .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+
+    // WHEN
+    let ca = std::fs::read(test_tls_file("ca.pem")).expect("reading test CA");
+    let client = reqwest::Client::builder()
+        .https_only(true)
+        .add_root_certificate(reqwest::Certificate::from_pem(&ca).expect("parsing test CA"))
+        .build()
+        .expect("creating client");
+    let actual = client
+        .get(format!("{}/domain/foo.example", test_srv.rdap_base))
+        .send()
+        .await
+        .expect("quering server");
+
+    // THEN
+    assert!(test_srv.rdap_base.starts_with("https://"));
+    assert_eq!(actual.status(), 200);
+}
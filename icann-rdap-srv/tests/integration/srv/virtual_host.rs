@@ -0,0 +1,158 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_common::response::{domain::Domain, types::Link, RdapResponse};
+use icann_rdap_srv::{
+    config::{RateLimitConfig, ServiceConfig, VirtualHostConfig},
+    status::LoadKind,
+};
+use test_dir::{DirBuilder, FileType, TestDir};
+
+use crate::test_jig::{service_config, wait_for_initial_load, SrvTestJig};
+
+const HOST: &str = "rdap.example.net";
+
+fn write_domain(temp: &TestDir, dir: &str, ldh_name: &str) {
+    let domain = Domain::basic()
+        .ldh_name(ldh_name)
+        .link(
+            Link::builder()
+                .href(format!("https://old.example/rdap/domain/{ldh_name}"))
+                .value(format!("https://old.example/rdap/domain/{ldh_name}"))
+                .rel("self")
+                .build(),
+        )
+        .build();
+    std::fs::write(
+        temp.path(dir).join(format!("{ldh_name}.json")),
+        serde_json::to_string(&domain).expect("serializing domain"),
+    )
+    .expect("writing file");
+}
+
+async fn new_virtual_host_srv(temp: &TestDir) -> SrvTestJig {
+    new_rate_limited_virtual_host_srv(temp, None).await
+}
+
+async fn new_rate_limited_virtual_host_srv(
+    temp: &TestDir,
+    rate_limit: Option<RateLimitConfig>,
+) -> SrvTestJig {
+    let service_config = ServiceConfig {
+        rate_limit,
+        virtual_hosts: vec![VirtualHostConfig::builder()
+            .host(HOST)
+            .base_url("https://rdap.example.net/rdap")
+            .build()],
+        ..service_config(temp.root())
+    };
+    let test_srv = SrvTestJig::new_service_config(service_config).await;
+    wait_for_initial_load(&test_srv.status).await;
+    test_srv
+}
+
+async fn get_domain(test_srv: &SrvTestJig, host: Option<&str>, ldh_name: &str) -> (u16, String) {
+    let mut request =
+        reqwest::Client::new().get(format!("{}/domain/{ldh_name}", test_srv.rdap_base));
+    if let Some(host) = host {
+        request = request.header("host", host);
+    }
+    let response = request.send().await.expect("querying domain");
+    let status_code = response.status().as_u16();
+    (status_code, response.text().await.expect("reading body"))
+}
+
+#[tokio::test]
+async fn GIVEN_virtual_host_WHEN_query_with_host_THEN_objects_of_host_returned() {
+    // GIVEN
+    let temp = TestDir::temp().create(HOST, FileType::Dir);
+    write_domain(&temp, "", "foo.example");
+    write_domain(&temp, HOST, "bar.example");
+    let test_srv = new_virtual_host_srv(&temp).await;
+
+    // WHEN
+    let (bar_status_code, bar) = get_domain(&test_srv, Some(HOST), "bar.example").await;
+    let (foo_status_code, _) = get_domain(&test_srv, Some(HOST), "foo.example").await;
+
+    // THEN
+    assert_eq!(bar_status_code, 200);
+    assert_eq!(foo_status_code, 404);
+    let RdapResponse::Domain(bar) = serde_json::from_str(&bar).expect("parsing domain") else {
+        panic!("not a domain")
+    };
+    let self_link = bar
+        .object_common
+        .links
+        .expect("no links")
+        .into_iter()
+        .next()
+        .expect("no self link");
+    assert_eq!(
+        self_link.href.as_deref(),
+        Some("https://rdap.example.net/rdap/domain/bar.example")
+    );
+}
+
+#[tokio::test]
+async fn GIVEN_virtual_host_WHEN_query_with_other_host_THEN_objects_of_data_dir_returned() {
+    // GIVEN
+    let temp = TestDir::temp().create(HOST, FileType::Dir);
+    write_domain(&temp, "", "foo.example");
+    write_domain(&temp, HOST, "bar.example");
+    let test_srv = new_virtual_host_srv(&temp).await;
+
+    // WHEN
+    let (foo_status_code, foo) = get_domain(&test_srv, None, "foo.example").await;
+    let (bar_status_code, _) = get_domain(&test_srv, None, "bar.example").await;
+
+    // THEN
+    assert_eq!(foo_status_code, 200);
+    assert_eq!(bar_status_code, 404);
+    assert!(foo.contains("https://old.example/rdap/domain/foo.example"));
+}
+
+#[tokio::test]
+async fn GIVEN_virtual_host_WHEN_initial_load_finished_THEN_load_of_each_host_reported() {
+    // GIVEN
+    let temp = TestDir::temp().create(HOST, FileType::Dir);
+    write_domain(&temp, "", "foo.example");
+    std::fs::write(temp.path(HOST).join("bad.json"), "not json").expect("writing file");
+    let test_srv = new_virtual_host_srv(&temp).await;
+
+    // WHEN
+    let (_, actual) = test_srv.ready_report().await;
+
+    // THEN
+    assert!(!actual.ready);
+    let last_load = actual.last_load.expect("no last load");
+    assert_eq!(last_load.kind, LoadKind::Initial);
+    assert!(last_load.status.success);
+    let host = actual.hosts.get(HOST).expect("no status of host");
+    assert!(host.initial_load_finished);
+    assert!(!host.ready);
+    assert!(
+        !host
+            .last_load
+            .as_ref()
+            .expect("no last load")
+            .status
+            .success
+    );
+}
+
+#[tokio::test]
+async fn GIVEN_rate_limited_virtual_host_WHEN_limit_used_by_other_host_THEN_status_code_429() {
+    // GIVEN
+    let temp = TestDir::temp().create(HOST, FileType::Dir);
+    write_domain(&temp, "", "foo.example");
+    write_domain(&temp, HOST, "bar.example");
+    let rate_limit = RateLimitConfig::builder().rate(1.0).burst(1).build();
+    let test_srv = new_rate_limited_virtual_host_srv(&temp, Some(rate_limit)).await;
+    let (foo_status_code, _) = get_domain(&test_srv, None, "foo.example").await;
+    assert_eq!(foo_status_code, 200);
+
+    // WHEN
+    let (bar_status_code, _) = get_domain(&test_srv, Some(HOST), "bar.example").await;
+
+    // THEN
+    assert_eq!(bar_status_code, 429);
+}
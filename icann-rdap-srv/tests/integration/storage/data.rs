@@ -22,8 +22,12 @@ use icann_rdap_srv::{
         CommonConfig, StoreOps,
     },
 };
+use rstest::rstest;
+use std::io::Write;
 use test_dir::{DirBuilder, TestDir};
 
+use crate::test_jig::tar_archive;
+
 async fn new_and_init_mem(data_dir: String) -> Mem {
     let mem_config = MemConfig::builder()
         .common_config(CommonConfig::default())
@@ -44,6 +48,48 @@ async fn new_and_init_mem(data_dir: String) -> Mem {
     mem
 }
 
+/// Makes JSON Lines of a domain and a template of two domains.
+fn domain_json_lines(ldh1: &str, ldh2: &str, ldh3: &str) -> String {
+    let domain = Domain::basic().ldh_name(ldh1).build();
+    let template = Template::Domain {
+        domain: DomainOrError::DomainObject(Domain::basic().ldh_name("example").build()),
+        ids: vec![
+            DomainId::builder().ldh_name(ldh2).build(),
+            DomainId::builder().ldh_name(ldh3).build(),
+        ],
+    };
+    format!(
+        "{}\n\n{}\n",
+        serde_json::to_string(&domain).expect("serializing domain"),
+        serde_json::to_string(&template).expect("serializing template")
+    )
+}
+
+fn compress(contents: &[u8], file_name: &str) -> Vec<u8> {
+    if file_name.ends_with(".gz") {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(contents).expect("compressing gzip");
+        encoder.finish().expect("finishing gzip")
+    } else if file_name.ends_with(".zst") {
+        zstd::encode_all(contents, 0).expect("compressing zstd")
+    } else {
+        contents.to_vec()
+    }
+}
+
+async fn assert_domains_loaded(mem: &Mem, ldh_names: &[&str]) {
+    for ldh in ldh_names {
+        let actual = mem
+            .get_domain_by_ldh(ldh)
+            .await
+            .expect("getting domain by ldh");
+        let RdapResponse::Domain(domain) = actual else {
+            panic!("{ldh} not loaded")
+        };
+        assert_eq!(domain.ldh_name.as_ref().expect("ldhName is none"), ldh)
+    }
+}
+
 #[tokio::test]
 async fn GIVEN_data_dir_with_domain_WHEN_mem_init_THEN_domain_is_loaded() {
     // GIVEN
@@ -582,3 +628,100 @@ async fn GIVEN_data_dir_with_host_help_WHEN_mem_init_THEN_host_help_is_loaded()
         "bar"
     );
 }
+
+#[rstest]
+#[case("example.jsonl")]
+#[case("example.jsonl.gz")]
+#[case("example.jsonl.zst")]
+#[tokio::test]
+async fn GIVEN_data_dir_with_json_lines_WHEN_mem_init_THEN_domains_are_loaded(
+    #[case] file_name: &str,
+) {
+    // GIVEN
+    let temp = TestDir::temp();
+    let lines = domain_json_lines("foo.example", "bar.example", "baz.example");
+    std::fs::write(temp.path(file_name), compress(lines.as_bytes(), file_name))
+        .expect("writing file");
+
+    // WHEN
+    let mem = new_and_init_mem(temp.root().to_string_lossy().to_string()).await;
+
+    // THEN
+    assert_domains_loaded(&mem, &["foo.example", "bar.example", "baz.example"]).await;
+}
+
+#[rstest]
+#[case("example.tar")]
+#[case("example.tar.gz")]
+#[case("example.tar.zst")]
+#[tokio::test]
+async fn GIVEN_data_dir_with_archive_WHEN_mem_init_THEN_objects_are_loaded(
+    #[case] file_name: &str,
+) {
+    // GIVEN
+    let temp = TestDir::temp();
+    let domain = serde_json::to_string(&Domain::basic().ldh_name("qux.example").build())
+        .expect("serializing domain");
+    let lines = domain_json_lines("foo.example", "bar.example", "baz.example");
+    let srvhelp = Help::basic()
+        .notice(Notice(
+            NoticeOrRemark::builder()
+                .description_entry("foo".to_string())
+                .build(),
+        ))
+        .build()
+        .expect("building help");
+    let srvhelp = serde_json::to_string(&srvhelp).expect("serializing srvhelp");
+    let archive = tar_archive(&[
+        ("data/qux_example.json", domain.as_bytes()),
+        ("data/example.jsonl", lines.as_bytes()),
+        ("data/foo_example_com.help", srvhelp.as_bytes()),
+        ("data/README", b"not data"),
+    ]);
+    std::fs::write(temp.path(file_name), compress(&archive, file_name)).expect("writing file");
+
+    // WHEN
+    let mem = new_and_init_mem(temp.root().to_string_lossy().to_string()).await;
+
+    // THEN
+    assert_domains_loaded(
+        &mem,
+        &["foo.example", "bar.example", "baz.example", "qux.example"],
+    )
+    .await;
+    let actual = mem
+        .get_srv_help(Some("foo.example.com"))
+        .await
+        .expect("getting srvhelp");
+    assert!(matches!(actual, RdapResponse::Help(_)));
+}
+
+#[tokio::test]
+async fn GIVEN_json_lines_with_bad_line_WHEN_load_data_THEN_error() {
+    // GIVEN
+    let temp = TestDir::temp();
+    let lines = domain_json_lines("foo.example", "bar.example", "baz.example");
+    let lines = format!("{lines}{{\"objectClassName\": \"domain\"}}\nnot json\n");
+    std::fs::write(temp.path("example.jsonl"), lines).expect("writing file");
+    let mem_config = MemConfig::builder()
+        .common_config(CommonConfig::default())
+        .build();
+    let mem = Mem::new(mem_config.clone());
+    mem.init().await.expect("initialzing memeory");
+
+    // WHEN
+    let actual = load_data(
+        &ServiceConfig::non_server()
+            .data_dir(temp.root().to_string_lossy().to_string())
+            .storage_type(StorageType::Memory(mem_config))
+            .build()
+            .expect("building service config"),
+        &mem,
+        false,
+    )
+    .await;
+
+    // THEN
+    let error = actual.expect_err("loading bad data");
+    assert!(error.to_string().contains("2 documents"));
+}
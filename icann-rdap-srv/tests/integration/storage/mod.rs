@@ -1,2 +1,5 @@
 mod data;
 mod mem;
+mod pg;
+mod sqlite;
+mod suite;
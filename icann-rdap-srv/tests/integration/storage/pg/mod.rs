@@ -0,0 +1,36 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_srv::storage::{
+    pg::{config::PgConfig, ops::Pg},
+    CommonConfig, StoreOps,
+};
+
+use super::suite::{store_ops_tests, TestStore};
+
+/// These tests are ignored by default. Run them with `cargo test -- --ignored` when this
+/// environment variable holds the URL of a PostgreSQL database, such as one in a local
+/// Postgres container. The tables in the database are truncated by each test.
+const TEST_DB_URL: &str = "RDAP_SRV_TEST_DB_URL";
+
+async fn new_store(common_config: CommonConfig) -> TestStore {
+    let db_url = std::env::var(TEST_DB_URL)
+        .unwrap_or_else(|_| panic!("{TEST_DB_URL} must be set to run PostgreSQL tests"));
+    let pg = Pg::new(
+        PgConfig::builder()
+            .db_url(db_url)
+            .common_config(common_config)
+            .build(),
+    )
+    .await
+    .expect("connecting to database");
+    pg.init().await.expect("initializing database");
+    let tx = pg.new_truncate_tx().await.expect("new truncate tx");
+    tx.commit().await.expect("tx commit");
+    TestStore::new(pg, None)
+}
+
+store_ops_tests!(
+    new_store,
+    #[ignore = "requires a PostgreSQL database in RDAP_SRV_TEST_DB_URL"],
+    #[serial_test::serial]
+);
@@ -0,0 +1,105 @@
+#![allow(non_snake_case)]
+
+use icann_rdap_common::response::{domain::Domain, RdapResponse};
+use icann_rdap_srv::{
+    config::{ServiceConfig, StorageType},
+    storage::{
+        data::load_data,
+        sqlite::{config::SqliteConfig, ops::Sqlite},
+        CommonConfig, StoreOps,
+    },
+};
+use test_dir::{DirBuilder, TestDir};
+
+use super::suite::{store_ops_tests, TestStore};
+
+/// Creates a SQLite store in a temporary directory. The directory is removed
+/// when the returned [TestDir] is dropped.
+async fn new_sqlite(common_config: CommonConfig) -> (TestDir, Sqlite) {
+    let temp = TestDir::temp();
+    let sqlite = Sqlite::new(
+        SqliteConfig::builder()
+            .db_file(temp.path("rdap.sqlite").to_string_lossy())
+            .common_config(common_config)
+            .build(),
+    )
+    .await
+    .expect("opening sqlite database");
+    sqlite.init().await.expect("initializing sqlite database");
+    (temp, sqlite)
+}
+
+async fn new_store(common_config: CommonConfig) -> TestStore {
+    let (temp, sqlite) = new_sqlite(common_config).await;
+    TestStore::new(sqlite, Some(temp))
+}
+
+store_ops_tests!(new_store);
+
+#[tokio::test]
+async fn GIVEN_domain_committed_WHEN_database_reopened_THEN_domain_returned() {
+    // GIVEN
+    let (temp, sqlite) = new_sqlite(CommonConfig::default()).await;
+    let mut tx = sqlite.new_tx().await.expect("new transaction");
+    tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+        .await
+        .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+    drop(sqlite);
+
+    // WHEN
+    let sqlite = Sqlite::new(
+        SqliteConfig::builder()
+            .db_file(temp.path("rdap.sqlite").to_string_lossy())
+            .common_config(CommonConfig::default())
+            .build(),
+    )
+    .await
+    .expect("reopening sqlite database");
+    sqlite.init().await.expect("initializing sqlite database");
+    let actual = sqlite
+        .get_domain_by_ldh("foo.example")
+        .await
+        .expect("getting domain by ldh");
+
+    // THEN
+    assert!(matches!(actual, RdapResponse::Domain(_)));
+}
+
+#[tokio::test]
+async fn GIVEN_data_dir_with_domain_WHEN_load_data_into_sqlite_THEN_domain_is_loaded() {
+    // GIVEN
+    let (temp, sqlite) = new_sqlite(CommonConfig::default()).await;
+    let data_dir = TestDir::temp();
+    std::fs::write(
+        data_dir.path("foo_example.json"),
+        serde_json::to_string(&Domain::basic().ldh_name("foo.example").build())
+            .expect("serializing domain"),
+    )
+    .expect("writing file");
+
+    // WHEN
+    load_data(
+        &ServiceConfig::non_server()
+            .data_dir(data_dir.root().to_string_lossy())
+            .storage_type(StorageType::Sqlite(
+                SqliteConfig::builder()
+                    .db_file(temp.path("rdap.sqlite").to_string_lossy())
+                    .common_config(CommonConfig::default())
+                    .build(),
+            ))
+            .build()
+            .expect("building service config"),
+        &sqlite,
+        false,
+    )
+    .await
+    .expect("loading data");
+
+    // THEN
+    let actual = sqlite
+        .get_domain_by_ldh("foo.example")
+        .await
+        .expect("getting domain by ldh");
+    assert!(matches!(actual, RdapResponse::Domain(_)));
+}
@@ -0,0 +1,1727 @@
+use std::ops::Deref;
+
+use icann_rdap_common::response::{domain::Domain, nameserver::Nameserver};
+use icann_rdap_srv::storage::StoreOps;
+use test_dir::TestDir;
+
+/// A store under the tests of [store_ops_tests]. The temporary directory holding the
+/// files of the store, if any, is removed when this is dropped.
+pub struct TestStore {
+    store: Box<dyn StoreOps>,
+    _temp: Option<TestDir>,
+}
+
+impl TestStore {
+    pub fn new(store: impl StoreOps + 'static, temp: Option<TestDir>) -> Self {
+        Self {
+            store: Box::new(store),
+            _temp: temp,
+        }
+    }
+}
+
+impl Deref for TestStore {
+    type Target = dyn StoreOps;
+
+    fn deref(&self) -> &Self::Target {
+        self.store.as_ref()
+    }
+}
+
+/// Adds domains with and without nameservers.
+pub async fn add_ns_domains(store: &dyn StoreOps) {
+    let mut tx = store.new_tx().await.expect("new transaction");
+    let ns1 = Nameserver::basic()
+        .ldh_name("ns1.example.net")
+        .address("10.0.0.1")
+        .build()
+        .unwrap();
+    let ns2 = Nameserver::basic()
+        .ldh_name("ns2.example.net")
+        .address("2001:db8::2")
+        .build()
+        .unwrap();
+    tx.add_domain(
+        &Domain::basic()
+            .ldh_name("foo.example")
+            .nameservers(vec![ns1.clone(), ns2.clone()])
+            .build(),
+    )
+    .await
+    .expect("add domain in tx");
+    tx.add_domain(
+        &Domain::basic()
+            .ldh_name("bar.example")
+            .nameservers(vec![ns2])
+            .build(),
+    )
+    .await
+    .expect("add domain in tx");
+    tx.add_domain(&Domain::basic().ldh_name("baz.example").build())
+        .await
+        .expect("add domain in tx");
+    tx.commit().await.expect("tx commit");
+}
+
+/// Defines the tests of the [StoreOps] behaviour that every type of storage shares, in a
+/// `store_ops` module. `$new_store` is an async function in the calling module which
+/// makes an empty [TestStore] from a [CommonConfig](icann_rdap_srv::storage::CommonConfig),
+/// and the attributes are added to every test.
+macro_rules! store_ops_tests {
+    ($new_store:ident $(, #[$attr:meta])*) => {
+        mod store_ops {
+            use icann_rdap_common::{
+                contact::Contact,
+                response::{
+                    autnum::Autnum,
+                    domain::Domain,
+                    entity::Entity,
+                    error::Error,
+                    help::Help,
+                    nameserver::Nameserver,
+                    network::Network,
+                    types::{Common, Notice, NoticeOrRemark, ObjectCommon},
+                    RdapResponse,
+                },
+            };
+            use icann_rdap_srv::storage::{
+                data::{AutnumId, DomainId, NameserverId, NetworkId, NetworkIdType},
+                CommonConfig,
+            };
+            use rstest::rstest;
+
+            use super::$new_store as new_store;
+            use crate::storage::suite::add_ns_domains;
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_in_store_WHEN_new_truncate_tx_THEN_no_domain_in_store() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+                .await
+                .expect("add domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let tx = store.new_truncate_tx().await.expect("new truncate tx");
+            tx.commit().await.expect("tx commit");
+
+            // THEN
+            let actual = store
+                .get_domain_by_ldh("foo.example")
+                .await
+                .expect("getting domain by ldh");
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_in_store_WHEN_lookup_domain_by_ldh_THEN_domain_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+                .await
+                .expect("add domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_domain_by_ldh("foo.example")
+                .await
+                .expect("getting domain by ldh");
+
+            // THEN
+            let RdapResponse::Domain(domain) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                domain.ldh_name.as_ref().expect("ldhName is none"),
+                "foo.example"
+            )
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_in_store_WHEN_lookup_domain_by_unicode_THEN_domain_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(
+                &Domain::idn()
+                    .unicode_name("foo.example")
+                    .ldh_name("foo.example")
+                    .build(),
+            )
+            .await
+            .expect("add domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_domain_by_unicode("foo.example")
+                .await
+                .expect("getting domain by unicode");
+
+            // THEN
+            let RdapResponse::Domain(domain) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                domain.unicode_name.as_ref().expect("unicodeName is none"),
+                "foo.example"
+            )
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_in_store_WHEN_search_domain_by_name_THEN_domain_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(
+                &Domain::idn()
+                    .unicode_name("foo.example.com")
+                    .ldh_name("foo.example.com")
+                    .build(),
+            )
+            .await
+            .expect("add domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_domains_by_name("foo.example.*")
+                .await
+                .expect("getting domain by unicode");
+
+            // THEN
+            let RdapResponse::DomainSearchResults(domains) = actual else {
+                panic!()
+            };
+            assert_eq!(domains.clone().results.len(), 1);
+            assert_eq!(
+                domains
+                    .results
+                    .first()
+                    .expect("at least one")
+                    .unicode_name
+                    .as_ref()
+                    .expect("unicodeName is none"),
+                "foo.example.com"
+            )
+        }
+
+        #[rstest]
+        #[case("fõo.example.*", &["xn--fo-cka.example.com"])]
+        #[case("xn--fo-cka.example.*", &["xn--fo-cka.example.com"])]
+        #[case("fõ*.example.com", &["xn--fo-cka.example.com"])]
+        #[case("xn--fo*.example.com", &["xn--fo-cka.example.com"])]
+        #[case("f*.ex*.c*", &["foo.example.com", "xn--fo-cka.example.com"])]
+        #[case("f*.ex*.n*", &["foo.example.net"])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_idns_in_store_WHEN_search_domain_by_name_THEN_domains_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(
+                CommonConfig::builder()
+                    .partial_label_search_enable(true)
+                    .build(),
+            )
+            .await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            for ldh_name in [
+                "xn--fo-cka.example.com",
+                "foo.example.com",
+                "foo.example.net",
+            ] {
+                tx.add_domain(&Domain::basic().ldh_name(ldh_name).build())
+                    .await
+                    .expect("add domain in tx");
+            }
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_domains_by_name(search)
+                .await
+                .expect("searching domains by name");
+
+            // THEN
+            let RdapResponse::DomainSearchResults(domains) = actual else {
+                panic!()
+            };
+            let mut actual = domains
+                .results
+                .iter()
+                .map(|d| d.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_in_store_but_search_not_enabled_WHEN_search_domain_by_name_THEN_not_implemented(
+        ) {
+            // GIVEN
+            let store = new_store(
+                CommonConfig::builder()
+                    .domain_search_by_name_enable(false)
+                    .build(),
+            )
+            .await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(
+                &Domain::idn()
+                    .unicode_name("foo.example.com")
+                    .ldh_name("foo.example.com")
+                    .build(),
+            )
+            .await
+            .expect("add domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_domains_by_name("foo.example.*")
+                .await
+                .expect("getting domain by unicode");
+
+            // THEN
+            let RdapResponse::ErrorResponse(_e) = actual else {
+                panic!()
+            };
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_nameserver_in_store_WHEN_search_nameserver_by_name_THEN_nameserver_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_nameserver(
+                &Nameserver::basic()
+                    .ldh_name("ns.foo.example")
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .expect("add nameserver in tx");
+            tx.add_nameserver(
+                &Nameserver::basic()
+                    .ldh_name("ns.bar.example")
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .expect("add nameserver in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_nameservers_by_name("ns.foo.*")
+                .await
+                .expect("searching nameservers by name");
+
+            // THEN
+            let RdapResponse::NameserverSearchResults(nameservers) = actual else {
+                panic!()
+            };
+            assert_eq!(nameservers.results.len(), 1);
+            assert_eq!(
+                nameservers
+                    .results
+                    .first()
+                    .expect("at least one")
+                    .ldh_name
+                    .as_ref()
+                    .expect("ldhName is none"),
+                "ns.foo.example"
+            )
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_entity_in_store_but_search_not_enabled_WHEN_search_entity_by_handle_THEN_not_implemented(
+        ) {
+            // GIVEN
+            let store = new_store(
+                CommonConfig::builder()
+                    .entity_search_by_handle_enable(false)
+                    .build(),
+            )
+            .await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_entity(&Entity::basic().handle("foo").build())
+                .await
+                .expect("add entity in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_entities_by_handle("foo")
+                .await
+                .expect("searching entities by handle");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 501)
+        }
+
+        #[rstest]
+        #[case("ns1.example.net", &["foo.example"])]
+        #[case("NS2.example.net", &["bar.example", "foo.example"])]
+        #[case("ns*.example.net", &["bar.example", "foo.example"])]
+        #[case("ns1.*", &["foo.example"])]
+        #[case("ns3.example.net", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domains_with_nameservers_in_store_WHEN_search_domain_by_ns_ldh_name_THEN_domains_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            add_ns_domains(&*store).await;
+
+            // WHEN
+            let actual = store
+                .search_domains_by_ns_ldh_name(search)
+                .await
+                .expect("searching domains by nameserver name");
+
+            // THEN
+            let RdapResponse::DomainSearchResults(domains) = actual else {
+                panic!()
+            };
+            let mut actual = domains
+                .results
+                .iter()
+                .map(|d| d.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        #[rstest]
+        #[case("10.0.0.1", &["foo.example"])]
+        #[case("2001:db8::2", &["bar.example", "foo.example"])]
+        #[case("10.0.0.2", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domains_with_nameservers_in_store_WHEN_search_domain_by_ns_ip_THEN_domains_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            add_ns_domains(&*store).await;
+
+            // WHEN
+            let actual = store
+                .search_domains_by_ns_ip(search)
+                .await
+                .expect("searching domains by nameserver ip");
+
+            // THEN
+            let RdapResponse::DomainSearchResults(domains) = actual else {
+                panic!()
+            };
+            let mut actual = domains
+                .results
+                .iter()
+                .map(|d| d.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_in_store_but_ns_search_not_enabled_WHEN_search_domain_by_ns_ldh_name_THEN_not_implemented(
+        ) {
+            // GIVEN
+            let store = new_store(
+                CommonConfig::builder()
+                    .domain_search_by_ns_ldh_name_enable(false)
+                    .build(),
+            )
+            .await;
+
+            // WHEN
+            let actual = store
+                .search_domains_by_ns_ldh_name("ns1.example.net")
+                .await
+                .expect("searching domains by nameserver name");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 501)
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_no_domain_in_store_WHEN_lookup_domain_by_ldh_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+
+            // WHEN
+            let actual = store
+                .get_domain_by_ldh("foo.example")
+                .await
+                .expect("getting domain by ldh");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_entity_in_store_WHEN_lookup_entity_by_handle_THEN_entity_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_entity(&Entity::basic().handle("foo").build())
+                .await
+                .expect("add entity in tx");
+            tx.commit().await.expect("entity tx commit");
+
+            // WHEN
+            let actual = store
+                .get_entity_by_handle("foo")
+                .await
+                .expect("getting entity by handle");
+
+            // THEN
+            let RdapResponse::Entity(entity) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                entity
+                    .object_common
+                    .handle
+                    .as_ref()
+                    .expect("handle is none"),
+                "foo"
+            )
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_no_entity_in_store_WHEN_lookup_entity_by_handle_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+
+            // WHEN
+            let actual = store
+                .get_entity_by_handle("foo")
+                .await
+                .expect("getting entity by handle");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_nameserver_in_store_WHEN_lookup_nameserver_by_ldh_THEN_nameserver_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_nameserver(
+                &Nameserver::basic()
+                    .ldh_name("ns.foo.example")
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .expect("add nameserver in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_nameserver_by_ldh("ns.foo.example")
+                .await
+                .expect("getting nameserver by ldh");
+
+            // THEN
+            let RdapResponse::Nameserver(nameserver) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                nameserver.ldh_name.as_ref().expect("ldhName is none"),
+                "ns.foo.example"
+            )
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_no_nameserver_in_store_WHEN_lookup_nameserver_by_ldh_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+
+            // WHEN
+            let actual = store
+                .get_nameserver_by_ldh("ns.foo.example")
+                .await
+                .expect("getting nameserver by ldh");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_autnum_in_store_WHEN_lookup_autnum_by_start_autnum_THEN_autnum_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_autnum(&Autnum::basic().autnum_range(700..710).build())
+                .await
+                .expect("add autnum in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_autnum_by_num(700)
+                .await
+                .expect("getting autnum by num");
+
+            // THEN
+            let RdapResponse::Autnum(autnum) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                *autnum.start_autnum.as_ref().expect("startNum is none"),
+                700
+            );
+            assert_eq!(*autnum.end_autnum.as_ref().expect("startNum is none"), 710);
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_autnum_in_store_WHEN_lookup_autnum_by_end_autnum_THEN_autnum_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_autnum(&Autnum::basic().autnum_range(700..710).build())
+                .await
+                .expect("add autnum in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_autnum_by_num(710)
+                .await
+                .expect("getting autnum by num");
+
+            // THEN
+            let RdapResponse::Autnum(autnum) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                *autnum.start_autnum.as_ref().expect("startNum is none"),
+                700
+            );
+            assert_eq!(*autnum.end_autnum.as_ref().expect("startNum is none"), 710);
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_no_autnum_in_store_WHEN_lookup_autnum_by_num_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+
+            // WHEN
+            let actual = store
+                .get_autnum_by_num(700)
+                .await
+                .expect("getting autnum by num");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[rstest]
+        #[case(700, 720, 706, Some(705))]
+        #[case(700, 720, 700, None)]
+        #[case(705, 709, 706, Some(700))]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_nested_autnums_in_store_WHEN_one_removed_THEN_lookup_finds_remaining(
+            #[case] removed_start: u32,
+            #[case] removed_end: u32,
+            #[case] num: u32,
+            #[case] expected_start: Option<u32>,
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_autnum(&Autnum::basic().autnum_range(700..720).build())
+                .await
+                .expect("add outer autnum in tx");
+            tx.add_autnum(&Autnum::basic().autnum_range(705..709).build())
+                .await
+                .expect("add inner autnum in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.remove_autnum(
+                &AutnumId::builder()
+                    .start_autnum(removed_start)
+                    .end_autnum(removed_end)
+                    .build(),
+            )
+            .await
+            .expect("remove autnum in tx");
+            tx.commit().await.expect("tx commit");
+
+            // THEN
+            let actual = store
+                .get_autnum_by_num(num)
+                .await
+                .expect("getting autnum by num");
+            let actual_start = match actual {
+                RdapResponse::Autnum(autnum) => autnum.start_autnum,
+                _ => None,
+            };
+            assert_eq!(actual_start, expected_start);
+        }
+
+        #[rstest]
+        #[case("192.168.0.0/24", "192.168.0.1", "192.168.0.0", "192.168.0.255")]
+        #[case("192.168.0.0/24", "192.168.0.0", "192.168.0.0", "192.168.0.255")]
+        #[case("192.168.0.0/24", "192.168.0.254", "192.168.0.0", "192.168.0.255")]
+        #[case("192.168.0.0/24", "192.168.0.255", "192.168.0.0", "192.168.0.255")]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_network_in_store_WHEN_lookup_network_by_address_THEN_network_returned(
+            #[case] cidr: &str,
+            #[case] addr: &str,
+            #[case] start: &str,
+            #[case] end: &str,
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network(&Network::basic().cidr(cidr).build().expect("cidr parsing"))
+                .await
+                .expect("add network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_network_by_ipaddr(addr)
+                .await
+                .expect("getting network by num");
+
+            // THEN
+            let RdapResponse::Network(network) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                *network
+                    .start_address
+                    .as_ref()
+                    .expect("startAddress is none"),
+                start
+            );
+            assert_eq!(
+                *network.end_address.as_ref().expect("endAddress is none"),
+                end
+            );
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_no_network_in_store_WHEN_lookup_network_by_address_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+
+            // WHEN
+            let actual = store
+                .get_network_by_ipaddr("192.168.0.1")
+                .await
+                .expect("getting network by address");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[rstest]
+        #[case(&["192.168.0.0/16", "192.168.0.0/8", "192.168.0.0/24"], "192.168.0.1", "192.168.0.0", "192.168.0.255")]
+        #[case(&["192.168.0.0/16", "192.168.0.0/8", "192.168.0.0/24"], "192.168.1.1", "192.168.0.0", "192.168.255.255")]
+        #[case(&["2001::/64", "2001::/56", "2001::/20"], "2001::1", "2001::", "2001::ffff:ffff:ffff:ffff")]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_contained_networks_in_store_WHEN_lookup_network_by_address_THEN_most_specific_network_returned(
+            #[case] cidrs: &[&str],
+            #[case] addr: &str,
+            #[case] start: &str,
+            #[case] end: &str,
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            for cidr in cidrs {
+                tx.add_network(&Network::basic().cidr(*cidr).build().expect("cidr parsing"))
+                    .await
+                    .expect("add network in tx");
+            }
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_network_by_ipaddr(addr)
+                .await
+                .expect("getting network by num");
+
+            // THEN
+            let RdapResponse::Network(network) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                *network
+                    .start_address
+                    .as_ref()
+                    .expect("startAddress is none"),
+                start
+            );
+            assert_eq!(
+                *network.end_address.as_ref().expect("endAddress is none"),
+                end
+            );
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_offbit_network_in_store_WHEN_lookup_network_by_first_address_THEN_network_returned() {
+            // GIVEN
+            let start = "10.0.0.0";
+            let end = "10.0.1.255";
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network(
+                &Network::builder()
+                    .start_address(start)
+                    .end_address(end)
+                    .ip_version("v4")
+                    .object_common(ObjectCommon::ip_network().build())
+                    .common(Common::builder().build())
+                    .build(),
+            )
+            .await
+            .expect("add network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_network_by_ipaddr(start)
+                .await
+                .expect("getting network by num");
+
+            // THEN
+            let RdapResponse::Network(network) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                *network
+                    .start_address
+                    .as_ref()
+                    .expect("startAddress is none"),
+                start
+            );
+            assert_eq!(
+                *network.end_address.as_ref().expect("endAddress is none"),
+                end
+            );
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_offbit_network_in_store_WHEN_lookup_network_by_last_address_THEN_network_returned() {
+            // GIVEN
+            let start = "10.0.0.0";
+            let end = "10.0.1.255";
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network(
+                &Network::builder()
+                    .start_address(start)
+                    .end_address(end)
+                    .ip_version("v4")
+                    .object_common(ObjectCommon::ip_network().build())
+                    .common(Common::builder().build())
+                    .build(),
+            )
+            .await
+            .expect("add network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_network_by_ipaddr(end)
+                .await
+                .expect("getting network by num");
+
+            // THEN
+            let RdapResponse::Network(network) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                *network
+                    .start_address
+                    .as_ref()
+                    .expect("startAddress is none"),
+                start
+            );
+            assert_eq!(
+                *network.end_address.as_ref().expect("endAddress is none"),
+                end
+            );
+        }
+
+        #[rstest]
+        #[case("192.168.0.0/16", "192.168.0.0/24", "192.168.0.0", "192.168.255.255")]
+        #[case("192.168.0.0/16", "192.168.0.0/16", "192.168.0.0", "192.168.255.255")]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_network_in_store_WHEN_lookup_network_by_cidr_THEN_network_returned(
+            #[case] cidr: &str,
+            #[case] lookup: &str,
+            #[case] start: &str,
+            #[case] end: &str,
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network(&Network::basic().cidr(cidr).build().expect("cidr parsing"))
+                .await
+                .expect("add network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_network_by_cidr(lookup)
+                .await
+                .expect("getting network by cidr");
+
+            // THEN
+            let RdapResponse::Network(network) = actual else {
+                panic!()
+            };
+            assert_eq!(
+                *network
+                    .start_address
+                    .as_ref()
+                    .expect("startAddress is none"),
+                start
+            );
+            assert_eq!(
+                *network.end_address.as_ref().expect("endAddress is none"),
+                end
+            );
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_no_network_in_store_WHEN_lookup_network_by_cidr_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+
+            // WHEN
+            let actual = store
+                .get_network_by_cidr("192.168.0.0/24")
+                .await
+                .expect("getting network by address");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_default_help_in_store_WHEN_lookup_help_with_no_host_THEN_get_default_help() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_srv_help(
+                &Help::basic()
+                    .notice(Notice(
+                        NoticeOrRemark::builder()
+                            .description_entry("foo".to_string())
+                            .build(),
+                    ))
+                    .build()
+                    .expect("building help"),
+                None,
+            )
+            .await
+            .expect("adding srv help");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store.get_srv_help(None).await.expect("getting srv helf");
+
+            // THEN
+            let RdapResponse::Help(srvhelp) = actual else {
+                panic!()
+            };
+            let notice = srvhelp
+                .common
+                .notices
+                .expect("no notices in srvhelp")
+                .first()
+                .expect("notices empty")
+                .to_owned();
+            assert_eq!(
+                notice
+                    .description
+                    .as_ref()
+                    .expect("no description!")
+                    .many()
+                    .first()
+                    .expect("no description in notice"),
+                "foo"
+            );
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_help_in_store_WHEN_lookup_help_with_host_THEN_get_host_help() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_srv_help(
+                &Help::basic()
+                    .notice(Notice(
+                        NoticeOrRemark::builder()
+                            .description_entry("bar".to_string())
+                            .build(),
+                    ))
+                    .build()
+                    .expect("building help"),
+                Some("bar.example.com"),
+            )
+            .await
+            .expect("adding srv help");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_srv_help(Some("bar.example.com"))
+                .await
+                .expect("getting srv helf");
+
+            // THEN
+            let RdapResponse::Help(srvhelp) = actual else {
+                panic!()
+            };
+            let notice = srvhelp
+                .common
+                .notices
+                .expect("no notices in srvhelp")
+                .first()
+                .expect("notices empty")
+                .to_owned();
+            assert_eq!(
+                notice
+                    .description
+                    .as_ref()
+                    .expect("no description")
+                    .many()
+                    .first()
+                    .expect("no description in notice"),
+                "bar"
+            );
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_idn_in_store_WHEN_remove_domain_THEN_domain_not_found_by_ldh_unicode_or_search() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(
+                &Domain::idn()
+                    .unicode_name("fõo.example")
+                    .ldh_name("xn--fo-5ja.example")
+                    .build(),
+            )
+            .await
+            .expect("add domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.remove_domain(&DomainId::builder().ldh_name("xn--fo-5ja.example").build())
+                .await
+                .expect("remove domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // THEN
+            let by_ldh = store
+                .get_domain_by_ldh("xn--fo-5ja.example")
+                .await
+                .expect("getting domain by ldh");
+            assert!(matches!(by_ldh, RdapResponse::ErrorResponse(_)));
+            let by_unicode = store
+                .get_domain_by_unicode("fõo.example")
+                .await
+                .expect("getting domain by unicode");
+            assert!(matches!(by_unicode, RdapResponse::ErrorResponse(_)));
+            let RdapResponse::DomainSearchResults(domains) = store
+                .search_domains_by_name("*.example")
+                .await
+                .expect("searching domains")
+            else {
+                panic!()
+            };
+            assert!(domains.results.is_empty());
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_removed_domain_and_nameserver_WHEN_search_by_partial_name_THEN_not_found() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+                .await
+                .expect("add domain in tx");
+            tx.add_nameserver(&Nameserver::basic().ldh_name("ns1.foo.example").build().unwrap())
+                .await
+                .expect("add nameserver in tx");
+            tx.commit().await.expect("tx commit");
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.remove_domain(&DomainId::builder().ldh_name("foo.example").build())
+                .await
+                .expect("remove domain in tx");
+            tx.remove_nameserver(&NameserverId::builder().ldh_name("ns1.foo.example").build())
+                .await
+                .expect("remove nameserver in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let domains = store
+                .search_domains_by_name("fo*.example")
+                .await
+                .expect("searching domains");
+            let nameservers = store
+                .search_nameservers_by_name("ns1.fo*.example")
+                .await
+                .expect("searching nameservers");
+
+            // THEN
+            let RdapResponse::DomainSearchResults(domains) = domains else {
+                panic!()
+            };
+            assert!(domains.results.is_empty());
+            let RdapResponse::NameserverSearchResults(nameservers) = nameservers else {
+                panic!()
+            };
+            assert!(nameservers.results.is_empty());
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_contained_networks_in_store_WHEN_remove_network_THEN_containing_network_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network(
+                &Network::basic()
+                    .cidr("10.0.0.0/24")
+                    .build()
+                    .expect("cidr parsing"),
+            )
+            .await
+            .expect("add network in tx");
+            tx.add_network(
+                &Network::basic()
+                    .cidr("10.0.0.0/8")
+                    .build()
+                    .expect("cidr parsing"),
+            )
+            .await
+            .expect("add network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.remove_network(
+                &NetworkId::builder()
+                    .network_id(NetworkIdType::Cidr(
+                        "10.0.0.0/24".parse().expect("parsing cidr"),
+                    ))
+                    .build(),
+            )
+            .await
+            .expect("remove network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // THEN
+            let actual = store
+                .get_network_by_ipaddr("10.0.0.1")
+                .await
+                .expect("getting network by address");
+            let RdapResponse::Network(network) = actual else {
+                panic!()
+            };
+            assert_eq!(network.end_address.as_deref(), Some("10.255.255.255"));
+        }
+
+        #[rstest]
+        #[case("ns1.foo.*", &["ns1.foo.example"])]
+        #[case("ns*.foo.example", &["ns1.foo.example", "ns2.foo.example"])]
+        #[case("ns1.*", &["ns1.bar.example", "ns1.foo.example"])]
+        #[case("*.example", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_nameservers_in_store_WHEN_search_nameserver_by_name_THEN_matching_nameservers_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            for name in ["ns1.foo.example", "ns2.foo.example", "ns1.bar.example"] {
+                tx.add_nameserver(&Nameserver::basic().ldh_name(name).build().unwrap())
+                    .await
+                    .expect("add nameserver in tx");
+            }
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_nameservers_by_name(search)
+                .await
+                .expect("searching nameservers");
+
+            // THEN
+            let RdapResponse::NameserverSearchResults(nameservers) = actual else {
+                panic!()
+            };
+            let mut actual = nameservers
+                .results
+                .iter()
+                .map(|n| n.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        #[rstest]
+        #[case("bücher.*", &["xn--bcher-kva.example"])]
+        #[case("BÜCH*.example", &["xn--bcher-kva.example"])]
+        #[case("xn--bcher-kva.*", &["xn--bcher-kva.example"])]
+        #[case("xn--bch*.example", &["xn--bcher-kva.example"])]
+        #[case("bach*.example", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_idn_in_store_WHEN_search_domain_by_name_THEN_found_by_a_labels_and_u_labels(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(
+                &Domain::idn()
+                    .unicode_name("bücher.example")
+                    .ldh_name("xn--bcher-kva.example")
+                    .build(),
+            )
+            .await
+            .expect("add domain in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_domains_by_name(search)
+                .await
+                .expect("searching domains");
+
+            // THEN
+            let RdapResponse::DomainSearchResults(domains) = actual else {
+                panic!()
+            };
+            let actual = domains
+                .results
+                .iter()
+                .map(|d| d.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            assert_eq!(actual, expected);
+        }
+
+        #[rstest]
+        #[case("ns1.bücher.*", &["ns1.xn--bcher-kva.example"])]
+        #[case("ns1.xn--bcher-kva.*", &["ns1.xn--bcher-kva.example"])]
+        #[case("ns1.bach*.example", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_idn_nameserver_in_store_WHEN_search_nameserver_by_name_THEN_found_by_a_labels_and_u_labels(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_nameserver(
+                &Nameserver::basic()
+                    .ldh_name("ns1.xn--bcher-kva.example")
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .expect("add nameserver in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_nameservers_by_name(search)
+                .await
+                .expect("searching nameservers");
+
+            // THEN
+            let RdapResponse::NameserverSearchResults(nameservers) = actual else {
+                panic!()
+            };
+            let actual = nameservers
+                .results
+                .iter()
+                .map(|n| n.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            assert_eq!(actual, expected);
+        }
+
+        #[rstest]
+        #[case("foo-1", &["foo-1"])]
+        #[case("FOO*", &["foo-1", "foo_2"])]
+        #[case("foo_*", &["foo_2"])]
+        #[case("bar*", &[])]
+        #[case("*", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_entities_in_store_WHEN_search_entity_by_handle_THEN_matching_entities_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            for handle in ["foo-1", "foo_2"] {
+                tx.add_entity(&Entity::basic().handle(handle).build())
+                    .await
+                    .expect("add entity in tx");
+            }
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_entities_by_handle(search)
+                .await
+                .expect("searching entities");
+
+            // THEN
+            let RdapResponse::EntitySearchResults(entities) = actual else {
+                panic!()
+            };
+            let mut actual = entities
+                .results
+                .iter()
+                .map(|e| e.object_common.handle.clone().expect("handle is none"))
+                .collect::<Vec<String>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        #[rstest]
+        #[case("10.0.0.1", &["ns.foo.example"])]
+        #[case("2001:DB8::1", &["ns.foo.example"])]
+        #[case("10.0.0.2", &[])]
+        #[case("10.0.0.3", &["ns.bar.example"])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_replaced_nameservers_in_store_WHEN_search_nameserver_by_ip_THEN_matching_nameservers_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_nameserver(
+                &Nameserver::basic()
+                    .ldh_name("ns.foo.example")
+                    .address("10.0.0.1")
+                    .address("2001:db8::1")
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .expect("add nameserver in tx");
+            for address in ["10.0.0.2", "10.0.0.3"] {
+                tx.add_nameserver(
+                    &Nameserver::basic()
+                        .ldh_name("ns.bar.example")
+                        .address(address)
+                        .build()
+                        .unwrap(),
+                )
+                .await
+                .expect("add nameserver in tx");
+            }
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_nameservers_by_ip(search)
+                .await
+                .expect("searching nameservers by ip");
+
+            // THEN
+            let RdapResponse::NameserverSearchResults(nameservers) = actual else {
+                panic!()
+            };
+            let actual = nameservers
+                .results
+                .iter()
+                .map(|n| n.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            assert_eq!(actual, expected);
+        }
+
+        #[rstest]
+        #[case("Bob Smurd", &["foo"])]
+        #[case("bob*", &["bar", "foo"])]
+        #[case("alice*", &[])]
+        #[case("*", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_entities_in_store_WHEN_search_entity_by_fn_THEN_matching_entities_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            for (handle, full_name) in [("foo", "Bob Smurd"), ("bar", "Bobby Smurd")] {
+                tx.add_entity(
+                    &Entity::basic()
+                        .handle(handle)
+                        .contact(Contact::builder().full_name(full_name).build())
+                        .build(),
+                )
+                .await
+                .expect("add entity in tx");
+            }
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_entities_by_fn(search)
+                .await
+                .expect("searching entities by fn");
+
+            // THEN
+            let RdapResponse::EntitySearchResults(entities) = actual else {
+                panic!()
+            };
+            let mut actual = entities
+                .results
+                .iter()
+                .map(|e| e.object_common.handle.clone().expect("handle is none"))
+                .collect::<Vec<String>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_network_range_in_store_WHEN_remove_network_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network(
+                &Network::builder()
+                    .start_address("10.0.0.0")
+                    .end_address("10.0.2.255")
+                    .ip_version("v4")
+                    .object_common(ObjectCommon::ip_network().build())
+                    .common(Common::builder().build())
+                    .build(),
+            )
+            .await
+            .expect("add network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.remove_network(
+                &NetworkId::builder()
+                    .network_id(NetworkIdType::Range {
+                        start_address: "10.0.0.0".to_string(),
+                        end_address: "10.0.2.255".to_string(),
+                    })
+                    .build(),
+            )
+            .await
+            .expect("remove network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // THEN
+            for addr in ["10.0.0.1", "10.0.2.1"] {
+                let actual = store
+                    .get_network_by_ipaddr(addr)
+                    .await
+                    .expect("getting network by address");
+                assert!(matches!(actual, RdapResponse::ErrorResponse(_)));
+            }
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_in_rolled_back_tx_WHEN_lookup_domain_by_ldh_THEN_404_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+                .await
+                .expect("add domain in tx");
+            tx.rollback().await.expect("tx rollback");
+
+            // WHEN
+            let actual = store
+                .get_domain_by_ldh("foo.example")
+                .await
+                .expect("getting domain by ldh");
+
+            // THEN
+            let RdapResponse::ErrorResponse(error) = actual else {
+                panic!()
+            };
+            assert_eq!(error.error_code, 404)
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domain_added_twice_WHEN_lookup_domain_by_ldh_THEN_last_domain_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_domain(&Domain::basic().ldh_name("foo.example").build())
+                .await
+                .expect("add domain in tx");
+            tx.add_domain(
+                &Domain::basic()
+                    .ldh_name("foo.example")
+                    .status("active")
+                    .build(),
+            )
+            .await
+            .expect("add domain again in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_domain_by_ldh("foo.example")
+                .await
+                .expect("getting domain by ldh");
+
+            // THEN
+            let RdapResponse::Domain(domain) = actual else {
+                panic!()
+            };
+            assert!(domain.object_common.status.is_some());
+        }
+
+        #[rstest]
+        #[case("foo.example.*", &["foo.example.com", "foo.example.net"])]
+        #[case("foo.example.n*", &["foo.example.net"])]
+        #[case("foo.ex*.com", &["foo.example.com"])]
+        #[case("fo*.example.com", &["foo.example.com"])]
+        #[case("foo.*.com", &["foo.example.com"])]
+        #[case("*.example.com", &[])]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_domains_in_store_WHEN_search_domain_by_name_THEN_matching_domains_returned(
+            #[case] search: &str,
+            #[case] expected: &[&str],
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            for name in [
+                "foo.example.com",
+                "bar.example.com",
+                "foo.example.net",
+                "bar.example.net",
+            ] {
+                tx.add_domain(&Domain::basic().ldh_name(name).build())
+                    .await
+                    .expect("add domain in tx");
+            }
+            tx.add_domain_err(
+                &DomainId::builder().ldh_name("foo.example.org").build(),
+                &Error::redirect().url("https://foo.example.org").build(),
+            )
+            .await
+            .expect("add domain error in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .search_domains_by_name(search)
+                .await
+                .expect("searching domains");
+
+            // THEN
+            let RdapResponse::DomainSearchResults(domains) = actual else {
+                panic!()
+            };
+            let mut actual = domains
+                .results
+                .iter()
+                .map(|d| d.ldh_name.clone().expect("ldhName is none"))
+                .collect::<Vec<String>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        #[rstest]
+        #[case(700, Some((700, 710)))]
+        #[case(705, Some((705, 705)))]
+        #[case(710, Some((700, 710)))]
+        #[case(711, None)]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_autnums_in_store_WHEN_lookup_autnum_by_num_THEN_most_specific_autnum_returned(
+            #[case] num: u32,
+            #[case] expected: Option<(u32, u32)>,
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_autnum(&Autnum::basic().autnum_range(700..710).build())
+                .await
+                .expect("add autnum in tx");
+            tx.add_autnum(&Autnum::basic().autnum_range(705..705).build())
+                .await
+                .expect("add autnum in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store.get_autnum_by_num(num).await.expect("getting autnum");
+
+            // THEN
+            match expected {
+                Some((start, end)) => {
+                    let RdapResponse::Autnum(autnum) = actual else {
+                        panic!()
+                    };
+                    assert_eq!(autnum.start_autnum, Some(start));
+                    assert_eq!(autnum.end_autnum, Some(end));
+                }
+                None => {
+                    let RdapResponse::ErrorResponse(error) = actual else {
+                        panic!()
+                    };
+                    assert_eq!(error.error_code, 404)
+                }
+            }
+        }
+
+        #[rstest]
+        #[case("192.168.0.0/16", "192.168.0.0/24", true)]
+        #[case("192.168.0.0/16", "192.168.0.0/16", true)]
+        #[case("192.168.0.0/16", "192.168.0.0/8", false)]
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_network_in_store_WHEN_lookup_network_by_cidr_THEN_containing_network_returned(
+            #[case] cidr: &str,
+            #[case] lookup: &str,
+            #[case] found: bool,
+        ) {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network(&Network::basic().cidr(cidr).build().expect("cidr parsing"))
+                .await
+                .expect("add network in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_network_by_cidr(lookup)
+                .await
+                .expect("getting network by cidr");
+
+            // THEN
+            assert_eq!(matches!(actual, RdapResponse::Network(_)), found);
+        }
+
+        #[tokio::test]
+        $(#[$attr])*
+        async fn GIVEN_network_error_in_store_WHEN_lookup_network_by_address_THEN_redirect_returned() {
+            // GIVEN
+            let store = new_store(CommonConfig::default()).await;
+            let mut tx = store.new_tx().await.expect("new transaction");
+            tx.add_network_err(
+                &NetworkId::builder()
+                    .network_id(NetworkIdType::Range {
+                        start_address: "10.0.0.0".to_string(),
+                        end_address: "10.0.1.255".to_string(),
+                    })
+                    .build(),
+                &Error::redirect().url("https://other.example/ip/").build(),
+            )
+            .await
+            .expect("add network error in tx");
+            tx.commit().await.expect("tx commit");
+
+            // WHEN
+            let actual = store
+                .get_network_by_ipaddr("10.0.1.1")
+                .await
+                .expect("getting network by address");
+
+            // THEN
+            assert!(actual.is_redirect());
+        }
+        }
+    };
+}
+
+pub(crate) use store_ops_tests;
@@ -1,10 +1,21 @@
 use assert_cmd::Command;
+use icann_rdap_srv::auth::Authenticator;
+use icann_rdap_srv::config::CacheConfig;
 use icann_rdap_srv::config::ListenConfig;
+use icann_rdap_srv::config::RateLimitConfig;
+use icann_rdap_srv::config::ServiceConfig;
+use icann_rdap_srv::config::StorageType;
+use icann_rdap_srv::config::TlsConfig;
+use icann_rdap_srv::redact::RedactionPolicy;
 use icann_rdap_srv::server::AppState;
 use icann_rdap_srv::server::Listener;
+use icann_rdap_srv::status::ServerStatus;
+use icann_rdap_srv::status::StatusReport;
 use icann_rdap_srv::storage::mem::config::MemConfig;
 use icann_rdap_srv::storage::mem::ops::Mem;
 use icann_rdap_srv::storage::CommonConfig;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 use test_dir::DirBuilder;
 use test_dir::TestDir;
@@ -72,61 +83,173 @@ impl RdapSrvDataTestJig {
     }
 }
 
+/// Creates a tar archive of the files, given as pairs of paths and contents.
+pub fn tar_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, *contents)
+            .expect("appending to archive");
+    }
+    builder.into_inner().expect("building archive")
+}
+
+/// Creates the configuration of a service serving the data directory from memory,
+/// without bootstrapping or reloading.
+pub fn service_config(data_dir: &Path) -> ServiceConfig {
+    let mem_config = MemConfig::builder()
+        .common_config(CommonConfig::default())
+        .build();
+    ServiceConfig::builder()
+        .storage_type(StorageType::Memory(mem_config))
+        .data_dir(data_dir.to_string_lossy())
+        .auto_reload(false)
+        .bootstrap(false)
+        .update_on_bootstrap(false)
+        .search_page_size(10)
+        .build()
+}
+
+/// Polls the server status until its report satisfies the condition.
+pub async fn wait_for_status(
+    status: &ServerStatus,
+    condition: impl Fn(&StatusReport) -> bool,
+) -> StatusReport {
+    for _ in 0..100 {
+        let report = status.report();
+        if condition(&report) {
+            return report;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("timed out waiting for server status")
+}
+
+/// Polls the server status until the initial load of the data has finished.
+pub async fn wait_for_initial_load(status: &ServerStatus) -> StatusReport {
+    wait_for_status(status, |report| report.initial_load_finished).await
+}
+
 pub struct SrvTestJig {
     pub mem: Mem,
+    pub status: Arc<ServerStatus>,
     pub rdap_base: String,
 }
 
 impl SrvTestJig {
     pub async fn new() -> Self {
-        let mem = Mem::default();
-        let app_state = AppState {
-            storage: mem.clone(),
-            bootstrap: false,
-        };
-        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
-        let listener = Listener::listen(&ListenConfig::default())
-            .await
-            .expect("listening on interface");
-        let rdap_base = listener.rdap_base();
-        tokio::spawn(async move {
-            listener
-                .start_with_state(app_state)
-                .await
-                .expect("starting server");
-        });
-        Self { mem, rdap_base }
+        Self::new_state(AppState::new(Mem::default())).await
     }
 
     pub async fn new_common_config(common_config: CommonConfig) -> Self {
+        Self::new_search_page_size(common_config, None).await
+    }
+
+    pub async fn new_search_page_size(
+        common_config: CommonConfig,
+        search_page_size: Option<usize>,
+    ) -> Self {
         let mem_config = MemConfig::builder().common_config(common_config).build();
-        let mem = Mem::new(mem_config);
-        let app_state = AppState {
-            storage: mem.clone(),
-            bootstrap: false,
-        };
-        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
-        let listener = Listener::listen(&ListenConfig::default())
-            .await
-            .expect("listening on interface");
-        let rdap_base = listener.rdap_base();
-        tokio::spawn(async move {
-            listener
-                .start_with_state(app_state)
-                .await
-                .expect("starting server");
-        });
-        Self { mem, rdap_base }
+        Self::new_state(AppState {
+            search_page_size,
+            ..AppState::new(Mem::new(mem_config))
+        })
+        .await
+    }
+
+    pub async fn new_rate_limit(rate_limit: RateLimitConfig) -> Self {
+        Self::new_state(AppState {
+            rate_limit: Some(rate_limit),
+            ..AppState::new(Mem::default())
+        })
+        .await
+    }
+
+    pub async fn new_cache(cache: CacheConfig) -> Self {
+        Self::new_state(AppState {
+            cache: Some(cache),
+            ..AppState::new(Mem::default())
+        })
+        .await
+    }
+
+    pub async fn new_tls(tls: TlsConfig) -> Self {
+        Self::new_listen_config(
+            AppState::new(Mem::default()),
+            &ListenConfig::builder()
+                .ip_addr("127.0.0.1".to_string())
+                .tls(tls)
+                .build(),
+        )
+        .await
+    }
+
+    pub async fn new_access(auth: Authenticator, redaction: RedactionPolicy) -> Self {
+        Self::new_state(AppState {
+            auth: Some(Arc::new(auth)),
+            redaction: Some(Arc::new(redaction)),
+            ..AppState::new(Mem::default())
+        })
+        .await
+    }
+
+    pub async fn new_admin(auth: Authenticator, admin_role: &str) -> Self {
+        Self::new_state(AppState {
+            auth: Some(Arc::new(auth)),
+            admin_role: Some(admin_role.to_string()),
+            ..AppState::new(Mem::default())
+        })
+        .await
+    }
+
+    /// Creates a server which loads its data from the data directory of the service
+    /// configuration in the same way as the `rdap-srv` command.
+    pub async fn new_service_config(service_config: ServiceConfig) -> Self {
+        let mem_config = MemConfig::builder()
+            .common_config(CommonConfig::default())
+            .build();
+        let app_state = AppState::new_mem(
+            mem_config,
+            &service_config,
+            Arc::new(ServerStatus::default()),
+        )
+        .await
+        .expect("creating app state");
+        Self::new_state(app_state).await
     }
 
     pub async fn new_bootstrap() -> Self {
-        let mem = Mem::default();
-        let app_state = AppState {
-            storage: mem.clone(),
+        Self::new_state(AppState {
             bootstrap: true,
-        };
+            ..AppState::new(Mem::default())
+        })
+        .await
+    }
+
+    /// Queries the readiness endpoint, giving its status code and report.
+    pub async fn ready_report(&self) -> (u16, StatusReport) {
+        let response = reqwest::get(self.rdap_base.replace("/rdap", "/ready"))
+            .await
+            .expect("querying ready");
+        let status_code = response.status().as_u16();
+        let report = response.json().await.expect("parsing status report");
+        (status_code, report)
+    }
+
+    /// Creates a server with the state, listening on any available port.
+    pub async fn new_state(app_state: AppState<Mem>) -> Self {
+        Self::new_listen_config(app_state, &ListenConfig::default()).await
+    }
+
+    async fn new_listen_config(app_state: AppState<Mem>, listen_config: &ListenConfig) -> Self {
+        let mem = app_state.storage.clone();
+        let status = app_state.status.clone();
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
-        let listener = Listener::listen(&ListenConfig::default())
+        let listener = Listener::listen(listen_config)
             .await
             .expect("listening on interface");
         let rdap_base = listener.rdap_base();
@@ -136,6 +259,10 @@ impl SrvTestJig {
                 .await
                 .expect("starting server");
         });
-        Self { mem, rdap_base }
+        Self {
+            mem,
+            status,
+            rdap_base,
+        }
     }
 }